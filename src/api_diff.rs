@@ -0,0 +1,270 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Result as FmtResult, Write},
+    iter,
+};
+
+use crate::public_api::PublicApi;
+
+/// The number of unchanged lines printed around each change.
+const CONTEXT: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+impl Line<'_> {
+    fn is_change(self) -> bool {
+        !matches!(self, Line::Same(_))
+    }
+}
+
+/// Renders both APIs as pseudo-Rust, each item being followed by an empty
+/// line, and returns the unified diff between them. It is empty when both
+/// renderings are the same.
+pub(crate) fn render(previous: &PublicApi, current: &PublicApi) -> String {
+    diff(
+        &previous.render(),
+        &current.render(),
+        ("previous", "current"),
+    )
+}
+
+/// Returns the unified diff between two renderings, whose items are sorted by
+/// key. Items are compared one by one, and only the lines of the items which
+/// exist in both renderings are diffed.
+pub(crate) fn diff<K: Ord>(
+    previous: &BTreeMap<K, Vec<String>>,
+    current: &BTreeMap<K, Vec<String>>,
+    (previous_label, current_label): (&str, &str),
+) -> String {
+    let lines = diff_items(previous, current);
+
+    let mut output = String::new();
+    write_hunks(&lines, previous_label, current_label, &mut output).unwrap();
+    output
+}
+
+fn diff_items<'a, K: Ord>(
+    previous: &'a BTreeMap<K, Vec<String>>,
+    current: &'a BTreeMap<K, Vec<String>>,
+) -> Vec<Line<'a>> {
+    let mut lines = Vec::new();
+
+    let mut previous = previous.iter().peekable();
+    let mut current = current.iter().peekable();
+
+    loop {
+        let (a, b) = match (previous.peek(), current.peek()) {
+            (None, None) => break,
+            (Some((a_key, _)), Some((b_key, _))) if a_key == b_key => {
+                (previous.next(), current.next())
+            }
+            (Some((a_key, _)), Some((b_key, _))) if a_key < b_key => (previous.next(), None),
+            (Some(_), None) => (previous.next(), None),
+            (_, Some(_)) => (None, current.next()),
+        };
+
+        let block = |item: Option<(_, &'a Vec<String>)>| {
+            item.map_or(Vec::new(), |(_, lines)| {
+                lines
+                    .iter()
+                    .map(String::as_str)
+                    .chain(iter::once(""))
+                    .collect()
+            })
+        };
+
+        diff_lines(&block(a), &block(b), &mut lines);
+    }
+
+    lines
+}
+
+/// Diffs two short sequences of lines, using their longest common
+/// subsequence.
+fn diff_lines<'a>(a: &[&'a str], b: &[&'a str], out: &mut Vec<Line<'a>>) {
+    // `lengths[i][j]` is the length of the longest common subsequence of
+    // `a[i..]` and `b[j..]`.
+    let mut lengths = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            out.push(Line::Same(a[i]));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            out.push(Line::Removed(a[i]));
+            i += 1;
+        } else {
+            out.push(Line::Added(b[j]));
+            j += 1;
+        }
+    }
+
+    out.extend(a[i..].iter().copied().map(Line::Removed));
+    out.extend(b[j..].iter().copied().map(Line::Added));
+}
+
+fn write_hunks(
+    lines: &[Line],
+    previous_label: &str,
+    current_label: &str,
+    out: &mut String,
+) -> FmtResult {
+    // The ranges of lines to print, which contain the changes and their
+    // context. Ranges which overlap or touch each other are merged.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (idx, _) in lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.is_change())
+    {
+        let start = idx.saturating_sub(CONTEXT);
+        let end = (idx + CONTEXT + 1).min(lines.len());
+
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    if hunks.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out, "--- {}", previous_label)?;
+    writeln!(out, "+++ {}", current_label)?;
+
+    let count_previous = |lines: &[Line]| {
+        lines
+            .iter()
+            .filter(|l| !matches!(l, Line::Added(_)))
+            .count()
+    };
+    let count_current = |lines: &[Line]| {
+        lines
+            .iter()
+            .filter(|l| !matches!(l, Line::Removed(_)))
+            .count()
+    };
+
+    for (start, end) in hunks {
+        let hunk = &lines[start..end];
+
+        writeln!(
+            out,
+            "@@ -{} +{} @@",
+            range(count_previous(&lines[..start]), count_previous(hunk)),
+            range(count_current(&lines[..start]), count_current(hunk)),
+        )?;
+
+        for line in hunk {
+            match line {
+                Line::Same(text) => writeln!(out, " {}", text)?,
+                Line::Removed(text) => writeln!(out, "-{}", text)?,
+                Line::Added(text) => writeln!(out, "+{}", text)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// The range of a hunk, as written in its header. An empty range starts at
+// the line before it.
+fn range(before: usize, len: usize) -> String {
+    let start = if len == 0 { before } else { before + 1 };
+    format!("{},{}", start, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::comparator::ApiComparator;
+
+    #[test]
+    fn unified_diff_of_the_rendered_apis() {
+        let comparator: ApiComparator = parse_quote! {
+            {
+                pub fn a() {}
+                pub fn b(x: u8) -> (u8, u8) {}
+                pub fn c() {}
+                pub fn d() {}
+
+                pub struct S {
+                    pub x: u8,
+                    pub y: u8,
+                }
+
+                #[non_exhaustive]
+                pub enum E {
+                    A,
+                    B(u8),
+                }
+            },
+            {
+                pub fn a() {}
+                pub fn b(x: u16) -> (u8, u8) {}
+                pub fn c() {}
+                pub fn d() {}
+
+                pub struct S {
+                    pub x: u8,
+                    pub z: Vec<u8>,
+                }
+
+                #[non_exhaustive]
+                pub enum E {
+                    A,
+                    B(u8),
+                }
+
+                pub trait T: Clone {
+                    const N: usize = 1 << 2;
+                    fn f(&self) -> u8;
+                }
+            },
+        };
+
+        assert_eq!(
+            comparator.api_diff(),
+            "--- previous\n\
+             +++ current\n\
+             @@ -6,12 +6,17 @@\n \
+             \n \
+             pub struct S {\n \
+             \x20   pub x: u8,\n\
+             -    pub y: u8,\n\
+             +    pub z: Vec<u8>,\n \
+             }\n \
+             \n\
+             +pub trait T: Clone {\n\
+             +    const N: usize = 4;\n\
+             +    fn f(&self) -> u8;\n\
+             +}\n\
+             +\n \
+             pub fn a();\n \
+             \n\
+             -pub fn b(x: u8) -> (u8, u8);\n\
+             +pub fn b(x: u16) -> (u8, u8);\n \
+             \n \
+             pub fn c();\n \
+             \n"
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use std::{collections::BTreeMap, fs, io::ErrorKind, path::Path};
+
+use anyhow::{Context, Result as AnyResult};
+
+use crate::{api_diff, public_api::PublicApi};
+
+/// The file the public API is locked in, at the root of the crate.
+pub(crate) const FILE_NAME: &str = "api.lock";
+
+const HEADER: &str = "\
+// This file is generated by `cargo breaking lock`. Do not edit it by hand.
+// Run `cargo breaking lock` again to update it after changing the public API.
+";
+
+// Each item is preceded by its path in a comment, so that the file can be read
+// back item by item.
+const ITEM_PREFIX: &str = "// ";
+
+/// Writes the public API of the crate to `api.lock` in `crate_dir`.
+pub(crate) fn write(crate_dir: &Path, api: &PublicApi) -> AnyResult<()> {
+    let path = crate_dir.join(FILE_NAME);
+
+    fs::write(&path, render(api)).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// How the public API of the crate compares to the one locked in `api.lock`.
+#[derive(Debug, PartialEq)]
+pub(crate) enum LockStatus {
+    Matches,
+    /// The lock was rewritten. `diff` shows how it changed.
+    Updated {
+        diff: String,
+    },
+    /// The API changed, and the lock was left as is. `lock` is what it
+    /// should contain.
+    Outdated {
+        diff: String,
+        lock: String,
+    },
+}
+
+/// Compares the public API of the crate to the one locked in `api.lock` in
+/// `crate_dir`. With `update`, the lock is rewritten when it does not match,
+/// or when it does not exist yet.
+pub(crate) fn check(crate_dir: &Path, api: &PublicApi, update: bool) -> AnyResult<LockStatus> {
+    let path = crate_dir.join(FILE_NAME);
+
+    let locked = match fs::read_to_string(&path) {
+        Ok(lock) => parse(&lock),
+        Err(e) if update && e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+        Err(e) => {
+            return Err(e).with_context(|| {
+                format!(
+                    "Failed to read {}, run `cargo breaking lock` to create it",
+                    path.display()
+                )
+            })
+        }
+    };
+
+    let diff = api_diff::diff(&locked, &items(api), (FILE_NAME, "current"));
+
+    if diff.is_empty() {
+        Ok(LockStatus::Matches)
+    } else if update {
+        write(crate_dir, api)?;
+        Ok(LockStatus::Updated { diff })
+    } else {
+        Ok(LockStatus::Outdated {
+            diff,
+            lock: render(api),
+        })
+    }
+}
+
+/// Explains how to approve the changes of an outdated lock.
+pub(crate) fn outdated_message(diff: &str) -> String {
+    format!(
+        "The public API no longer matches {file}:\n\
+         {diff}\n\
+         Run `cargo breaking --locked-api --update-lock` and commit {file} along with the API change. \
+         The regenerated {file} was printed on the standard output. \
+         Listing {file} in CODEOWNERS makes the API owners review every API change.",
+        file = FILE_NAME,
+        diff = diff,
+    )
+}
+
+// The rendered items, sorted by path as text, which is how they are sorted
+// once read back. The other data the comparison checks follows, in sections
+// whose names can't be item paths: the probed auto traits and `no_std`
+// support, the C header, the exported symbols and the hidden macros used by
+// the public ones.
+fn items(api: &PublicApi) -> BTreeMap<String, Vec<String>> {
+    let mut items = api
+        .render()
+        .into_iter()
+        .map(|(path, lines)| (path.to_string(), lines))
+        .collect::<BTreeMap<_, _>>();
+
+    for (path, traits) in api.auto_traits() {
+        items.insert(format!("[auto traits] {}", path), traits.clone());
+    }
+
+    if let Some(no_std) = api.no_std() {
+        items.insert("[no_std]".to_owned(), vec![no_std.to_string()]);
+    }
+
+    for (name, declaration) in api.c_header() {
+        items.insert(
+            format!("[C header] {}", name),
+            vec![declaration.code().to_owned()],
+        );
+    }
+
+    if !api.symbols().is_empty() {
+        items.insert(
+            "[symbols]".to_owned(),
+            api.symbols().iter().cloned().collect(),
+        );
+    }
+
+    for (path, rules) in api.macro_helpers() {
+        items.insert(format!("[macro helper] {}", path), lines(rules));
+    }
+
+    items
+}
+
+// Empty lines are not kept when the lock is read back.
+fn lines(text: &str) -> Vec<String> {
+    text.lines()
+        .filter(|line| !line.is_empty())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+fn render(api: &PublicApi) -> String {
+    let mut output = HEADER.to_owned();
+
+    for (path, lines) in items(api) {
+        output.push('\n');
+        output.push_str(ITEM_PREFIX);
+        output.push_str(&path);
+        output.push('\n');
+
+        for line in lines {
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+fn parse(lock: &str) -> BTreeMap<String, Vec<String>> {
+    let mut items = BTreeMap::new();
+    let mut current: Option<(String, Vec<String>)> = None;
+
+    for line in lock.lines().skip(HEADER.lines().count()) {
+        match line.strip_prefix(ITEM_PREFIX) {
+            Some(path) => {
+                items.extend(current.take());
+                current = Some((path.to_owned(), Vec::new()));
+            }
+            None if line.is_empty() => {}
+            None => {
+                if let Some((_, lines)) = &mut current {
+                    lines.push(line.to_owned());
+                }
+            }
+        }
+    }
+
+    items.extend(current);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use syn::parse_quote;
+
+    #[test]
+    fn lock_round_trip() {
+        let api: PublicApi = parse_quote! {
+            pub mod a {
+                pub fn f(x: u8) {}
+            }
+
+            pub struct S {
+                pub x: u8,
+            }
+        };
+
+        let lock = render(&api);
+
+        assert_eq!(
+            lock,
+            format!(
+                "{}\n\
+                 // S\n\
+                 pub struct S {{\n\
+                 \x20   pub x: u8,\n\
+                 }}\n\
+                 \n\
+                 // a::f\n\
+                 pub fn a::f(x: u8);\n",
+                HEADER
+            )
+        );
+        assert_eq!(parse(&lock), items(&api));
+    }
+
+    #[test]
+    fn data_besides_items_is_locked() {
+        let mut api: PublicApi = parse_quote! {
+            #[macro_export]
+            macro_rules! public {
+                () => { $crate::__helper!() };
+            }
+
+            #[doc(hidden)]
+            #[macro_export]
+            macro_rules! __helper {
+                () => {};
+            }
+
+            pub fn f() -> impl Fn() {}
+        };
+        let f = api
+            .items()
+            .keys()
+            .find(|path| path.to_string() == "f")
+            .unwrap()
+            .clone();
+        api.set_auto_traits(vec![(f, vec!["Send".to_owned()])].into_iter().collect());
+        api.set_no_std(true);
+        api.set_c_header(crate::c_header::parse("void handle_free(Handle *handle);"));
+        api.set_symbols(vec!["handle_free".to_owned()].into_iter().collect());
+
+        let lock = render(&api);
+
+        for section in [
+            "// [auto traits] f\nSend\n",
+            "// [no_std]\ntrue\n",
+            "// [C header] handle_free\nvoid handle_free(Handle *handle)\n",
+            "// [symbols]\nhandle_free\n",
+            "// [macro helper] __helper\n",
+        ] {
+            assert!(lock.contains(section), "{} not in {}", section, lock);
+        }
+        assert_eq!(parse(&lock), items(&api));
+
+        let mut changed = api.clone();
+        changed.set_no_std(false);
+        assert!(
+            !api_diff::diff(&parse(&lock), &items(&changed), (FILE_NAME, "current")).is_empty()
+        );
+    }
+
+    #[test]
+    fn outdated_locks_are_updated_on_demand() {
+        let dir = std::env::temp_dir().join(format!("cb-test-lock-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let locked: PublicApi = parse_quote! {
+            pub fn f(x: u8) {}
+        };
+        let current: PublicApi = parse_quote! {
+            pub fn f(x: u16) {}
+        };
+
+        assert!(check(&dir, &current, false).is_err());
+
+        write(&dir, &locked).unwrap();
+        assert_eq!(check(&dir, &locked, false).unwrap(), LockStatus::Matches);
+
+        match check(&dir, &current, false).unwrap() {
+            LockStatus::Outdated { lock, .. } => assert_eq!(lock, render(&current)),
+            other => panic!("Unexpected status: {:?}", other),
+        }
+
+        assert!(matches!(
+            check(&dir, &current, true).unwrap(),
+            LockStatus::Updated { .. }
+        ));
+        assert_eq!(check(&dir, &current, false).unwrap(), LockStatus::Matches);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn changes_are_shown() {
+        let locked: PublicApi = parse_quote! {
+            pub fn f(x: u8) {}
+        };
+        let current: PublicApi = parse_quote! {
+            pub fn f(x: u16) {}
+        };
+
+        let diff = api_diff::diff(
+            &parse(&render(&locked)),
+            &items(&current),
+            (FILE_NAME, "current"),
+        );
+
+        assert_eq!(
+            diff,
+            "--- api.lock\n\
+             +++ current\n\
+             @@ -1,2 +1,2 @@\n\
+             -pub fn f(x: u8);\n\
+             +pub fn f(x: u16);\n \
+             \n"
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context, Result as AnyResult};
+
+use crate::git::TempCheckout;
+
+/// Unpacks a crate archive in a temporary directory. It is a gzipped
+/// tarball whose content is in a single top-level directory, such as the
+/// `.crate` files made by `cargo package`.
+pub(crate) fn unpack(archive: &Path) -> AnyResult<TempCheckout> {
+    let checkout = TempCheckout::new(PathBuf::new());
+    let dir = checkout.crate_dir();
+
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(archive)
+        .arg("-C")
+        .arg(dir)
+        .arg("--strip-components=1")
+        .status()
+        .context("Failed to run tar")?;
+
+    if !status.success() {
+        bail!("Failed to unpack {}", archive.display());
+    }
+
+    if !dir.join("Cargo.toml").is_file() {
+        bail!("{} does not contain a crate", archive.display());
+    }
+
+    Ok(checkout)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn crate_is_unpacked() {
+        let dir = env::temp_dir().join(format!("cb-test-archive-{}", process::id()));
+        fs::create_dir_all(dir.join("foo-1.0.0/src")).unwrap();
+        fs::write(dir.join("foo-1.0.0/Cargo.toml"), "[package]\n").unwrap();
+        fs::write(dir.join("foo-1.0.0/src/lib.rs"), "pub fn f() {}").unwrap();
+
+        let archive = dir.join("foo-1.0.0.crate");
+        let status = Command::new("tar")
+            .arg("-czf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(&dir)
+            .arg("foo-1.0.0")
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let checkout = unpack(&archive).unwrap();
+        assert_eq!(
+            fs::read_to_string(checkout.crate_dir().join("src/lib.rs")).unwrap(),
+            "pub fn f() {}"
+        );
+
+        drop(checkout);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_archive() {
+        assert!(unpack(Path::new("does-not-exist.crate")).is_err());
+    }
+}
@@ -1,9 +1,16 @@
+mod cfg;
+mod files;
+
 use syn::{
     parse::{Parse, ParseStream, Result as ParseResult},
-    Error as SynError, File,
+    parse_quote, Error as SynError, File, Ident, ItemMod,
 };
 
-use std::str::FromStr;
+use std::{path::Path, str::FromStr};
+
+use anyhow::{anyhow, bail, Context, Result as AnyResult};
+
+pub(crate) use cfg::CfgOptions;
 
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct CrateAst(pub File);
@@ -12,6 +19,43 @@ impl CrateAst {
     pub(crate) fn ast(&self) -> &File {
         &self.0
     }
+
+    /// Parses a crate whose modules are spread across several files.
+    ///
+    /// `root` is the path of the crate root (usually `lib.rs`), and `read`
+    /// returns the content of the file at a given path, if it exists. Items
+    /// disabled by `cfg` attributes are removed.
+    pub(crate) fn from_sources(
+        root: &Path,
+        mut read: impl FnMut(&Path) -> Option<String>,
+        cfg: &CfgOptions,
+    ) -> AnyResult<CrateAst> {
+        let code = read(root).ok_or_else(|| anyhow!("Failed to read {}", root.display()))?;
+        let mut file = syn::parse_file(&code)
+            .with_context(|| format!("Failed to parse {}", root.display()))?;
+
+        let dir = root.parent().unwrap_or_else(|| Path::new(""));
+
+        cfg.strip_disabled(&mut file.items);
+        files::inline_modules(&mut file.items, dir, cfg, &mut read)?;
+
+        if files::includes_generated_code(&file) {
+            bail!("The crate includes code generated by its build script, which is only available to the expanded backend");
+        }
+
+        Ok(CrateAst(file))
+    }
+
+    /// Returns a public module named `name` which contains the items of the
+    /// crate.
+    pub(crate) fn into_module(self, name: Ident) -> ItemMod {
+        let items = self.0.items;
+        parse_quote! {
+            pub mod #name {
+                #(#items)*
+            }
+        }
+    }
 }
 
 impl FromStr for CrateAst {
@@ -0,0 +1,366 @@
+use std::{collections::BTreeSet, env, process::Command};
+
+use anyhow::{bail, Context, Result as AnyResult};
+
+use syn::{
+    punctuated::Punctuated,
+    visit_mut::{self, VisitMut},
+    Attribute, Field, ImplItem, Item, ItemImpl, ItemMod, ItemTrait, Lit, Meta, NestedMeta,
+    TraitItem, Variant,
+};
+
+/// The configuration predicates which are considered enabled when evaluating
+/// `#[cfg(...)]` attributes.
+///
+/// `feature = "..."` predicates are enabled for the given features, and the
+/// other ones for the host configuration, when it was added with
+/// [`CfgOptions::with_host_cfgs`]. Any other predicate, such as `test` or
+/// `doc`, is considered disabled.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct CfgOptions {
+    features: BTreeSet<String>,
+    // The names and values of the enabled predicates, such as `unix` and
+    // `target_os = "linux"`.
+    cfgs: BTreeSet<(String, Option<String>)>,
+}
+
+impl CfgOptions {
+    pub(crate) fn new(features: impl IntoIterator<Item = String>) -> CfgOptions {
+        let features = features.into_iter().collect();
+        CfgOptions {
+            features,
+            cfgs: BTreeSet::new(),
+        }
+    }
+
+    /// Enables the predicates which hold on the host, as printed by
+    /// `rustc --print cfg`. Stable compilers print them too.
+    pub(crate) fn with_host_cfgs(self) -> AnyResult<CfgOptions> {
+        let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+        let output = Command::new(rustc)
+            .args(["--print", "cfg"])
+            .output()
+            .context("Failed to run `rustc --print cfg`")?;
+
+        if !output.status.success() {
+            bail!("`rustc --print cfg` failed");
+        }
+
+        Ok(self.with_cfgs(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    // Enables the predicates listed one per line, as `name` or
+    // `name="value"`.
+    fn with_cfgs(mut self, cfgs: &str) -> CfgOptions {
+        self.cfgs.extend(
+            cfgs.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(|line| match line.split_once('=') {
+                    Some((name, value)) => {
+                        (name.to_owned(), Some(value.trim_matches('"').to_owned()))
+                    }
+                    None => (line.to_owned(), None),
+                }),
+        );
+        self
+    }
+
+    /// Removes every item, field, variant and associated item whose `cfg`
+    /// attributes evaluate to false.
+    pub(crate) fn strip_disabled(&self, items: &mut Vec<Item>) {
+        items.retain(|item| self.is_enabled(item_attrs(item)));
+
+        let mut stripper = CfgStripper { options: self };
+        items
+            .iter_mut()
+            .for_each(|item| stripper.visit_item_mut(item));
+    }
+
+    fn is_enabled(&self, attrs: &[Attribute]) -> bool {
+        attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("cfg"))
+            .all(|attr| match attr.parse_meta() {
+                Ok(Meta::List(list)) if list.nested.len() == 1 => {
+                    self.eval(list.nested.first().unwrap())
+                }
+                _ => false,
+            })
+    }
+
+    fn eval(&self, predicate: &NestedMeta) -> bool {
+        match predicate {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("feature") => match &nv.lit {
+                Lit::Str(feature) => self.features.contains(&feature.value()),
+                _ => false,
+            },
+
+            NestedMeta::Meta(Meta::NameValue(nv)) => match (nv.path.get_ident(), &nv.lit) {
+                (Some(name), Lit::Str(value)) => {
+                    self.cfgs.contains(&(name.to_string(), Some(value.value())))
+                }
+                _ => false,
+            },
+
+            NestedMeta::Meta(Meta::Path(path)) => path
+                .get_ident()
+                .is_some_and(|name| self.cfgs.contains(&(name.to_string(), None))),
+
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("all") => {
+                list.nested.iter().all(|p| self.eval(p))
+            }
+
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("any") => {
+                list.nested.iter().any(|p| self.eval(p))
+            }
+
+            NestedMeta::Meta(Meta::List(list))
+                if list.path.is_ident("not") && list.nested.len() == 1 =>
+            {
+                !self.eval(list.nested.first().unwrap())
+            }
+
+            _ => false,
+        }
+    }
+}
+
+struct CfgStripper<'a> {
+    options: &'a CfgOptions,
+}
+
+impl<'a> VisitMut for CfgStripper<'a> {
+    fn visit_item_mod_mut(&mut self, i: &mut ItemMod) {
+        if let Some((_, items)) = &mut i.content {
+            items.retain(|item| self.options.is_enabled(item_attrs(item)));
+        }
+
+        visit_mut::visit_item_mod_mut(self, i);
+    }
+
+    fn visit_item_impl_mut(&mut self, i: &mut ItemImpl) {
+        i.items
+            .retain(|item| self.options.is_enabled(impl_item_attrs(item)));
+
+        visit_mut::visit_item_impl_mut(self, i);
+    }
+
+    fn visit_item_trait_mut(&mut self, i: &mut ItemTrait) {
+        i.items
+            .retain(|item| self.options.is_enabled(trait_item_attrs(item)));
+
+        visit_mut::visit_item_trait_mut(self, i);
+    }
+
+    fn visit_fields_named_mut(&mut self, i: &mut syn::FieldsNamed) {
+        retain_punctuated(&mut i.named, |field: &Field| {
+            self.options.is_enabled(&field.attrs)
+        });
+
+        visit_mut::visit_fields_named_mut(self, i);
+    }
+
+    fn visit_fields_unnamed_mut(&mut self, i: &mut syn::FieldsUnnamed) {
+        retain_punctuated(&mut i.unnamed, |field: &Field| {
+            self.options.is_enabled(&field.attrs)
+        });
+
+        visit_mut::visit_fields_unnamed_mut(self, i);
+    }
+
+    fn visit_item_enum_mut(&mut self, i: &mut syn::ItemEnum) {
+        retain_punctuated(&mut i.variants, |variant: &Variant| {
+            self.options.is_enabled(&variant.attrs)
+        });
+
+        visit_mut::visit_item_enum_mut(self, i);
+    }
+}
+
+fn retain_punctuated<T, P: Default>(items: &mut Punctuated<T, P>, f: impl Fn(&T) -> bool) {
+    *items = std::mem::take(items).into_iter().filter(f).collect();
+}
+
+fn item_attrs(item: &Item) -> &[Attribute] {
+    match item {
+        Item::Const(i) => &i.attrs,
+        Item::Enum(i) => &i.attrs,
+        Item::ExternCrate(i) => &i.attrs,
+        Item::Fn(i) => &i.attrs,
+        Item::ForeignMod(i) => &i.attrs,
+        Item::Impl(i) => &i.attrs,
+        Item::Macro(i) => &i.attrs,
+        Item::Macro2(i) => &i.attrs,
+        Item::Mod(i) => &i.attrs,
+        Item::Static(i) => &i.attrs,
+        Item::Struct(i) => &i.attrs,
+        Item::Trait(i) => &i.attrs,
+        Item::TraitAlias(i) => &i.attrs,
+        Item::Type(i) => &i.attrs,
+        Item::Union(i) => &i.attrs,
+        Item::Use(i) => &i.attrs,
+        _ => &[],
+    }
+}
+
+fn impl_item_attrs(item: &ImplItem) -> &[Attribute] {
+    match item {
+        ImplItem::Const(i) => &i.attrs,
+        ImplItem::Method(i) => &i.attrs,
+        ImplItem::Type(i) => &i.attrs,
+        ImplItem::Macro(i) => &i.attrs,
+        _ => &[],
+    }
+}
+
+fn trait_item_attrs(item: &TraitItem) -> &[Attribute] {
+    match item {
+        TraitItem::Const(i) => &i.attrs,
+        TraitItem::Method(i) => &i.attrs,
+        TraitItem::Type(i) => &i.attrs,
+        TraitItem::Macro(i) => &i.attrs,
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+    use syn::{parse_quote, File};
+
+    use super::*;
+
+    fn strip(features: &[&str], mut file: File) -> String {
+        let options = CfgOptions::new(features.iter().map(ToString::to_string));
+        options.strip_disabled(&mut file.items);
+        file.into_token_stream().to_string()
+    }
+
+    fn tokens(file: File) -> String {
+        file.into_token_stream().to_string()
+    }
+
+    #[test]
+    fn keeps_enabled_feature() {
+        let file: File = parse_quote! {
+            #[cfg(feature = "a")]
+            pub fn f() {}
+        };
+
+        assert_eq!(strip(&["a"], file.clone()), tokens(file));
+    }
+
+    #[test]
+    fn removes_disabled_feature() {
+        let file = parse_quote! {
+            #[cfg(feature = "a")]
+            pub fn f() {}
+        };
+
+        assert_eq!(strip(&[], file), "");
+    }
+
+    #[test]
+    fn evaluates_combinators() {
+        let file = parse_quote! {
+            #[cfg(all(feature = "a", not(feature = "b")))]
+            pub fn f() {}
+
+            #[cfg(any(feature = "b", feature = "c"))]
+            pub fn g() {}
+        };
+
+        let expected = parse_quote! {
+            #[cfg(all(feature = "a", not(feature = "b")))]
+            pub fn f() {}
+        };
+
+        assert_eq!(strip(&["a"], file), tokens(expected));
+    }
+
+    #[test]
+    fn strips_nested_items_fields_and_variants() {
+        let file = parse_quote! {
+            pub mod m {
+                #[cfg(feature = "a")]
+                pub fn f() {}
+            }
+
+            pub struct S {
+                #[cfg(feature = "a")]
+                pub a: u8,
+            }
+
+            pub enum E {
+                #[cfg(feature = "a")]
+                A,
+            }
+
+            impl S {
+                #[cfg(feature = "a")]
+                pub fn f() {}
+            }
+        };
+
+        let expected = parse_quote! {
+            pub mod m {}
+
+            pub struct S {}
+
+            pub enum E {}
+
+            impl S {}
+        };
+
+        assert_eq!(strip(&[], file), tokens(expected));
+    }
+
+    #[test]
+    fn evaluates_host_cfgs() {
+        let options = CfgOptions::new(Vec::new()).with_cfgs(
+            "debug_assertions\nunix\ntarget_os=\"linux\"\ntarget_pointer_width=\"64\"\n",
+        );
+
+        let mut file: File = parse_quote! {
+            #[cfg(unix)]
+            pub fn unix() {}
+
+            #[cfg(not(unix))]
+            pub fn not_unix() {}
+
+            #[cfg(target_os = "linux")]
+            pub fn linux() {}
+
+            #[cfg(target_os = "windows")]
+            pub fn windows() {}
+
+            #[cfg(all(target_pointer_width = "64", not(doc)))]
+            pub fn wide() {}
+        };
+        options.strip_disabled(&mut file.items);
+
+        let expected = parse_quote! {
+            #[cfg(unix)]
+            pub fn unix() {}
+
+            #[cfg(target_os = "linux")]
+            pub fn linux() {}
+
+            #[cfg(all(target_pointer_width = "64", not(doc)))]
+            pub fn wide() {}
+        };
+
+        assert_eq!(tokens(file), tokens(expected));
+    }
+
+    #[test]
+    fn unknown_predicates_are_disabled() {
+        let file = parse_quote! {
+            #[cfg(test)]
+            pub fn f() {}
+        };
+
+        assert_eq!(strip(&[], file), "");
+    }
+}
@@ -0,0 +1,286 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result as AnyResult};
+use syn::{
+    visit::{self, Visit},
+    AttrStyle, Block, File, ImplItemMacro, Item, ItemMacro, ItemMod, Lit, Macro, Meta,
+    MetaNameValue, TraitItemMacro,
+};
+
+use super::CfgOptions;
+
+/// Replaces every out-of-line module declaration (`mod foo;`) by the content
+/// of the file it refers to, following the same lookup rules as rustc.
+///
+/// `dir` is the directory in which the submodules of the items are looked
+/// for, and `read` returns the content of a file, if it exists. Items of the
+/// inlined files which are disabled by `cfg` are removed before their own
+/// submodules are looked for.
+pub(crate) fn inline_modules(
+    items: &mut [Item],
+    dir: &Path,
+    cfg: &CfgOptions,
+    read: &mut dyn FnMut(&Path) -> Option<String>,
+) -> AnyResult<()> {
+    for item in items {
+        if let Item::Mod(module) = item {
+            inline_module(module, dir, cfg, read)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn inline_module(
+    module: &mut ItemMod,
+    dir: &Path,
+    cfg: &CfgOptions,
+    read: &mut dyn FnMut(&Path) -> Option<String>,
+) -> AnyResult<()> {
+    let name = module.ident.to_string();
+
+    if let Some((_, items)) = &mut module.content {
+        return inline_modules(items, &dir.join(&name), cfg, read);
+    }
+
+    let candidates = match path_attribute(module) {
+        Some(path) => vec![dir.join(path)],
+        None => vec![
+            dir.join(format!("{}.rs", name)),
+            dir.join(&name).join("mod.rs"),
+        ],
+    };
+
+    let (file_path, code) = candidates
+        .iter()
+        .find_map(|path| read(path).map(|code| (path, code)))
+        .ok_or_else(|| anyhow!("Failed to find the file for module `{}`", name))?;
+
+    let file = syn::parse_file(&code)
+        .with_context(|| format!("Failed to parse {}", file_path.display()))?;
+
+    let File {
+        attrs, mut items, ..
+    } = file;
+
+    let submodules_dir = if file_path.ends_with("mod.rs") || path_attribute(module).is_some() {
+        file_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    } else {
+        dir.join(&name)
+    };
+
+    cfg.strip_disabled(&mut items);
+    inline_modules(&mut items, &submodules_dir, cfg, read)?;
+
+    // The inner attributes of the file (such as `#![doc(hidden)]`) apply to
+    // the module itself.
+    module.attrs.extend(attrs.into_iter().map(|mut attr| {
+        attr.style = AttrStyle::Outer;
+        attr
+    }));
+
+    module.content = Some((Default::default(), items));
+    module.semi = None;
+
+    Ok(())
+}
+
+/// Returns whether the items include code generated by the build script,
+/// such as `include!(concat!(env!("OUT_DIR"), "/generated.rs"))`. It only
+/// exists once the crate has been built.
+pub(crate) fn includes_generated_code(file: &File) -> bool {
+    struct IncludeVisitor(bool);
+
+    impl IncludeVisitor {
+        fn check(&mut self, mac: &Macro) {
+            self.0 |= mac.path.is_ident("include") && mac.tokens.to_string().contains("OUT_DIR");
+        }
+    }
+
+    // Includes in function bodies don't add items to the API.
+    impl<'ast> Visit<'ast> for IncludeVisitor {
+        fn visit_item_macro(&mut self, item: &'ast ItemMacro) {
+            self.check(&item.mac);
+            visit::visit_item_macro(self, item);
+        }
+
+        fn visit_impl_item_macro(&mut self, item: &'ast ImplItemMacro) {
+            self.check(&item.mac);
+        }
+
+        fn visit_trait_item_macro(&mut self, item: &'ast TraitItemMacro) {
+            self.check(&item.mac);
+        }
+
+        fn visit_block(&mut self, _: &'ast Block) {}
+    }
+
+    let mut visitor = IncludeVisitor(false);
+    visitor.visit_file(file);
+    visitor.0
+}
+
+fn path_attribute(module: &ItemMod) -> Option<PathBuf> {
+    module.attrs.iter().find_map(|attr| {
+        if !attr.path.is_ident("path") {
+            return None;
+        }
+
+        match attr.parse_meta().ok()? {
+            Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(path),
+                ..
+            }) => Some(PathBuf::from(path.value())),
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use quote::ToTokens;
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn inline(root: &str, files: &[(&str, &str)]) -> AnyResult<File> {
+        let files = files
+            .iter()
+            .map(|(path, code)| (PathBuf::from(path), code.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        let mut file = syn::parse_file(root).unwrap();
+        inline_modules(
+            &mut file.items,
+            Path::new(""),
+            &CfgOptions::default(),
+            &mut |path| files.get(path).cloned(),
+        )?;
+
+        Ok(file)
+    }
+
+    fn assert_same_tokens(left: File, right: File) {
+        assert_eq!(
+            left.into_token_stream().to_string(),
+            right.into_token_stream().to_string()
+        );
+    }
+
+    #[test]
+    fn inlines_sibling_file() {
+        let file = inline("pub mod foo;", &[("foo.rs", "pub fn f() {}")]).unwrap();
+
+        let expected = parse_quote! {
+            pub mod foo {
+                pub fn f() {}
+            }
+        };
+
+        assert_same_tokens(file, expected);
+    }
+
+    #[test]
+    fn inlines_mod_rs_and_nested_modules() {
+        let file = inline(
+            "pub mod foo;",
+            &[
+                ("foo/mod.rs", "pub mod bar;"),
+                ("foo/bar.rs", "pub struct S;"),
+            ],
+        )
+        .unwrap();
+
+        let expected = parse_quote! {
+            pub mod foo {
+                pub mod bar {
+                    pub struct S;
+                }
+            }
+        };
+
+        assert_same_tokens(file, expected);
+    }
+
+    #[test]
+    fn inlines_nested_modules_of_non_mod_rs_files() {
+        let file = inline(
+            "pub mod foo;",
+            &[("foo.rs", "pub mod bar;"), ("foo/bar.rs", "pub struct S;")],
+        )
+        .unwrap();
+
+        let expected = parse_quote! {
+            pub mod foo {
+                pub mod bar {
+                    pub struct S;
+                }
+            }
+        };
+
+        assert_same_tokens(file, expected);
+    }
+
+    #[test]
+    fn follows_path_attribute() {
+        let file = inline(
+            r#"#[path = "other.rs"] pub mod foo;"#,
+            &[("other.rs", "pub fn f() {}")],
+        )
+        .unwrap();
+
+        let expected = parse_quote! {
+            #[path = "other.rs"]
+            pub mod foo {
+                pub fn f() {}
+            }
+        };
+
+        assert_same_tokens(file, expected);
+    }
+
+    #[test]
+    fn skips_disabled_nested_declarations() {
+        let file = inline(
+            "pub mod foo;",
+            &[("foo.rs", r#"#[cfg(feature = "a")] pub mod bar;"#)],
+        )
+        .unwrap();
+
+        let expected = parse_quote! {
+            pub mod foo {}
+        };
+
+        assert_same_tokens(file, expected);
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        assert!(inline("pub mod foo;", &[]).is_err());
+    }
+
+    #[test]
+    fn generated_code_is_detected() {
+        let generated = parse_quote! {
+            pub mod generated {
+                include!(concat!(env!("OUT_DIR"), "/generated.rs"));
+            }
+        };
+        let in_body = parse_quote! {
+            pub fn f() -> &'static str {
+                include!(concat!(env!("OUT_DIR"), "/version.rs"))
+            }
+        };
+
+        assert!(includes_generated_code(&generated));
+        assert!(!includes_generated_code(&in_body));
+        assert!(!includes_generated_code(
+            &parse_quote! { include!("other.rs"); }
+        ));
+    }
+}
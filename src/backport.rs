@@ -0,0 +1,169 @@
+use anyhow::{anyhow, bail, Context, Result as AnyResult};
+use semver::Version;
+
+use crate::{comparator::SemverBump, config::BackportConfig, git::CrateRepo};
+
+/// Checks that the changes made on a release branch are allowed there.
+///
+/// The current code is compared against the latest tag of its release line,
+/// and the resulting change must not exceed what the branch allows.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct BackportGuard {
+    branch: String,
+    tag: String,
+    allowed: SemverBump,
+}
+
+impl BackportGuard {
+    pub(crate) fn new(
+        repo: &CrateRepo,
+        config: &BackportConfig,
+        version: &Version,
+    ) -> AnyResult<BackportGuard> {
+        let branch = repo
+            .current_branch()
+            .context("Failed to get the current branch")?;
+
+        let tags = repo.tag_names()?;
+        let tag = latest_release_line_tag(tags.iter().map(String::as_str), version)
+            .ok_or_else(|| {
+                anyhow!(
+                    "No tag found for the {}.{} release line",
+                    version.major,
+                    version.minor
+                )
+            })?
+            .to_owned();
+
+        let allowed = config.allowed_bump(&branch);
+
+        Ok(BackportGuard {
+            branch,
+            tag,
+            allowed,
+        })
+    }
+
+    /// The tag the current code is compared against.
+    pub(crate) fn tag(&self) -> &str {
+        self.tag.as_str()
+    }
+
+    pub(crate) fn check(&self, bump: SemverBump) -> AnyResult<()> {
+        if bump > self.allowed && self.allowed == SemverBump::None {
+            bail!(
+                "`{}` does not accept API changes, but the code contains {} changes since `{}`",
+                self.branch,
+                bump,
+                self.tag,
+            );
+        }
+
+        if bump > self.allowed {
+            bail!(
+                "`{}` only accepts {} changes, but the code contains {} changes since `{}`",
+                self.branch,
+                self.allowed,
+                bump,
+                self.tag,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the tag of the latest release sharing the major and minor numbers
+/// of `version`. Tags may be prefixed with `v`, and pre-releases are ignored.
+fn latest_release_line_tag<'a>(
+    tags: impl Iterator<Item = &'a str>,
+    version: &Version,
+) -> Option<&'a str> {
+    tags.filter_map(|tag| {
+        let release = Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()?;
+        Some((release, tag))
+    })
+    .filter(|(release, _)| {
+        release.major == version.major && release.minor == version.minor && release.pre.is_empty()
+    })
+    .max_by(|(a, _), (b, _)| a.cmp(b))
+    .map(|(_, tag)| tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard(allowed: SemverBump) -> BackportGuard {
+        BackportGuard {
+            branch: "release-1.2".to_owned(),
+            tag: "v1.2.3".to_owned(),
+            allowed,
+        }
+    }
+
+    #[test]
+    fn latest_tag_of_the_release_line() {
+        let tags = ["v1.1.9", "v1.2.0", "v1.2.10", "v1.2.2", "v1.3.0", "foo"];
+
+        assert_eq!(
+            latest_release_line_tag(tags.iter().copied(), &Version::new(1, 2, 4)),
+            Some("v1.2.10")
+        );
+    }
+
+    #[test]
+    fn unprefixed_tags() {
+        let tags = ["0.4.1", "0.4.2-rc.1"];
+
+        assert_eq!(
+            latest_release_line_tag(tags.iter().copied(), &Version::new(0, 4, 1)),
+            Some("0.4.1")
+        );
+    }
+
+    #[test]
+    fn no_tag_on_the_release_line() {
+        let tags = ["v1.1.0", "v2.2.0"];
+
+        assert_eq!(
+            latest_release_line_tag(tags.iter().copied(), &Version::new(1, 2, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn patch_changes_are_accepted() {
+        assert!(guard(SemverBump::Patch).check(SemverBump::Patch).is_ok());
+    }
+
+    #[test]
+    fn additions_are_denied_on_patch_branches() {
+        assert_eq!(
+            guard(SemverBump::Patch)
+                .check(SemverBump::Minor)
+                .unwrap_err()
+                .to_string(),
+            "`release-1.2` only accepts patch changes, but the code contains minor changes since `v1.2.3`"
+        );
+    }
+
+    #[test]
+    fn breaking_changes_are_denied_on_minor_branches() {
+        let guard = guard(SemverBump::Minor);
+
+        assert!(guard.check(SemverBump::Minor).is_ok());
+        assert!(guard.check(SemverBump::Major).is_err());
+    }
+
+    #[test]
+    fn frozen_branches_deny_any_api_change() {
+        let guard = guard(SemverBump::None);
+
+        assert!(guard.check(SemverBump::None).is_ok());
+        assert_eq!(
+            guard.check(SemverBump::Patch).unwrap_err().to_string(),
+            "`release-1.2` does not accept API changes, but the code contains patch changes since `v1.2.3`"
+        );
+    }
+}
@@ -0,0 +1,389 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result as AnyResult};
+use proc_macro2::Span;
+use syn::Ident;
+
+use crate::{diagnosis::DiagnosisItem, manifest, public_api::ItemPath, rules::RuleId};
+
+/// What a declaration of a C header declares.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CItemKind {
+    Function,
+    Struct,
+    Union,
+    Enum,
+    Typedef,
+    Variable,
+}
+
+impl Display for CItemKind {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let name = match self {
+            CItemKind::Function => "function",
+            CItemKind::Struct => "struct",
+            CItemKind::Union => "union",
+            CItemKind::Enum => "enum",
+            CItemKind::Typedef => "typedef",
+            CItemKind::Variable => "variable",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// A declaration of a C header, written on a single line.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CDeclaration {
+    kind: CItemKind,
+    code: String,
+}
+
+impl CDeclaration {
+    pub(crate) fn code(&self) -> &str {
+        self.code.as_str()
+    }
+}
+
+/// Generates the C header of the crate located in `dir` with cbindgen, then
+/// returns its declarations by name. The `cbindgen.toml` file of the crate is
+/// used if there is one, but the header is always generated in C.
+pub(crate) fn extract(dir: &Path) -> AnyResult<BTreeMap<String, CDeclaration>> {
+    manifest::check_c_library(dir)?;
+
+    let output = Command::new("cbindgen")
+        .args(["--lang", "c", "--quiet"])
+        .arg(dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to run cbindgen. It can be installed with `cargo install cbindgen`")?;
+
+    if !output.status.success() {
+        bail!(
+            "cbindgen failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let header = String::from_utf8(output.stdout).context("cbindgen yielded invalid UTF-8")?;
+
+    Ok(parse(&header))
+}
+
+/// Reads the declarations of a header generated by cbindgen. The preprocessor
+/// directives and the comments are skipped.
+pub(crate) fn parse(header: &str) -> BTreeMap<String, CDeclaration> {
+    split_statements(&strip(header))
+        .iter()
+        .filter_map(|statement| declaration(statement))
+        .collect()
+}
+
+// Removes the comments and the preprocessor directives, along with the
+// `extern "C" {` block of the headers which can be included from C++.
+fn strip(header: &str) -> String {
+    let mut code = String::new();
+    let mut rest = header;
+
+    while let Some(start) = rest.find("/*") {
+        code.push_str(&rest[..start]);
+        rest = rest[start..]
+            .find("*/")
+            .map_or("", |end| &rest[start + end + 2..]);
+    }
+    code.push_str(rest);
+
+    code.lines()
+        .map(|line| line.split("//").next().unwrap_or_default())
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .replace("extern \"C\" {", "")
+}
+
+// Splits the code at each `;` which is not inside braces, and collapses the
+// whitespace of each statement.
+fn split_statements(code: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+
+    for c in code.chars() {
+        match c {
+            '{' => depth += 1,
+            // The closing brace of an `extern "C"` block.
+            '}' if depth == 0 => continue,
+            '}' => depth -= 1,
+            ';' if depth == 0 => {
+                statements.push(normalize(&current));
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+
+        current.push(c);
+    }
+
+    statements.retain(|statement| !statement.is_empty());
+    statements
+}
+
+fn normalize(statement: &str) -> String {
+    statement
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace(" ,", ",")
+        .replace(" ;", ";")
+}
+
+// Returns the name and the declaration of a statement, if it declares
+// something.
+fn declaration(statement: &str) -> Option<(String, CDeclaration)> {
+    // The size of an array is not a name.
+    let named = statement.split('[').next().unwrap_or_default();
+    let words = named
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+
+    let aggregate = |keyword: &str| match keyword {
+        "struct" => Some(CItemKind::Struct),
+        "union" => Some(CItemKind::Union),
+        "enum" => Some(CItemKind::Enum),
+        _ => None,
+    };
+
+    let (kind, name) = match words.as_slice() {
+        // `typedef struct Point { ... } Point`, or an opaque
+        // `typedef struct Handle Handle`.
+        ["typedef", keyword, .., name] if aggregate(keyword).is_some() => {
+            (aggregate(keyword)?, *name)
+        }
+        // `typedef void (*Callback)(int32_t)`.
+        ["typedef", ..] if statement.contains("(*") => {
+            let pointer = &statement[statement.find("(*")? + 2..];
+            (CItemKind::Typedef, &pointer[..pointer.find(')')?])
+        }
+        ["typedef", .., name] => (CItemKind::Typedef, *name),
+        // `struct Point { ... }`, as generated with the `tag` style.
+        [keyword, name, ..] if aggregate(keyword).is_some() && statement.ends_with('}') => {
+            (aggregate(keyword)?, *name)
+        }
+        _ if statement.contains('(') => {
+            let before_args = &statement[..statement.find('(')?];
+            let name = before_args
+                .rsplit(|c: char| !c.is_alphanumeric() && c != '_')
+                .find(|word| !word.is_empty())?;
+            (CItemKind::Function, name)
+        }
+        [.., name] => (CItemKind::Variable, *name),
+        [] => return None,
+    };
+
+    let declaration = CDeclaration {
+        kind,
+        code: statement.to_owned(),
+    };
+
+    Some((name.to_owned(), declaration))
+}
+
+/// Finds the declarations of the C header which were removed, modified or
+/// added. They are reported at the path of the Rust item of the same name,
+/// which `#[no_mangle]` items usually are at the crate root.
+pub(crate) fn changes(
+    previous: &BTreeMap<String, CDeclaration>,
+    current: &BTreeMap<String, CDeclaration>,
+) -> Vec<DiagnosisItem> {
+    let path = |name: &str| ItemPath::from_segments(vec![Ident::new(name, Span::call_site())]);
+    let diagnosis = |rule, name: &str, kind: CItemKind| {
+        DiagnosisItem::new(rule, path(name), None).with_message(format!("C {}", kind))
+    };
+
+    let removals_and_modifications =
+        previous
+            .iter()
+            .filter_map(|(name, previous)| match current.get(name) {
+                None => Some(
+                    diagnosis(RuleId::CItemRemoved, name, previous.kind)
+                        .with_signatures(Some(previous.code.clone()), None),
+                ),
+                Some(current) if current != previous => Some(
+                    diagnosis(RuleId::CItemModified, name, current.kind)
+                        .with_signatures(Some(previous.code.clone()), Some(current.code.clone())),
+                ),
+                Some(_) => None,
+            });
+
+    let additions = current
+        .iter()
+        .filter(|(name, _)| !previous.contains_key(*name))
+        .map(|(name, current)| {
+            diagnosis(RuleId::CItemAdded, name, current.kind)
+                .with_signatures(None, Some(current.code.clone()))
+        });
+
+    removals_and_modifications.chain(additions).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = r#"
+#include <stdarg.h>
+#include <stdint.h>
+
+#define MAX_LEN 16
+
+typedef void (*Callback)(int32_t code);
+
+/**
+ * A color.
+ */
+typedef enum Color {
+  Red,
+  Green,
+} Color;
+
+typedef struct Handle Handle;
+
+typedef struct Point {
+  int32_t x;
+  int32_t y;
+} Point;
+
+typedef uint32_t Id;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+extern const int32_t VERSION;
+
+extern uint8_t BUFFER[16];
+
+// Creates a handle.
+Handle *handle_new(const char *name, struct Point origin);
+
+void handle_free(Handle *handle);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+"#;
+
+    fn declarations(header: &str) -> Vec<(String, CItemKind, String)> {
+        parse(header)
+            .into_iter()
+            .map(|(name, decl)| (name, decl.kind, decl.code))
+            .collect()
+    }
+
+    #[test]
+    fn header_declarations() {
+        assert_eq!(
+            declarations(HEADER),
+            [
+                (
+                    "BUFFER".to_owned(),
+                    CItemKind::Variable,
+                    "extern uint8_t BUFFER[16]".to_owned()
+                ),
+                (
+                    "Callback".to_owned(),
+                    CItemKind::Typedef,
+                    "typedef void (*Callback)(int32_t code)".to_owned()
+                ),
+                (
+                    "Color".to_owned(),
+                    CItemKind::Enum,
+                    "typedef enum Color { Red, Green, } Color".to_owned()
+                ),
+                (
+                    "Handle".to_owned(),
+                    CItemKind::Struct,
+                    "typedef struct Handle Handle".to_owned()
+                ),
+                (
+                    "Id".to_owned(),
+                    CItemKind::Typedef,
+                    "typedef uint32_t Id".to_owned()
+                ),
+                (
+                    "Point".to_owned(),
+                    CItemKind::Struct,
+                    "typedef struct Point { int32_t x; int32_t y; } Point".to_owned()
+                ),
+                (
+                    "VERSION".to_owned(),
+                    CItemKind::Variable,
+                    "extern const int32_t VERSION".to_owned()
+                ),
+                (
+                    "handle_free".to_owned(),
+                    CItemKind::Function,
+                    "void handle_free(Handle *handle)".to_owned()
+                ),
+                (
+                    "handle_new".to_owned(),
+                    CItemKind::Function,
+                    "Handle *handle_new(const char *name, struct Point origin)".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn tag_style_declarations() {
+        assert_eq!(
+            declarations("struct Point {\n  int32_t x;\n};\n\nvoid f(struct Point p);\n"),
+            [
+                (
+                    "Point".to_owned(),
+                    CItemKind::Struct,
+                    "struct Point { int32_t x; }".to_owned()
+                ),
+                (
+                    "f".to_owned(),
+                    CItemKind::Function,
+                    "void f(struct Point p)".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn header_changes() {
+        let previous = parse(
+            "typedef struct Point { int32_t x; } Point;\n\
+             void f(int32_t a);\n\
+             void g(void);\n",
+        );
+        let current = parse(
+            "typedef struct Point { int32_t x; int32_t y; } Point;\n\
+             void f(int32_t a);\n\
+             int32_t h(void);\n",
+        );
+
+        let changes = changes(&previous, &current)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            ["≠ Point (C struct)", "- g (C function)", "+ h (C function)"]
+        );
+    }
+}
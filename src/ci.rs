@@ -0,0 +1,76 @@
+use std::env;
+
+/// The CI environments whose merge requests can be detected, along with the
+/// variable holding the branch they target.
+const BASE_REF_VARIABLES: &[(&str, &str)] = &[
+    ("GitHub Actions", "GITHUB_BASE_REF"),
+    ("GitLab CI", "CI_MERGE_REQUEST_TARGET_BRANCH_NAME"),
+];
+
+/// The branch targeted by the pull request being built, when running in a
+/// supported CI environment.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct BaseRef {
+    pub environment: &'static str,
+    pub branch: String,
+}
+
+impl BaseRef {
+    pub(crate) fn detect() -> Option<BaseRef> {
+        BaseRef::from_env(|name| env::var(name).ok())
+    }
+
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Option<BaseRef> {
+        // The variables are set, but empty, for builds which are not
+        // triggered by a pull request.
+        BASE_REF_VARIABLES.iter().find_map(|(environment, name)| {
+            var(name)
+                .filter(|branch| !branch.is_empty())
+                .map(|branch| BaseRef {
+                    environment,
+                    branch,
+                })
+        })
+    }
+
+    /// The reference to compare against. CI checkouts usually don't create
+    /// a local branch for the target branch, only the remote one.
+    pub(crate) fn revision(&self) -> String {
+        format!("origin/{}", self.branch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base_ref(vars: &[(&str, &str)]) -> Option<BaseRef> {
+        BaseRef::from_env(|name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| (*value).to_owned())
+        })
+    }
+
+    #[test]
+    fn github_pull_request() {
+        let base = base_ref(&[("GITHUB_BASE_REF", "main")]).unwrap();
+
+        assert_eq!(base.environment, "GitHub Actions");
+        assert_eq!(base.revision(), "origin/main");
+    }
+
+    #[test]
+    fn gitlab_merge_request() {
+        let base = base_ref(&[("CI_MERGE_REQUEST_TARGET_BRANCH_NAME", "develop")]).unwrap();
+
+        assert_eq!(base.environment, "GitLab CI");
+        assert_eq!(base.revision(), "origin/develop");
+    }
+
+    #[test]
+    fn push_builds_have_no_base() {
+        assert_eq!(base_ref(&[("GITHUB_BASE_REF", "")]), None);
+        assert_eq!(base_ref(&[]), None);
+    }
+}
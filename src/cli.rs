@@ -1,7 +1,72 @@
-use clap::{crate_authors, crate_description, crate_name, crate_version, App, Arg};
+use std::path::PathBuf;
+
+use clap::{crate_authors, crate_description, crate_name, crate_version, App, Arg, SubCommand};
+
+use crate::{
+    glue::{Backend, CheckoutStrategy, Lockfile},
+    hook::HookCommand,
+    public_api::PathPrefix,
+    release::ReleaseConfig,
+    report::OutputFormat,
+};
+
+/// How `cargo breaking self-test` behaves.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SelfTestConfig {
+    /// The corpus file. The default corpus is used if there is none.
+    pub corpus: Option<PathBuf>,
+}
+
+/// The reference to compare against when neither the command line, the
+/// configuration nor the CI environment give one.
+pub(crate) const DEFAULT_AGAINST: &str = "main";
+
+/// The target `--probe-no-std` builds for when none is given. It has no `std`
+/// and is commonly installed for embedded development.
+const DEFAULT_NO_STD_TARGET: &str = "thumbv7em-none-eabi";
 
 pub(crate) struct ProgramConfig {
-    pub comparaison_ref: String,
+    pub comparaison_refs: Vec<String>,
+    pub against_repo: Option<String>,
+    pub against_archive: Option<PathBuf>,
+    pub current_archive: Option<PathBuf>,
+    pub explain: Option<String>,
+    pub verbose: bool,
+    pub include_private: bool,
+    pub display_build_output: bool,
+    pub install_nightly: bool,
+    pub checkout: CheckoutStrategy,
+    pub no_stash: bool,
+    pub backend: Backend,
+    pub lockfile: Lockfile,
+    pub bin: Option<String>,
+    pub examples: Vec<String>,
+    pub verify_backends: bool,
+    pub format: OutputFormat,
+    pub stats: bool,
+    pub pedantic: bool,
+    pub macro_internals: bool,
+    pub probe_auto_traits: bool,
+    pub no_std_target: Option<String>,
+    pub c_header: bool,
+    pub symbols: bool,
+    pub wasm_bindgen: bool,
+    pub pyo3: bool,
+    pub serde: bool,
+    pub display_examples: bool,
+    pub foreign_depth: Option<usize>,
+    pub doctor: bool,
+    pub restore: bool,
+    pub package_verify: bool,
+    pub lock_api: bool,
+    pub locked_api: bool,
+    pub update_lock: bool,
+    pub release: Option<ReleaseConfig>,
+    pub hook: Option<HookCommand>,
+    pub self_test: Option<SelfTestConfig>,
+    pub backport: bool,
+    pub check_registry: bool,
+    pub path_filters: Vec<PathPrefix>,
 }
 
 impl ProgramConfig {
@@ -17,14 +82,437 @@ impl ProgramConfig {
             .arg(
                 Arg::with_name("against")
                     .short("a")
-                    .help("Sets the git reference to compare the API against. Can be a tag, a branch name or a commit.")
+                    .help("Sets the git reference to compare the API against. Can be a tag, a branch name or a commit. Can be given several times to print, for each reference, the kind of change the current code is relative to it. Defaults to the `against` setting of the configuration, then to the target branch of the pull request when running in GitHub Actions or GitLab CI, then to main.")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .required(false)
+            )
+            .arg(
+                Arg::with_name("against_repo")
+                    .long("against-repo")
+                    .help("Compares against a revision of another repository, given as <url>#<ref>. The revision defaults to HEAD.")
+                    .takes_value(true)
+                    .value_name("URL#REF")
+                    .required(false)
+            )
+            .arg(
+                Arg::with_name("against_archive")
+                    .long("against-archive")
+                    .help("Compares against a crate archive, such as the .crate files made by `cargo package`.")
+                    .takes_value(true)
+                    .value_name("PATH")
+                    .conflicts_with("against_repo")
+            )
+            .arg(
+                Arg::with_name("current_archive")
+                    .long("current-archive")
+                    .help("Reads the current version of the crate from a crate archive, such as the .crate files made by `cargo package`, instead of the working tree.")
+                    .takes_value(true)
+                    .value_name("PATH")
+                    .conflicts_with("verify_backends")
+            )
+            .arg(
+                Arg::with_name("explain")
+                    .long("explain")
+                    .help("Prints the description, severity and audience of a diagnosis rule, then exits.")
                     .takes_value(true)
+                    .value_name("RULE")
                     .required(false)
-                    .default_value("main")
+            )
+            .arg(
+                Arg::with_name("verbose")
+                    .short("v")
+                    .long("verbose")
+                    .help("Prints additional details for each diagnosis, such as the list of items of a moved module.")
+            )
+            .arg(
+                Arg::with_name("include_private")
+                    .long("include-private")
+                    .help("Also compares the items which are not reachable from outside of the crate. Their changes are informational and don't affect the next version.")
+            )
+            .arg(
+                Arg::with_name("display_build_output")
+                    .long("display-build-output")
+                    .help("Prints the output of the builds while they run, each line being prefixed with [previous] or [next].")
+            )
+            .arg(
+                Arg::with_name("install_nightly")
+                    .long("install-nightly")
+                    .help("Installs the nightly toolchain with rustup if it is missing.")
+            )
+            .arg(
+                Arg::with_name("checkout")
+                    .long("checkout")
+                    .help("Sets how the code to compare against is checked out: in the working tree (in-place, stashing local changes) or in a temporary directory (temp-dir). Defaults to temp-dir on Windows and in-place elsewhere.")
+                    .takes_value(true)
+                    .possible_values(CheckoutStrategy::VARIANTS)
+            )
+            .arg(
+                Arg::with_name("no_stash")
+                    .long("no-stash")
+                    .help("Fails instead of stashing the local changes when the reference is checked out in place.")
+                    .conflicts_with("autostash")
+            )
+            .arg(
+                Arg::with_name("autostash")
+                    .long("autostash")
+                    .help("Stashes the local changes while the reference is checked out in place, and restores them afterwards. This is the default.")
+            )
+            .arg(
+                Arg::with_name("backend")
+                    .long("backend")
+                    .help("Sets how the code of the crate is read: expanded with the nightly compiler (expanded), which sees the items generated by macros, or parsed from the source files (source), which does not need any toolchain.")
+                    .takes_value(true)
+                    .possible_values(Backend::VARIANTS)
+                    .default_value("expanded")
+            )
+            .arg(
+                Arg::with_name("bin")
+                    .long("bin")
+                    .help("Compares the public items of the given binary instead of the library.")
+                    .takes_value(true)
+                    .value_name("NAME")
+            )
+            .arg(
+                Arg::with_name("include_target")
+                    .long("include-target")
+                    .help("Also compares the public items of an example, given as examples/<name>, as if they were in the examples module of the crate. Can be given several times.")
+                    .takes_value(true)
+                    .value_name("TARGET")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .validator(|s| parse_example(&s).map(drop))
+            )
+            .arg(
+                Arg::with_name("locked")
+                    .long("locked")
+                    .help("Builds each version with its own Cargo.lock, when it has one, and fails if it needs to be updated.")
+            )
+            .arg(
+                Arg::with_name("frozen")
+                    .long("frozen")
+                    .help("Same as --locked, without accessing the network.")
+            )
+            .arg(
+                Arg::with_name("verify_backends")
+                    .long("verify-backends")
+                    .help("Compares the crate with each backend, then prints the diagnostics which are emitted by one of them only. Fails if there is any. The backend argument is ignored.")
+            )
+            .arg(
+                Arg::with_name("format")
+                    .long("format")
+                    .help("Sets the output format.")
+                    .takes_value(true)
+                    .possible_values(OutputFormat::VARIANTS)
+                    .default_value("text")
+            )
+            .arg(
+                Arg::with_name("stats")
+                    .long("stats")
+                    .help("Prints the number of public items of each kind, and how many of them changed.")
+            )
+            .arg(
+                Arg::with_name("pedantic")
+                    .long("pedantic")
+                    .help("Also reports the observable differences which don't change the API, such as `#[inline]` hints, moved impl blocks or the provided trait methods an implementation overrides. They don't affect the next version.")
+            )
+            .arg(
+                Arg::with_name("macro_internals")
+                    .long("macro-internals")
+                    .help("Also reports the removed or modified helper macros: the exported macros which are `#[doc(hidden)]` or defined in a `__private` module. The expansion of the macros of the previous version may rely on them.")
+            )
+            .arg(
+                Arg::with_name("probe_auto_traits")
+                    .long("probe-auto-traits")
+                    .help("Also builds a crate depending on each version, to check whether the opaque types returned by the `-> impl Trait` and `async fn` functions are still `Send` and `Sync`. These auto traits leak through opaque types whatever their bounds say.")
+            )
+            .arg(
+                Arg::with_name("probe_no_std")
+                    .long("probe-no-std")
+                    .help("Also builds a `#![no_std]` crate depending on each version without its default features, to check whether the crate still supports `no_std`. The probe is built for the target given by --no-std-target, which must be installed.")
+            )
+            .arg(
+                Arg::with_name("no_std_target")
+                    .long("no-std-target")
+                    .help("Sets the target without `std` which --probe-no-std builds for. Defaults to thumbv7em-none-eabi.")
+                    .takes_value(true)
+                    .requires("probe_no_std")
+            )
+            .arg(
+                Arg::with_name("c_header")
+                    .long("c-header")
+                    .help("Also generates the C header of each version with cbindgen, and compares its functions, structs, enums and other declarations. Only supported for `cdylib` and `staticlib` crates. cbindgen must be installed.")
+            )
+            .arg(
+                Arg::with_name("symbols")
+                    .long("symbols")
+                    .help("Also builds the `cdylib` or `staticlib` library of each version, and compares the symbols it exports, including the `#[no_mangle]` functions generated by macros. Requires nm.")
+            )
+            .arg(
+                Arg::with_name("wasm_bindgen")
+                    .long("wasm-bindgen")
+                    .help("Also compares the functions, classes, enums, methods and properties exported to JavaScript by the `#[wasm_bindgen]` attributes of the crate. They are read from the source files.")
+            )
+            .arg(
+                Arg::with_name("pyo3")
+                    .long("pyo3")
+                    .help("Also compares the functions, classes, methods and attributes exported to Python by the `#[pyfunction]`, `#[pyclass]` and `#[pymethods]` attributes of the crate. They are read from the source files.")
+            )
+            .arg(
+                Arg::with_name("serde")
+                    .long("serde")
+                    .help("Also reports the changes which break the compatibility of serialized data for the public types deriving `Serialize` or `Deserialize`: renamed fields and variants, and reordered tuple struct fields. The serde attributes are read from the source files.")
+            )
+            .arg(
+                Arg::with_name("display_examples")
+                    .long("display-examples")
+                    .help("Also reports the changes of the `Display` outputs asserted by the examples of the documentation of the public types, such as `assert_eq!(v.to_string(), \"1.2\")`. The examples are read from the source files.")
+            )
+            .arg(
+                Arg::with_name("follow_reexports")
+                    .long("follow-reexports")
+                    .help("Compares the items re-exported from the dependencies of the crate, such as `pub use other_crate::*`, as if the crate defined them. The dependencies are read from their source code, and their own re-exports are not followed. Same as --foreign-depth 1.")
+                    .conflicts_with("foreign_depth")
+            )
+            .arg(
+                Arg::with_name("foreign_depth")
+                    .long("foreign-depth")
+                    .help("How many levels of dependencies the re-exported items are followed into: 0 follows nothing, 1 the direct re-exports, and more the re-exports of the dependencies themselves. Overrides the `foreign-depth` setting of the configuration file.")
+                    .takes_value(true)
+                    .value_name("DEPTH")
+                    .validator(|s| s.parse::<usize>().map(drop).map_err(|e| e.to_string()))
+            )
+            .arg(
+                Arg::with_name("backport")
+                    .long("backport")
+                    .help("Compares against the latest tag of the release line of the crate version, and fails if the change is not allowed on the current branch. Only patch-level changes are allowed unless the configuration file says otherwise.")
+                    .conflicts_with_all(&["against_repo", "against_archive"])
+            )
+            .arg(
+                Arg::with_name("check_registry")
+                    .long("check-registry")
+                    .help("Warns when crates.io already has the guessed next version, in addition to the warning given when a git tag has it, and suggests the next free version.")
+            )
+            .arg(
+                Arg::with_name("locked_api")
+                    .long("locked-api")
+                    .help("Checks that the public API of the crate matches the one written in api.lock by `cargo breaking lock`, instead of comparing it against a reference. Otherwise, prints the regenerated api.lock and fails with the difference.")
+            )
+            .arg(
+                Arg::with_name("update_lock")
+                    .long("update-lock")
+                    .help("With --locked-api, rewrites api.lock when the public API no longer matches it, and prints how it changed, instead of failing.")
+                    .requires("locked_api")
+            )
+            .arg(
+                Arg::with_name("path_filter")
+                    .long("path-filter")
+                    .help("Only compares the items located in the given module or item, such as api::v2. Can be given several times.")
+                    .takes_value(true)
+                    .value_name("PREFIX")
+                    .multiple(true)
+                    .number_of_values(1)
+                    .validator(|s| s.parse::<PathPrefix>().map(drop).map_err(|e| e.to_string()))
+            )
+            .subcommand(
+                SubCommand::with_name("doctor")
+                    .about("Checks the toolchain, the git repository and the crate manifest, without building anything.")
+            )
+            .subcommand(
+                SubCommand::with_name("restore")
+                    .about("Restores the local changes stashed by a run which was interrupted, and switches back to their branch.")
+            )
+            .subcommand(
+                SubCommand::with_name("package-verify")
+                    .about("Packages the crate with `cargo package`, and compares the API of the archive against the API of the working tree. Fails if items are lost when the crate is published, for instance because of the include or exclude settings of the manifest.")
+            )
+            .subcommand(
+                SubCommand::with_name("lock")
+                    .about("Writes the public API of the crate to api.lock, to be checked in and checked with --locked-api.")
+            )
+            .subcommand(
+                SubCommand::with_name("release")
+                    .about("Compares the crate, then sets its version to the next one, adds a section to CHANGELOG.md if it exists, and creates a signed release commit and tag. Only prints what would be done unless --execute is given.")
+                    .arg(
+                        Arg::with_name("execute")
+                            .long("execute")
+                            .help("Makes the release instead of printing what would be done.")
+                    )
+                    .arg(
+                        Arg::with_name("no_sign")
+                            .long("no-sign")
+                            .help("Creates an unsigned commit and an annotated tag.")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("self-test")
+                    .about("Downloads two adjacent published versions of each crate of a corpus, and compares them. Fails if a comparison panics or fails, or does not find the change or the rules the corpus expects. Meant to catch the regressions of cargo-breaking itself.")
+                    .arg(
+                        Arg::with_name("corpus")
+                            .long("corpus")
+                            .help("Compares the crates listed in the given file, or a few popular crates if no file is given.")
+                            .takes_value(true)
+                            .value_name("FILE")
+                            .min_values(0)
+                            .required(true)
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("install-hook")
+                    .about("Installs a pre-push git hook which prints the API changes of the crate, compared against the references given with -a, before pushing. The push is never blocked.")
+                    .arg(
+                        Arg::with_name("remove")
+                            .long("remove")
+                            .help("Removes the hook installed by cargo-breaking instead.")
+                    )
             ).get_matches();
 
-        let comparaison_ref = matches.value_of("against").unwrap().to_owned();
+        let comparaison_refs = matches
+            .values_of("against")
+            .map_or_else(Vec::new, |refs| refs.map(ToOwned::to_owned).collect());
+        let against_repo = matches.value_of("against_repo").map(ToOwned::to_owned);
+        let against_archive = matches.value_of("against_archive").map(PathBuf::from);
+        let current_archive = matches.value_of("current_archive").map(PathBuf::from);
+        let explain = matches.value_of("explain").map(ToOwned::to_owned);
+        let verbose = matches.is_present("verbose");
+        let include_private = matches.is_present("include_private");
+        let display_build_output = matches.is_present("display_build_output");
+        let install_nightly = matches.is_present("install_nightly");
+        let checkout = matches
+            .value_of("checkout")
+            .map_or(CheckoutStrategy::DEFAULT, |s| s.parse().unwrap());
+        let no_stash = matches.is_present("no_stash");
+        // Validated by clap.
+        let backend = matches.value_of("backend").unwrap().parse().unwrap();
+        let bin = matches.value_of("bin").map(ToOwned::to_owned);
+        // Validated by clap.
+        let examples = matches
+            .values_of("include_target")
+            .map_or_else(Vec::new, |targets| {
+                targets
+                    .map(|target| parse_example(target).unwrap())
+                    .collect()
+            });
+        let lockfile = if matches.is_present("frozen") {
+            Lockfile::Frozen
+        } else if matches.is_present("locked") {
+            Lockfile::Locked
+        } else {
+            Lockfile::Unlocked
+        };
+        let verify_backends = matches.is_present("verify_backends");
+        // Validated by clap.
+        let format = matches.value_of("format").unwrap().parse().unwrap();
+        let stats = matches.is_present("stats");
+        let pedantic = matches.is_present("pedantic");
+        let macro_internals = matches.is_present("macro_internals");
+        let probe_auto_traits = matches.is_present("probe_auto_traits");
+        let no_std_target = matches.is_present("probe_no_std").then(|| {
+            matches
+                .value_of("no_std_target")
+                .unwrap_or(DEFAULT_NO_STD_TARGET)
+                .to_owned()
+        });
+        let c_header = matches.is_present("c_header");
+        let symbols = matches.is_present("symbols");
+        let wasm_bindgen = matches.is_present("wasm_bindgen");
+        let pyo3 = matches.is_present("pyo3");
+        let serde = matches.is_present("serde");
+        let display_examples = matches.is_present("display_examples");
+        let foreign_depth = if matches.is_present("follow_reexports") {
+            Some(1)
+        } else {
+            matches
+                .value_of("foreign_depth")
+                .map(|s| s.parse().unwrap())
+        };
+        let doctor = matches.subcommand_matches("doctor").is_some();
+        let restore = matches.subcommand_matches("restore").is_some();
+        let package_verify = matches.subcommand_matches("package-verify").is_some();
+        let lock_api = matches.subcommand_matches("lock").is_some();
+        let locked_api = matches.is_present("locked_api");
+        let update_lock = matches.is_present("update_lock");
+        let release = matches
+            .subcommand_matches("release")
+            .map(|release| ReleaseConfig {
+                execute: release.is_present("execute"),
+                sign: !release.is_present("no_sign"),
+            });
+        let hook = matches.subcommand_matches("install-hook").map(|hook| {
+            if hook.is_present("remove") {
+                HookCommand::Remove
+            } else {
+                HookCommand::Install
+            }
+        });
+        let self_test = matches
+            .subcommand_matches("self-test")
+            .map(|self_test| SelfTestConfig {
+                corpus: self_test.value_of("corpus").map(PathBuf::from),
+            });
+        let backport = matches.is_present("backport");
+        let check_registry = matches.is_present("check_registry");
+        // Validated by clap.
+        let path_filters = matches
+            .values_of("path_filter")
+            .map_or_else(Vec::new, |filters| {
+                filters.map(|filter| filter.parse().unwrap()).collect()
+            });
+
+        ProgramConfig {
+            comparaison_refs,
+            against_repo,
+            against_archive,
+            current_archive,
+            explain,
+            verbose,
+            include_private,
+            display_build_output,
+            install_nightly,
+            checkout,
+            no_stash,
+            backend,
+            lockfile,
+            bin,
+            examples,
+            verify_backends,
+            format,
+            stats,
+            pedantic,
+            macro_internals,
+            probe_auto_traits,
+            no_std_target,
+            c_header,
+            symbols,
+            wasm_bindgen,
+            pyo3,
+            serde,
+            display_examples,
+            foreign_depth,
+            doctor,
+            restore,
+            package_verify,
+            lock_api,
+            locked_api,
+            update_lock,
+            release,
+            hook,
+            self_test,
+            backport,
+            check_registry,
+            path_filters,
+        }
+    }
+}
 
-        ProgramConfig { comparaison_ref }
+/// Parses an `--include-target` value. Only examples can be included.
+fn parse_example(target: &str) -> Result<String, String> {
+    match target.strip_prefix("examples/") {
+        Some(name) if !name.is_empty() => Ok(name.to_owned()),
+        _ => Err(format!(
+            "`{}` is not an example, expected examples/<name>",
+            target
+        )),
     }
 }
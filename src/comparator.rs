@@ -1,10 +1,23 @@
+mod auto_traits;
+mod deref_methods;
+mod generic_uses;
+mod impact;
+mod macro_helpers;
+mod method_transitions;
+mod module_moves;
+mod pedantic;
+mod widening;
+
+pub(crate) use impact::ImpactEdge;
+
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, BTreeSet},
     fmt::{Display, Formatter, Result as FmtResult},
-    hash::Hash,
+    iter,
 };
 
 use semver::{BuildMetadata, Prerelease, Version};
+use serde::{Deserialize, Serialize};
 
 use syn::{
     braced,
@@ -13,50 +26,613 @@ use syn::{
 };
 
 use crate::{
-    diagnosis::{DiagnosisCollector, DiagnosisItem, DiagnosticGenerator},
-    public_api::PublicApi,
+    api_diff, c_header,
+    diagnosis::{
+        DiagnosisCollector, DiagnosisItem, DiagnosisItemKind, Diagnostic, DiagnosticGenerator,
+    },
+    display_examples, js_exports,
+    metrics::ApiMetrics,
+    public_api::{display_code, ItemKind, ItemPath, ItemPattern, PathPrefix, PublicApi},
+    py_exports,
+    rules::{RuleId, Severity},
+    serde_compat, symbols,
 };
 
 pub struct ApiComparator {
     previous: PublicApi,
     current: PublicApi,
+    pedantic: bool,
+    macro_internals: bool,
+    extensions: ExtensionPolicy,
+    const_values: ConstValuePolicy,
+    downstream_lints: DownstreamLintPolicy,
+    widening: bool,
+    stability: StabilityTiers,
 }
 
 impl ApiComparator {
     pub(crate) fn new(previous: PublicApi, current: PublicApi) -> ApiComparator {
-        ApiComparator { previous, current }
+        ApiComparator {
+            previous,
+            current,
+            pedantic: false,
+            macro_internals: false,
+            extensions: ExtensionPolicy::default(),
+            const_values: ConstValuePolicy::default(),
+            downstream_lints: DownstreamLintPolicy::default(),
+            widening: false,
+            stability: StabilityTiers::default(),
+        }
+    }
+
+    /// Also reports the observable differences which don't change the API.
+    pub(crate) fn with_pedantic(mut self, pedantic: bool) -> ApiComparator {
+        self.pedantic = pedantic;
+        self
+    }
+
+    /// Also reports the changes of the hidden helper macros, which the
+    /// expansion of the public macros may rely on.
+    pub(crate) fn with_macro_internals(mut self, macro_internals: bool) -> ApiComparator {
+        self.macro_internals = macro_internals;
+        self
+    }
+
+    /// Sets how the changes which only extend the API affect the next
+    /// version.
+    pub(crate) fn with_extensions(mut self, extensions: ExtensionPolicy) -> ApiComparator {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Sets whether the changes of the values of associated constants are
+    /// breaking.
+    pub(crate) fn with_const_values(mut self, const_values: ConstValuePolicy) -> ApiComparator {
+        self.const_values = const_values;
+        self
+    }
+
+    /// Sets how the changes which give new warnings to the code using the
+    /// items affect the next version.
+    pub(crate) fn with_downstream_lints(
+        mut self,
+        downstream_lints: DownstreamLintPolicy,
+    ) -> ApiComparator {
+        self.downstream_lints = downstream_lints;
+        self
+    }
+
+    /// Reports the parameters whose numeric type was widened with a specific
+    /// rule.
+    pub(crate) fn with_widening(mut self, widening: bool) -> ApiComparator {
+        self.widening = widening;
+        self
+    }
+
+    /// Sets the stability tier of the modules and items, which decides how
+    /// their changes affect the next version.
+    pub(crate) fn with_stability(mut self, stability: StabilityTiers) -> ApiComparator {
+        self.stability = stability;
+        self
+    }
+
+    /// Links the changed types and traits to the public items which mention
+    /// them.
+    pub(crate) fn impact(&self, diagnostics: &ApiCompatibilityDiagnostics) -> Vec<ImpactEdge> {
+        impact::edges(diagnostics, &self.previous, &self.current)
+    }
+
+    /// Returns the previous and the current signature of the functions and
+    /// methods whose modification is reported.
+    pub(crate) fn signature_changes(
+        &self,
+        diagnostics: &ApiCompatibilityDiagnostics,
+    ) -> BTreeMap<ItemPath, (String, String)> {
+        diagnostics
+            .items()
+            .filter(|diag| diag.kind() == DiagnosisItemKind::Modification)
+            .filter_map(|diag| match diag.signatures() {
+                (Some(previous), Some(current)) if previous != current => Some((
+                    diag.path().clone(),
+                    (previous.to_owned(), current.to_owned()),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the unified diff of both APIs, rendered as pseudo-Rust.
+    pub(crate) fn api_diff(&self) -> String {
+        api_diff::render(&self.previous, &self.current)
+    }
+
+    pub(crate) fn metrics(&self) -> ApiMetrics {
+        ApiMetrics::new(&self.previous, &self.current)
     }
 
     pub fn run(&self) -> ApiCompatibilityDiagnostics {
         let mut collector = DiagnosisCollector::new();
 
-        self.item_removals(&mut collector);
+        let module_moves = module_moves::find(self.previous.items(), self.current.items());
+        module_moves.iter().for_each(|move_| {
+            let is_private = move_
+                .previous_paths()
+                .all(|path| self.previous.is_private(path));
+
+            collect(is_private, &mut collector, |collector| {
+                move_.diagnosis(collector)
+            })
+        });
+
+        // The methods moved between an inherent impl block and a trait
+        // implementation are reported once, instead of being removed from
+        // one and added to the other.
+        let transitions = method_transitions::find(self.previous.items(), self.current.items());
+        let absorbed_impls = method_transitions::absorbed_impls(
+            &transitions,
+            self.previous.items(),
+            self.current.items(),
+        );
+
+        let moved_previous = module_moves
+            .iter()
+            .flat_map(|move_| move_.previous_paths())
+            .chain(
+                transitions
+                    .iter()
+                    .filter(|transition| transition.is_to_trait())
+                    .map(|transition| transition.method_path()),
+            )
+            .collect();
+        let moved_current = module_moves
+            .iter()
+            .flat_map(|move_| move_.current_paths())
+            .chain(
+                transitions
+                    .iter()
+                    .filter(|transition| !transition.is_to_trait())
+                    .map(|transition| transition.method_path()),
+            )
+            .collect();
+
+        self.unanalyzable_items(&mut collector);
+        self.item_removals(&moved_previous, &mut collector);
         self.item_modifications(&mut collector);
-        self.item_additions(&mut collector);
-
-        let mut diags = collector.finalize();
+        self.method_transitions(&transitions, &mut collector);
+        self.deref_changes(&mut collector);
+        self.macro_path_changes(&mut collector);
+        self.pedantic_changes(&mut collector);
+        self.macro_helper_changes(&mut collector);
+        self.auto_trait_changes(&mut collector);
+        self.no_std_changes(&mut collector);
+        self.edition_changes(&mut collector);
+        self.c_header_changes(&mut collector);
+        self.symbol_changes(&mut collector);
+        self.js_export_changes(&mut collector);
+        self.py_export_changes(&mut collector);
+        self.serde_changes(&mut collector);
+        self.display_example_changes(&mut collector);
+        self.visibility_changes(&mut collector);
+        self.item_additions(&moved_current, &mut collector);
+
+        let mut diags = self
+            .with_generic_uses(collector.finalize())
+            .into_iter()
+            .filter(|diag| {
+                diag.rule() == RuleId::UnanalyzableItem || !self.is_unanalyzable(diag.path())
+            })
+            .filter(|diag| match diag.trait_impl() {
+                Some(trait_name) => !absorbed_impls.iter().any(|(path, absorbed, kind)| {
+                    path == diag.path() && absorbed == trait_name && *kind == diag.kind()
+                }),
+                None => true,
+            })
+            .map(|diag| {
+                let diag = self.classify_widening(diag);
+                let aliases = self.aliases(diag.path());
+                let diag = self.extensions.apply(diag.with_aliases(aliases));
+                let diag = self.const_values.apply(diag);
+                let diag = self.downstream_lints.apply(diag);
+                let diag = self.annotate(self.stability.apply(diag));
+                self.with_signatures(diag)
+            })
+            .collect::<Vec<_>>();
         diags.sort();
 
         ApiCompatibilityDiagnostics { diags }
     }
 
-    fn item_removals(&self, diagnosis_collector: &mut DiagnosisCollector) {
+    // The items mentioning a type whose number of generic arguments changed
+    // are affected too. Their own modification, if any, is explained, and the
+    // other ones are added.
+    fn with_generic_uses(&self, mut diags: Vec<DiagnosisItem>) -> Vec<DiagnosisItem> {
+        let uses = generic_uses::find(self.previous.items(), self.current.items());
+
+        for (path, message) in uses {
+            if self.current.is_private(&path) {
+                continue;
+            }
+
+            let modification = diags.iter().position(|diag| {
+                diag.rule() == RuleId::ItemModified
+                    && diag.path() == &path
+                    && diag.trait_impl().is_none()
+                    && diag.message().is_none()
+            });
+
+            match modification {
+                Some(idx) => {
+                    let diag = diags.remove(idx);
+                    diags.push(diag.with_message(message));
+                }
+                None => diags.push(
+                    DiagnosisItem::new(RuleId::GenericCountChangedUse, path, None)
+                        .with_message(message),
+                ),
+            }
+        }
+
+        diags
+    }
+
+    // The breaking changes of the items annotated as intentionally broken in
+    // the current version are labeled with the reason of the annotation.
+    fn annotate(&self, diag: DiagnosisItem) -> DiagnosisItem {
+        if diag.severity() != Severity::Breaking {
+            return diag;
+        }
+
+        let reason = iter::once(diag.path())
+            .chain(&self.aliases(diag.path()))
+            .find_map(|path| self.current.breaking_reason(path))
+            .map(ToOwned::to_owned);
+
+        match reason {
+            Some(reason) => diag.into_annotated(reason),
+            None => diag,
+        }
+    }
+
+    fn with_signatures(&self, diag: DiagnosisItem) -> DiagnosisItem {
+        // The changes of the C header come with their declarations.
+        if diag.trait_impl().is_some() || diag.has_signatures() {
+            return diag;
+        }
+
+        let signature = |api: &PublicApi| {
+            api.items()
+                .get(diag.path())
+                .and_then(ItemKind::signature)
+                .map(display_code)
+        };
+        let (previous, current) = (signature(&self.previous), signature(&self.current));
+
+        diag.with_signatures(previous, current)
+    }
+
+    // The modifications of functions and methods whose only change is the
+    // widening of parameter types.
+    fn classify_widening(&self, diag: DiagnosisItem) -> DiagnosisItem {
+        if !self.widening || diag.rule() != RuleId::ItemModified || diag.message().is_some() {
+            return diag;
+        }
+
+        let previous = self.previous.items().get(diag.path());
+        let current = self.current.items().get(diag.path());
+
+        let widenings = match (
+            previous.and_then(ItemKind::signature),
+            current.and_then(ItemKind::signature),
+        ) {
+            (Some(previous), Some(current)) => widening::parameter_widenings(previous, current),
+            _ => None,
+        };
+
+        match widenings {
+            Some(widenings) => {
+                DiagnosisItem::new(RuleId::ParameterWidened, diag.path().clone(), None)
+                    .with_message(widenings)
+            }
+            None => diag,
+        }
+    }
+
+    // The other paths of an item, in either version.
+    fn aliases(&self, path: &ItemPath) -> Vec<ItemPath> {
+        let mut aliases = self
+            .previous
+            .aliases(path)
+            .iter()
+            .chain(self.current.aliases(path))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        aliases.sort();
+        aliases.dedup();
+
+        aliases
+    }
+
+    fn item_removals(
+        &self,
+        moved: &BTreeSet<&ItemPath>,
+        diagnosis_collector: &mut DiagnosisCollector,
+    ) {
         map_difference(self.previous.items(), self.current.items())
-            .for_each(|(path, kind)| kind.removal_diagnosis(path, diagnosis_collector))
+            .filter(|(path, _)| !moved.contains(path))
+            .for_each(|(path, kind)| {
+                collect(
+                    self.previous.is_private(path),
+                    diagnosis_collector,
+                    |collector| kind.removal_diagnosis(path, collector),
+                )
+            })
     }
 
     fn item_modifications(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        // The visibility changes are reported on their own, the rest of the
+        // item is still compared. It only matters to the public users if it
+        // is public in both versions.
         map_modifications(self.previous.items(), self.current.items()).for_each(
             |(path, kind_a, kind_b)| {
-                kind_a.modification_diagnosis(kind_b, path, diagnosis_collector)
+                collect(
+                    self.previous.is_private(path) || self.current.is_private(path),
+                    diagnosis_collector,
+                    |collector| kind_a.modification_diagnosis(kind_b, path, collector),
+                )
             },
         )
     }
 
-    fn item_additions(&self, diagnosis_collector: &mut DiagnosisCollector) {
+    fn method_transitions(
+        &self,
+        transitions: &[method_transitions::MethodTransition],
+        diagnosis_collector: &mut DiagnosisCollector,
+    ) {
+        transitions.iter().for_each(|transition| {
+            let is_private = if transition.is_to_trait() {
+                self.previous.is_private(transition.method_path())
+            } else {
+                self.current.is_private(transition.method_path())
+            };
+
+            collect(is_private, diagnosis_collector, |collector| {
+                transition.diagnosis(collector)
+            })
+        })
+    }
+
+    fn deref_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        deref_methods::find(self.previous.items(), self.current.items())
+            .iter()
+            .for_each(|unreachable| {
+                collect(
+                    self.current.is_private(unreachable.type_path()),
+                    diagnosis_collector,
+                    |collector| unreachable.diagnosis(collector),
+                )
+            })
+    }
+
+    // Macros are imported by path, as in `use crate::macros::m;`. The other
+    // paths of an item are only displayed, but losing one of them breaks the
+    // imports of a macro.
+    fn macro_path_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        self.previous
+            .items()
+            .iter()
+            .filter(|(path, kind)| {
+                kind.as_macro().is_some()
+                    && self
+                        .current
+                        .items()
+                        .get(path)
+                        .and_then(ItemKind::as_macro)
+                        .is_some()
+            })
+            .for_each(|(path, _)| {
+                let removed = self
+                    .previous
+                    .aliases(path)
+                    .iter()
+                    .filter(|alias| !self.current.aliases(path).contains(alias))
+                    .map(|alias| format!("`{}`", alias))
+                    .collect::<Vec<_>>();
+
+                if !removed.is_empty() {
+                    diagnosis_collector.add(
+                        DiagnosisItem::new(RuleId::MacroPathRemoved, path.clone(), None)
+                            .with_message(format!(
+                                "no longer importable as {}",
+                                removed.join(", ")
+                            )),
+                    );
+                }
+            })
+    }
+
+    fn pedantic_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        if !self.pedantic {
+            return;
+        }
+
+        pedantic::changes(self.previous.items(), self.current.items())
+            .into_iter()
+            .for_each(|diag| {
+                collect(
+                    self.current.is_private(diag.path()),
+                    diagnosis_collector,
+                    |collector| collector.add(diag),
+                )
+            })
+    }
+
+    fn macro_helper_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        if !self.macro_internals {
+            return;
+        }
+
+        macro_helpers::changes(self.previous.macro_helpers(), self.current.macro_helpers())
+            .into_iter()
+            .for_each(|diag| diagnosis_collector.add(diag))
+    }
+
+    // The items which could not be analyzed in either version. Their changes
+    // are not reported, so that they don't show up as removed or added.
+    fn unanalyzable_items(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        // The reason given for the current version wins.
+        let unanalyzable = self
+            .previous
+            .unanalyzable()
+            .iter()
+            .chain(self.current.unanalyzable())
+            .collect::<BTreeMap<_, _>>();
+
+        unanalyzable.into_iter().for_each(|(path, reason)| {
+            diagnosis_collector.add(
+                DiagnosisItem::new(RuleId::UnanalyzableItem, path.clone(), None)
+                    .with_message(reason.as_str()),
+            )
+        })
+    }
+
+    fn is_unanalyzable(&self, path: &ItemPath) -> bool {
+        self.previous.unanalyzable().contains_key(path)
+            || self.current.unanalyzable().contains_key(path)
+    }
+
+    fn auto_trait_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        auto_traits::changes(self.previous.auto_traits(), self.current.auto_traits())
+            .into_iter()
+            .for_each(|diag| diagnosis_collector.add(diag))
+    }
+
+    fn c_header_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        c_header::changes(self.previous.c_header(), self.current.c_header())
+            .into_iter()
+            .for_each(|diag| diagnosis_collector.add(diag))
+    }
+
+    fn symbol_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        symbols::changes(self.previous.symbols(), self.current.symbols())
+            .into_iter()
+            .for_each(|diag| diagnosis_collector.add(diag))
+    }
+
+    fn js_export_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        js_exports::changes(self.previous.js_exports(), self.current.js_exports())
+            .into_iter()
+            .for_each(|diag| diagnosis_collector.add(diag))
+    }
+
+    fn py_export_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        py_exports::changes(self.previous.py_exports(), self.current.py_exports())
+            .into_iter()
+            .for_each(|diag| diagnosis_collector.add(diag))
+    }
+
+    fn serde_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        serde_compat::changes(self.previous.serde_types(), self.current.serde_types())
+            .into_iter()
+            .for_each(|diag| diagnosis_collector.add(diag))
+    }
+
+    fn display_example_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        display_examples::changes(
+            self.previous.display_examples(),
+            self.current.display_examples(),
+        )
+        .into_iter()
+        .for_each(|diag| diagnosis_collector.add(diag))
+    }
+
+    fn no_std_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        if let (Some(true), Some(false)) = (self.previous.no_std(), self.current.no_std()) {
+            diagnosis_collector.add(
+                DiagnosisItem::new(
+                    RuleId::StdRequired,
+                    ItemPath::from_segments(Vec::new()),
+                    None,
+                )
+                .with_message("now requires `std`"),
+            );
+        }
+    }
+
+    fn edition_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        if let (Some(previous), Some(current)) = (self.previous.edition(), self.current.edition()) {
+            if previous != current {
+                diagnosis_collector.add(
+                    DiagnosisItem::new(
+                        RuleId::EditionChanged,
+                        ItemPath::from_segments(Vec::new()),
+                        None,
+                    )
+                    .with_message(format!("edition changed from {} to {}", previous, current)),
+                );
+            }
+        }
+    }
+
+    // Only relevant when private items are extracted: the item exists in both
+    // versions, but it is reachable from outside of the crate in only one of
+    // them.
+    fn visibility_changes(&self, diagnosis_collector: &mut DiagnosisCollector) {
+        self.previous
+            .items()
+            .keys()
+            .filter(|path| self.current.items().contains_key(path))
+            .for_each(|path| {
+                let diagnosis = match (
+                    self.previous.is_private(path),
+                    self.current.is_private(path),
+                ) {
+                    (false, true) => DiagnosisItem::new(RuleId::ItemRemoved, path.clone(), None)
+                        .with_message("no longer public"),
+                    (true, false) => DiagnosisItem::new(RuleId::ItemAdded, path.clone(), None)
+                        .with_message("now public"),
+                    _ => return,
+                };
+
+                diagnosis_collector.add(diagnosis);
+            })
+    }
+
+    fn item_additions(
+        &self,
+        moved: &BTreeSet<&ItemPath>,
+        diagnosis_collector: &mut DiagnosisCollector,
+    ) {
         map_difference(self.current.items(), self.previous.items())
-            .for_each(|(path, kind)| kind.addition_diagnosis(path, diagnosis_collector))
+            .filter(|(path, _)| !moved.contains(path))
+            .for_each(|(path, kind)| {
+                collect(
+                    self.current.is_private(path),
+                    diagnosis_collector,
+                    |collector| kind.addition_diagnosis(path, collector),
+                )
+            })
+    }
+}
+
+/// Runs `generate`, reporting the diagnoses it emits as changes to private
+/// items if `is_private` is true.
+fn collect(
+    is_private: bool,
+    collector: &mut DiagnosisCollector,
+    generate: impl FnOnce(&mut DiagnosisCollector),
+) {
+    if !is_private {
+        return generate(collector);
     }
+
+    let mut private_collector = DiagnosisCollector::new();
+    generate(&mut private_collector);
+
+    private_collector
+        .finalize()
+        .into_iter()
+        .for_each(|diag| collector.add(diag.into_private()));
 }
 
 impl Parse for ApiComparator {
@@ -77,16 +653,185 @@ impl Parse for ApiComparator {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(transparent)]
 pub struct ApiCompatibilityDiagnostics {
     diags: Vec<DiagnosisItem>,
 }
 
+/// How the changes which extend the API in ways existing code can't observe,
+/// such as a variant added to a `#[non_exhaustive]` enum, affect the next
+/// version.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ExtensionPolicy {
+    /// They are additions, like any other.
+    #[default]
+    Minor,
+    /// They are invisible, and don't require a new minor version.
+    Patch,
+}
+
+impl ExtensionPolicy {
+    const RULES: &'static [RuleId] =
+        &[RuleId::NonExhaustiveExtended, RuleId::DefaultedGenericAdded];
+
+    fn apply(self, diag: DiagnosisItem) -> DiagnosisItem {
+        if self == ExtensionPolicy::Patch && ExtensionPolicy::RULES.contains(&diag.rule()) {
+            diag.with_severity(Severity::Informational)
+        } else {
+            diag
+        }
+    }
+}
+
+/// How the changes of the values of associated constants affect the next
+/// version. They break the code which relies on the value at compile time,
+/// such as array lengths or patterns, but many constants are only read at
+/// runtime.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ConstValuePolicy {
+    #[default]
+    Breaking,
+    /// They are reported, but don't affect the next version.
+    Informational,
+}
+
+impl ConstValuePolicy {
+    fn apply(self, diag: DiagnosisItem) -> DiagnosisItem {
+        if self == ConstValuePolicy::Informational && diag.rule() == RuleId::AssocConstValueModified
+        {
+            diag.with_severity(Severity::Informational)
+        } else {
+            diag
+        }
+    }
+}
+
+/// How the `#[deprecated]` and `#[must_use]` attributes added to items affect
+/// the next version. The code using the items only gets new warnings, unless
+/// it is built with `-D warnings`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DownstreamLintPolicy {
+    /// They are reported, but don't affect the next version.
+    Informational,
+    #[default]
+    Minor,
+    /// For crates whose users deny warnings.
+    Breaking,
+}
+
+impl DownstreamLintPolicy {
+    // `#[non_exhaustive]` gives errors rather than warnings, it is always
+    // breaking.
+    const RULES: &'static [RuleId] = &[RuleId::DeprecationAdded, RuleId::MustUseAdded];
+
+    fn apply(self, diag: DiagnosisItem) -> DiagnosisItem {
+        if !DownstreamLintPolicy::RULES.contains(&diag.rule()) {
+            return diag;
+        }
+
+        match self {
+            DownstreamLintPolicy::Informational => diag.with_severity(Severity::Informational),
+            DownstreamLintPolicy::Minor => diag,
+            DownstreamLintPolicy::Breaking => diag.with_severity(Severity::Breaking),
+        }
+    }
+}
+
+/// The stability tier of the items, each listed with the paths of the modules
+/// or items it contains. The items which are not listed are stable, and the
+/// most specific path wins.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct StabilityTiers {
+    pub stable: Vec<PathPrefix>,
+    /// Their breaking changes only require a new minor version.
+    pub preview: Vec<PathPrefix>,
+    /// Their changes don't affect the next version.
+    pub internal: Vec<PathPrefix>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StabilityTier {
+    Stable,
+    Preview,
+    Internal,
+}
+
+impl StabilityTiers {
+    /// Adds the paths of `other` to the ones of `self`.
+    pub(crate) fn extend(&mut self, other: StabilityTiers) {
+        self.stable.extend(other.stable);
+        self.preview.extend(other.preview);
+        self.internal.extend(other.internal);
+    }
+
+    fn tier(&self, path: &ItemPath) -> StabilityTier {
+        let tiers = [
+            (StabilityTier::Stable, &self.stable),
+            (StabilityTier::Preview, &self.preview),
+            (StabilityTier::Internal, &self.internal),
+        ];
+
+        tiers
+            .iter()
+            .flat_map(|(tier, prefixes)| prefixes.iter().map(move |prefix| (*tier, prefix)))
+            .filter(|(_, prefix)| prefix.matches(path))
+            .max_by_key(|(_, prefix)| prefix.depth())
+            .map_or(StabilityTier::Stable, |(tier, _)| tier)
+    }
+
+    fn apply(&self, diag: DiagnosisItem) -> DiagnosisItem {
+        match (self.tier(diag.path()), diag.severity()) {
+            (StabilityTier::Preview, Severity::Breaking) => diag.with_severity(Severity::Minor),
+            (StabilityTier::Internal, _) => diag.with_severity(Severity::Informational),
+            _ => diag,
+        }
+    }
+}
+
+/// The part of the version number which has to be incremented.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SemverBump {
+    /// The API did not change.
+    None,
+    /// The API changed in ways which don't affect the code using it.
+    Patch,
+    /// Items were added.
+    Minor,
+    /// Existing code may break.
+    #[serde(alias = "breaking")]
+    Major,
+}
+
+impl Display for SemverBump {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let name = match self {
+            SemverBump::Major => "breaking",
+            SemverBump::Minor => "minor",
+            SemverBump::Patch => "patch",
+            SemverBump::None => "none",
+        };
+
+        f.write_str(name)
+    }
+}
+
 impl Display for ApiCompatibilityDiagnostics {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        self.diags
-            .iter()
-            .try_for_each(|diag| writeln!(f, "{}", diag))
+        if f.alternate() {
+            self.diags
+                .iter()
+                .try_for_each(|diag| writeln!(f, "{:#}", diag))
+        } else {
+            self.diags
+                .iter()
+                .try_for_each(|diag| writeln!(f, "{}", diag))
+        }
     }
 }
 
@@ -95,6 +840,15 @@ impl ApiCompatibilityDiagnostics {
         self.diags.is_empty()
     }
 
+    /// Iterates over the diagnostics, sorted by kind and path.
+    pub fn iter(&self) -> impl Iterator<Item = Diagnostic<'_>> {
+        self.diags.iter().map(Diagnostic::new)
+    }
+
+    pub(crate) fn items(&self) -> impl Iterator<Item = &DiagnosisItem> {
+        self.diags.iter()
+    }
+
     pub(crate) fn guess_next_version(&self, mut v: Version) -> Version {
         // TODO: handle pre and build data
         if !v.pre.is_empty() {
@@ -111,15 +865,68 @@ impl ApiCompatibilityDiagnostics {
             Self::clear_build(&mut v);
         }
 
+        match self.required_bump() {
+            SemverBump::Major => Self::next_major(&mut v),
+            SemverBump::Minor => Self::next_minor(&mut v),
+            // Even without API changes, a new release needs a new version.
+            SemverBump::Patch | SemverBump::None => Self::next_patch(&mut v),
+        }
+
+        v
+    }
+
+    /// Labels the breaking changes of the items matched by `patterns` as
+    /// expected. They are still breaking. Returns the patterns which match no
+    /// breaking change.
+    pub(crate) fn mark_expected<'a>(
+        &mut self,
+        patterns: &'a [ItemPattern],
+    ) -> Vec<&'a ItemPattern> {
+        let mut matched = vec![false; patterns.len()];
+
+        self.diags = std::mem::take(&mut self.diags)
+            .into_iter()
+            .map(|diag| {
+                if diag.severity() != Severity::Breaking {
+                    return diag;
+                }
+
+                let mut expected = false;
+                for (pattern, matched) in patterns.iter().zip(&mut matched) {
+                    if pattern.matches(diag.path()) {
+                        *matched = true;
+                        expected = true;
+                    }
+                }
+
+                if expected {
+                    diag.into_expected()
+                } else {
+                    diag
+                }
+            })
+            .collect();
+
+        patterns
+            .iter()
+            .zip(matched)
+            .filter(|(_, matched)| !matched)
+            .map(|(pattern, _)| pattern)
+            .collect()
+    }
+
+    /// Returns the smallest version bump which accounts for every change, for
+    /// tools which compute the next version themselves.
+    pub fn required_bump(&self) -> SemverBump {
         if self.contains_breaking_changes() {
-            Self::next_major(&mut v);
+            SemverBump::Major
         } else if self.contains_additions() {
-            Self::next_minor(&mut v);
+            SemverBump::Minor
+        } else if !self.is_empty() {
+            SemverBump::Patch
         } else {
-            Self::next_patch(&mut v);
+            SemverBump::None
         }
-
-        v
     }
 
     fn clear_pre(v: &mut Version) {
@@ -133,11 +940,13 @@ impl ApiCompatibilityDiagnostics {
     fn contains_breaking_changes(&self) -> bool {
         self.diags
             .iter()
-            .any(|diag| diag.is_removal() || diag.is_modification())
+            .any(|diag| diag.severity() == Severity::Breaking)
     }
 
     fn contains_additions(&self) -> bool {
-        self.diags.iter().any(|diag| diag.is_addition())
+        self.diags
+            .iter()
+            .any(|diag| diag.severity() == Severity::Minor)
     }
 
     fn next_major(v: &mut Version) {
@@ -165,21 +974,21 @@ impl Parse for ApiCompatibilityDiagnostics {
 }
 
 fn map_difference<'a, K, V>(
-    a: &'a HashMap<K, V>,
-    b: &'a HashMap<K, V>,
+    a: &'a BTreeMap<K, V>,
+    b: &'a BTreeMap<K, V>,
 ) -> impl Iterator<Item = (&'a K, &'a V)>
 where
-    K: Eq + Hash,
+    K: Ord,
 {
     a.iter().filter(move |(k, _)| b.get(k).is_none())
 }
 
 fn map_modifications<'a, K, V>(
-    a: &'a HashMap<K, V>,
-    b: &'a HashMap<K, V>,
+    a: &'a BTreeMap<K, V>,
+    b: &'a BTreeMap<K, V>,
 ) -> impl Iterator<Item = (&'a K, &'a V, &'a V)>
 where
-    K: Eq + Hash,
+    K: Ord,
     V: PartialEq,
 {
     a.iter()
@@ -252,7 +1061,11 @@ mod tests {
             };
 
             let left = comparator.run();
-            compatibility_diag!(right: removal);
+            let right = ApiCompatibilityDiagnostics {
+                diags: vec![
+                    removal_diagnosis().with_signatures(Some("fn baz(n: usize)".to_owned()), None)
+                ],
+            };
 
             assert_eq!(left, right);
         }
@@ -276,10 +1089,284 @@ mod tests {
                 },
             };
             let left = comparator.run();
-            compatibility_diag!(right: modification);
+            let right = ApiCompatibilityDiagnostics {
+                diags: vec![modification_diagnosis().with_signatures(
+                    Some("fn baz(n: usize)".to_owned()),
+                    Some("fn baz(n: u32) -> u32".to_owned()),
+                )],
+            };
 
             assert_eq!(left, right);
         }
+
+        #[test]
+        fn reexported_item_is_reported_with_its_aliases() {
+            let comparator: ApiComparator = parse_quote! {
+                {
+                    pub mod foo {
+                        pub fn baz(n: usize) {}
+                    }
+
+                    pub use foo::baz;
+                },
+                {
+                    pub mod foo {
+                        pub fn baz(n: u32) {}
+                    }
+
+                    pub use foo::baz;
+                    pub use foo as bar;
+                },
+            };
+
+            let diags = comparator.run();
+            let diag = diags.items().next().unwrap();
+
+            assert_eq!(diags.to_string(), "≠ baz\n");
+            assert_eq!(
+                serde_json::to_value(diag).unwrap()["aliases"],
+                serde_json::json!(["bar::baz", "foo::baz"])
+            );
+        }
+
+        #[test]
+        fn canonical_path_does_not_depend_on_reexport_order() {
+            let comparator: ApiComparator = parse_quote! {
+                {
+                    pub mod b {
+                        pub struct S;
+                    }
+
+                    pub mod a {
+                        pub use crate::b::S;
+                    }
+                },
+                {
+                    pub mod a {
+                        pub use crate::b::S;
+                    }
+
+                    pub mod b {
+                        pub struct S;
+                    }
+                },
+            };
+
+            assert!(comparator.run().is_empty());
+        }
+
+        #[test]
+        fn expected_breaking_changes() {
+            let mut diagnostics: ApiCompatibilityDiagnostics = parse_quote! {
+                {
+                    pub fn f() {}
+                    pub mod m {
+                        pub fn g() {}
+                    }
+                },
+                {
+                    pub fn f(a: u8) {}
+                    pub mod m {}
+                    pub fn h() {}
+                },
+            };
+
+            let patterns = ["f", "m::*", "h", "old::*"]
+                .iter()
+                .map(|pattern| pattern.parse().unwrap())
+                .collect::<Vec<ItemPattern>>();
+            let stale = diagnostics.mark_expected(&patterns);
+
+            assert_eq!(
+                diagnostics.to_string(),
+                "- m::g [expected]\n≠ f [expected]\n+ h\n"
+            );
+            assert_eq!(stale, [&patterns[2], &patterns[3]]);
+        }
+
+        #[test]
+        fn extensions_can_be_patch_level() {
+            let comparator: ApiComparator = parse_quote! {
+                {
+                    #[non_exhaustive]
+                    pub enum E { A }
+                },
+                {
+                    #[non_exhaustive]
+                    pub enum E { A, B }
+                },
+            };
+
+            let minor = comparator.run();
+            assert_eq!(minor.required_bump(), SemverBump::Minor);
+
+            let patch = comparator.with_extensions(ExtensionPolicy::Patch).run();
+            assert_eq!(patch.to_string(), minor.to_string());
+            assert_eq!(patch.required_bump(), SemverBump::Patch);
+        }
+
+        #[test]
+        fn widenings_are_opt_in() {
+            let comparator: ApiComparator = parse_quote! {
+                {
+                    pub fn f(a: u8) {}
+                    pub fn g(a: u32) {}
+                },
+                {
+                    pub fn f(a: u16) {}
+                    pub fn g(a: usize) {}
+                },
+            };
+
+            assert_eq!(comparator.run().to_string(), "≠ f\n≠ g\n");
+            assert_eq!(
+                comparator.with_widening(true).run().to_string(),
+                "≠ f (`a` widened from `u8` to `u16`, callers passing literals keep compiling)\n\
+                 ≠ g\n"
+            );
+        }
+
+        #[test]
+        fn stability_tiers() {
+            let comparator: ApiComparator = parse_quote! {
+                {
+                    pub fn core() {}
+                    pub mod experimental {
+                        pub fn f() {}
+                        pub mod settled {
+                            pub fn g() {}
+                        }
+                    }
+                    pub mod raw {
+                        pub fn h() {}
+                    }
+                },
+                {
+                    pub mod experimental {
+                        pub mod settled {}
+                    }
+                    pub mod raw {
+                        pub fn i() {}
+                    }
+                },
+            };
+            let stability = StabilityTiers {
+                stable: vec!["experimental::settled".parse().unwrap()],
+                preview: vec!["experimental".parse().unwrap()],
+                internal: vec!["raw".parse().unwrap()],
+            };
+
+            let severities = |diags: &ApiCompatibilityDiagnostics| {
+                diags
+                    .items()
+                    .map(|diag| (diag.path().to_string(), diag.severity()))
+                    .collect::<Vec<_>>()
+            };
+
+            let diags = comparator.with_stability(stability).run();
+            assert_eq!(
+                severities(&diags),
+                [
+                    ("core".to_owned(), Severity::Breaking),
+                    ("experimental::f".to_owned(), Severity::Minor),
+                    ("experimental::settled::g".to_owned(), Severity::Breaking),
+                    ("raw::h".to_owned(), Severity::Informational),
+                    ("raw::i".to_owned(), Severity::Informational),
+                ]
+            );
+        }
+
+        #[test]
+        fn std_requirement() {
+            let comparator = |previous: Option<bool>, current: Option<bool>| {
+                let mut comparator: ApiComparator = parse_quote! { {}, {} };
+                if let Some(no_std) = previous {
+                    comparator.previous.set_no_std(no_std);
+                }
+                if let Some(no_std) = current {
+                    comparator.current.set_no_std(no_std);
+                }
+                comparator.run().to_string()
+            };
+
+            assert_eq!(
+                comparator(Some(true), Some(false)),
+                "≠ crate (now requires `std`)\n"
+            );
+            assert_eq!(comparator(Some(false), Some(true)), "");
+            assert_eq!(comparator(None, Some(false)), "");
+        }
+
+        #[test]
+        fn edition_change() {
+            let comparator = |previous: &str, current: &str| {
+                let mut comparator: ApiComparator = parse_quote! { {}, {} };
+                comparator.previous.set_edition(previous);
+                comparator.current.set_edition(current);
+                comparator.run()
+            };
+
+            let diagnosis = comparator("2018", "2021");
+            assert_eq!(
+                diagnosis.to_string(),
+                "≠ crate (edition changed from 2018 to 2021)\n"
+            );
+            assert_eq!(diagnosis.required_bump(), SemverBump::Patch);
+
+            assert!(comparator("2021", "2021").is_empty());
+        }
+
+        #[test]
+        fn c_header_changes_are_reported_with_the_rust_ones() {
+            let mut comparator: ApiComparator = parse_quote! {
+                {
+                    pub extern "C" fn f(a: i32) {}
+                },
+                {
+                    pub extern "C" fn f(a: i64) {}
+                },
+            };
+            comparator
+                .previous
+                .set_c_header(c_header::parse("void f(int32_t a);"));
+            comparator
+                .current
+                .set_c_header(c_header::parse("void f(int64_t a);"));
+
+            let diags = comparator.run();
+            assert_eq!(diags.to_string(), "≠ f\n≠ f (C function)\n");
+
+            let c_diag = diags.iter().nth(1).unwrap();
+            assert_eq!(c_diag.rule(), "c-item-modified");
+            assert_eq!(c_diag.previous_signature(), Some("void f(int32_t a)"));
+        }
+
+        #[test]
+        fn const_values_can_be_informational() {
+            let comparator: ApiComparator = parse_quote! {
+                {
+                    pub struct S;
+                    impl Default for S {
+                        const LEN: usize = 4;
+                    }
+                },
+                {
+                    pub struct S;
+                    impl Default for S {
+                        const LEN: usize = 8;
+                    }
+                },
+            };
+
+            let breaking = comparator.run();
+            assert_eq!(breaking.required_bump(), SemverBump::Major);
+
+            let informational = comparator
+                .with_const_values(ConstValuePolicy::Informational)
+                .run();
+            assert_eq!(informational.to_string(), breaking.to_string());
+            assert_eq!(informational.required_bump(), SemverBump::Patch);
+        }
     }
 
     mod api_compatibility_diagnostic {
@@ -300,6 +1387,12 @@ mod tests {
                 assert!(!comp.contains_additions());
             }
 
+            #[test]
+            fn required_bump() {
+                compatibility_diag!(comp: removal);
+                assert_eq!(comp.required_bump(), SemverBump::Major);
+            }
+
             #[test]
             fn display() {
                 compatibility_diag!(comp: removal);
@@ -357,6 +1450,12 @@ mod tests {
                 assert!(comp.contains_additions());
             }
 
+            #[test]
+            fn required_bump() {
+                compatibility_diag!(comp: addition);
+                assert_eq!(comp.required_bump(), SemverBump::Minor);
+            }
+
             #[test]
             fn display() {
                 compatibility_diag!(comp: addition);
@@ -385,6 +1484,20 @@ mod tests {
                 assert!(!comp.contains_additions());
             }
 
+            #[test]
+            fn required_bump() {
+                compatibility_diag!(comp: empty);
+                assert_eq!(comp.required_bump(), SemverBump::None);
+            }
+
+            #[test]
+            fn informational_changes_are_patch() {
+                let mut comp = ApiCompatibilityDiagnostics::default();
+                comp.diags.push(removal_diagnosis().into_private());
+
+                assert_eq!(comp.required_bump(), SemverBump::Patch);
+            }
+
             #[test]
             fn is_empty() {
                 compatibility_diag!(comp: empty);
@@ -436,6 +1549,16 @@ mod tests {
                 assert_eq!(next_version, Version::parse("3.2.4").unwrap());
             }
 
+            #[test]
+            fn private_changes_effects() {
+                let mut comp = ApiCompatibilityDiagnostics::default();
+                comp.diags.push(removal_diagnosis().into_private());
+                comp.diags.push(addition_diagnosis().into_private());
+
+                let next_version = comp.guess_next_version(sample_version());
+                assert_eq!(next_version, Version::parse("3.2.4").unwrap());
+            }
+
             #[test]
             fn pre_is_cleared() {
                 compatibility_diag!(comp: empty);
@@ -456,16 +1579,16 @@ mod tests {
         mod map_functions {
             use super::*;
 
-            fn bare_hashmap_1() -> HashMap<usize, usize> {
-                let mut tmp = HashMap::new();
+            fn bare_map_1() -> BTreeMap<usize, usize> {
+                let mut tmp = BTreeMap::new();
                 tmp.insert(1, 42);
                 tmp.insert(2, 101);
                 tmp.insert(3, 13);
                 tmp
             }
 
-            fn bare_hashmap_2() -> HashMap<usize, usize> {
-                let mut tmp = HashMap::new();
+            fn bare_map_2() -> BTreeMap<usize, usize> {
+                let mut tmp = BTreeMap::new();
                 tmp.insert(1, 13);
                 tmp.insert(2, 101);
                 tmp.insert(4, 123);
@@ -475,8 +1598,8 @@ mod tests {
 
             #[test]
             fn difference() {
-                let a = bare_hashmap_1();
-                let b = bare_hashmap_2();
+                let a = bare_map_1();
+                let b = bare_map_2();
 
                 let mut diff = map_difference(&a, &b).collect::<Vec<_>>();
                 diff.sort_by_key(|(k, _)| *k);
@@ -486,8 +1609,8 @@ mod tests {
 
             #[test]
             fn modification() {
-                let a = bare_hashmap_1();
-                let b = bare_hashmap_2();
+                let a = bare_map_1();
+                let b = bare_map_2();
 
                 let mut modif = map_modifications(&a, &b).collect::<Vec<_>>();
                 modif.sort_by_key(|(k, _, _)| *k);
@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+
+use crate::{diagnosis::DiagnosisItem, public_api::ItemPath, rules::RuleId};
+
+/// Finds the functions whose opaque return type no longer implements an auto
+/// trait it used to. Only the functions which were probed in both versions
+/// are compared, which requires `--probe-auto-traits`.
+pub(crate) fn changes(
+    previous: &BTreeMap<ItemPath, Vec<String>>,
+    current: &BTreeMap<ItemPath, Vec<String>>,
+) -> Vec<DiagnosisItem> {
+    previous
+        .iter()
+        .filter_map(|(path, previous_traits)| {
+            let current_traits = current.get(path)?;
+
+            let lost = previous_traits
+                .iter()
+                .filter(|trait_| !current_traits.contains(trait_))
+                .map(|trait_| format!("`{}`", trait_))
+                .collect::<Vec<_>>();
+
+            if lost.is_empty() {
+                return None;
+            }
+
+            Some(
+                DiagnosisItem::new(RuleId::AutoTraitLost, path.clone(), None).with_message(
+                    format!("return type no longer implements {}", lost.join(" and ")),
+                ),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn lost_auto_traits() {
+        let traits = |traits: &[&str]| traits.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+
+        let previous = BTreeMap::from([
+            (parse_quote! { a }, traits(&["Send", "Sync"])),
+            (parse_quote! { b }, traits(&["Send"])),
+            (parse_quote! { c }, traits(&["Send"])),
+        ]);
+        let current = BTreeMap::from([
+            (parse_quote! { a }, traits(&[])),
+            (parse_quote! { b }, traits(&["Send", "Sync"])),
+        ]);
+
+        let changes = changes(&previous, &current)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            ["≠ a (return type no longer implements `Send` and `Sync`)"]
+        );
+    }
+}
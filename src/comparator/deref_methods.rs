@@ -0,0 +1,260 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    iter,
+};
+
+use syn::Ident;
+
+use crate::{
+    diagnosis::{DiagnosisCollector, DiagnosisItem},
+    public_api::{ItemKind, ItemPath},
+    rules::RuleId,
+};
+
+/// The methods which could be called on a type through its `Deref`
+/// implementation (or a chain of them), and which no longer can.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct UnreachableMethods<'a> {
+    type_: &'a ItemPath,
+    // The first link of the chain which changed: `changed` dereferenced to
+    // `previous_target`, and now dereferences to `current_target`.
+    changed: &'a ItemPath,
+    previous_target: &'a ItemPath,
+    current_target: Option<&'a ItemPath>,
+    methods: Vec<&'a ItemPath>,
+}
+
+impl<'a> UnreachableMethods<'a> {
+    pub(crate) fn type_path(&self) -> &'a ItemPath {
+        self.type_
+    }
+
+    pub(crate) fn diagnosis(&self, collector: &mut DiagnosisCollector) {
+        let names = self
+            .methods
+            .iter()
+            .map(|method| format!("`{}`", method.segments().last().unwrap()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let subject = if self.changed == self.type_ {
+            String::new()
+        } else {
+            format!("`{}` ", self.changed)
+        };
+
+        let target_change = match self.current_target {
+            Some(current) => format!(
+                "{}deref target changed from `{}` to `{}`",
+                subject, self.previous_target, current
+            ),
+            None => format!(
+                "{}no longer dereferences to `{}`",
+                subject, self.previous_target
+            ),
+        };
+
+        let details = self
+            .methods
+            .iter()
+            .map(|method| method.to_string())
+            .collect();
+
+        let diagnosis =
+            DiagnosisItem::new(RuleId::DerefMethodsUnreachable, self.type_.clone(), None)
+                .with_message(format!("{}, {} no longer reachable", target_change, names))
+                .with_details(details);
+
+        collector.add(diagnosis);
+    }
+}
+
+/// Finds the types whose `Deref` chain changed, and the inherited methods
+/// which can no longer be called on them.
+///
+/// Methods which are still reachable, either through the new chain or
+/// because the type now defines them itself, are not reported.
+pub(crate) fn find<'a>(
+    previous: &'a BTreeMap<ItemPath, ItemKind>,
+    current: &'a BTreeMap<ItemPath, ItemKind>,
+) -> Vec<UnreachableMethods<'a>> {
+    previous
+        .iter()
+        .filter(|(path, kind)| {
+            kind.as_type().is_some() && current.get(path).and_then(ItemKind::as_type).is_some()
+        })
+        .filter_map(|(path, _)| {
+            let previous_chain = deref_chain(previous, path);
+            let current_chain = deref_chain(current, path);
+
+            let changed_link = previous_chain
+                .iter()
+                .zip(current_chain.iter().map(Some).chain(iter::repeat(None)))
+                .position(|(previous, current)| Some(previous) != current)?;
+
+            let reachable = inherent_methods(current, path)
+                .chain(inherited_methods(current, &current_chain))
+                .filter_map(|method| method.segments().last())
+                .collect::<BTreeSet<_>>();
+
+            let methods = inherited_methods(previous, &previous_chain)
+                .into_iter()
+                .filter(|method| !reachable.contains(method.segments().last().unwrap()))
+                .collect::<Vec<_>>();
+
+            if methods.is_empty() {
+                return None;
+            }
+
+            let changed = match changed_link {
+                0 => path,
+                idx => previous_chain[idx - 1],
+            };
+
+            Some(UnreachableMethods {
+                type_: path,
+                changed,
+                previous_target: previous_chain[changed_link],
+                current_target: current_chain.get(changed_link).copied(),
+                methods,
+            })
+        })
+        .collect()
+}
+
+// The types `type_` successively dereferences to.
+fn deref_chain<'a>(items: &'a BTreeMap<ItemPath, ItemKind>, type_: &ItemPath) -> Vec<&'a ItemPath> {
+    let mut chain: Vec<&ItemPath> = Vec::new();
+    let mut current = items.get(type_).and_then(ItemKind::as_type);
+
+    while let Some(target) = current.and_then(|type_| type_.deref_target()) {
+        if target == type_ || chain.contains(&target) {
+            break;
+        }
+
+        chain.push(target);
+        current = items.get(target).and_then(ItemKind::as_type);
+    }
+
+    chain
+}
+
+// The methods defined on `type_` which take `self`.
+fn inherent_methods<'a>(
+    items: &'a BTreeMap<ItemPath, ItemKind>,
+    type_: &'a ItemPath,
+) -> impl Iterator<Item = &'a ItemPath> {
+    let depth = type_.segments().len() + 1;
+
+    items
+        .iter()
+        .filter(move |(path, kind)| {
+            path.segments().len() == depth
+                && path.segments().starts_with(type_.segments())
+                && kind.as_method().is_some_and(|method| method.has_receiver())
+        })
+        .map(|(path, _)| path)
+}
+
+// The methods reachable through `chain`. A method hides the methods with the
+// same name found further in the chain.
+fn inherited_methods<'a>(
+    items: &'a BTreeMap<ItemPath, ItemKind>,
+    chain: &[&'a ItemPath],
+) -> Vec<&'a ItemPath> {
+    let mut seen: BTreeSet<&Ident> = BTreeSet::new();
+
+    chain
+        .iter()
+        .flat_map(|target| inherent_methods(items, target))
+        .filter(|method| seen.insert(method.segments().last().unwrap()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::public_api::PublicApi;
+
+    use super::*;
+
+    #[test]
+    fn follows_deref_chain() {
+        let api: PublicApi = parse_quote! {
+            pub struct A;
+            pub struct B;
+            pub struct C;
+
+            impl Deref for A {
+                type Target = B;
+            }
+
+            impl Deref for B {
+                type Target = C;
+            }
+        };
+
+        let chain = deref_chain(api.items(), &parse_quote! { A });
+
+        assert_eq!(
+            chain,
+            [&parse_quote! { B }, &parse_quote! { C }] as [&ItemPath; 2]
+        );
+    }
+
+    #[test]
+    fn deref_cycle_terminates() {
+        let api: PublicApi = parse_quote! {
+            pub struct A;
+            pub struct B;
+
+            impl Deref for A {
+                type Target = B;
+            }
+
+            impl Deref for B {
+                type Target = A;
+            }
+        };
+
+        let chain = deref_chain(api.items(), &parse_quote! { A });
+
+        assert_eq!(chain, [&parse_quote! { B }] as [&ItemPath; 1]);
+    }
+
+    #[test]
+    fn associated_functions_are_not_inherited() {
+        let previous: PublicApi = parse_quote! {
+            pub struct A;
+            pub struct B;
+
+            impl Deref for A {
+                type Target = B;
+            }
+
+            impl B {
+                pub fn new() -> B {}
+                pub fn len(&self) -> usize {}
+            }
+        };
+
+        let current: PublicApi = parse_quote! {
+            pub struct A;
+            pub struct B;
+
+            impl B {
+                pub fn new() -> B {}
+                pub fn len(&self) -> usize {}
+            }
+        };
+
+        let unreachable = find(previous.items(), current.items());
+
+        assert_eq!(unreachable.len(), 1);
+        assert_eq!(
+            unreachable[0].methods,
+            [&parse_quote! { B::len }] as [&ItemPath; 1]
+        );
+    }
+}
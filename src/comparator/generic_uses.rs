@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+
+use crate::public_api::{ItemKind, ItemPath};
+
+use super::impact;
+
+/// Finds the items mentioning a type whose number of required generic
+/// arguments changed, as they are affected by the change too. Returns their
+/// path, along with a description of the change.
+///
+/// Types are matched by name, as the paths written in signatures are not
+/// resolved.
+pub(crate) fn find(
+    previous: &BTreeMap<ItemPath, ItemKind>,
+    current: &BTreeMap<ItemPath, ItemKind>,
+) -> Vec<(ItemPath, String)> {
+    let changed = previous
+        .iter()
+        .filter_map(|(path, kind)| {
+            let previous_count = kind.as_type()?.required_generic_args();
+            let current_count = current.get(path)?.as_type()?.required_generic_args();
+
+            (previous_count != current_count).then_some((path, previous_count, current_count))
+        })
+        .collect::<Vec<_>>();
+
+    let mut uses = Vec::new();
+
+    for (type_path, previous_count, current_count) in changed {
+        let name = type_path.segments().last().unwrap();
+
+        for path in impact::users(current, type_path, name) {
+            if !previous.contains_key(path) {
+                continue;
+            }
+
+            uses.push((
+                path.clone(),
+                format!(
+                    "mentions `{}`, which now takes {} instead of {}",
+                    name,
+                    generic_args(current_count),
+                    previous_count
+                ),
+            ));
+        }
+    }
+
+    uses
+}
+
+fn generic_args(count: usize) -> String {
+    match count {
+        1 => "1 generic argument".to_owned(),
+        count => format!("{} generic arguments", count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::comparator::ApiCompatibilityDiagnostics;
+
+    #[test]
+    fn uses_of_a_type_which_gained_a_generic_parameter() {
+        let diagnostics: ApiCompatibilityDiagnostics = parse_quote! {
+            {
+                pub struct E;
+                pub struct Wrapper {
+                    pub inner: Option<E>,
+                }
+                pub fn f() -> Vec<E> {}
+                pub fn g(n: u8) {}
+            },
+            {
+                pub struct E<T>(T);
+                pub struct Wrapper {
+                    pub inner: Option<E<u8>>,
+                }
+                pub fn f() -> Vec<E<u8>> {}
+                pub fn g(n: u8) {}
+            },
+        };
+
+        assert_eq!(
+            diagnostics.to_string(),
+            "≠ E\n\
+             ≠ Wrapper (mentions `E`, which now takes 1 generic argument instead of 0)\n\
+             ≠ f (mentions `E`, which now takes 1 generic argument instead of 0)\n"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+use syn::{visit::Visit, Ident, Path};
+
+use crate::{
+    comparator::ApiCompatibilityDiagnostics,
+    public_api::{ItemKind, ItemPath, PublicApi},
+};
+
+/// Links a changed type or trait to a public item whose signature or fields
+/// mention it.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub(crate) struct ImpactEdge {
+    changed: String,
+    used_by: String,
+}
+
+impl ImpactEdge {
+    pub(crate) fn changed(&self) -> &str {
+        &self.changed
+    }
+
+    pub(crate) fn used_by(&self) -> &str {
+        &self.used_by
+    }
+}
+
+/// Builds the edges going from each changed type or trait to the items which
+/// mention it, so that the blast radius of a change can be drawn.
+///
+/// The items mentioning a removed item are searched in the previous version,
+/// the other ones in the current version. Types are matched by name, as the
+/// paths written in signatures are not resolved.
+pub(crate) fn edges(
+    diagnostics: &ApiCompatibilityDiagnostics,
+    previous: &PublicApi,
+    current: &PublicApi,
+) -> Vec<ImpactEdge> {
+    let changed = diagnostics
+        .items()
+        .map(|diag| diag.path())
+        .collect::<BTreeSet<_>>();
+
+    let mut edges = BTreeSet::new();
+
+    for path in changed {
+        let api = if current.items().contains_key(path) {
+            current
+        } else {
+            previous
+        };
+
+        match api.items().get(path) {
+            Some(ItemKind::Type(_)) | Some(ItemKind::TraitDef(_)) => {}
+            _ => continue,
+        }
+
+        let name = path.segments().last().unwrap();
+        for user in users(api.items(), path, name) {
+            if !api.is_private(user) {
+                edges.insert(ImpactEdge {
+                    changed: path.to_string(),
+                    used_by: user.to_string(),
+                });
+            }
+        }
+    }
+
+    edges.into_iter().collect()
+}
+
+/// Returns the items which mention `name`, other than the item at `path`.
+pub(super) fn users<'a>(
+    items: &'a BTreeMap<ItemPath, ItemKind>,
+    path: &'a ItemPath,
+    name: &'a Ident,
+) -> impl Iterator<Item = &'a ItemPath> {
+    items
+        .iter()
+        .filter(move |(user, kind)| *user != path && mentions(kind, name))
+        .map(|(user, _)| user)
+}
+
+fn mentions(kind: &ItemKind, name: &Ident) -> bool {
+    let mut visitor = Mentions { name, found: false };
+    kind.visit_types(&mut visitor);
+    visitor.found
+}
+
+struct Mentions<'a> {
+    name: &'a Ident,
+    found: bool,
+}
+
+impl<'a, 'ast> Visit<'ast> for Mentions<'a> {
+    fn visit_path(&mut self, path: &'ast Path) {
+        if path
+            .segments
+            .last()
+            .is_some_and(|segment| &segment.ident == self.name)
+        {
+            self.found = true;
+        }
+
+        syn::visit::visit_path(self, path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::comparator::ApiComparator;
+
+    #[test]
+    fn changed_types_are_linked_to_their_users() {
+        let comparator: ApiComparator = parse_quote! {
+            {
+                pub struct Config;
+                pub struct Unchanged;
+                pub struct Removed;
+
+                pub fn load() -> Config {}
+                pub fn unrelated(u: Unchanged) {}
+                pub fn take(r: &Removed) {}
+
+                impl Config {
+                    pub fn merge(&self, other: Config) {}
+                }
+            },
+            {
+                pub struct Config {
+                    pub verbose: bool,
+                }
+                pub struct Unchanged;
+
+                pub fn load() -> Config {}
+                pub fn unrelated(u: Unchanged) {}
+
+                impl Config {
+                    pub fn merge(&self, other: Config) {}
+                }
+            },
+        };
+
+        let edges = comparator.impact(&comparator.run());
+        let edges = serde_json::to_string(&edges).unwrap();
+
+        assert_eq!(
+            edges,
+            "[{\"changed\":\"Config\",\"used_by\":\"Config::merge\"},\
+             {\"changed\":\"Config\",\"used_by\":\"load\"},\
+             {\"changed\":\"Removed\",\"used_by\":\"take\"}]"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+use crate::{diagnosis::DiagnosisItem, public_api::ItemPath, rules::RuleId};
+
+/// Finds the hidden helper macros which were removed or whose rules changed.
+/// Code generated by the macros of the previous version may still invoke
+/// them, for instance when a companion procedural macro crate is not updated
+/// at the same time. Only reported with `--macro-internals`.
+pub(crate) fn changes(
+    previous: &BTreeMap<ItemPath, String>,
+    current: &BTreeMap<ItemPath, String>,
+) -> Vec<DiagnosisItem> {
+    previous
+        .iter()
+        .filter_map(|(path, previous_rules)| match current.get(path) {
+            None => Some(DiagnosisItem::new(
+                RuleId::MacroHelperRemoved,
+                path.clone(),
+                None,
+            )),
+            Some(current_rules) if current_rules != previous_rules => Some(
+                DiagnosisItem::new(RuleId::MacroHelperModified, path.clone(), None)
+                    .with_message("macro rules changed"),
+            ),
+            Some(_) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::comparator::ApiComparator;
+
+    #[test]
+    fn helper_macros_are_opt_in() {
+        let comparator: ApiComparator = parse_quote! {
+            {
+                #[doc(hidden)]
+                #[macro_export]
+                macro_rules! __removed {
+                    () => {};
+                }
+
+                #[doc(hidden)]
+                #[macro_export]
+                macro_rules! __modified {
+                    ($e:expr) => { $e };
+                }
+
+                #[doc(hidden)]
+                #[macro_export]
+                macro_rules! __unchanged {
+                    () => {};
+                }
+            },
+            {
+                #[doc(hidden)]
+                #[macro_export]
+                macro_rules! __modified {
+                    ($e:expr, $f:expr) => { $e };
+                }
+
+                #[doc(hidden)]
+                #[macro_export]
+                macro_rules! __unchanged {
+                    () => {};
+                }
+
+                #[doc(hidden)]
+                #[macro_export]
+                macro_rules! __added {
+                    () => {};
+                }
+            },
+        };
+
+        assert!(comparator.run().is_empty());
+        assert_eq!(
+            comparator.with_macro_internals(true).run().to_string(),
+            "- __removed\n≠ __modified (macro rules changed)\n"
+        );
+    }
+}
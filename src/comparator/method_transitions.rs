@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+
+use syn::{Ident, Signature};
+
+use crate::{
+    diagnosis::{DiagnosisCollector, DiagnosisItem, DiagnosisItemKind},
+    public_api::{call_form, ItemKind, ItemPath},
+    rules::RuleId,
+};
+
+/// The traits of the prelude of every edition, which never need to be
+/// imported. `TryFrom`, `TryInto` and `FromIterator` are left out, as they
+/// are only in the 2021 prelude.
+const PRELUDE_TRAITS: &[&str] = &[
+    "AsMut",
+    "AsRef",
+    "Clone",
+    "Copy",
+    "Default",
+    "DoubleEndedIterator",
+    "Drop",
+    "Eq",
+    "ExactSizeIterator",
+    "Extend",
+    "Fn",
+    "FnMut",
+    "FnOnce",
+    "From",
+    "Into",
+    "IntoIterator",
+    "Iterator",
+    "Ord",
+    "PartialEq",
+    "PartialOrd",
+    "Send",
+    "Sized",
+    "Sync",
+    "ToOwned",
+    "ToString",
+    "Unpin",
+];
+
+/// A method which moved between an inherent impl block of a type and one of
+/// its trait implementations, keeping its name.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MethodTransition<'a> {
+    method: &'a ItemPath,
+    type_: ItemPath,
+    trait_name: &'a Ident,
+    // The signature of the inherent method. The signatures of the methods of
+    // trait implementations are not kept.
+    signature: &'a Signature,
+    to_trait: bool,
+}
+
+impl<'a> MethodTransition<'a> {
+    /// The path of the inherent method, in the version which has it.
+    pub(crate) fn method_path(&self) -> &'a ItemPath {
+        self.method
+    }
+
+    /// Whether the inherent method was moved to a trait implementation, as
+    /// opposed to from one.
+    pub(crate) fn is_to_trait(&self) -> bool {
+        self.to_trait
+    }
+
+    pub(crate) fn diagnosis(&self, collector: &mut DiagnosisCollector) {
+        let type_name = self.type_.segments().last().unwrap();
+        let inherent = call_form(type_name, self.signature, None);
+        let trait_ = call_form(type_name, self.signature, Some(self.trait_name));
+
+        let in_prelude = PRELUDE_TRAITS.contains(&self.trait_name.to_string().as_str());
+
+        let (rule, message) = if self.to_trait && in_prelude {
+            (
+                RuleId::MethodMovedToPreludeTraitImpl,
+                format!(
+                    "moved to the `{}` implementation, which is in the prelude",
+                    self.trait_name
+                ),
+            )
+        } else if self.to_trait {
+            (
+                RuleId::MethodMovedToTraitImpl,
+                format!(
+                    "moved to the `{0}` implementation: now requires `{0}` to be in scope",
+                    self.trait_name
+                ),
+            )
+        } else {
+            (
+                RuleId::MethodMovedToInherentImpl,
+                format!(
+                    "moved from the `{}` implementation to an inherent impl: `{}` becomes `{}`",
+                    self.trait_name, trait_, inherent
+                ),
+            )
+        };
+
+        collector.add(DiagnosisItem::new(rule, self.method.clone(), None).with_message(message));
+    }
+}
+
+/// Finds the inherent methods which were removed while a trait
+/// implementation of their type started defining a method with the same name,
+/// and the opposite.
+pub(crate) fn find<'a>(
+    previous: &'a BTreeMap<ItemPath, ItemKind>,
+    current: &'a BTreeMap<ItemPath, ItemKind>,
+) -> Vec<MethodTransition<'a>> {
+    let to_trait = transitions(previous, current, true);
+    let from_trait = transitions(current, previous, false);
+
+    to_trait.chain(from_trait).collect()
+}
+
+// The inherent methods of `with_method` which are defined by a trait
+// implementation in `with_impl` instead.
+fn transitions<'a>(
+    with_method: &'a BTreeMap<ItemPath, ItemKind>,
+    with_impl: &'a BTreeMap<ItemPath, ItemKind>,
+    to_trait: bool,
+) -> impl Iterator<Item = MethodTransition<'a>> {
+    with_method.iter().filter_map(move |(path, kind)| {
+        let method = kind.as_method()?;
+        if with_impl.contains_key(path) {
+            return None;
+        }
+
+        let (name, type_) = path.segments().split_last()?;
+        let type_ = ItemPath::from_segments(type_.to_owned());
+
+        let trait_name = defining_traits(with_impl, &type_, name).find(|trait_name| {
+            !defining_traits(with_method, &type_, name).any(|t| t == *trait_name)
+        })?;
+
+        Some(MethodTransition {
+            method: path,
+            type_,
+            trait_name,
+            signature: method.signature(),
+            to_trait,
+        })
+    })
+}
+
+// The traits whose implementation for `type_` defines a method named `name`.
+fn defining_traits<'a>(
+    items: &'a BTreeMap<ItemPath, ItemKind>,
+    type_: &ItemPath,
+    name: &'a Ident,
+) -> impl Iterator<Item = &'a Ident> {
+    items
+        .get(type_)
+        .and_then(ItemKind::as_type)
+        .into_iter()
+        .flat_map(|type_| type_.traits())
+        .filter(move |impl_| impl_.methods().contains(name))
+        .map(|impl_| impl_.trait_name())
+}
+
+/// The trait implementations whose addition (or removal) is explained by
+/// the methods moved into (or out of) them, as they define no other method.
+/// Their own diagnosis is redundant.
+pub(crate) fn absorbed_impls(
+    transitions: &[MethodTransition],
+    previous: &BTreeMap<ItemPath, ItemKind>,
+    current: &BTreeMap<ItemPath, ItemKind>,
+) -> Vec<(ItemPath, Ident, DiagnosisItemKind)> {
+    let mut absorbed = Vec::new();
+
+    for transition in transitions {
+        let (items, kind) = if transition.to_trait {
+            (current, DiagnosisItemKind::Addition)
+        } else {
+            (previous, DiagnosisItemKind::Removal)
+        };
+
+        let moved = transitions
+            .iter()
+            .filter(|other| {
+                other.type_ == transition.type_
+                    && other.trait_name == transition.trait_name
+                    && other.to_trait == transition.to_trait
+            })
+            .filter_map(|other| other.method.segments().last())
+            .collect::<Vec<_>>();
+
+        let impls = items
+            .get(&transition.type_)
+            .and_then(ItemKind::as_type)
+            .into_iter()
+            .flat_map(|type_| type_.traits())
+            .filter(|impl_| impl_.trait_name() == transition.trait_name);
+
+        for impl_ in impls {
+            let entry = (transition.type_.clone(), impl_.trait_name().clone(), kind);
+            if impl_.methods().iter().all(|method| moved.contains(&method))
+                && !absorbed.contains(&entry)
+            {
+                absorbed.push(entry);
+            }
+        }
+    }
+
+    absorbed
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::public_api::PublicApi;
+
+    use super::*;
+
+    fn transitions(previous: PublicApi, current: PublicApi) -> Vec<(String, String, bool)> {
+        find(previous.items(), current.items())
+            .iter()
+            .map(|transition| {
+                (
+                    transition.method_path().to_string(),
+                    transition.trait_name.to_string(),
+                    transition.is_to_trait(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_moves_between_inherent_and_trait_impls() {
+        let previous: PublicApi = parse_quote! {
+            pub struct S;
+
+            impl S {
+                pub fn len(&self) -> usize {}
+            }
+
+            impl Default for S {
+                fn default() -> S {}
+            }
+        };
+        let current: PublicApi = parse_quote! {
+            pub struct S;
+
+            impl S {
+                pub fn default() -> S {}
+            }
+
+            impl Length for S {
+                fn len(&self) -> usize {}
+            }
+        };
+
+        assert_eq!(
+            transitions(previous, current),
+            [
+                ("S::len".to_owned(), "Length".to_owned(), true),
+                ("S::default".to_owned(), "Default".to_owned(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn method_kept_in_trait_impl_is_not_a_move() {
+        let previous: PublicApi = parse_quote! {
+            pub struct S;
+
+            impl S {
+                pub fn len(&self) -> usize {}
+            }
+
+            impl Length for S {
+                fn len(&self) -> usize {}
+            }
+        };
+        let current: PublicApi = parse_quote! {
+            pub struct S;
+
+            impl Length for S {
+                fn len(&self) -> usize {}
+            }
+        };
+
+        assert!(transitions(previous, current).is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use syn::Ident;
+
+use crate::{
+    diagnosis::{DiagnosisCollector, DiagnosisItem},
+    public_api::{ItemKind, ItemPath},
+    rules::RuleId,
+};
+
+/// A public module which has been moved to another path, with exactly the
+/// same content.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ModuleMove<'a> {
+    from: &'a [Ident],
+    to: &'a [Ident],
+    // The path of each moved item, in the previous and in the current version.
+    items: Vec<(&'a ItemPath, &'a ItemPath)>,
+}
+
+impl<'a> ModuleMove<'a> {
+    pub(crate) fn previous_paths(&self) -> impl Iterator<Item = &'a ItemPath> + '_ {
+        self.items.iter().map(|(previous, _)| *previous)
+    }
+
+    pub(crate) fn current_paths(&self) -> impl Iterator<Item = &'a ItemPath> + '_ {
+        self.items.iter().map(|(_, current)| *current)
+    }
+
+    pub(crate) fn diagnosis(&self, collector: &mut DiagnosisCollector) {
+        let to = ItemPath::from_segments(self.to.to_vec());
+        let message = format!("moved to {}, {} items", to, self.items.len());
+
+        let details = self
+            .items
+            .iter()
+            .map(|(previous, current)| format!("{} -> {}", previous, current))
+            .collect();
+
+        let diagnosis = DiagnosisItem::new(
+            RuleId::ModuleMoved,
+            ItemPath::from_segments(self.from.to_vec()),
+            None,
+        )
+        .with_message(message)
+        .with_details(details);
+
+        collector.add(diagnosis);
+    }
+}
+
+/// Finds the modules which exist only in the previous version and whose
+/// content can be found, unchanged, in a module which exists only in the
+/// current version.
+pub(crate) fn find<'a>(
+    previous: &'a BTreeMap<ItemPath, ItemKind>,
+    current: &'a BTreeMap<ItemPath, ItemKind>,
+) -> Vec<ModuleMove<'a>> {
+    let previous_modules = modules(previous);
+    let current_modules = modules(current);
+
+    // Moving `foo` to `bar::baz` makes both `bar` and `bar::baz` new modules,
+    // so every new module is a potential destination. Every removed module is
+    // a potential source as well, parents first: a submodule may have been
+    // moved out of a parent which was otherwise reorganized.
+    let removed = previous_modules
+        .difference(&current_modules)
+        .copied()
+        .collect::<Vec<_>>();
+    let added = current_modules
+        .difference(&previous_modules)
+        .copied()
+        .collect::<Vec<_>>();
+
+    let mut used = BTreeSet::new();
+    let mut moves = Vec::new();
+
+    for from in removed {
+        // The submodules of a moved module are part of its move.
+        if moves
+            .iter()
+            .any(|move_: &ModuleMove| from.starts_with(move_.from))
+        {
+            continue;
+        }
+
+        let from_content = content(previous, from);
+
+        let to = added.iter().copied().find(|to| {
+            !used.contains(to)
+                && relative_content_eq((from, &from_content), (to, &content(current, to)))
+        });
+
+        if let Some(to) = to {
+            used.insert(to);
+
+            let items = from_content
+                .iter()
+                .zip(content(current, to))
+                .map(|((previous, _), (current, _))| (*previous, current))
+                .collect();
+
+            moves.push(ModuleMove { from, to, items });
+        }
+    }
+
+    moves
+}
+
+// Returns every proper prefix of the item paths which is not an item itself.
+fn modules(items: &BTreeMap<ItemPath, ItemKind>) -> BTreeSet<&[Ident]> {
+    items
+        .keys()
+        .flat_map(|path| {
+            let segments = path.segments();
+            (1..segments.len()).map(move |len| &segments[..len])
+        })
+        .filter(|prefix| !items.contains_key(&ItemPath::from_segments(prefix.to_vec())))
+        .collect()
+}
+
+fn content<'a>(
+    items: &'a BTreeMap<ItemPath, ItemKind>,
+    module: &[Ident],
+) -> Vec<(&'a ItemPath, &'a ItemKind)> {
+    items
+        .iter()
+        .filter(|(path, _)| path.segments().starts_with(module))
+        .collect()
+}
+
+fn relative_content_eq(
+    (left_module, left): (&[Ident], &[(&ItemPath, &ItemKind)]),
+    (right_module, right): (&[Ident], &[(&ItemPath, &ItemKind)]),
+) -> bool {
+    left.len() == right.len()
+        && relative_content(left, left_module.len()) == relative_content(right, right_module.len())
+}
+
+fn relative_content<'a>(
+    items: &[(&'a ItemPath, &'a ItemKind)],
+    prefix_len: usize,
+) -> Vec<(&'a [Ident], &'a ItemKind)> {
+    items
+        .iter()
+        .map(|(path, kind)| (&path.segments()[prefix_len..], *kind))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::public_api::PublicApi;
+
+    use super::*;
+
+    fn segments(path: ItemPath) -> Vec<Ident> {
+        path.segments().to_vec()
+    }
+
+    #[test]
+    fn finds_renamed_module() {
+        let previous: PublicApi = parse_quote! {
+            pub mod foo {
+                pub fn a() {}
+                pub struct B;
+            }
+        };
+
+        let current: PublicApi = parse_quote! {
+            pub mod bar {
+                pub fn a() {}
+                pub struct B;
+            }
+        };
+
+        let moves = find(previous.items(), current.items());
+
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].from, segments(parse_quote! { foo }));
+        assert_eq!(moves[0].to, segments(parse_quote! { bar }));
+        assert_eq!(moves[0].items.len(), 2);
+    }
+
+    #[test]
+    fn finds_nested_module_move() {
+        let previous: PublicApi = parse_quote! {
+            pub mod foo {
+                pub mod bar {
+                    pub fn a() {}
+                }
+            }
+        };
+
+        let current: PublicApi = parse_quote! {
+            pub mod baz {
+                pub mod bar {
+                    pub fn a() {}
+                }
+            }
+        };
+
+        let moves = find(previous.items(), current.items());
+
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].from, segments(parse_quote! { foo }));
+        assert_eq!(moves[0].to, segments(parse_quote! { baz }));
+    }
+
+    #[test]
+    fn finds_submodule_moved_out_of_reorganized_parent() {
+        let previous: PublicApi = parse_quote! {
+            pub mod foo {
+                pub fn a() {}
+
+                pub mod bar {
+                    pub fn b() {}
+                    pub struct C;
+                }
+            }
+        };
+
+        let current: PublicApi = parse_quote! {
+            pub mod qux {
+                pub fn a(x: u8) {}
+            }
+
+            pub mod baz {
+                pub mod bar {
+                    pub fn b() {}
+                    pub struct C;
+                }
+            }
+        };
+
+        let moves = find(previous.items(), current.items());
+
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].from, segments(parse_quote! { foo::bar }));
+        assert_eq!(moves[0].to, segments(parse_quote! { baz::bar }));
+    }
+
+    #[test]
+    fn ignores_modified_content() {
+        let previous: PublicApi = parse_quote! {
+            pub mod foo {
+                pub fn a() {}
+            }
+        };
+
+        let current: PublicApi = parse_quote! {
+            pub mod bar {
+                pub fn a(b: u8) {}
+            }
+        };
+
+        assert!(find(previous.items(), current.items()).is_empty());
+    }
+
+    #[test]
+    fn ignores_modules_present_in_both_versions() {
+        let previous: PublicApi = parse_quote! {
+            pub mod foo {
+                pub fn a() {}
+            }
+        };
+
+        let current: PublicApi = parse_quote! {
+            pub mod foo {
+                pub fn a() {}
+            }
+
+            pub mod bar {
+                pub fn a() {}
+            }
+        };
+
+        assert!(find(previous.items(), current.items()).is_empty());
+    }
+
+    #[test]
+    fn ignores_renamed_types() {
+        let previous: PublicApi = parse_quote! {
+            pub struct A;
+
+            impl A {
+                pub fn f() {}
+            }
+        };
+
+        let current: PublicApi = parse_quote! {
+            pub struct B;
+
+            impl B {
+                pub fn f() {}
+            }
+        };
+
+        assert!(find(previous.items(), current.items()).is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+
+use syn::Ident;
+
+use crate::{
+    diagnosis::DiagnosisItem,
+    public_api::{module_path, ItemKind, ItemPath},
+    rules::RuleId,
+};
+
+/// Finds the observable differences which don't change the API, and are only
+/// reported with `--pedantic`.
+pub(crate) fn changes(
+    previous: &BTreeMap<ItemPath, ItemKind>,
+    current: &BTreeMap<ItemPath, ItemKind>,
+) -> Vec<DiagnosisItem> {
+    let mut diags = Vec::new();
+
+    for (path, previous_kind) in previous {
+        let current_kind = match current.get(path) {
+            Some(kind) => kind,
+            None => continue,
+        };
+
+        diags.extend(inline_change(
+            path,
+            previous_kind.inline(),
+            current_kind.inline(),
+        ));
+
+        if let (Some(previous_method), Some(current_method)) =
+            (previous_kind.as_method(), current_kind.as_method())
+        {
+            diags.extend(impl_move(
+                path,
+                None,
+                previous_method.module(),
+                current_method.module(),
+            ));
+        }
+
+        if let (Some(previous_type), Some(current_type)) =
+            (previous_kind.as_type(), current_kind.as_type())
+        {
+            for previous_impl in previous_type.traits() {
+                let current_impl = current_type
+                    .traits()
+                    .iter()
+                    .find(|impl_| impl_.trait_name() == previous_impl.trait_name());
+
+                if let Some(current_impl) = current_impl {
+                    diags.extend(impl_move(
+                        path,
+                        Some(previous_impl.trait_name()),
+                        previous_impl.module(),
+                        current_impl.module(),
+                    ));
+                    diags.extend(overrides_change(
+                        path,
+                        previous_impl.trait_name(),
+                        previous_impl.methods(),
+                        current_impl.methods(),
+                    ));
+                }
+            }
+        }
+    }
+
+    diags
+}
+
+fn inline_change(
+    path: &ItemPath,
+    previous: Option<&str>,
+    current: Option<&str>,
+) -> Option<DiagnosisItem> {
+    let message = match (previous, current) {
+        (Some(a), Some(b)) if a == b => return None,
+        (None, None) => return None,
+        (Some(a), Some(b)) => format!("#[{}] changed to #[{}]", a, b),
+        (Some(a), None) => format!("no longer #[{}]", a),
+        (None, Some(b)) => format!("now #[{}]", b),
+    };
+
+    Some(DiagnosisItem::new(RuleId::InlineModified, path.clone(), None).with_message(message))
+}
+
+fn impl_move(
+    path: &ItemPath,
+    trait_name: Option<&Ident>,
+    previous: &[Ident],
+    current: &[Ident],
+) -> Option<DiagnosisItem> {
+    if previous == current {
+        return None;
+    }
+
+    let message = format!(
+        "impl block moved from `{}` to `{}`",
+        module_path(previous),
+        module_path(current)
+    );
+
+    Some(
+        DiagnosisItem::new(RuleId::ImplMoved, path.clone(), trait_name.cloned())
+            .with_message(message),
+    )
+}
+
+fn overrides_change(
+    path: &ItemPath,
+    trait_name: &Ident,
+    previous: &[Ident],
+    current: &[Ident],
+) -> Option<DiagnosisItem> {
+    let names = |methods: Vec<&Ident>| {
+        methods
+            .iter()
+            .map(|method| format!("`{}`", method))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let added = current
+        .iter()
+        .filter(|method| !previous.contains(method))
+        .collect::<Vec<_>>();
+    let removed = previous
+        .iter()
+        .filter(|method| !current.contains(method))
+        .collect::<Vec<_>>();
+
+    let message = match (added.is_empty(), removed.is_empty()) {
+        (true, true) => return None,
+        (false, true) => format!("now overrides {}", names(added)),
+        (true, false) => format!("no longer overrides {}", names(removed)),
+        (false, false) => format!(
+            "now overrides {}, no longer overrides {}",
+            names(added),
+            names(removed)
+        ),
+    };
+
+    Some(
+        DiagnosisItem::new(
+            RuleId::TraitImplOverridesModified,
+            path.clone(),
+            Some(trait_name.clone()),
+        )
+        .with_message(message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::comparator::ApiComparator;
+
+    #[test]
+    fn overrides_are_pedantic() {
+        let comparator: ApiComparator = parse_quote! {
+            {
+                pub struct S;
+                impl Iterator for S {
+                    type Item = u8;
+                    fn next(&mut self) -> Option<u8> { None }
+                    fn count(self) -> usize { 0 }
+                }
+            },
+            {
+                pub struct S;
+                impl Iterator for S {
+                    type Item = u8;
+                    fn next(&mut self) -> Option<u8> { None }
+                    fn nth(&mut self, n: usize) -> Option<u8> { None }
+                }
+            },
+        };
+
+        assert!(comparator.run().is_empty());
+        assert_eq!(
+            comparator.with_pedantic(true).run().to_string(),
+            "≠ S: Iterator (now overrides `nth`, no longer overrides `count`)\n"
+        );
+    }
+
+    #[test]
+    fn inline_hints_are_pedantic() {
+        let comparator: ApiComparator = parse_quote! {
+            {
+                #[inline]
+                pub fn f() {}
+                pub fn g() {}
+            },
+            {
+                #[inline(always)]
+                pub fn f() {}
+                #[inline]
+                pub fn g() {}
+            },
+        };
+
+        assert!(comparator.run().is_empty());
+        assert_eq!(
+            comparator.with_pedantic(true).run().to_string(),
+            "≠ f (#[inline] changed to #[inline(always)])\n\
+             ≠ g (now #[inline])\n"
+        );
+    }
+
+    #[test]
+    fn impl_moves_are_pedantic() {
+        let comparator: ApiComparator = parse_quote! {
+            {
+                pub struct S;
+                impl S {
+                    pub fn f(&self) {}
+                }
+                impl Clone for S {
+                    fn clone(&self) -> S { S }
+                }
+            },
+            {
+                pub struct S;
+                mod impls {
+                    impl crate::S {
+                        pub fn f(&self) {}
+                    }
+                    impl Clone for crate::S {
+                        fn clone(&self) -> crate::S { crate::S }
+                    }
+                }
+            },
+        };
+
+        assert!(comparator.run().is_empty());
+        assert_eq!(
+            comparator.with_pedantic(true).run().to_string(),
+            "≠ S: Clone (impl block moved from `crate` to `crate::impls`)\n\
+             ≠ S::f (impl block moved from `crate` to `crate::impls`)\n"
+        );
+    }
+}
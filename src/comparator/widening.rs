@@ -0,0 +1,138 @@
+use syn::{FnArg, Signature, Type};
+
+use quote::ToTokens;
+
+/// The primitive types each numeric type can be converted to with `From`,
+/// without losing information.
+const WIDENINGS: &[(&str, &[&str])] = &[
+    (
+        "u8",
+        &[
+            "u16", "u32", "u64", "u128", "usize", "i16", "i32", "i64", "i128", "isize",
+        ],
+    ),
+    (
+        "u16",
+        &["u32", "u64", "u128", "usize", "i32", "i64", "i128"],
+    ),
+    ("u32", &["u64", "u128", "i64", "i128"]),
+    ("u64", &["u128", "i128"]),
+    ("i8", &["i16", "i32", "i64", "i128", "isize"]),
+    ("i16", &["i32", "i64", "i128", "isize"]),
+    ("i32", &["i64", "i128"]),
+    ("i64", &["i128"]),
+    ("f32", &["f64"]),
+];
+
+/// Describes the parameters of a function whose type was widened, such as
+/// `u8` becoming `u16`, when they are the only change of its signature.
+///
+/// Arguments of the previous type no longer fit, but the literals do: an
+/// integer literal never becomes a float, so the widenings stay within
+/// integers or within floats.
+pub(crate) fn parameter_widenings(previous: &Signature, current: &Signature) -> Option<String> {
+    if previous.inputs.len() != current.inputs.len() {
+        return None;
+    }
+
+    let mut widened = current.clone();
+    let mut changes = Vec::new();
+
+    for (previous_arg, current_arg) in previous.inputs.iter().zip(widened.inputs.iter_mut()) {
+        let (previous_arg, current_arg) = match (previous_arg, current_arg) {
+            (FnArg::Typed(previous_arg), FnArg::Typed(current_arg)) => (previous_arg, current_arg),
+            _ => continue,
+        };
+
+        if previous_arg.ty == current_arg.ty {
+            continue;
+        }
+
+        let (from, to) = (primitive(&previous_arg.ty)?, primitive(&current_arg.ty)?);
+        if !is_widening(&from, &to) {
+            return None;
+        }
+
+        changes.push(format!(
+            "`{}` widened from `{}` to `{}`",
+            previous_arg.pat.to_token_stream(),
+            from,
+            to
+        ));
+        current_arg.ty = previous_arg.ty.clone();
+    }
+
+    if changes.is_empty() || &widened != previous {
+        return None;
+    }
+
+    Some(format!(
+        "{}, callers passing literals keep compiling",
+        changes.join(", ")
+    ))
+}
+
+fn primitive(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.get_ident().map(ToString::to_string),
+        _ => None,
+    }
+}
+
+fn is_widening(from: &str, to: &str) -> bool {
+    WIDENINGS
+        .iter()
+        .any(|(source, targets)| *source == from && targets.contains(&to))
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn widenings(previous: Signature, current: Signature) -> Option<String> {
+        parameter_widenings(&previous, &current)
+    }
+
+    #[test]
+    fn lossless_widenings() {
+        assert_eq!(
+            widenings(
+                parse_quote! { fn f(a: u8, b: &str, c: f32) },
+                parse_quote! { fn f(a: u16, b: &str, c: f64) },
+            )
+            .as_deref(),
+            Some(
+                "`a` widened from `u8` to `u16`, `c` widened from `f32` to `f64`, \
+                 callers passing literals keep compiling"
+            )
+        );
+    }
+
+    #[test]
+    fn other_changes() {
+        assert_eq!(
+            widenings(
+                parse_quote! { fn f(a: u32) },
+                parse_quote! { fn f(a: usize) }
+            ),
+            None
+        );
+        assert_eq!(
+            widenings(parse_quote! { fn f(a: u8) }, parse_quote! { fn f(a: f32) }),
+            None
+        );
+        assert_eq!(
+            widenings(
+                parse_quote! { fn f(a: u8) },
+                parse_quote! { fn f(a: u16) -> bool }
+            ),
+            None
+        );
+        assert_eq!(
+            widenings(parse_quote! { fn f(a: u8) }, parse_quote! { fn f(b: u16) }),
+            None
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use std::{fs, io::ErrorKind, path::Path, str::FromStr};
+
+use anyhow::{Context, Error as AnyError, Result as AnyResult};
+use serde::Deserialize;
+
+use crate::{
+    comparator::{
+        ConstValuePolicy, DownstreamLintPolicy, ExtensionPolicy, SemverBump, StabilityTiers,
+    },
+    public_api::{ItemPattern, PathPrefix},
+};
+
+/// The configuration file, looked up in the crate directory.
+pub(crate) const CONFIG_FILE: &str = "cargo-breaking.toml";
+
+/// The table of the crate manifest, under `[package.metadata]`, which holds
+/// the same settings as the configuration file.
+const METADATA_TABLE: &str = "cargo-breaking";
+
+/// Settings which are read from the configuration file and from the
+/// `[package.metadata.cargo-breaking]` table of the crate manifest. Every
+/// setting is optional.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    /// The reference to compare against when none is given on the command
+    /// line.
+    pub against: Option<String>,
+    /// The features enabled when reading both versions, in addition to the
+    /// default ones.
+    pub features: Vec<String>,
+    /// The items which are not compared, along with everything they contain.
+    pub ignore: Vec<PathPrefix>,
+    /// How the variants and fields added to `#[non_exhaustive]` types, and
+    /// the defaulted generic parameters added to types, affect the next
+    /// version.
+    pub extensions: Option<ExtensionPolicy>,
+    /// Whether the changes of the values of associated constants are
+    /// breaking.
+    #[serde(rename = "const-values")]
+    pub const_values: Option<ConstValuePolicy>,
+    /// How the attributes giving new warnings to the code using the items
+    /// affect the next version.
+    #[serde(rename = "downstream-lints")]
+    pub downstream_lints: Option<DownstreamLintPolicy>,
+    /// Whether the parameters whose numeric type was widened are reported
+    /// with their own rule.
+    #[serde(rename = "numeric-widening")]
+    pub numeric_widening: Option<bool>,
+    /// How many levels of dependencies the re-exported items are followed
+    /// into.
+    #[serde(rename = "foreign-depth")]
+    pub foreign_depth: Option<usize>,
+    /// The modules and items which are not stable, and whose changes affect
+    /// the next version less.
+    pub stability: StabilityTiers,
+    /// The items which are intentionally broken for the next major release.
+    #[serde(rename = "expected-breaking")]
+    pub expected_breaking: Vec<ItemPattern>,
+    pub backport: BackportConfig,
+}
+
+impl Config {
+    /// Loads the configuration of the crate located in `dir`. The settings of
+    /// the configuration file take precedence over the ones of the manifest.
+    pub(crate) fn load(dir: &Path) -> AnyResult<Config> {
+        let path = dir.join(CONFIG_FILE);
+        let file = match read_if_exists(&path)? {
+            Some(content) => content
+                .parse()
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+            None => Config::default(),
+        };
+
+        let path = dir.join("Cargo.toml");
+        let metadata = match read_if_exists(&path)? {
+            Some(content) => Config::from_manifest(&content)
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+            None => Config::default(),
+        };
+
+        Ok(file.merge(metadata))
+    }
+
+    /// Reads the settings of the `[package.metadata.cargo-breaking]` table of
+    /// a crate manifest.
+    fn from_manifest(s: &str) -> AnyResult<Config> {
+        let manifest = toml::from_str::<toml::Value>(s)?;
+
+        let table = manifest
+            .get("package")
+            .and_then(|package| package.get("metadata"))
+            .and_then(|metadata| metadata.get(METADATA_TABLE));
+
+        match table {
+            Some(table) => table.clone().try_into().map_err(Into::into),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// Adds the settings of `other` to the ones of `self`. Lists are
+    /// concatenated, and `self` wins for the other settings. As the first
+    /// matching backport policy is used, the ones of `self` come first.
+    fn merge(mut self, other: Config) -> Config {
+        self.against = self.against.or(other.against);
+        self.extensions = self.extensions.or(other.extensions);
+        self.const_values = self.const_values.or(other.const_values);
+        self.downstream_lints = self.downstream_lints.or(other.downstream_lints);
+        self.numeric_widening = self.numeric_widening.or(other.numeric_widening);
+        self.foreign_depth = self.foreign_depth.or(other.foreign_depth);
+        self.features.extend(other.features);
+        self.ignore.extend(other.ignore);
+        self.expected_breaking.extend(other.expected_breaking);
+        self.stability.extend(other.stability);
+        self.backport.branches.extend(other.backport.branches);
+
+        self
+    }
+}
+
+fn read_if_exists(path: &Path) -> AnyResult<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+impl FromStr for Config {
+    type Err = AnyError;
+
+    fn from_str(s: &str) -> AnyResult<Config> {
+        toml::from_str(s).map_err(Into::into)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct BackportConfig {
+    /// The highest change allowed on the branches matching each pattern. The
+    /// first matching pattern wins.
+    pub branches: Vec<BranchPolicy>,
+}
+
+impl BackportConfig {
+    /// Returns the highest change allowed on `branch`. Branches which match
+    /// no pattern only accept patch-level changes.
+    pub(crate) fn allowed_bump(&self, branch: &str) -> SemverBump {
+        self.branches
+            .iter()
+            .find(|policy| crate::glob::matches(&policy.pattern, branch))
+            .map_or(SemverBump::Patch, |policy| policy.allow)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct BranchPolicy {
+    pub pattern: String,
+    pub allow: SemverBump,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_config() {
+        assert_eq!(Config::from_str("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(Config::from_str("[backprot]\n").is_err());
+    }
+
+    #[test]
+    fn missing_config_file() {
+        let dir = Path::new("this/directory/does/not/exist");
+
+        assert_eq!(Config::load(dir).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn manifest_metadata() {
+        let config = Config::from_manifest(
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [package.metadata.cargo-breaking]
+            against = "v1.0.0"
+            features = ["serde"]
+            ignore = ["internal"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.against.as_deref(), Some("v1.0.0"));
+        assert_eq!(config.features, ["serde"]);
+        assert_eq!(config.ignore, ["internal".parse().unwrap()]);
+    }
+
+    #[test]
+    fn manifest_without_metadata() {
+        let config = Config::from_manifest(
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [package.metadata.docs.rs]
+            all-features = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config, Config::default());
+    }
+
+    #[test]
+    fn extension_policy() {
+        let config = Config::from_str("extensions = \"patch\"").unwrap();
+
+        assert_eq!(config.extensions, Some(ExtensionPolicy::Patch));
+        assert!(Config::from_str("extensions = \"major\"").is_err());
+    }
+
+    #[test]
+    fn const_value_policy() {
+        let config = Config::from_str("const-values = \"informational\"").unwrap();
+
+        assert_eq!(config.const_values, Some(ConstValuePolicy::Informational));
+        assert!(Config::from_str("const-values = \"minor\"").is_err());
+    }
+
+    #[test]
+    fn downstream_lint_policy() {
+        let config = Config::from_str("downstream-lints = \"breaking\"").unwrap();
+
+        assert_eq!(
+            config.downstream_lints,
+            Some(DownstreamLintPolicy::Breaking)
+        );
+        assert!(Config::from_str("downstream-lints = \"major\"").is_err());
+    }
+
+    #[test]
+    fn numeric_widening() {
+        let config = Config::from_str("numeric-widening = true").unwrap();
+
+        assert_eq!(config.numeric_widening, Some(true));
+    }
+
+    #[test]
+    fn foreign_depth() {
+        let config = Config::from_str("foreign-depth = 2").unwrap();
+
+        assert_eq!(config.foreign_depth, Some(2));
+        assert!(Config::from_str("foreign-depth = -1").is_err());
+    }
+
+    #[test]
+    fn stability_tiers() {
+        let config = Config::from_str(
+            r#"
+            [stability]
+            preview = ["experimental"]
+            internal = ["raw", "api::ffi"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.stability.preview, ["experimental".parse().unwrap()]);
+        assert_eq!(config.stability.internal.len(), 2);
+        assert!(config.stability.stable.is_empty());
+        assert!(Config::from_str("[stability]\nunstable = [\"a\"]").is_err());
+    }
+
+    #[test]
+    fn expected_breaking_items() {
+        let config = Config::from_str("expected-breaking = [\"foo::Bar\", \"baz::*\"]").unwrap();
+
+        assert_eq!(
+            config.expected_breaking,
+            ["foo::Bar".parse().unwrap(), "baz::*".parse().unwrap()]
+        );
+    }
+
+    #[test]
+    fn invalid_ignored_path() {
+        assert!(Config::from_str("ignore = [\"a::\"]").is_err());
+    }
+
+    #[test]
+    fn file_takes_precedence_over_manifest() {
+        let file = Config::from_str(
+            r#"
+            against = "release"
+            features = ["a"]
+
+            [[backport.branches]]
+            pattern = "release-*"
+            allow = "minor"
+            "#,
+        )
+        .unwrap();
+        let metadata = Config::from_str(
+            r#"
+            against = "main"
+            features = ["b"]
+
+            [[backport.branches]]
+            pattern = "*"
+            allow = "patch"
+            "#,
+        )
+        .unwrap();
+
+        let config = file.merge(metadata);
+
+        assert_eq!(config.against.as_deref(), Some("release"));
+        assert_eq!(config.features, ["a", "b"]);
+        assert_eq!(config.backport.allowed_bump("release-1"), SemverBump::Minor);
+    }
+
+    #[test]
+    fn branch_policies() {
+        let config = Config::from_str(
+            r#"
+            [[backport.branches]]
+            pattern = "release-0.*"
+            allow = "minor"
+
+            [[backport.branches]]
+            pattern = "release-*"
+            allow = "patch"
+
+            [[backport.branches]]
+            pattern = "next"
+            allow = "major"
+            "#,
+        )
+        .unwrap();
+
+        let backport = config.backport;
+        assert_eq!(backport.allowed_bump("release-0.4"), SemverBump::Minor);
+        assert_eq!(backport.allowed_bump("release-1.2"), SemverBump::Patch);
+        assert_eq!(backport.allowed_bump("next"), SemverBump::Major);
+        assert_eq!(backport.allowed_bump("hotfix"), SemverBump::Patch);
+    }
+
+    #[test]
+    fn breaking_is_an_alias_for_major() {
+        let config = Config::from_str(
+            r#"
+            [[backport.branches]]
+            pattern = "*"
+            allow = "breaking"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.backport.allowed_bump("main"), SemverBump::Major);
+    }
+}
@@ -0,0 +1,357 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context, Result as AnyResult};
+use serde::Deserialize;
+
+use crate::{
+    archive,
+    comparator::{ApiComparator, ApiCompatibilityDiagnostics, SemverBump},
+    error::panic_message,
+    git::TempCheckout,
+    glue::{self, ExtractionConfig},
+    rules::RuleId,
+};
+
+/// Where the crate archives are downloaded from, unless the corpus says
+/// otherwise. `{registry}/{name}/{name}-{version}.crate` is downloaded.
+const DEFAULT_REGISTRY: &str = "https://static.crates.io/crates";
+
+/// The corpus used when no file is given: popular crates with various kinds of
+/// API, each at two adjacent published versions. Only the absence of panics
+/// and errors is checked.
+const DEFAULT_CORPUS: &str = r#"
+[[crates]]
+name = "either"
+previous = "1.8.0"
+current = "1.8.1"
+
+[[crates]]
+name = "itoa"
+previous = "1.0.5"
+current = "1.0.6"
+
+[[crates]]
+name = "bitflags"
+previous = "1.3.1"
+current = "1.3.2"
+
+[[crates]]
+name = "log"
+previous = "0.4.17"
+current = "0.4.18"
+
+[[crates]]
+name = "semver"
+previous = "1.0.16"
+current = "1.0.17"
+
+[[crates]]
+name = "anyhow"
+previous = "1.0.68"
+current = "1.0.69"
+"#;
+
+/// A list of published crates to compare, in order to catch the regressions of
+/// cargo-breaking itself.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Corpus {
+    #[serde(default = "default_registry")]
+    registry: String,
+    crates: Vec<CorpusCrate>,
+}
+
+fn default_registry() -> String {
+    DEFAULT_REGISTRY.to_owned()
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CorpusCrate {
+    name: String,
+    previous: String,
+    current: String,
+    /// The change the comparison must find, if any.
+    expect: Option<SemverBump>,
+    /// The rules which must be reported.
+    #[serde(default)]
+    rules: Vec<String>,
+}
+
+impl Corpus {
+    fn parse(s: &str) -> AnyResult<Corpus> {
+        let corpus = toml::from_str::<Corpus>(s)?;
+
+        for krate in &corpus.crates {
+            if let Some(rule) = krate
+                .rules
+                .iter()
+                .find(|rule| RuleId::from_id(rule).is_none())
+            {
+                bail!("Unknown rule `{}` expected for `{}`", rule, krate.name);
+            }
+        }
+
+        Ok(corpus)
+    }
+
+    fn load(path: Option<&Path>) -> AnyResult<Corpus> {
+        match path {
+            Some(path) => fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))
+                .and_then(|content| Corpus::parse(&content))
+                .with_context(|| format!("Failed to load the corpus of {}", path.display())),
+            None => Corpus::parse(DEFAULT_CORPUS),
+        }
+    }
+}
+
+/// Compares the two versions of each crate of the corpus read from `path`, or
+/// of the default one. Fails if a comparison panics, fails, or does not find
+/// what the corpus expects.
+pub(crate) fn run(path: Option<&Path>, config: &ExtractionConfig) -> AnyResult<()> {
+    let corpus = Corpus::load(path)?;
+    let mut failures = 0;
+
+    for krate in &corpus.crates {
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+            compare(&corpus.registry, krate, config)
+        }));
+
+        let problems = match outcome {
+            Ok(Ok(diagnosis)) => krate.mismatches(&diagnosis),
+            Ok(Err(e)) => vec![format!("{:#}", e)],
+            Err(payload) => vec![format!("panicked: {}", panic_message(&*payload))],
+        };
+
+        if problems.is_empty() {
+            println!("ok      {}", krate);
+        } else {
+            failures += 1;
+            println!("FAILED  {}", krate);
+            problems
+                .iter()
+                .for_each(|problem| println!("        {}", problem));
+        }
+    }
+
+    if failures > 0 {
+        bail!(
+            "{} of the {} crates of the corpus failed",
+            failures,
+            corpus.crates.len()
+        );
+    }
+
+    Ok(())
+}
+
+fn compare(
+    registry: &str,
+    krate: &CorpusCrate,
+    config: &ExtractionConfig,
+) -> AnyResult<ApiCompatibilityDiagnostics> {
+    let downloads = TempCheckout::new(PathBuf::new());
+    fs::create_dir_all(downloads.crate_dir())?;
+
+    let extract = |version: &str, label: &str| {
+        let url = format!(
+            "{registry}/{name}/{name}-{version}.crate",
+            registry = registry.trim_end_matches('/'),
+            name = krate.name,
+            version = version
+        );
+        let archive = downloads
+            .crate_dir()
+            .join(format!("{}-{}.crate", krate.name, version));
+
+        download(&url, &archive)?;
+        let checkout = archive::unpack(&archive)?;
+
+        glue::extract_api(config, checkout.crate_dir(), label)
+            .with_context(|| format!("Failed to extract the API of {}", version))
+    };
+
+    let previous = extract(&krate.previous, "previous")?;
+    let current = extract(&krate.current, "current")?;
+
+    Ok(ApiComparator::new(previous, current).run())
+}
+
+fn download(url: &str, destination: &Path) -> AnyResult<()> {
+    let status = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--location",
+            "--connect-timeout",
+            "30",
+            "--output",
+        ])
+        .arg(destination)
+        .arg(url)
+        .status()
+        .context("Failed to run curl")?;
+
+    if !status.success() {
+        bail!("Failed to download {}", url);
+    }
+
+    Ok(())
+}
+
+impl CorpusCrate {
+    // What the comparison found, but the corpus did not expect.
+    fn mismatches(&self, diagnosis: &ApiCompatibilityDiagnostics) -> Vec<String> {
+        let mut mismatches = Vec::new();
+
+        if let Some(expected) = self.expect {
+            let found = diagnosis.required_bump();
+            if found != expected {
+                mismatches.push(format!(
+                    "expected a {} change, found a {} change",
+                    expected, found
+                ));
+            }
+        }
+
+        for rule in &self.rules {
+            if !diagnosis.items().any(|diag| diag.rule().rule().id == rule) {
+                mismatches.push(format!("expected `{}` to be reported", rule));
+            }
+        }
+
+        mismatches
+    }
+}
+
+impl Display for CorpusCrate {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{} {} -> {}", self.name, self.previous, self.current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use syn::parse_quote;
+
+    use crate::glue::Backend;
+
+    use super::*;
+
+    #[test]
+    fn default_corpus_is_valid() {
+        let corpus = Corpus::load(None).unwrap();
+
+        assert_eq!(corpus.registry, DEFAULT_REGISTRY);
+        assert!(!corpus.crates.is_empty());
+    }
+
+    #[test]
+    fn unknown_rules_are_rejected() {
+        let corpus = r#"
+            [[crates]]
+            name = "foo"
+            previous = "1.0.0"
+            current = "1.0.1"
+            rules = ["not-a-rule"]
+        "#;
+
+        assert!(Corpus::parse(corpus).is_err());
+    }
+
+    #[test]
+    fn expectations_are_checked() {
+        let krate = CorpusCrate {
+            name: "foo".to_owned(),
+            previous: "1.0.0".to_owned(),
+            current: "1.1.0".to_owned(),
+            expect: Some(SemverBump::Minor),
+            rules: vec!["item-added".to_owned(), "item-removed".to_owned()],
+        };
+
+        let diagnosis: ApiCompatibilityDiagnostics = parse_quote! {
+            {},
+            {
+                pub fn f() {}
+            },
+        };
+        assert_eq!(
+            krate.mismatches(&diagnosis),
+            ["expected `item-removed` to be reported"]
+        );
+
+        let diagnosis: ApiCompatibilityDiagnostics = parse_quote! {
+            {
+                pub fn f() {}
+            },
+            {},
+        };
+        assert_eq!(
+            krate.mismatches(&diagnosis),
+            [
+                "expected a minor change, found a breaking change",
+                "expected `item-added` to be reported"
+            ]
+        );
+    }
+
+    #[test]
+    fn crates_are_downloaded_and_compared() {
+        let dir = env::temp_dir().join(format!("cb-test-corpus-{}", process::id()));
+
+        for (version, code) in [
+            ("1.0.0", "pub fn f() {}"),
+            ("1.1.0", "pub fn f() {} pub fn g() {}"),
+        ] {
+            let name = format!("foo-{}", version);
+            fs::create_dir_all(dir.join(&name).join("src")).unwrap();
+            fs::write(
+                dir.join(&name).join("Cargo.toml"),
+                format!("[package]\nname = \"foo\"\nversion = \"{}\"\n", version),
+            )
+            .unwrap();
+            fs::write(dir.join(&name).join("src/lib.rs"), code).unwrap();
+
+            fs::create_dir_all(dir.join("registry/foo")).unwrap();
+            let status = Command::new("tar")
+                .arg("-czf")
+                .arg(dir.join("registry/foo").join(format!("{}.crate", name)))
+                .arg("-C")
+                .arg(&dir)
+                .arg(&name)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+
+        let corpus = format!(
+            "registry = \"file://{}\"\n\n\
+             [[crates]]\nname = \"foo\"\nprevious = \"1.0.0\"\ncurrent = \"1.1.0\"\n\
+             expect = \"minor\"\nrules = [\"item-added\"]\n\n\
+             [[crates]]\nname = \"foo\"\nprevious = \"1.0.0\"\ncurrent = \"9.9.9\"\n",
+            dir.join("registry").display()
+        );
+        fs::write(dir.join("corpus.toml"), corpus).unwrap();
+
+        let config = ExtractionConfig {
+            backend: Backend::Source,
+            ..ExtractionConfig::default()
+        };
+
+        // The second crate can't be downloaded.
+        let error = run(Some(&dir.join("corpus.toml")), &config).unwrap_err();
+        assert_eq!(error.to_string(), "1 of the 2 crates of the corpus failed");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
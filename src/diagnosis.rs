@@ -1,5 +1,6 @@
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
+use serde::{ser::SerializeStruct, Serialize, Serializer};
 use syn::Ident;
 
 #[cfg(test)]
@@ -8,7 +9,10 @@ use syn::{
     Token,
 };
 
-use crate::public_api::ItemPath;
+use crate::{
+    public_api::ItemPath,
+    rules::{Audience, RuleId, Severity},
+};
 
 pub struct DiagnosisCollector {
     inner: Vec<DiagnosisItem>,
@@ -29,8 +33,10 @@ impl DiagnosisCollector {
 }
 
 pub(crate) trait DiagnosticGenerator {
+    const RULES: DiagnosisRules = DiagnosisRules::ITEM;
+
     fn removal_diagnosis(&self, path: &ItemPath, collector: &mut DiagnosisCollector) {
-        collector.add(DiagnosisItem::removal(path.clone(), None));
+        collector.add(DiagnosisItem::new(Self::RULES.removal, path.clone(), None));
     }
 
     fn modification_diagnosis(
@@ -39,11 +45,57 @@ pub(crate) trait DiagnosticGenerator {
         path: &ItemPath,
         collector: &mut DiagnosisCollector,
     ) {
-        collector.add(DiagnosisItem::modification(path.clone(), None));
+        collector.add(DiagnosisItem::new(
+            Self::RULES.modification,
+            path.clone(),
+            None,
+        ));
     }
 
     fn addition_diagnosis(&self, path: &ItemPath, collector: &mut DiagnosisCollector) {
-        collector.add(DiagnosisItem::addition(path.clone(), None));
+        collector.add(DiagnosisItem::new(Self::RULES.addition, path.clone(), None));
+    }
+}
+
+/// The rules a `DiagnosticGenerator` reports its removals, modifications and
+/// additions with.
+pub(crate) struct DiagnosisRules {
+    pub(crate) removal: RuleId,
+    pub(crate) modification: RuleId,
+    pub(crate) addition: RuleId,
+}
+
+impl DiagnosisRules {
+    pub(crate) const ITEM: DiagnosisRules = DiagnosisRules {
+        removal: RuleId::ItemRemoved,
+        modification: RuleId::ItemModified,
+        addition: RuleId::ItemAdded,
+    };
+
+    pub(crate) const TRAIT_IMPL: DiagnosisRules = DiagnosisRules {
+        removal: RuleId::TraitImplRemoved,
+        modification: RuleId::TraitImplModified,
+        addition: RuleId::TraitImplAdded,
+    };
+
+    pub(crate) const TRAIT_ITEM: DiagnosisRules = DiagnosisRules {
+        removal: RuleId::TraitItemRemoved,
+        modification: RuleId::TraitItemModified,
+        addition: RuleId::TraitItemAdded,
+    };
+
+    pub(crate) const PRIVATE_ITEM: DiagnosisRules = DiagnosisRules {
+        removal: RuleId::PrivateItemRemoved,
+        modification: RuleId::PrivateItemModified,
+        addition: RuleId::PrivateItemAdded,
+    };
+
+    fn for_kind(&self, kind: DiagnosisItemKind) -> RuleId {
+        match kind {
+            DiagnosisItemKind::Removal => self.removal,
+            DiagnosisItemKind::Modification => self.modification,
+            DiagnosisItemKind::Addition => self.addition,
+        }
     }
 }
 
@@ -52,43 +104,141 @@ pub(crate) struct DiagnosisItem {
     kind: DiagnosisItemKind,
     path: ItemPath,
     trait_impl: Option<Ident>,
+    rule: RuleId,
+    // The default severity of the rule, unless the configuration says
+    // otherwise.
+    severity: Severity,
+    message: Option<String>,
+    details: Vec<String>,
+    // The other public paths of the item, only displayed in the JSON output.
+    aliases: Vec<ItemPath>,
+    // Set for the breaking changes declared in the `expected-breaking`
+    // setting of the configuration, or annotated in the code.
+    expected: bool,
+    // The reason given by the annotation of the item.
+    expected_reason: Option<String>,
+    // The previous and the current signature of functions and methods, as
+    // they are displayed.
+    previous_signature: Option<String>,
+    current_signature: Option<String>,
 }
 
 impl DiagnosisItem {
-    pub(crate) fn removal(path: ItemPath, trait_impl: Option<Ident>) -> DiagnosisItem {
+    pub(crate) fn new(rule: RuleId, path: ItemPath, trait_impl: Option<Ident>) -> DiagnosisItem {
         DiagnosisItem {
-            kind: DiagnosisItemKind::Removal,
+            kind: rule.rule().kind,
             path,
             trait_impl,
+            rule,
+            severity: rule.rule().default_severity,
+            message: None,
+            details: Vec::new(),
+            aliases: Vec::new(),
+            expected: false,
+            expected_reason: None,
+            previous_signature: None,
+            current_signature: None,
         }
     }
 
-    pub(crate) fn modification(path: ItemPath, trait_impl: Option<Ident>) -> DiagnosisItem {
-        DiagnosisItem {
-            kind: DiagnosisItemKind::Modification,
-            path,
-            trait_impl,
-        }
+    /// Adds a short explanation, displayed right after the item path.
+    pub(crate) fn with_message(mut self, message: impl Into<String>) -> DiagnosisItem {
+        self.message = Some(message.into());
+        self
     }
 
-    pub(crate) fn addition(path: ItemPath, trait_impl: Option<Ident>) -> DiagnosisItem {
-        DiagnosisItem {
-            kind: DiagnosisItemKind::Addition,
-            path,
-            trait_impl,
-        }
+    /// Adds lines which are displayed only in verbose mode.
+    pub(crate) fn with_details(mut self, details: Vec<String>) -> DiagnosisItem {
+        self.details = details;
+        self
+    }
+
+    /// Sets the other public paths the item can be named with.
+    pub(crate) fn with_aliases(mut self, aliases: Vec<ItemPath>) -> DiagnosisItem {
+        self.aliases = aliases;
+        self
     }
 
-    pub(crate) fn is_removal(&self) -> bool {
-        self.kind == DiagnosisItemKind::Removal
+    /// Sets the previous and the current signature of the item, when it is a
+    /// function or a method.
+    pub(crate) fn with_signatures(
+        mut self,
+        previous: Option<String>,
+        current: Option<String>,
+    ) -> DiagnosisItem {
+        self.previous_signature = previous;
+        self.current_signature = current;
+        self
     }
 
-    pub(crate) fn is_modification(&self) -> bool {
-        self.kind == DiagnosisItemKind::Modification
+    pub(crate) fn has_signatures(&self) -> bool {
+        self.previous_signature.is_some() || self.current_signature.is_some()
     }
 
-    pub(crate) fn is_addition(&self) -> bool {
-        self.kind == DiagnosisItemKind::Addition
+    /// Labels the change as intended.
+    pub(crate) fn into_expected(mut self) -> DiagnosisItem {
+        self.expected = true;
+        self
+    }
+
+    /// Labels the change as intended, for the reason given in the code.
+    pub(crate) fn into_annotated(mut self, reason: impl Into<String>) -> DiagnosisItem {
+        self.expected = true;
+        self.expected_reason = Some(reason.into());
+        self
+    }
+
+    /// Reports the same change, for an item which is not reachable from
+    /// outside of the crate.
+    pub(crate) fn into_private(mut self) -> DiagnosisItem {
+        self.rule = DiagnosisRules::PRIVATE_ITEM.for_kind(self.kind);
+        self.severity = self.rule.rule().default_severity;
+        self.message = Some(match self.message {
+            Some(message) => format!("private, {}", message),
+            None => "private".to_owned(),
+        });
+
+        self
+    }
+
+    pub(crate) fn path(&self) -> &ItemPath {
+        &self.path
+    }
+
+    pub(crate) fn kind(&self) -> DiagnosisItemKind {
+        self.kind
+    }
+
+    pub(crate) fn trait_impl(&self) -> Option<&Ident> {
+        self.trait_impl.as_ref()
+    }
+
+    pub(crate) fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    pub(crate) fn details(&self) -> &[String] {
+        &self.details
+    }
+
+    pub(crate) fn rule(&self) -> RuleId {
+        self.rule
+    }
+
+    pub(crate) fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub(crate) fn signatures(&self) -> (Option<&str>, Option<&str>) {
+        (
+            self.previous_signature.as_deref(),
+            self.current_signature.as_deref(),
+        )
+    }
+
+    pub(crate) fn with_severity(mut self, severity: Severity) -> DiagnosisItem {
+        self.severity = severity;
+        self
     }
 }
 
@@ -97,10 +247,55 @@ impl Display for DiagnosisItem {
         write!(f, "{} {}", self.kind, self.path)?;
 
         if let Some(trait_) = &self.trait_impl {
-            write!(f, ": {}", trait_)
-        } else {
-            Ok(())
+            write!(f, ": {}", trait_)?;
+        }
+
+        if let Some(message) = &self.message {
+            write!(f, " ({})", message)?;
+        }
+
+        match &self.expected_reason {
+            Some(reason) => write!(f, " [expected: {}]", reason)?,
+            None if self.expected => write!(f, " [expected]")?,
+            None => {}
+        }
+
+        if f.alternate() {
+            self.details
+                .iter()
+                .try_for_each(|detail| write!(f, "\n    {}", detail))?;
         }
+
+        Ok(())
+    }
+}
+
+impl Serialize for DiagnosisItem {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Diagnosis", 10)?;
+
+        s.serialize_field("kind", &self.kind)?;
+        s.serialize_field("path", &self.path.to_string())?;
+        s.serialize_field(
+            "trait_impl",
+            &self.trait_impl.as_ref().map(ToString::to_string),
+        )?;
+        s.serialize_field("rule", self.rule.rule().id)?;
+        s.serialize_field("severity", &self.severity())?;
+        s.serialize_field("message", &self.message)?;
+        s.serialize_field("details", &self.details)?;
+        s.serialize_field(
+            "aliases",
+            &self
+                .aliases
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        )?;
+        s.serialize_field("expected", &self.expected)?;
+        s.serialize_field("expected_reason", &self.expected_reason)?;
+
+        s.end()
     }
 }
 
@@ -119,16 +314,99 @@ impl Parse for DiagnosisItem {
             None
         };
 
-        Ok(DiagnosisItem {
-            kind,
-            path,
-            trait_impl,
-        })
+        let rules = if trait_impl.is_some() {
+            DiagnosisRules::TRAIT_IMPL
+        } else {
+            DiagnosisRules::ITEM
+        };
+
+        Ok(DiagnosisItem::new(rules.for_kind(kind), path, trait_impl))
+    }
+}
+
+/// A change reported by cargo-breaking, as seen by the code embedding it.
+#[derive(Clone, Copy, Debug)]
+pub struct Diagnostic<'a> {
+    item: &'a DiagnosisItem,
+}
+
+impl<'a> Diagnostic<'a> {
+    pub(crate) fn new(item: &'a DiagnosisItem) -> Diagnostic<'a> {
+        Diagnostic { item }
+    }
+
+    /// The segments of the path of the item, from the crate root. It is empty
+    /// for the crate root itself.
+    pub fn path(&self) -> Vec<String> {
+        self.item
+            .path
+            .segments()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// The trait, when the change is about one of its implementations.
+    pub fn trait_impl(&self) -> Option<String> {
+        self.item.trait_impl.as_ref().map(ToString::to_string)
+    }
+
+    pub fn kind(&self) -> DiagnosisItemKind {
+        self.item.kind
+    }
+
+    /// The identifier of the rule, such as `item-removed`.
+    pub fn rule(&self) -> &'static str {
+        self.item.rule.rule().id
+    }
+
+    /// The effect of the change on the next version, once the configuration
+    /// is applied.
+    pub fn severity(&self) -> Severity {
+        self.item.severity
+    }
+
+    pub fn audience(&self) -> Audience {
+        self.item.rule.rule().audience
+    }
+
+    /// The short explanation displayed after the item path.
+    pub fn message(&self) -> Option<&'a str> {
+        self.item.message()
+    }
+
+    /// The signature of the function or method before the change.
+    pub fn previous_signature(&self) -> Option<&'a str> {
+        self.item.previous_signature.as_deref()
+    }
+
+    /// The signature of the function or method after the change.
+    pub fn current_signature(&self) -> Option<&'a str> {
+        self.item.current_signature.as_deref()
+    }
+
+    /// Whether the change is declared in the `expected-breaking` setting, or
+    /// annotated in the code.
+    pub fn is_expected(&self) -> bool {
+        self.item.expected
+    }
+
+    /// The reason given by the annotation of the item.
+    pub fn expected_reason(&self) -> Option<&'a str> {
+        self.item.expected_reason.as_deref()
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialOrd, PartialEq)]
-enum DiagnosisItemKind {
+impl Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        Display::fmt(self.item, f)
+    }
+}
+
+/// Whether an item was removed, modified or added.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialOrd, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosisItemKind {
     Removal,
     Modification,
     Addition,
@@ -198,6 +476,38 @@ mod tests {
         assert_eq!(diag.to_string(), "+ foo::bar::Baz");
     }
 
+    #[test]
+    fn display_message() {
+        let diag: DiagnosisItem = parse_quote! {
+            <> foo
+        };
+        let diag = diag.with_message("moved to bar, 2 items");
+
+        assert_eq!(diag.to_string(), "≠ foo (moved to bar, 2 items)");
+    }
+
+    #[test]
+    fn details_are_displayed_in_alternate_mode_only() {
+        let diag: DiagnosisItem = parse_quote! {
+            - foo
+        };
+        let diag = diag.with_details(vec!["a".to_owned(), "b".to_owned()]);
+
+        assert_eq!(diag.to_string(), "- foo");
+        assert_eq!(format!("{:#}", diag), "- foo\n    a\n    b");
+    }
+
+    #[test]
+    fn private_items_are_informational() {
+        let diag: DiagnosisItem = parse_quote! {
+            <> foo::Bar: impl Foo
+        };
+        let diag = diag.into_private();
+
+        assert_eq!(diag.severity(), Severity::Informational);
+        assert_eq!(diag.to_string(), "≠ foo::Bar: Foo (private)");
+    }
+
     #[test]
     fn display_implementation_trait_impl() {
         let diag: DiagnosisItem = parse_quote! {
@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use syn::{
+    parse::Parser,
+    punctuated::Punctuated,
+    token::Comma,
+    visit::{self, Visit},
+    Attribute, Block, Expr, ExprLit, ExprMacro, ExprMethodCall, Ident, ItemEnum, ItemImpl, ItemMod,
+    ItemStruct, Lit, Macro, Meta, Type, Visibility,
+};
+
+use crate::{
+    ast::CrateAst,
+    diagnosis::DiagnosisItem,
+    public_api::{display_code, ItemPath},
+    rules::RuleId,
+};
+
+/// The `Display` outputs the documentation of a type asserts, by the
+/// expression which is displayed.
+pub(crate) type DisplayExamples = BTreeMap<String, String>;
+
+/// Finds the `Display` outputs asserted in the examples of the documentation
+/// of the public types, and of their `Display` implementations: the
+/// `assert_eq!`s comparing `value.to_string()` or `format!("{}", value)` to a
+/// string literal. The examples are read from the source files.
+pub(crate) fn extract(ast: &CrateAst) -> BTreeMap<ItemPath, DisplayExamples> {
+    let mut visitor = DocVisitor::default();
+    visitor.visit_file(ast.ast());
+
+    let public = visitor.public;
+    visitor
+        .examples
+        .into_iter()
+        .filter(|(path, examples)| public.contains(path) && !examples.is_empty())
+        .collect()
+}
+
+#[derive(Default)]
+struct DocVisitor {
+    path: Vec<Ident>,
+    public: BTreeSet<ItemPath>,
+    examples: BTreeMap<ItemPath, DisplayExamples>,
+}
+
+impl DocVisitor {
+    fn path_of(&self, ident: &Ident) -> ItemPath {
+        let mut path = self.path.clone();
+        path.push(ident.clone());
+        ItemPath::from_segments(path)
+    }
+
+    fn add_type(&mut self, ident: &Ident, vis: &Visibility, attrs: &[Attribute]) {
+        let path = self.path_of(ident);
+
+        if matches!(vis, Visibility::Public(_)) {
+            self.public.insert(path.clone());
+        }
+
+        self.examples
+            .entry(path)
+            .or_default()
+            .extend(examples(attrs));
+    }
+}
+
+impl<'ast> Visit<'ast> for DocVisitor {
+    fn visit_item_mod(&mut self, mod_: &'ast ItemMod) {
+        if matches!(mod_.vis, Visibility::Public(_)) {
+            self.path.push(mod_.ident.clone());
+            visit::visit_item_mod(self, mod_);
+            self.path.pop();
+        }
+    }
+
+    fn visit_item_struct(&mut self, struct_: &'ast ItemStruct) {
+        self.add_type(&struct_.ident, &struct_.vis, &struct_.attrs);
+    }
+
+    fn visit_item_enum(&mut self, enum_: &'ast ItemEnum) {
+        self.add_type(&enum_.ident, &enum_.vis, &enum_.attrs);
+    }
+
+    // The implementation is expected in the module of the type.
+    fn visit_item_impl(&mut self, impl_: &'ast ItemImpl) {
+        let is_display = impl_.trait_.as_ref().is_some_and(|(_, trait_, _)| {
+            trait_
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Display")
+        });
+
+        let type_name = match &*impl_.self_ty {
+            Type::Path(ty) if is_display => ty.path.segments.last().map(|s| s.ident.clone()),
+            _ => None,
+        };
+
+        if let Some(type_name) = type_name {
+            let attrs = impl_
+                .attrs
+                .iter()
+                .chain(impl_.items.iter().flat_map(|item| match item {
+                    syn::ImplItem::Method(method) => method.attrs.as_slice(),
+                    _ => &[],
+                }));
+
+            let examples = examples(&attrs.cloned().collect::<Vec<_>>());
+            let path = self.path_of(&type_name);
+            self.examples.entry(path).or_default().extend(examples);
+        }
+    }
+}
+
+// Reads the code blocks of the documentation, and the `Display` outputs they
+// assert.
+fn examples(attrs: &[Attribute]) -> DisplayExamples {
+    let doc = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(meta)) => match meta.lit {
+                Lit::Str(s) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut visitor = AssertVisitor::default();
+
+    for block in code_blocks(&doc) {
+        if let Ok(block) = syn::parse_str::<Block>(&format!("{{\n{}\n}}", block)) {
+            visitor.visit_block(&block);
+        }
+    }
+
+    visitor.0
+}
+
+// The Rust code blocks of some documentation, with the `# ` of the hidden
+// lines removed.
+fn code_blocks(doc: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    // The lines of the current block, and whether it is Rust code.
+    let mut current: Option<(Vec<&str>, bool)> = None;
+
+    for line in doc.lines().map(str::trim) {
+        match (line.strip_prefix("```"), current.take()) {
+            (Some(_), Some((lines, is_rust))) => {
+                if is_rust {
+                    blocks.push(lines.join("\n"));
+                }
+            }
+            (Some(lang), None) => {
+                let is_rust = lang
+                    .split(',')
+                    .map(str::trim)
+                    .all(|attr| attr.is_empty() || !is_other_lang(attr));
+                current = Some((Vec::new(), is_rust));
+            }
+            (None, Some((mut lines, is_rust))) => {
+                lines.push(match line {
+                    "#" => "",
+                    line => line.strip_prefix("# ").unwrap_or(line),
+                });
+                current = Some((lines, is_rust));
+            }
+            (None, None) => {}
+        }
+    }
+
+    blocks
+}
+
+fn is_other_lang(attr: &str) -> bool {
+    !matches!(
+        attr,
+        "rust"
+            | "ignore"
+            | "should_panic"
+            | "no_run"
+            | "compile_fail"
+            | "edition2015"
+            | "edition2018"
+            | "edition2021"
+    )
+}
+
+#[derive(Default)]
+struct AssertVisitor(DisplayExamples);
+
+impl<'ast> Visit<'ast> for AssertVisitor {
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        if !mac.path.is_ident("assert_eq") {
+            return;
+        }
+
+        let args = match Punctuated::<Expr, Comma>::parse_terminated.parse2(mac.tokens.clone()) {
+            Ok(args) => args,
+            Err(_) => return,
+        };
+
+        let mut args = args.iter();
+        if let (Some(left), Some(right)) = (args.next(), args.next()) {
+            let example = match (displayed(left), string(right)) {
+                (Some(expr), Some(output)) => Some((expr, output)),
+                _ => displayed(right).zip(string(left)),
+            };
+
+            if let Some((expr, output)) = example {
+                self.0.insert(display_code(&expr), output);
+            }
+        }
+    }
+}
+
+// The value displayed by `value.to_string()` or `format!("{}", value)`.
+fn displayed(expr: &Expr) -> Option<Expr> {
+    match expr {
+        Expr::MethodCall(ExprMethodCall {
+            receiver,
+            method,
+            args,
+            ..
+        }) if method == "to_string" && args.is_empty() => Some(*receiver.clone()),
+        Expr::Macro(ExprMacro { mac, .. }) if mac.path.is_ident("format") => {
+            let args = Punctuated::<Expr, Comma>::parse_terminated
+                .parse2(mac.tokens.clone())
+                .ok()?;
+
+            match (args.len(), args.first().and_then(string)) {
+                (2, Some(format)) if format == "{}" => args.last().cloned(),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn string(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(s), ..
+        }) => Some(s.value()),
+        _ => None,
+    }
+}
+
+/// Finds the values whose `Display` output, as asserted by the documentation
+/// of both versions, changed.
+pub(crate) fn changes(
+    previous: &BTreeMap<ItemPath, DisplayExamples>,
+    current: &BTreeMap<ItemPath, DisplayExamples>,
+) -> Vec<DiagnosisItem> {
+    previous
+        .iter()
+        .filter_map(|(path, previous)| Some((path, previous, current.get(path)?)))
+        .flat_map(|(path, previous, current)| {
+            previous.iter().filter_map(move |(expr, output)| {
+                let new_output = current.get(expr).filter(|new| *new != output)?;
+
+                Some(
+                    DiagnosisItem::new(RuleId::DisplayOutputModified, path.clone(), None)
+                        .with_message(format!(
+                            "`{}` is now displayed as {:?} instead of {:?}",
+                            expr, new_output, output
+                        )),
+                )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn examples(code: &str) -> Vec<(String, String, String)> {
+        extract(&CrateAst::from_str(code).unwrap())
+            .into_iter()
+            .flat_map(|(path, examples)| {
+                examples
+                    .into_iter()
+                    .map(move |(expr, output)| (path.to_string(), expr, output))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn asserted_outputs() {
+        let examples = examples(
+            r#"
+            /// A version.
+            ///
+            /// ```
+            /// # use foo::Version;
+            /// let v = Version::new(1, 2);
+            /// assert_eq!(v.to_string(), "1.2");
+            /// assert_eq!("v1.2", format!("{}", Version::tagged(1, 2)));
+            /// assert_eq!(format!("{:?}", v), "Version(1, 2)");
+            /// ```
+            ///
+            /// ```text
+            /// assert_eq!(v.to_string(), "not rust");
+            /// ```
+            pub struct Version;
+
+            pub mod level {
+                pub enum Level { Low }
+
+                impl fmt::Display for Level {
+                    /// ```
+                    /// assert_eq!(Level::Low.to_string(), "low");
+                    /// ```
+                    fn fmt(&self, f: &mut Formatter) -> fmt::Result {}
+                }
+            }
+
+            /// ```
+            /// assert_eq!(Private.to_string(), "private");
+            /// ```
+            struct Private;
+            "#,
+        );
+
+        let expected = [
+            ("Version", "Version::tagged(1, 2)", "v1.2"),
+            ("Version", "v", "1.2"),
+            ("level::Level", "Level::Low", "low"),
+        ]
+        .iter()
+        .map(|(path, expr, output)| (path.to_string(), expr.to_string(), output.to_string()))
+        .collect::<Vec<_>>();
+
+        assert_eq!(examples, expected);
+    }
+
+    #[test]
+    fn output_changes() {
+        let previous = extract(
+            &CrateAst::from_str(
+                "/// ```\n/// assert_eq!(v.to_string(), \"1.2\");\n/// assert_eq!(w.to_string(), \"1\");\n/// ```\npub struct Version;",
+            )
+            .unwrap(),
+        );
+        let current = extract(
+            &CrateAst::from_str(
+                "/// ```\n/// assert_eq!(v.to_string(), \"v1.2\");\n/// assert_eq!(w.to_string(), \"1\");\n/// ```\npub struct Version;",
+            )
+            .unwrap(),
+        );
+
+        let changes = changes(&previous, &current)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            ["≠ Version (`v` is now displayed as \"v1.2\" instead of \"1.2\")"]
+        );
+    }
+}
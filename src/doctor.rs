@@ -0,0 +1,188 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use anyhow::{bail, Result as AnyResult};
+
+use crate::{
+    git::{CrateRepo, GitBackend},
+    manifest,
+    toolchain::{NightlyToolchain, KNOWN_GOOD_NIGHTLIES},
+};
+
+/// Checks that everything needed to compare the crate is available, without
+/// building anything.
+pub(crate) fn run(comparison_refs: &[String]) -> AnyResult<()> {
+    let mut checks = toolchain_checks();
+    checks.extend(git_checks(comparison_refs));
+    checks.extend(manifest_checks());
+
+    checks.iter().for_each(|check| println!("{}", check));
+
+    summary(&checks)
+}
+
+fn toolchain_checks() -> Vec<Check> {
+    let toolchain = match NightlyToolchain::query() {
+        Ok(toolchain) => toolchain,
+        Err(e) => {
+            return vec![Check::error(format!(
+                "nightly toolchain: {:#}. Install it with `rustup toolchain install nightly --profile minimal`",
+                e
+            ))]
+        }
+    };
+
+    let mut checks = vec![Check::ok(format!("nightly toolchain: {}", toolchain))];
+
+    checks.push(if toolchain.is_known_good() {
+        Check::ok("nightly toolchain is known to work")
+    } else {
+        Check::warning(format!(
+            "nightly toolchain has not been tested (known good nightlies: {})",
+            KNOWN_GOOD_NIGHTLIES.join(", ")
+        ))
+    });
+
+    checks.push(match toolchain.check_expansion_flags() {
+        Ok(()) => Check::ok("nightly toolchain supports the expansion flags"),
+        Err(e) => Check::error(format!(
+            "nightly toolchain does not support the expansion flags: {}",
+            e
+        )),
+    });
+
+    checks
+}
+
+fn git_checks(comparison_refs: &[String]) -> Vec<Check> {
+    let repo = match CrateRepo::current() {
+        Ok(repo) => repo,
+        Err(e) => return vec![Check::error(format!("git repository: {:#}", e))],
+    };
+
+    let mut checks = vec![Check::ok("git repository found")];
+
+    checks.push(match repo.head_name() {
+        Ok(Some(_)) => Check::ok("HEAD is a branch"),
+        _ => Check::error("HEAD is detached: cargo-breaking needs a branch to switch back to"),
+    });
+
+    if repo.needs_stash() {
+        checks.push(Check::warning(
+            "the worktree has uncommitted changes: they will be stashed during the comparison",
+        ));
+    }
+
+    checks.extend(comparison_refs.iter().map(|comparison_ref| {
+        if repo.resolves(comparison_ref) {
+            Check::ok(format!("`{}` can be compared against", comparison_ref))
+        } else {
+            Check::error(format!(
+                "`{}` is not a branch, tag or commit of the repository",
+                comparison_ref
+            ))
+        }
+    }));
+
+    checks
+}
+
+fn manifest_checks() -> Vec<Check> {
+    let mut checks = vec![match manifest::get_crate_version() {
+        Ok(version) => Check::ok(format!("crate version: {}", version)),
+        Err(e) => Check::error(format!("crate version: {:#}", e)),
+    }];
+
+    checks.push(match manifest::has_lib_target() {
+        Ok(true) => Check::ok("the crate has a library target"),
+        Ok(false) => {
+            Check::error("the crate has no library target, pass --bin to compare a binary")
+        }
+        Err(e) => Check::error(format!("crate manifest: {:#}", e)),
+    });
+
+    checks
+}
+
+fn summary(checks: &[Check]) -> AnyResult<()> {
+    let errors = checks
+        .iter()
+        .filter(|check| check.status == Status::Error)
+        .count();
+
+    if errors > 0 {
+        bail!("{} problem(s) found", errors);
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Check {
+    status: Status,
+    message: String,
+}
+
+impl Check {
+    fn ok(message: impl Into<String>) -> Check {
+        Check::new(Status::Ok, message)
+    }
+
+    fn warning(message: impl Into<String>) -> Check {
+        Check::new(Status::Warning, message)
+    }
+
+    fn error(message: impl Into<String>) -> Check {
+        Check::new(Status::Error, message)
+    }
+
+    fn new(status: Status, message: impl Into<String>) -> Check {
+        let message = message.into();
+        Check { status, message }
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let status = match self.status {
+            Status::Ok => "ok",
+            Status::Warning => "warning",
+            Status::Error => "error",
+        };
+
+        write!(f, "[{}] {}", status, self.message)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_check() {
+        assert_eq!(Check::warning("foo").to_string(), "[warning] foo");
+    }
+
+    #[test]
+    fn warnings_are_not_problems() {
+        let checks = [Check::ok("a"), Check::warning("b")];
+
+        assert!(summary(&checks).is_ok());
+    }
+
+    #[test]
+    fn errors_are_problems() {
+        let checks = [Check::ok("a"), Check::error("b"), Check::error("c")];
+
+        assert_eq!(
+            summary(&checks).unwrap_err().to_string(),
+            "2 problem(s) found"
+        );
+    }
+}
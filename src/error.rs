@@ -0,0 +1,228 @@
+use std::{
+    any::Any,
+    error::Error as StdError,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+};
+
+use anyhow::Error as AnyError;
+use serde_json::{json, Value};
+
+/// Why cargo-breaking failed. Each kind of failure has its own exit code, so
+/// that scripts can tell them apart.
+///
+/// Internally, errors are [`anyhow`] errors. The ones of a known kind are
+/// tagged with a variant of this enum where they occur, and the tag is found
+/// back in the chain of causes when the error reaches [`run`](crate::run).
+pub enum Error {
+    /// No nightly toolchain could be found or installed.
+    ToolchainMissing(AnyError),
+    /// A git operation failed.
+    Git(AnyError),
+    /// The API of the version compared against could not be extracted,
+    /// usually because it does not build.
+    BaselineBuild(AnyError),
+    /// The compiler yielded code which can't be read, usually because the
+    /// nightly toolchain is newer than what cargo-breaking supports.
+    UnsupportedFormat(AnyError),
+    /// cargo-breaking panicked.
+    ComparisonPanic(AnyError),
+    /// Any other failure, including the checks which did not pass.
+    Other(AnyError),
+}
+
+impl Error {
+    /// The exit code of the process when it fails with this error. A panic
+    /// exits with the same code as an uncaught one.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Other(_) => 1,
+            Error::ToolchainMissing(_) => 3,
+            Error::Git(_) => 4,
+            Error::BaselineBuild(_) => 5,
+            Error::UnsupportedFormat(_) => 6,
+            Error::ComparisonPanic(_) => 101,
+        }
+    }
+
+    /// The name of the kind of failure, as written in the JSON error object.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::ToolchainMissing(_) => "toolchain-missing",
+            Error::Git(_) => "git",
+            Error::BaselineBuild(_) => "baseline-build",
+            Error::UnsupportedFormat(_) => "unsupported-format",
+            Error::ComparisonPanic(_) => "comparison-panic",
+            Error::Other(_) => "other",
+        }
+    }
+
+    /// The underlying error, along with the context added to it.
+    pub fn inner(&self) -> &AnyError {
+        match self {
+            Error::ToolchainMissing(e)
+            | Error::Git(e)
+            | Error::BaselineBuild(e)
+            | Error::UnsupportedFormat(e)
+            | Error::ComparisonPanic(e)
+            | Error::Other(e) => e,
+        }
+    }
+
+    /// The error object printed instead of the report when the output format
+    /// is JSON.
+    pub fn to_json(&self) -> Value {
+        let mut causes = self.inner().chain().map(ToString::to_string);
+
+        json!({
+            "error": {
+                "kind": self.kind(),
+                "exit-code": self.exit_code(),
+                "message": causes.next().unwrap_or_default(),
+                "causes": causes.collect::<Vec<_>>(),
+            }
+        })
+    }
+
+    pub(crate) fn from_panic(payload: &(dyn Any + Send)) -> Error {
+        Error::ComparisonPanic(AnyError::msg(format!(
+            "cargo-breaking panicked: {}",
+            panic_message(payload)
+        )))
+    }
+
+    fn variant(&self) -> fn(AnyError) -> Error {
+        match self {
+            Error::ToolchainMissing(_) => Error::ToolchainMissing,
+            Error::Git(_) => Error::Git,
+            Error::BaselineBuild(_) => Error::BaselineBuild,
+            Error::UnsupportedFormat(_) => Error::UnsupportedFormat,
+            Error::ComparisonPanic(_) => Error::ComparisonPanic,
+            Error::Other(_) => Error::Other,
+        }
+    }
+}
+
+/// Tags `error` with `variant`, unless it was already given a more precise
+/// kind where it occurred.
+pub(crate) fn tag(error: AnyError, variant: fn(AnyError) -> Error) -> AnyError {
+    if find_tag(&error).is_some() {
+        error
+    } else {
+        variant(error).into()
+    }
+}
+
+fn find_tag(error: &AnyError) -> Option<fn(AnyError) -> Error> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<Error>())
+        .map(Error::variant)
+}
+
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+impl From<AnyError> for Error {
+    fn from(error: AnyError) -> Error {
+        find_tag(&error).unwrap_or(Error::Other)(error)
+    }
+}
+
+// The tag is transparent: the error is displayed as the one it wraps.
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(self.inner(), f)
+    }
+}
+
+impl Debug for Error {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Debug::fmt(self.inner(), f)
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.inner().source()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn untagged_errors_are_other() {
+        let error = Error::from(anyhow!("The check failed"));
+
+        assert_eq!(error.kind(), "other");
+        assert_eq!(error.exit_code(), 1);
+    }
+
+    #[test]
+    fn tag_is_found_through_the_context() {
+        let error = AnyError::from(Error::Git(anyhow!("HEAD is detached")))
+            .context("Failed to fetch repository data");
+        let error = Error::from(error);
+
+        assert_eq!(error.kind(), "git");
+        assert_eq!(error.exit_code(), 4);
+        assert_eq!(
+            format!("{:#}", error),
+            "Failed to fetch repository data: HEAD is detached"
+        );
+    }
+
+    #[test]
+    fn precise_tags_are_kept() {
+        let error = AnyError::from(Error::UnsupportedFormat(anyhow!("invalid")))
+            .context("Failed to get crate API");
+        let error = Error::from(tag(error, Error::BaselineBuild));
+
+        assert_eq!(error.kind(), "unsupported-format");
+
+        let error = Error::from(tag(anyhow!("does not build"), Error::BaselineBuild));
+
+        assert_eq!(error.kind(), "baseline-build");
+    }
+
+    #[test]
+    fn json_error_object() {
+        let error = Error::from(
+            AnyError::from(Error::ToolchainMissing(anyhow!(
+                "No nightly toolchain found"
+            )))
+            .context("cargo-breaking requires a nightly toolchain"),
+        );
+
+        assert_eq!(
+            error.to_json(),
+            json!({
+                "error": {
+                    "kind": "toolchain-missing",
+                    "exit-code": 3,
+                    "message": "cargo-breaking requires a nightly toolchain",
+                    "causes": ["No nightly toolchain found"],
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn panics_are_reported() {
+        let error = Error::from_panic(&"index out of bounds");
+
+        assert_eq!(error.exit_code(), 101);
+        assert_eq!(
+            error.to_string(),
+            "cargo-breaking panicked: index out of bounds"
+        );
+    }
+}
@@ -0,0 +1,448 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result as AnyResult};
+use syn::{Ident, Item, Type, UseTree, Visibility};
+
+use crate::ast::CrateAst;
+
+/// The crates which are always available, and whose items are not compared.
+const SYSROOT_CRATES: &[&str] = &["std", "core", "alloc"];
+
+/// Returns the names the crates whose items are re-exported with `pub use`
+/// are known by in the code, along with the name of the crate each one
+/// stands for. They differ for `extern crate foo as bar;`.
+pub(crate) fn reexported_crates(ast: &CrateAst) -> BTreeMap<Ident, Ident> {
+    let items = &ast.ast().items;
+
+    // The names of the items of the crate root, which `use` paths may start
+    // with, unlike the names of other crates.
+    let local = items
+        .iter()
+        .filter(|item| !matches!(item, Item::ExternCrate(_)))
+        .filter_map(item_ident)
+        .collect::<BTreeSet<_>>();
+
+    let renames = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::ExternCrate(extern_crate) => extern_crate
+                .rename
+                .as_ref()
+                .map(|(_, alias)| (alias.clone(), extern_crate.ident.clone())),
+            _ => None,
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let mut crates = BTreeMap::new();
+    visit_public_uses(items, &mut |tree| {
+        let first = match tree {
+            UseTree::Path(path) => &path.ident,
+            _ => return,
+        };
+
+        if first == "crate"
+            || first == "self"
+            || first == "super"
+            || SYSROOT_CRATES.iter().any(|name| first == name)
+            || local.contains(first)
+        {
+            return;
+        }
+
+        let name = renames.get(first).unwrap_or(first).clone();
+        crates.insert(first.clone(), name);
+    });
+
+    crates
+}
+
+fn visit_public_uses(items: &[Item], f: &mut impl FnMut(&UseTree)) {
+    for item in items {
+        match item {
+            Item::Use(use_) if matches!(use_.vis, Visibility::Public(_)) => f(&use_.tree),
+            Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    visit_public_uses(items, f);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the directory of each dependency of the crate located in `dir`,
+/// by the name its library is known by in the code.
+pub(crate) fn dependency_dirs(dir: &Path) -> AnyResult<BTreeMap<String, PathBuf>> {
+    let output = Command::new("cargo")
+        .current_dir(dir)
+        .args(["metadata", "--format-version", "1"])
+        .stderr(Stdio::inherit())
+        .output()
+        .context("Failed to run `cargo metadata`")?;
+
+    if !output.status.success() {
+        bail!("`cargo metadata` failed");
+    }
+
+    parse_dependency_dirs(&output.stdout)
+}
+
+fn parse_dependency_dirs(metadata: &[u8]) -> AnyResult<BTreeMap<String, PathBuf>> {
+    let metadata = serde_json::from_slice::<serde_json::Value>(metadata)
+        .context("Failed to parse `cargo metadata` output")?;
+
+    let root = match metadata["resolve"]["root"].as_str() {
+        Some(root) => root,
+        None => bail!("`cargo metadata` did not return the crate dependencies"),
+    };
+
+    let manifest_dirs = metadata["packages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|package| {
+            let id = package["id"].as_str()?;
+            let manifest = Path::new(package["manifest_path"].as_str()?);
+
+            Some((id, manifest.parent()?.to_owned()))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let nodes = metadata["resolve"]["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten();
+    let root = match nodes.into_iter().find(|node| node["id"] == root) {
+        Some(root) => root,
+        None => bail!("`cargo metadata` did not return the crate dependencies"),
+    };
+
+    // The name of each dependency accounts for the renames of the manifest.
+    Ok(root["deps"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|dep| {
+            let name = dep["name"].as_str()?;
+            let dir = manifest_dirs.get(dep["pkg"].as_str()?)?;
+
+            Some((name.to_owned(), dir.clone()))
+        })
+        .collect())
+}
+
+/// Replaces the `pub use` declarations which re-export items of other crates
+/// with the items themselves, taken from `dependencies`, so that they are
+/// compared as if they were defined by the crate. The re-exports of the
+/// dependencies themselves are only followed if they were inlined in
+/// `dependencies` first.
+///
+/// Declarations which can't be resolved entirely are left as they are.
+pub(crate) fn inline_reexports(ast: &mut CrateAst, dependencies: &BTreeMap<Ident, CrateAst>) {
+    inline_in(&mut ast.0.items, dependencies);
+}
+
+fn inline_in(items: &mut Vec<Item>, dependencies: &BTreeMap<Ident, CrateAst>) {
+    *items = std::mem::take(items)
+        .into_iter()
+        .flat_map(|item| match item {
+            Item::Use(use_) if matches!(use_.vis, Visibility::Public(_)) => {
+                match inlined(&use_.tree, dependencies) {
+                    Some(items) => items,
+                    None => vec![Item::Use(use_)],
+                }
+            }
+
+            Item::Mod(mut module) => {
+                if let Some((_, items)) = &mut module.content {
+                    inline_in(items, dependencies);
+                }
+
+                vec![Item::Mod(module)]
+            }
+
+            item => vec![item],
+        })
+        .collect();
+}
+
+// The items a `use` tree stands for, if it only names items of one of
+// `dependencies`.
+fn inlined(tree: &UseTree, dependencies: &BTreeMap<Ident, CrateAst>) -> Option<Vec<Item>> {
+    let (dependency, tree) = match tree {
+        UseTree::Path(path) => (dependencies.get(&path.ident)?, &*path.tree),
+        _ => return None,
+    };
+
+    let mut imports = Vec::new();
+    flatten(tree, &mut Vec::new(), &mut imports);
+
+    imports
+        .into_iter()
+        .map(|import| import.resolve(&dependency.ast().items))
+        .collect::<Option<Vec<_>>>()
+        .map(|items| items.into_iter().flatten().collect())
+}
+
+enum Import {
+    /// `use module::*`.
+    Glob(Vec<Ident>),
+    /// `use module::name as alias`.
+    Name(Vec<Ident>, Ident, Option<Ident>),
+}
+
+fn flatten(tree: &UseTree, module: &mut Vec<Ident>, imports: &mut Vec<Import>) {
+    match tree {
+        UseTree::Path(path) => {
+            module.push(path.ident.clone());
+            flatten(&path.tree, module, imports);
+            module.pop();
+        }
+
+        UseTree::Name(name) if name.ident == "self" => {
+            if let Some((last, parent)) = module.split_last() {
+                imports.push(Import::Name(parent.to_vec(), last.clone(), None));
+            }
+        }
+
+        UseTree::Name(name) => imports.push(Import::Name(module.clone(), name.ident.clone(), None)),
+
+        UseTree::Rename(rename) => imports.push(Import::Name(
+            module.clone(),
+            rename.ident.clone(),
+            Some(rename.rename.clone()),
+        )),
+
+        UseTree::Glob(_) => imports.push(Import::Glob(module.clone())),
+
+        UseTree::Group(group) => group
+            .items
+            .iter()
+            .for_each(|tree| flatten(tree, module, imports)),
+    }
+}
+
+impl Import {
+    fn resolve(&self, root: &[Item]) -> Option<Vec<Item>> {
+        match self {
+            Import::Glob(module) => {
+                let items = module_items(root, module)?;
+
+                Some(
+                    items
+                        .iter()
+                        .filter(|item| !matches!(item, Item::ExternCrate(_)))
+                        .cloned()
+                        .collect(),
+                )
+            }
+
+            Import::Name(module, name, alias) => {
+                let items = module_items(root, module)?;
+
+                // Types come along with their inherent methods.
+                let named = items
+                    .iter()
+                    .filter(|item| {
+                        item_ident(item) == Some(name) || impl_target(item) == Some(name)
+                    })
+                    .cloned()
+                    .map(|mut item| {
+                        if let Some(alias) = alias {
+                            rename(&mut item, alias);
+                        }
+                        item
+                    })
+                    .collect::<Vec<_>>();
+
+                (!named.is_empty()).then_some(named)
+            }
+        }
+    }
+}
+
+fn module_items<'a>(mut items: &'a [Item], path: &[Ident]) -> Option<&'a [Item]> {
+    for segment in path {
+        items = items.iter().find_map(|item| match item {
+            Item::Mod(module) if &module.ident == segment => {
+                module.content.as_ref().map(|(_, items)| items.as_slice())
+            }
+            _ => None,
+        })?;
+    }
+
+    Some(items)
+}
+
+fn item_ident(item: &Item) -> Option<&Ident> {
+    match item {
+        Item::Const(i) => Some(&i.ident),
+        Item::Enum(i) => Some(&i.ident),
+        Item::ExternCrate(i) => Some(i.rename.as_ref().map_or(&i.ident, |(_, alias)| alias)),
+        Item::Fn(i) => Some(&i.sig.ident),
+        Item::Macro(i) => i.ident.as_ref(),
+        Item::Mod(i) => Some(&i.ident),
+        Item::Static(i) => Some(&i.ident),
+        Item::Struct(i) => Some(&i.ident),
+        Item::Trait(i) => Some(&i.ident),
+        Item::TraitAlias(i) => Some(&i.ident),
+        Item::Type(i) => Some(&i.ident),
+        Item::Union(i) => Some(&i.ident),
+        _ => None,
+    }
+}
+
+// The type an inherent impl block is for, when it is named by a single
+// segment.
+fn impl_target(item: &Item) -> Option<&Ident> {
+    match item {
+        Item::Impl(impl_) if impl_.trait_.is_none() => match &*impl_.self_ty {
+            Type::Path(path) if path.qself.is_none() && path.path.segments.len() == 1 => {
+                Some(&path.path.segments[0].ident)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn rename(item: &mut Item, alias: &Ident) {
+    let ident = match item {
+        Item::Const(i) => &mut i.ident,
+        Item::Enum(i) => &mut i.ident,
+        Item::Fn(i) => &mut i.sig.ident,
+        Item::Mod(i) => &mut i.ident,
+        Item::Static(i) => &mut i.ident,
+        Item::Struct(i) => &mut i.ident,
+        Item::Trait(i) => &mut i.ident,
+        Item::TraitAlias(i) => &mut i.ident,
+        Item::Type(i) => &mut i.ident,
+        Item::Union(i) => &mut i.ident,
+        Item::Macro(i) => match &mut i.ident {
+            Some(ident) => ident,
+            None => return,
+        },
+        Item::Impl(impl_) => match &mut *impl_.self_ty {
+            Type::Path(path) => &mut path.path.segments[0].ident,
+            _ => return,
+        },
+        _ => return,
+    };
+
+    *ident = alias.clone();
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn reexported_crates_exclude_local_paths() {
+        let ast: CrateAst = parse_quote! {
+            extern crate inner as renamed;
+
+            pub mod local {}
+
+            pub use renamed::*;
+            pub use other::Item;
+            pub use local::*;
+            pub use std::fmt;
+            pub use self::local::Item;
+            use private::Item;
+        };
+
+        let crates = reexported_crates(&ast)
+            .into_iter()
+            .map(|(ident, name)| (ident.to_string(), name.to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            crates,
+            [
+                ("other".to_owned(), "other".to_owned()),
+                ("renamed".to_owned(), "inner".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn reexports_are_inlined() {
+        let mut ast: CrateAst = parse_quote! {
+            pub use dep::*;
+            pub use dep::m::{S as T, missing};
+
+            pub mod m {
+                pub use dep::m::S;
+            }
+        };
+
+        let dependency: CrateAst = parse_quote! {
+            extern crate other;
+
+            pub fn f() {}
+
+            pub mod m {
+                pub struct S;
+                impl S {
+                    pub fn new() {}
+                }
+                impl Clone for S {}
+            }
+        };
+
+        let dependencies = BTreeMap::from([(parse_quote! { dep }, dependency)]);
+        inline_reexports(&mut ast, &dependencies);
+
+        let expected: CrateAst = parse_quote! {
+            pub fn f() {}
+
+            pub mod m {
+                pub struct S;
+                impl S {
+                    pub fn new() {}
+                }
+                impl Clone for S {}
+            }
+
+            // `missing` can't be found, so the declaration is kept.
+            pub use dep::m::{S as T, missing};
+
+            pub mod m {
+                pub struct S;
+                impl S {
+                    pub fn new() {}
+                }
+            }
+        };
+
+        assert_eq!(ast, expected);
+    }
+
+    #[test]
+    fn dependency_dirs_account_for_renames() {
+        let metadata = br#"{
+            "packages": [
+                { "id": "facade 0.1.0", "manifest_path": "/facade/Cargo.toml" },
+                { "id": "inner 0.1.0", "manifest_path": "/inner/Cargo.toml" }
+            ],
+            "resolve": {
+                "root": "facade 0.1.0",
+                "nodes": [
+                    { "id": "facade 0.1.0", "deps": [{ "name": "renamed", "pkg": "inner 0.1.0" }] },
+                    { "id": "inner 0.1.0", "deps": [] }
+                ]
+            }
+        }"#;
+
+        assert_eq!(
+            parse_dependency_dirs(metadata).unwrap(),
+            BTreeMap::from([("renamed".to_owned(), PathBuf::from("/inner"))])
+        );
+    }
+}
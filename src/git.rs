@@ -1,6 +1,17 @@
-use anyhow::{Context, Result as AnyResult};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
+};
 
-use git2::{Repository, StashFlags, StatusOptions};
+use anyhow::{anyhow, bail, Context, Result as AnyResult};
+
+use git2::{build::CheckoutBuilder, Object, Reference, Repository, StashFlags, StatusOptions};
+
+use crate::revision::DatedRevision;
 
 pub(crate) trait GitBackend: Sized {
     fn run_in<F, O>(&mut self, id: &str, f: F) -> AnyResult<O>
@@ -68,6 +79,10 @@ pub(crate) trait GitBackend: Sized {
     fn checkout_to(&mut self, id: &str) -> AnyResult<()>;
 }
 
+/// Identifies the stashes created by `cargo-breaking`, followed by the name
+/// of the branch the changes were made on.
+const STASH_MARKER: &str = "cargo-breaking autostash on";
+
 pub(crate) struct CrateRepo {
     repo: Repository,
     previous_branch_name: Option<String>,
@@ -117,8 +132,16 @@ impl GitBackend for CrateRepo {
             .signature()
             .context("Failed to create user signature")?;
 
+        // The branch is recorded so that `cargo breaking restore` can switch
+        // back to it if the run is interrupted.
+        let branch = self
+            .head_name()
+            .context("Failed to get HEAD name")?
+            .unwrap_or_default();
+        let message = format!("{} {}", STASH_MARKER, branch);
+
         self.repo
-            .stash_save2(&signature, None, Some(stash_options))
+            .stash_save2(&signature, Some(&message), Some(stash_options))
             .map(drop)
             .map_err(Into::into)
     }
@@ -130,10 +153,7 @@ impl GitBackend for CrateRepo {
     }
 
     fn checkout_to(&mut self, id: &str) -> AnyResult<()> {
-        let (obj, reference) = self
-            .repo
-            .revparse_ext(id)
-            .with_context(|| format!("Failed to get object corresponding to {}", id))?;
+        let (obj, reference) = self.find_object(id)?;
 
         self.repo
             .checkout_tree(&obj, None)
@@ -153,6 +173,223 @@ impl GitBackend for CrateRepo {
 }
 
 impl CrateRepo {
+    /// Returns whether `id` can be resolved to a commit.
+    pub(crate) fn resolves(&self, id: &str) -> bool {
+        self.find_object(id)
+            .and_then(|(obj, _)| obj.peel_to_commit().map_err(Into::into))
+            .is_ok()
+    }
+
+    /// Resolves a revision, along with the reference it names if it is one.
+    ///
+    /// On top of what git understands, `<reference>@{YYYY-MM-DD}` is resolved
+    /// from the history of the reference rather than from its reflog, which
+    /// only knows about the local updates of the reference: it is the last
+    /// commit made before the date, following the first parents.
+    fn find_object(&self, id: &str) -> AnyResult<(Object<'_>, Option<Reference<'_>>)> {
+        let dated = match DatedRevision::parse(id) {
+            Some(dated) => dated,
+            None => {
+                return self
+                    .repo
+                    .revparse_ext(id)
+                    .with_context(|| format!("Failed to get object corresponding to {}", id))
+            }
+        };
+
+        let tip = self
+            .repo
+            .revparse_single(&dated.reference)
+            .and_then(|obj| obj.peel_to_commit())
+            .with_context(|| {
+                format!("Failed to get commit corresponding to {}", dated.reference)
+            })?;
+
+        let mut walk = self.repo.revwalk()?;
+        walk.push(tip.id())?;
+        walk.simplify_first_parent()?;
+
+        for oid in walk {
+            let commit = self.repo.find_commit(oid?)?;
+
+            if commit.committer().when().seconds() < dated.timestamp {
+                return Ok((commit.into_object(), None));
+            }
+        }
+
+        bail!("`{}` has no commit before {}", dated.reference, dated.date)
+    }
+
+    /// Returns the short name of the branch HEAD points to.
+    pub(crate) fn current_branch(&self) -> AnyResult<String> {
+        let head = self.repo.head().context("Failed to get HEAD")?;
+
+        if !head.is_branch() {
+            bail!("HEAD is detached");
+        }
+
+        head.shorthand()
+            .map(ToOwned::to_owned)
+            .ok_or_else(|| anyhow!("Branch name must be UTF-8"))
+    }
+
+    /// Restores the local changes stashed by a run which was interrupted
+    /// before it could restore them, and switches back to their branch.
+    ///
+    /// Returns the branch name, or `None` if no such stash exists.
+    pub(crate) fn restore_leftover_stash(&mut self) -> AnyResult<Option<String>> {
+        let mut leftover = None;
+
+        self.repo
+            .stash_foreach(|index, message, _| {
+                leftover = message
+                    .split_once(STASH_MARKER)
+                    .map(|(_, branch)| (index, branch.trim().to_owned()));
+
+                leftover.is_none()
+            })
+            .context("Failed to list stashes")?;
+
+        let (index, branch) = match leftover {
+            Some(leftover) => leftover,
+            None => return Ok(None),
+        };
+
+        if !branch.is_empty() {
+            self.checkout_to(&branch)
+                .with_context(|| format!("Failed to checkout to {}", branch))?;
+        }
+
+        self.repo
+            .stash_pop(index, None)
+            .context("Failed to pop the stashed state")?;
+
+        Ok(Some(branch))
+    }
+
+    /// Returns the names of every tag of the repository.
+    pub(crate) fn tag_names(&self) -> AnyResult<Vec<String>> {
+        let tags = self.repo.tag_names(None).context("Failed to list tags")?;
+
+        Ok(tags.iter().flatten().map(ToOwned::to_owned).collect())
+    }
+
+    /// Checks `id` out in a temporary worktree of the repository, leaving the
+    /// working tree untouched. The worktree is removed with the checkout.
+    ///
+    /// The crate directory is located at the same path, relative to the
+    /// repository root, as the current directory.
+    pub(crate) fn checkout_in_temp_dir(&self, id: &str) -> AnyResult<TempCheckout> {
+        let current_dir = env::current_dir().context("Failed to get current directory")?;
+        self.checkout_crate_in_temp_dir(id, &current_dir)
+    }
+
+    // The revision is checked out as a detached worktree of the repository:
+    // git writes the files itself, which handles the long paths and the
+    // symbolic links of each platform, and the objects are not copied.
+    fn checkout_crate_in_temp_dir(&self, id: &str, crate_dir: &Path) -> AnyResult<TempCheckout> {
+        let commit = self
+            .find_object(id)?
+            .0
+            .peel_to_commit()
+            .with_context(|| format!("Failed to get commit corresponding to {}", id))?;
+
+        let mut checkout = TempCheckout::new(self.crate_path(crate_dir)?);
+
+        let output = Command::new("git")
+            .arg("--git-dir")
+            .arg(self.repo.path())
+            .args(["-c", "core.longpaths=true", "worktree", "add", "--detach"])
+            .arg(&checkout.root)
+            .arg(commit.id().to_string())
+            .output()
+            .context("Failed to run git")?;
+
+        if !output.status.success() {
+            bail!(
+                "Failed to write the tree of {}: {}",
+                id,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        checkout.git_dir = Some(self.repo.path().to_owned());
+
+        Ok(checkout)
+    }
+
+    /// Clones the repository at `url` in a temporary directory, and checks
+    /// `id` out.
+    ///
+    /// The crate is expected to be at the same path, relative to the
+    /// repository root, as in the current repository.
+    pub(crate) fn clone_in_temp_dir(&self, url: &str, id: &str) -> AnyResult<TempCheckout> {
+        let current_dir = env::current_dir().context("Failed to get current directory")?;
+        clone_in_temp_dir(url, id, self.crate_path(&current_dir)?)
+    }
+
+    /// Returns whether the current crate is the same at `id` and in the
+    /// working tree, in which case comparing them can't find any change.
+    pub(crate) fn is_identical_to(&self, id: &str) -> AnyResult<bool> {
+        let current_dir = env::current_dir().context("Failed to get current directory")?;
+        self.crate_is_identical_to(id, &current_dir)
+    }
+
+    fn crate_is_identical_to(&self, id: &str, crate_dir: &Path) -> AnyResult<bool> {
+        if self.needs_stash {
+            return Ok(false);
+        }
+
+        let crate_path = self.crate_path(crate_dir)?;
+        let crate_tree = |id: &str| -> AnyResult<Option<git2::Oid>> {
+            let tree = self
+                .find_object(id)?
+                .0
+                .peel_to_tree()
+                .with_context(|| format!("Failed to get the tree of {}", id))?;
+
+            if crate_path.as_os_str().is_empty() {
+                return Ok(Some(tree.id()));
+            }
+
+            Ok(tree.get_path(&crate_path).ok().map(|entry| entry.id()))
+        };
+
+        let previous = crate_tree(id)?;
+        Ok(previous.is_some() && previous == crate_tree("HEAD")?)
+    }
+
+    /// Returns the path of the current crate, relative to the repository
+    /// root.
+    pub(crate) fn current_crate_path(&self) -> AnyResult<PathBuf> {
+        let current_dir = env::current_dir().context("Failed to get current directory")?;
+        self.crate_path(&current_dir)
+    }
+
+    /// Returns the directory git runs the hooks from.
+    pub(crate) fn hooks_dir(&self) -> AnyResult<PathBuf> {
+        let configured = self
+            .repo
+            .config()
+            .context("Failed to read the repository configuration")?
+            .get_path("core.hooksPath");
+
+        match (configured, self.repo.workdir()) {
+            (Ok(path), Some(workdir)) => Ok(workdir.join(path)),
+            (Ok(path), None) => Ok(path),
+            (Err(_), _) => Ok(self.repo.path().join("hooks")),
+        }
+    }
+
+    fn crate_path(&self, crate_dir: &Path) -> AnyResult<PathBuf> {
+        let workdir = self
+            .repo
+            .workdir()
+            .ok_or_else(|| anyhow!("The repository has no working directory"))?;
+
+        relative_crate_path(workdir, crate_dir)
+    }
+
     fn needs_stash(repo: &Repository) -> AnyResult<bool> {
         let mut options = StatusOptions::new();
         let options = options.include_untracked(true);
@@ -165,6 +402,103 @@ impl CrateRepo {
     }
 }
 
+/// A revision of the repository, written in a temporary directory. The
+/// directory is removed when this is dropped.
+#[derive(Debug)]
+pub(crate) struct TempCheckout {
+    root: PathBuf,
+    crate_dir: PathBuf,
+    // The repository the checkout is a worktree of, if any.
+    git_dir: Option<PathBuf>,
+}
+
+impl TempCheckout {
+    /// Reserves a temporary directory, in which the crate is located at
+    /// `crate_path`. Nothing is written until the caller does.
+    pub(crate) fn new(crate_path: PathBuf) -> TempCheckout {
+        static CHECKOUTS: AtomicUsize = AtomicUsize::new(0);
+
+        // The path is kept as short as possible, as deeply nested files may
+        // exceed the maximum path length on Windows.
+        let root = env::temp_dir().join(format!(
+            "cb-{}-{}",
+            process::id(),
+            CHECKOUTS.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        TempCheckout {
+            crate_dir: root.join(crate_path),
+            root,
+            git_dir: None,
+        }
+    }
+
+    pub(crate) fn crate_dir(&self) -> &Path {
+        self.crate_dir.as_path()
+    }
+}
+
+impl Drop for TempCheckout {
+    fn drop(&mut self) {
+        // On Windows, files may still be locked for a short while after the
+        // compiler has exited.
+        let removed = (0..3).any(|attempt| {
+            if attempt > 0 {
+                thread::sleep(Duration::from_millis(200));
+            }
+
+            fs::remove_dir_all(&self.root).is_ok() || !self.root.exists()
+        });
+
+        if !removed {
+            eprintln!(
+                "Warning: failed to remove temporary directory {}",
+                self.root.display()
+            );
+            return;
+        }
+
+        // The repository still lists the worktree until it is pruned.
+        if let Some(git_dir) = &self.git_dir {
+            let _ = Command::new("git")
+                .arg("--git-dir")
+                .arg(git_dir)
+                .args(["worktree", "prune"])
+                .output();
+        }
+    }
+}
+
+fn clone_in_temp_dir(url: &str, id: &str, crate_path: PathBuf) -> AnyResult<TempCheckout> {
+    let checkout = TempCheckout::new(crate_path);
+
+    let repo = Repository::clone(url, &checkout.root)
+        .with_context(|| format!("Failed to clone {}", url))?;
+
+    // Only the default branch exists locally after cloning, other branches
+    // are remote-tracking ones.
+    let obj = repo
+        .revparse_single(id)
+        .or_else(|_| repo.revparse_single(&format!("origin/{}", id)))
+        .with_context(|| format!("Failed to get object corresponding to {}", id))?;
+
+    repo.checkout_tree(&obj, Some(CheckoutBuilder::new().force()))
+        .with_context(|| format!("Failed to checkout to {}", id))?;
+    repo.set_head_detached(obj.id())?;
+
+    Ok(checkout)
+}
+
+fn relative_crate_path(workdir: &Path, current_dir: &Path) -> AnyResult<PathBuf> {
+    let workdir = fs::canonicalize(workdir).context("Failed to resolve repository path")?;
+    let current_dir = fs::canonicalize(current_dir).context("Failed to resolve current path")?;
+
+    current_dir
+        .strip_prefix(&workdir)
+        .map(Path::to_path_buf)
+        .map_err(|_| anyhow!("The current directory is not in the repository"))
+}
+
 #[cfg(test)]
 use std::{cell::RefCell, rc::Rc};
 
@@ -310,6 +644,320 @@ enum FakeRepoCall {
 mod tests {
     use super::*;
 
+    mod checkout_in_temp_dir {
+        use git2::Signature;
+
+        use super::*;
+
+        pub(super) fn commit_files(repo: &Repository, files: &[(&str, &str)]) {
+            let workdir = repo.workdir().unwrap();
+            let mut index = repo.index().unwrap();
+
+            for (path, content) in files {
+                let full_path = workdir.join(path);
+                fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+                fs::write(full_path, content).unwrap();
+                index.add_path(Path::new(path)).unwrap();
+            }
+
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = Signature::now("test", "test@example.com").unwrap();
+            let parent = repo.head().ok().map(|h| h.peel_to_commit().unwrap());
+
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "commit",
+                &tree,
+                parent.as_ref().into_iter().collect::<Vec<_>>().as_slice(),
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn writes_revision_without_touching_worktree() {
+            let dir = env::temp_dir().join(format!("cb-test-repo-{}", process::id()));
+            let repo = Repository::init(&dir).unwrap();
+
+            commit_files(&repo, &[("foo/src/lib.rs", "pub fn a() {}")]);
+            commit_files(&repo, &[("foo/src/lib.rs", "pub fn b() {}")]);
+            fs::write(dir.join("foo/src/lib.rs"), "pub fn dirty() {}").unwrap();
+
+            let crate_repo = CrateRepo {
+                repo,
+                previous_branch_name: None,
+                needs_stash: true,
+            };
+
+            let checkout = crate_repo
+                .checkout_crate_in_temp_dir("HEAD~1", &dir.join("foo"))
+                .unwrap();
+            let root = checkout.root.clone();
+
+            assert_eq!(checkout.crate_dir(), root.join("foo"));
+            assert_eq!(
+                fs::read_to_string(checkout.crate_dir().join("src/lib.rs")).unwrap(),
+                "pub fn a() {}"
+            );
+            assert_eq!(
+                fs::read_to_string(dir.join("foo/src/lib.rs")).unwrap(),
+                "pub fn dirty() {}"
+            );
+
+            drop(checkout);
+            assert!(!root.exists());
+            assert!(Repository::open(&dir)
+                .unwrap()
+                .worktrees()
+                .unwrap()
+                .is_empty());
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn clone_other_repository() {
+            let dir = env::temp_dir().join(format!("cb-test-remote-{}", process::id()));
+            let repo = Repository::init(&dir).unwrap();
+
+            commit_files(&repo, &[("foo/src/lib.rs", "pub fn a() {}")]);
+            let head = repo.head().unwrap().peel_to_commit().unwrap().id();
+            commit_files(&repo, &[("foo/src/lib.rs", "pub fn b() {}")]);
+
+            let url = dir.to_str().unwrap();
+            let checkout = clone_in_temp_dir(url, &head.to_string(), PathBuf::from("foo")).unwrap();
+
+            assert_eq!(
+                fs::read_to_string(checkout.crate_dir().join("src/lib.rs")).unwrap(),
+                "pub fn a() {}"
+            );
+
+            drop(checkout);
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn identical_revisions() {
+            let dir = env::temp_dir().join(format!("cb-test-identical-{}", process::id()));
+            let repo = Repository::init(&dir).unwrap();
+
+            commit_files(&repo, &[("foo/src/lib.rs", "pub fn a() {}")]);
+            commit_files(&repo, &[("foo/src/lib.rs", "pub fn b() {}")]);
+            commit_files(&repo, &[("README.md", "Hello")]);
+
+            let mut crate_repo = CrateRepo {
+                repo,
+                previous_branch_name: None,
+                needs_stash: false,
+            };
+            let crate_dir = dir.join("foo");
+
+            // Only the crate directory is compared.
+            assert!(crate_repo
+                .crate_is_identical_to("HEAD~1", &crate_dir)
+                .unwrap());
+            assert!(!crate_repo.crate_is_identical_to("HEAD~1", &dir).unwrap());
+            assert!(!crate_repo
+                .crate_is_identical_to("HEAD~2", &crate_dir)
+                .unwrap());
+
+            crate_repo.needs_stash = true;
+            assert!(!crate_repo
+                .crate_is_identical_to("HEAD", &crate_dir)
+                .unwrap());
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn directory_outside_of_repository() {
+            let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+            assert!(relative_crate_path(&manifest_dir.join("src"), manifest_dir).is_err());
+        }
+    }
+
+    mod in_place_checkout {
+        use super::{checkout_in_temp_dir::commit_files, *};
+
+        #[test]
+        fn local_changes_are_restored() {
+            let dir = env::temp_dir().join(format!("cb-test-stash-{}", process::id()));
+            let repo = Repository::init(&dir).unwrap();
+
+            let mut config = repo.config().unwrap();
+            config.set_str("user.name", "test").unwrap();
+            config.set_str("user.email", "test@example.com").unwrap();
+
+            commit_files(&repo, &[("src/lib.rs", "pub fn a() {}")]);
+            commit_files(&repo, &[("src/lib.rs", "pub fn b() {}")]);
+
+            fs::write(dir.join("src/lib.rs"), "pub fn dirty() {}").unwrap();
+            fs::write(dir.join("src/untracked.rs"), "pub fn c() {}").unwrap();
+
+            let needs_stash = CrateRepo::needs_stash(&repo).unwrap();
+            let mut crate_repo = CrateRepo {
+                repo,
+                previous_branch_name: None,
+                needs_stash,
+            };
+
+            let (previous, untracked) = crate_repo
+                .run_in("HEAD~1", || {
+                    (
+                        fs::read_to_string(dir.join("src/lib.rs")).unwrap(),
+                        dir.join("src/untracked.rs").exists(),
+                    )
+                })
+                .unwrap();
+
+            assert_eq!(previous, "pub fn a() {}");
+            assert!(!untracked);
+
+            assert_eq!(
+                fs::read_to_string(dir.join("src/lib.rs")).unwrap(),
+                "pub fn dirty() {}"
+            );
+            assert_eq!(
+                fs::read_to_string(dir.join("src/untracked.rs")).unwrap(),
+                "pub fn c() {}"
+            );
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    mod dated_revision {
+        use git2::{Signature, Time};
+
+        use super::*;
+
+        fn commit_at(repo: &Repository, content: &str, seconds: i64) -> git2::Oid {
+            fs::write(repo.workdir().unwrap().join("lib.rs"), content).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("lib.rs")).unwrap();
+
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = Signature::new("test", "test@example.com", &Time::new(seconds, 0));
+            let signature = signature.unwrap();
+            let parent = repo.head().ok().map(|h| h.peel_to_commit().unwrap());
+
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "commit",
+                &tree,
+                parent.as_ref().into_iter().collect::<Vec<_>>().as_slice(),
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn resolved_from_history() {
+            let dir = env::temp_dir().join(format!("cb-test-dated-{}", process::id()));
+            let repo = Repository::init(&dir).unwrap();
+
+            // 2024-01-01 and 2024-02-01, at noon.
+            let january = commit_at(&repo, "pub fn a() {}", 1_704_110_400);
+            let february = commit_at(&repo, "pub fn b() {}", 1_706_788_800);
+
+            let crate_repo = CrateRepo {
+                repo,
+                previous_branch_name: None,
+                needs_stash: false,
+            };
+            let resolve = |id: &str| crate_repo.find_object(id).map(|(obj, _)| obj.id());
+
+            assert_eq!(resolve("HEAD@{2024-01-15}").unwrap(), january);
+            assert_eq!(resolve("@{2024-03-01}").unwrap(), february);
+            assert_eq!(
+                resolve("HEAD@{2024-01-01}").unwrap_err().to_string(),
+                "`HEAD` has no commit before 2024-01-01"
+            );
+            assert!(crate_repo.resolves("HEAD@{2024-02-01}"));
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    mod restore {
+        use super::{checkout_in_temp_dir::commit_files, *};
+
+        #[test]
+        fn leftover_stash_is_restored() {
+            let dir = env::temp_dir().join(format!("cb-test-restore-{}", process::id()));
+            let repo = Repository::init(&dir).unwrap();
+
+            let mut config = repo.config().unwrap();
+            config.set_str("user.name", "test").unwrap();
+            config.set_str("user.email", "test@example.com").unwrap();
+
+            commit_files(&repo, &[("src/lib.rs", "pub fn a() {}")]);
+            commit_files(&repo, &[("src/lib.rs", "pub fn b() {}")]);
+            let branch = repo.head().unwrap().name().unwrap().to_owned();
+
+            fs::write(dir.join("src/lib.rs"), "pub fn dirty() {}").unwrap();
+
+            let mut crate_repo = CrateRepo {
+                repo,
+                previous_branch_name: None,
+                needs_stash: true,
+            };
+
+            // Interrupted after the checkout.
+            crate_repo.switch_to("HEAD~1").unwrap();
+            assert_eq!(
+                fs::read_to_string(dir.join("src/lib.rs")).unwrap(),
+                "pub fn a() {}"
+            );
+
+            let restored = crate_repo.restore_leftover_stash().unwrap();
+
+            assert_eq!(restored, Some(branch.clone()));
+            assert_eq!(crate_repo.head_name().unwrap(), Some(branch));
+            assert_eq!(
+                fs::read_to_string(dir.join("src/lib.rs")).unwrap(),
+                "pub fn dirty() {}"
+            );
+            assert_eq!(crate_repo.restore_leftover_stash().unwrap(), None);
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    mod release_lines {
+        use super::{checkout_in_temp_dir::commit_files, *};
+
+        #[test]
+        fn branch_and_tags() {
+            let dir = env::temp_dir().join(format!("cb-test-tags-{}", process::id()));
+            let repo = Repository::init(&dir).unwrap();
+
+            commit_files(&repo, &[("src/lib.rs", "pub fn a() {}")]);
+            let head = repo.head().unwrap().peel_to_commit().unwrap();
+            repo.branch("release-1.2", &head, false).unwrap();
+            repo.set_head("refs/heads/release-1.2").unwrap();
+            repo.tag_lightweight("v1.2.0", head.as_object(), false)
+                .unwrap();
+            repo.tag_lightweight("v1.2.1", head.as_object(), false)
+                .unwrap();
+            drop(head);
+
+            let crate_repo = CrateRepo {
+                repo,
+                previous_branch_name: None,
+                needs_stash: false,
+            };
+
+            assert_eq!(crate_repo.current_branch().unwrap(), "release-1.2");
+            assert_eq!(crate_repo.tag_names().unwrap(), ["v1.2.0", "v1.2.1"]);
+
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
     mod switch_to_default_impl {
         use super::*;
 
@@ -0,0 +1,66 @@
+/// Returns whether `text` matches `pattern`, in which `*` stands for any
+/// sequence of characters, including an empty one.
+pub(crate) fn matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+
+    // There is always at least one part, even for an empty pattern.
+    let first = parts.next().unwrap();
+    let mut rest = match text.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let mut parts = parts.collect::<Vec<_>>();
+    let last = match parts.pop() {
+        Some(last) => last,
+        // No wildcard: the whole text must have been matched.
+        None => return rest.is_empty(),
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literal() {
+        assert!(matches("main", "main"));
+        assert!(!matches("main", "main2"));
+        assert!(!matches("main", "mai"));
+    }
+
+    #[test]
+    fn trailing_wildcard() {
+        assert!(matches("release-*", "release-1.2"));
+        assert!(matches("release-*", "release-"));
+        assert!(!matches("release-*", "hotfix-1.2"));
+    }
+
+    #[test]
+    fn inner_wildcards() {
+        assert!(matches("v*.*.x", "v1.2.x"));
+        assert!(matches("foo::*::Bar", "foo::baz::qux::Bar"));
+        assert!(!matches("v*.*.x", "v1.2"));
+    }
+
+    #[test]
+    fn wildcard_only() {
+        assert!(matches("*", ""));
+        assert!(matches("*", "anything"));
+    }
+
+    #[test]
+    fn overlapping_prefix_and_suffix() {
+        assert!(!matches("ab*ba", "aba"));
+        assert!(matches("ab*ba", "abba"));
+    }
+}
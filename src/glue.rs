@@ -1,47 +1,631 @@
 use std::{
-    error::Error,
+    collections::BTreeMap,
+    error::Error as StdError,
     fmt::{Display, Formatter, Result as FmtResult},
-    process::Command,
+    fs,
+    io::{BufRead, BufReader, Read, Result as IoResult},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
     str::FromStr,
+    thread,
 };
 
-use anyhow::{bail, Context, Result as AnyResult};
-use syn::Error as SynError;
+use anyhow::{bail, Context, Error as AnyError, Result as AnyResult};
+use syn::{parse_quote, Error as SynError, Ident};
+
+use crate::{
+    archive,
+    ast::{CfgOptions, CrateAst},
+    c_header,
+    comparator::ApiComparator,
+    display_examples,
+    error::Error,
+    facade,
+    git::{CrateRepo, GitBackend},
+    js_exports, manifest, probe,
+    public_api::{PathPrefix, PublicApi},
+    py_exports, serde_compat, symbols,
+};
+
+/// The crate root, unless the manifest says otherwise.
+const DEFAULT_CRATE_ROOT: &str = "src/lib.rs";
+
+/// The unstable rustc flags used to get the expanded code of a crate.
+pub(crate) const EXPANSION_FLAGS: &[&str] =
+    &["-Z", "unpretty=expanded", "-Z", "unpretty=everybody_loops"];
+
+/// How the public API of a crate is extracted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ExtractionConfig {
+    pub backend: Backend,
+    pub include_private: bool,
+    /// Print the compiler output while building, each line prefixed with the
+    /// version being built.
+    pub display_build_output: bool,
+    /// Only keep the items located in these subtrees. Everything is kept if
+    /// it is empty.
+    pub path_filters: Vec<PathPrefix>,
+    /// Remove the items located in these subtrees.
+    pub ignored_paths: Vec<PathPrefix>,
+    /// The features to enable, in addition to the default ones.
+    pub features: Vec<String>,
+    /// The target directory shared by every build. The previous version,
+    /// when built in a temporary directory, reuses the dependencies compiled
+    /// for the current one instead of building them from scratch.
+    pub target_dir: Option<PathBuf>,
+    /// How the builds may update the lockfile of each version.
+    pub lockfile: Lockfile,
+    pub target: Target,
+    /// The examples whose public items are compared too, as if they were
+    /// in the `examples` module of the crate.
+    pub examples: Vec<String>,
+    /// Build a crate depending on each version to find the auto traits
+    /// leaked by the opaque types its functions return.
+    pub probe_auto_traits: bool,
+    /// Build a `#![no_std]` crate depending on each version, without its
+    /// default features, for this target to find whether it still supports
+    /// `no_std`.
+    pub no_std_target: Option<String>,
+    /// Generate the C header of each version with cbindgen, to compare its
+    /// declarations too.
+    pub c_header: bool,
+    /// Build the `cdylib` or `staticlib` library of each version, to compare
+    /// the symbols it exports.
+    pub symbols: bool,
+    /// Look for the items exported to JavaScript by the `#[wasm_bindgen]`
+    /// attributes of each version, to compare them too.
+    pub wasm_bindgen: bool,
+    /// Look for the items exported to Python by the PyO3 attributes of each
+    /// version, to compare them too.
+    pub pyo3: bool,
+    /// Read how the types deriving serde traits are serialized in each
+    /// version, to find the changes of their serialized form.
+    pub serde: bool,
+    /// Read the `Display` outputs asserted by the documentation of each
+    /// version, to find the ones which changed.
+    pub display_examples: bool,
+    /// How many levels of dependencies the re-exported items are followed
+    /// into. The items re-exported from the dependencies of the crate are
+    /// compared as if it defined them. Nothing is followed if it is 0.
+    pub foreign_depth: usize,
+}
+
+/// The target of the package whose API is compared.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) enum Target {
+    #[default]
+    Lib,
+    /// A binary, whose public items are compared as if it was a library.
+    Bin(String),
+    /// An example. Examples are only read when they are explicitly
+    /// included, in addition to the library or the binary.
+    Example(String),
+}
+
+impl Target {
+    fn cargo_args(&self) -> Vec<&str> {
+        match self {
+            Target::Lib => vec!["--lib"],
+            Target::Bin(name) => vec!["--bin", name],
+            Target::Example(name) => vec!["--example", name],
+        }
+    }
+}
+
+/// How the builds may update `Cargo.lock`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Lockfile {
+    /// Let cargo update the lockfile as it needs.
+    #[default]
+    Unlocked,
+    /// Build with the lockfile of each version, failing if it is out of
+    /// date.
+    Locked,
+    /// Same as `Locked`, without accessing the network.
+    Frozen,
+}
+
+impl Lockfile {
+    /// Returns the cargo flag to build the crate located in `dir` with.
+    /// Versions which have no lockfile are built unlocked.
+    pub(crate) fn cargo_flag(self, dir: &Path) -> AnyResult<Option<&'static str>> {
+        let flag = match self {
+            Lockfile::Unlocked => return Ok(None),
+            Lockfile::Locked => "--locked",
+            Lockfile::Frozen => "--frozen",
+        };
+
+        // The lockfile of a workspace member is at the workspace root, and
+        // nowhere else.
+        let root = workspace_root(dir)?;
+
+        Ok(root.join("Cargo.lock").is_file().then_some(flag))
+    }
+}
+
+/// How the code of a crate is turned into an AST.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Backend {
+    /// Expand the crate with the nightly compiler, so that the items
+    /// generated by macros are seen.
+    #[default]
+    Expanded,
+    /// Parse the source files, following `mod` declarations and evaluating
+    /// `#[cfg(feature = "...")]` attributes with the default features. The
+    /// items generated by macros are missed, but no toolchain is needed.
+    Source,
+}
+
+impl Backend {
+    pub(crate) const VARIANTS: &'static [&'static str] = &["expanded", "source"];
+
+    fn extractor(self) -> &'static dyn ApiExtractor {
+        match self {
+            Backend::Expanded => &ExpandedExtractor,
+            Backend::Source => &SourceExtractor,
+        }
+    }
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Backend::Expanded => "expanded",
+            Backend::Source => "source",
+        }
+        .fmt(f)
+    }
+}
+
+impl FromStr for Backend {
+    type Err = AnyError;
+
+    fn from_str(s: &str) -> AnyResult<Backend> {
+        match s {
+            "expanded" => Ok(Backend::Expanded),
+            "source" => Ok(Backend::Source),
+            other => bail!("Unknown backend `{}`", other),
+        }
+    }
+}
+
+/// Reads the code of a crate.
+trait ApiExtractor {
+    /// Returns the AST of the `target` of the crate located in `dir`.
+    /// `version` is either `previous` or `next`.
+    fn crate_ast(
+        &self,
+        config: &ExtractionConfig,
+        target: &Target,
+        dir: &Path,
+        version: &str,
+    ) -> AnyResult<CrateAst>;
+}
+
+struct ExpandedExtractor;
+
+impl ApiExtractor for ExpandedExtractor {
+    fn crate_ast(
+        &self,
+        config: &ExtractionConfig,
+        target: &Target,
+        dir: &Path,
+        version: &str,
+    ) -> AnyResult<CrateAst> {
+        let mut command = Command::new("cargo");
+        command
+            .current_dir(dir)
+            .arg("+nightly")
+            .arg("rustc")
+            .args(target.cargo_args())
+            .args(config.lockfile.cargo_flag(dir)?)
+            .args(features_args(&config.features))
+            .arg("--")
+            .args(EXPANSION_FLAGS)
+            .arg("--emit=mir")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        if let Some(target_dir) = &config.target_dir {
+            command.env("CARGO_TARGET_DIR", target_dir);
+        }
+
+        let mut child = command.spawn().context("Failed to run `cargo rustc`")?;
+
+        // stdout must be drained while stderr is being read, otherwise the
+        // compiler may block on a full pipe.
+        let mut stdout = child.stdout.take().unwrap();
+        let stdout = thread::spawn(move || {
+            let mut buffer = Vec::new();
+            stdout.read_to_end(&mut buffer).map(|_| buffer)
+        });
+
+        let stderr = stream_build_output(
+            child.stderr.take().unwrap(),
+            config.display_build_output.then_some(version),
+        )
+        .context("Failed to read rustc output")?;
+
+        let stdout = stdout
+            .join()
+            .unwrap()
+            .context("Failed to read rustc output")?;
+        let status = child.wait().context("Failed to run `cargo rustc`")?;
+
+        if !status.success() {
+            let stderr = String::from_utf8(stderr)
+                .map_err(|_| InvalidRustcOutputEncoding)
+                .context("Failed to get rustc error message")?;
+            bail!(stderr);
+        }
+
+        let expanded_code = String::from_utf8(stdout)
+            .map_err(|_| InvalidRustcOutputEncoding)
+            .context("Failed to get rustc-expanded crate code")
+            .map_err(Error::UnsupportedFormat)?;
+
+        CrateAst::from_str(&expanded_code)
+            .map_err(InvalidRustcAst)
+            .context("Failed to parse rustc-provided crate AST")
+            .map_err(|e| Error::UnsupportedFormat(e).into())
+    }
+}
+
+struct SourceExtractor;
 
-use crate::{ast::CrateAst, comparator::ApiComparator, public_api::PublicApi};
+impl ApiExtractor for SourceExtractor {
+    fn crate_ast(
+        &self,
+        config: &ExtractionConfig,
+        target: &Target,
+        dir: &Path,
+        _version: &str,
+    ) -> AnyResult<CrateAst> {
+        let manifest = manifest::load_manifest_in(dir)?;
 
-pub(crate) fn extract_api() -> AnyResult<PublicApi> {
+        let root = match target {
+            Target::Lib => manifest
+                .lib
+                .as_ref()
+                .and_then(|lib| lib.path.clone())
+                .unwrap_or_else(|| DEFAULT_CRATE_ROOT.to_owned()),
+            Target::Bin(name) => manifest::bin_root(&manifest, name)?,
+            Target::Example(name) => manifest::example_root(&manifest, name)?,
+        };
+
+        let cfg = CfgOptions::new(manifest::enabled_features(
+            &manifest,
+            config.features.iter().cloned(),
+        ))
+        .with_host_cfgs()?;
+
+        CrateAst::from_sources(
+            Path::new(&root),
+            |path| fs::read_to_string(dir.join(path)).ok(),
+            &cfg,
+        )
+    }
+}
+
+/// How the code of the previous version is made available to the compiler.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CheckoutStrategy {
+    /// Stash the local changes, check the revision out in the working tree,
+    /// then switch back.
+    InPlace,
+    /// Write the revision in a temporary directory. The working tree is left
+    /// untouched, so files locked by other programs (editors, language
+    /// servers) are not a problem.
+    TempDir,
+}
+
+impl CheckoutStrategy {
+    pub(crate) const VARIANTS: &'static [&'static str] = &["in-place", "temp-dir"];
+
+    /// Files which are open in another program can't be overwritten on
+    /// Windows, which makes in-place checkouts fragile there.
+    #[cfg(windows)]
+    pub(crate) const DEFAULT: CheckoutStrategy = CheckoutStrategy::TempDir;
+    #[cfg(not(windows))]
+    pub(crate) const DEFAULT: CheckoutStrategy = CheckoutStrategy::InPlace;
+}
+
+impl FromStr for CheckoutStrategy {
+    type Err = AnyError;
+
+    fn from_str(s: &str) -> AnyResult<CheckoutStrategy> {
+        match s {
+            "in-place" => Ok(CheckoutStrategy::InPlace),
+            "temp-dir" => Ok(CheckoutStrategy::TempDir),
+            other => bail!("Unknown checkout strategy `{}`", other),
+        }
+    }
+}
+
+/// Where the code of the previous version of the crate comes from.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum CrateCodeProvider {
+    /// A revision of the repository of the current directory.
+    Local {
+        revision: String,
+        strategy: CheckoutStrategy,
+    },
+    /// A revision of another repository, such as the upstream repository of
+    /// a fork. It is cloned in a temporary directory.
+    Remote { url: String, revision: String },
+    /// A crate archive, such as the `.crate` files made by `cargo package`.
+    /// It is unpacked in a temporary directory.
+    Archive { path: PathBuf },
+}
+
+impl CrateCodeProvider {
+    /// Parses a `<url>#<revision>` specification. The revision defaults to
+    /// `HEAD` when it is omitted.
+    pub(crate) fn remote(spec: &str) -> AnyResult<CrateCodeProvider> {
+        let (url, revision) = match spec.rsplit_once('#') {
+            Some((url, revision)) => (url, revision),
+            None => (spec, "HEAD"),
+        };
+
+        if url.is_empty() || revision.is_empty() {
+            bail!("Expected `<url>#<revision>`, found `{}`", spec);
+        }
+
+        Ok(CrateCodeProvider::Remote {
+            url: url.to_owned(),
+            revision: revision.to_owned(),
+        })
+    }
+}
+
+pub(crate) fn features_args(features: &[String]) -> Vec<String> {
+    if features.is_empty() {
+        return Vec::new();
+    }
+
+    vec!["--features".to_owned(), features.join(",")]
+}
+
+/// Returns the target directory cargo uses for the crate located in `dir`.
+pub(crate) fn target_dir(dir: &Path) -> AnyResult<PathBuf> {
+    parse_target_dir(&metadata(dir)?)
+}
+
+/// Returns the root of the workspace the crate located in `dir` belongs to,
+/// which is the crate directory itself for a crate outside of a workspace.
+fn workspace_root(dir: &Path) -> AnyResult<PathBuf> {
+    parse_workspace_root(&metadata(dir)?)
+}
+
+fn metadata(dir: &Path) -> AnyResult<Vec<u8>> {
     let output = Command::new("cargo")
-        .arg("+nightly")
-        .arg("rustc")
-        .arg("--lib")
-        .arg("--")
-        .args(&["-Z", "unpretty=expanded"])
-        .args(&["-Z", "unpretty=everybody_loops"])
-        .arg("--emit=mir")
+        .current_dir(dir)
+        .args(["metadata", "--no-deps", "--format-version", "1"])
+        .stderr(Stdio::inherit())
         .output()
-        .context("Failed to run `cargo rustc`")?;
+        .context("Failed to run `cargo metadata`")?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8(output.stderr)
-            .map_err(|_| InvalidRustcOutputEncoding)
-            .context("Failed to get rustc error message")?;
-        bail!(stderr);
+        bail!("`cargo metadata` failed");
+    }
+
+    Ok(output.stdout)
+}
+
+fn parse_target_dir(metadata: &[u8]) -> AnyResult<PathBuf> {
+    let metadata = serde_json::from_slice::<serde_json::Value>(metadata)
+        .context("Failed to parse `cargo metadata` output")?;
+
+    match metadata["target_directory"].as_str() {
+        Some(target_dir) => Ok(PathBuf::from(target_dir)),
+        None => bail!("`cargo metadata` did not return the target directory"),
+    }
+}
+
+fn parse_workspace_root(metadata: &[u8]) -> AnyResult<PathBuf> {
+    let metadata = serde_json::from_slice::<serde_json::Value>(metadata)
+        .context("Failed to parse `cargo metadata` output")?;
+
+    match metadata["workspace_root"].as_str() {
+        Some(root) => Ok(PathBuf::from(root)),
+        None => bail!("`cargo metadata` did not return the workspace root"),
+    }
+}
+
+/// Extracts the API of the previous version of the crate.
+pub(crate) fn extract_previous_api(
+    repo: &mut CrateRepo,
+    provider: &CrateCodeProvider,
+    config: &ExtractionConfig,
+) -> AnyResult<PublicApi> {
+    match provider {
+        CrateCodeProvider::Local {
+            revision,
+            strategy: CheckoutStrategy::InPlace,
+        } => repo
+            .run_in(revision, || extract_api(config, Path::new("."), "previous"))
+            .map_err(Error::Git)?,
+
+        CrateCodeProvider::Local {
+            revision,
+            strategy: CheckoutStrategy::TempDir,
+        } => {
+            let checkout = repo.checkout_in_temp_dir(revision).map_err(Error::Git)?;
+            extract_api(config, checkout.crate_dir(), "previous")
+        }
+
+        CrateCodeProvider::Remote { url, revision } => {
+            let checkout = repo.clone_in_temp_dir(url, revision).map_err(Error::Git)?;
+            extract_api(config, checkout.crate_dir(), "previous")
+        }
+
+        CrateCodeProvider::Archive { path } => {
+            let checkout = archive::unpack(path)?;
+            extract_api(config, checkout.crate_dir(), "previous")
+        }
+    }
+}
+
+/// Extracts the API of the crate located in `dir`.
+pub(crate) fn extract_api(
+    config: &ExtractionConfig,
+    dir: &Path,
+    version: &str,
+) -> AnyResult<PublicApi> {
+    let extractor = config.backend.extractor();
+    let mut ast = extractor.crate_ast(config, &config.target, dir, version)?;
+
+    if config.foreign_depth > 0 {
+        follow_reexports(&mut ast, dir, version, config.foreign_depth).with_context(|| {
+            format!("Failed to follow the re-exports of the {} version", version)
+        })?;
     }
 
-    let expanded_code = String::from_utf8(output.stdout)
-        .map_err(|_| InvalidRustcOutputEncoding)
-        .context("Failed to get rustc-expanded crate code")?;
+    if !config.examples.is_empty() {
+        let examples = config
+            .examples
+            .iter()
+            .map(|name| {
+                let example = Target::Example(name.clone());
+                let module = syn::parse_str::<Ident>(&name.replace('-', "_"))
+                    .with_context(|| format!("`{}` can't be used as a module name", name))?;
+
+                extractor
+                    .crate_ast(config, &example, dir, version)
+                    .map(|ast| ast.into_module(module))
+                    .with_context(|| format!("Failed to read the `{}` example", name))
+            })
+            .collect::<AnyResult<Vec<_>>>()?;
+
+        ast.0.items.push(parse_quote! {
+            pub mod examples {
+                #(#examples)*
+            }
+        });
+    }
 
-    let ast = CrateAst::from_str(&expanded_code)
-        .map_err(InvalidRustcAst)
-        .context("Failed to parse rustc-provided crate AST")?;
+    let mut api = if config.include_private {
+        PublicApi::from_ast_including_private(&ast)
+    } else {
+        PublicApi::from_ast(&ast)
+    };
 
-    let api = PublicApi::from_ast(&ast);
+    api.retain_prefixes(&config.path_filters);
+    api.remove_prefixes(&config.ignored_paths);
+
+    let manifest = manifest::load_manifest_in(dir)
+        .with_context(|| format!("Failed to read the manifest of the {} version", version))?;
+    api.set_edition(manifest::edition(&manifest));
+
+    if config.probe_auto_traits {
+        let auto_traits = probe::auto_traits(config, dir, &api).with_context(|| {
+            format!("Failed to probe the auto traits of the {} version", version)
+        })?;
+        api.set_auto_traits(auto_traits);
+    }
+
+    if let Some(target) = &config.no_std_target {
+        let no_std = probe::no_std(config, dir, target).with_context(|| {
+            format!(
+                "Failed to probe the `no_std` support of the {} version",
+                version
+            )
+        })?;
+        api.set_no_std(no_std);
+    }
+
+    if config.c_header {
+        let c_header = c_header::extract(dir).with_context(|| {
+            format!("Failed to generate the C header of the {} version", version)
+        })?;
+        api.set_c_header(c_header);
+    }
+
+    if config.symbols {
+        let symbols = symbols::exported(config, dir)
+            .with_context(|| format!("Failed to read the symbols of the {} version", version))?;
+        api.set_symbols(symbols);
+    }
+
+    if config.wasm_bindgen || config.pyo3 || config.serde || config.display_examples {
+        // The attributes are gone once the crate is expanded.
+        let source = SourceExtractor
+            .crate_ast(config, &config.target, dir, version)
+            .with_context(|| format!("Failed to read the sources of the {} version", version))?;
+
+        if config.wasm_bindgen {
+            api.set_js_exports(js_exports::extract(&source));
+        }
+
+        if config.pyo3 {
+            api.set_py_exports(py_exports::extract(&source));
+        }
+
+        if config.serde {
+            api.set_serde_types(serde_compat::extract(&source));
+        }
+
+        if config.display_examples {
+            api.set_display_examples(display_examples::extract(&source));
+        }
+    }
 
     Ok(api)
 }
 
+/// Replaces the `pub use` declarations of the crate located in `dir` which
+/// re-export items of its dependencies with the items themselves. The
+/// dependencies are always read from their source code, and their own
+/// re-exports are followed as long as `depth` allows it.
+fn follow_reexports(ast: &mut CrateAst, dir: &Path, version: &str, depth: usize) -> AnyResult<()> {
+    let crates = facade::reexported_crates(ast);
+    if crates.is_empty() {
+        return Ok(());
+    }
+
+    let dirs = facade::dependency_dirs(dir)?;
+    let dependencies = crates
+        .into_iter()
+        .filter_map(|(ident, name)| Some((ident, dirs.get(&name.to_string())?)))
+        .map(|(ident, dep_dir)| {
+            let mut dep = SourceExtractor
+                .crate_ast(&ExtractionConfig::default(), &Target::Lib, dep_dir, version)
+                .with_context(|| format!("Failed to read the `{}` dependency", ident))?;
+
+            if depth > 1 {
+                follow_reexports(&mut dep, dep_dir, version, depth - 1)?;
+            }
+
+            Ok((ident, dep))
+        })
+        .collect::<AnyResult<BTreeMap<_, _>>>()?;
+
+    facade::inline_reexports(ast, &dependencies);
+
+    Ok(())
+}
+
+/// Reads the whole build output. If a prefix is provided, each line is also
+/// printed on stderr as soon as it is read.
+fn stream_build_output(output: impl Read, prefix: Option<&str>) -> IoResult<Vec<u8>> {
+    let mut output = BufReader::new(output);
+    let mut buffer = Vec::new();
+
+    loop {
+        let start = buffer.len();
+        if output.read_until(b'\n', &mut buffer)? == 0 {
+            break;
+        }
+
+        if let Some(prefix) = prefix {
+            let line = String::from_utf8_lossy(&buffer[start..]);
+            eprintln!("[{}] {}", prefix, line.trim_end_matches(&['\r', '\n'][..]));
+        }
+    }
+
+    Ok(buffer)
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 struct InvalidRustcOutputEncoding;
 
@@ -51,7 +635,7 @@ impl Display for InvalidRustcOutputEncoding {
     }
 }
 
-impl Error for InvalidRustcOutputEncoding {}
+impl StdError for InvalidRustcOutputEncoding {}
 
 #[derive(Clone, Debug)]
 struct InvalidRustcAst(SynError);
@@ -62,7 +646,7 @@ impl Display for InvalidRustcAst {
     }
 }
 
-impl Error for InvalidRustcAst {}
+impl StdError for InvalidRustcAst {}
 
 pub fn compare(prev: &str, curr: &str) -> AnyResult<ApiComparator> {
     let prev_ast = CrateAst::from_str(prev).context("Failed to parse code for previous version")?;
@@ -73,3 +657,331 @@ pub fn compare(prev: &str, curr: &str) -> AnyResult<ApiComparator> {
 
     Ok(ApiComparator::new(prev_api, curr_api))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn parse_checkout_strategy() {
+        for strategy in CheckoutStrategy::VARIANTS {
+            assert!(CheckoutStrategy::from_str(strategy).is_ok());
+        }
+
+        assert!(CheckoutStrategy::from_str("copy").is_err());
+    }
+
+    #[test]
+    fn parse_backend() {
+        for backend in Backend::VARIANTS {
+            assert!(Backend::from_str(backend).is_ok());
+        }
+
+        assert!(Backend::from_str("rustdoc").is_err());
+    }
+
+    #[test]
+    fn source_backend_reads_modules_and_default_features() {
+        let dir = env::temp_dir().join(format!("cb-test-source-{}", process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n\n\
+             [features]\ndefault = [\"a\"]\na = []\nb = []\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("src/lib.rs"),
+            "pub mod m;\n\
+             #[cfg(feature = \"a\")] pub fn a() {}\n\
+             #[cfg(feature = \"b\")] pub fn b() {}\n",
+        )
+        .unwrap();
+        fs::write(dir.join("src/m.rs"), "pub struct S;").unwrap();
+
+        let config = ExtractionConfig {
+            backend: Backend::Source,
+            ..ExtractionConfig::default()
+        };
+        let api = extract_api(&config, &dir, "next").unwrap();
+
+        let paths = api
+            .items()
+            .keys()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["a", "m::S"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn source_backend_evaluates_host_cfgs() {
+        let dir = env::temp_dir().join(format!("cb-test-source-cfg-{}", process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("src/lib.rs"),
+            "#[cfg(unix)] pub fn unix() {}\n\
+             #[cfg(not(unix))] pub fn not_unix() {}\n",
+        )
+        .unwrap();
+
+        let config = ExtractionConfig {
+            backend: Backend::Source,
+            ..ExtractionConfig::default()
+        };
+        let api = extract_api(&config, &dir, "next").unwrap();
+
+        let paths = api
+            .items()
+            .keys()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let expected = if cfg!(unix) { "unix" } else { "not_unix" };
+        assert_eq!(paths, [expected]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn source_backend_reads_binaries() {
+        let dir = env::temp_dir().join(format!("cb-test-source-bin-{}", process::id()));
+        fs::create_dir_all(dir.join("src/bin")).unwrap();
+
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("src/main.rs"), "pub fn main() {}").unwrap();
+        fs::write(dir.join("src/bin/tool.rs"), "pub fn run() {}").unwrap();
+
+        let config = ExtractionConfig {
+            backend: Backend::Source,
+            target: Target::Bin("tool".to_owned()),
+            ..ExtractionConfig::default()
+        };
+        let api = extract_api(&config, &dir, "next").unwrap();
+
+        let paths = api
+            .items()
+            .keys()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["run"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn examples_are_only_read_when_included() {
+        let dir = env::temp_dir().join(format!("cb-test-source-examples-{}", process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("examples")).unwrap();
+        fs::create_dir_all(dir.join("benches")).unwrap();
+
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("src/lib.rs"), "pub fn f() {}").unwrap();
+        fs::write(dir.join("examples/my-plugin.rs"), "pub struct Plugin;").unwrap();
+        fs::write(dir.join("benches/bench.rs"), "pub fn bench() {}").unwrap();
+
+        let paths = |examples: Vec<String>| {
+            let config = ExtractionConfig {
+                backend: Backend::Source,
+                examples,
+                ..ExtractionConfig::default()
+            };
+
+            extract_api(&config, &dir, "next")
+                .unwrap()
+                .items()
+                .keys()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(paths(Vec::new()), ["f"]);
+        assert_eq!(
+            paths(vec!["my-plugin".to_owned()]),
+            ["examples::my_plugin::Plugin", "f"]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn source_backend_follows_reexports() {
+        let dir = env::temp_dir().join(format!("cb-test-source-facade-{}", process::id()));
+        fs::create_dir_all(dir.join("facade/src")).unwrap();
+        fs::create_dir_all(dir.join("inner/src")).unwrap();
+        fs::create_dir_all(dir.join("deep/src")).unwrap();
+
+        fs::write(
+            dir.join("facade/Cargo.toml"),
+            "[package]\nname = \"facade\"\nversion = \"0.1.0\"\n\n\
+             [dependencies]\nrenamed = { package = \"inner\", path = \"../inner\" }\n\n\
+             [workspace]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("facade/src/lib.rs"),
+            "pub use renamed::*;\npub mod extra { pub use renamed::m::S as T; }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("inner/Cargo.toml"),
+            "[package]\nname = \"inner\"\nversion = \"0.1.0\"\n\n\
+             [dependencies]\ndeep = { path = \"../deep\" }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("inner/src/lib.rs"),
+            "pub use deep::g;\npub fn f() {}\n\
+             pub mod m { pub struct S; impl S { pub fn new() {} } }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("deep/Cargo.toml"),
+            "[package]\nname = \"deep\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(dir.join("deep/src/lib.rs"), "pub fn g() {}\n").unwrap();
+
+        let paths = |foreign_depth| {
+            let config = ExtractionConfig {
+                backend: Backend::Source,
+                foreign_depth,
+                ..ExtractionConfig::default()
+            };
+
+            extract_api(&config, &dir.join("facade"), "next")
+                .unwrap()
+                .items()
+                .keys()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(paths(0).is_empty());
+        assert_eq!(
+            paths(1),
+            ["extra::T", "extra::T::new", "f", "m::S", "m::S::new"]
+        );
+        assert_eq!(
+            paths(2),
+            ["extra::T", "extra::T::new", "f", "g", "m::S", "m::S::new"]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn lockfile_flag_requires_lockfile() {
+        let dir = env::temp_dir().join(format!("cb-test-lockfile-{}", process::id()));
+        let workspace = dir.join("workspace");
+        let member = workspace.join("member");
+        fs::create_dir_all(member.join("src")).unwrap();
+        fs::write(
+            workspace.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"member\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            member.join("Cargo.toml"),
+            "[package]\nname = \"member\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(member.join("src/lib.rs"), "").unwrap();
+
+        // Lockfiles outside of the workspace are unrelated.
+        fs::write(dir.join("Cargo.lock"), "").unwrap();
+
+        assert_eq!(Lockfile::Locked.cargo_flag(&member).unwrap(), None);
+
+        fs::write(workspace.join("Cargo.lock"), "").unwrap();
+
+        assert_eq!(Lockfile::Unlocked.cargo_flag(&member).unwrap(), None);
+        assert_eq!(
+            Lockfile::Locked.cargo_flag(&member).unwrap(),
+            Some("--locked")
+        );
+        assert_eq!(
+            Lockfile::Frozen.cargo_flag(&member).unwrap(),
+            Some("--frozen")
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn parse_metadata_workspace_root() {
+        let metadata = br#"{"packages": [], "workspace_root": "/work", "version": 1}"#;
+
+        assert_eq!(
+            parse_workspace_root(metadata).unwrap(),
+            PathBuf::from("/work")
+        );
+        assert!(parse_workspace_root(b"{}").is_err());
+    }
+
+    #[test]
+    fn parse_metadata_target_dir() {
+        let metadata = br#"{"packages": [], "target_directory": "/work/target", "version": 1}"#;
+
+        assert_eq!(
+            parse_target_dir(metadata).unwrap(),
+            PathBuf::from("/work/target")
+        );
+        assert!(parse_target_dir(b"{}").is_err());
+    }
+
+    #[test]
+    fn parse_remote() {
+        let expected = CrateCodeProvider::Remote {
+            url: "https://example.com/foo.git".to_owned(),
+            revision: "v1.0.0".to_owned(),
+        };
+
+        assert_eq!(
+            CrateCodeProvider::remote("https://example.com/foo.git#v1.0.0").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn parse_remote_default_revision() {
+        let expected = CrateCodeProvider::Remote {
+            url: "../foo".to_owned(),
+            revision: "HEAD".to_owned(),
+        };
+
+        assert_eq!(CrateCodeProvider::remote("../foo").unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_remote_empty_revision() {
+        assert!(CrateCodeProvider::remote("../foo#").is_err());
+    }
+
+    #[test]
+    fn build_output_is_fully_read() {
+        let output = "   Compiling foo\nwarning: unused\r\nerror";
+
+        let read = stream_build_output(output.as_bytes(), Some("next")).unwrap();
+
+        assert_eq!(read, output.as_bytes());
+    }
+}
@@ -0,0 +1,186 @@
+use std::{fs, io::ErrorKind, path::Path};
+
+use anyhow::{bail, Context, Result as AnyResult};
+
+use crate::git::CrateRepo;
+
+const HOOK_NAME: &str = "pre-push";
+
+/// Identifies the hooks written by `cargo-breaking`, which are the only ones
+/// it overwrites or removes.
+const HOOK_MARKER: &str = "# Installed by cargo-breaking.";
+
+/// What `cargo breaking install-hook` does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum HookCommand {
+    Install,
+    Remove,
+}
+
+/// Installs or removes the pre-push hook of the repository. The hook
+/// compares the current crate against `against`, or against the default
+/// reference if it is empty.
+///
+/// Returns a message describing what was done.
+pub(crate) fn run(repo: &CrateRepo, command: HookCommand, against: &[String]) -> AnyResult<String> {
+    let hook = repo.hooks_dir()?.join(HOOK_NAME);
+
+    match command {
+        HookCommand::Install => {
+            let script = script(&repo.current_crate_path()?, against);
+            install(&hook, &script)?;
+            Ok(format!("Installed {}", hook.display()))
+        }
+        HookCommand::Remove if remove(&hook)? => Ok(format!("Removed {}", hook.display())),
+        HookCommand::Remove => Ok("No hook installed by cargo-breaking was found".to_owned()),
+    }
+}
+
+/// Returns the hook script. It only prints the changes, the push is never
+/// blocked.
+fn script(crate_path: &Path, against: &[String]) -> String {
+    let mut command = "cargo breaking --checkout temp-dir".to_owned();
+    for reference in against {
+        command.push_str(" -a ");
+        command.push_str(&quote(reference));
+    }
+
+    let cd = if crate_path.as_os_str().is_empty() {
+        String::new()
+    } else {
+        format!("cd {} || exit 0\n", quote(&crate_path.to_string_lossy()))
+    };
+
+    format!(
+        "#!/bin/sh\n\
+         {}\n\
+         # Remove it with `cargo breaking install-hook --remove`.\n\
+         #\n\
+         # Prints the API changes of the crate before pushing, without blocking\n\
+         # the push.\n\
+         \n\
+         {}{} >&2 || echo \"cargo-breaking failed, the API changes were not checked\" >&2\n\
+         exit 0\n",
+        HOOK_MARKER, cd, command
+    )
+}
+
+fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+fn install(hook: &Path, script: &str) -> AnyResult<()> {
+    if !is_ours(hook)?.unwrap_or(true) {
+        bail!(
+            "{} already exists and was not installed by cargo-breaking",
+            hook.display()
+        );
+    }
+
+    if let Some(dir) = hook.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    fs::write(hook, script).with_context(|| format!("Failed to write {}", hook.display()))?;
+    make_executable(hook)
+}
+
+/// Removes the hook if it was installed by `cargo-breaking`. Returns whether
+/// it was.
+fn remove(hook: &Path) -> AnyResult<bool> {
+    match is_ours(hook)? {
+        Some(true) => {
+            fs::remove_file(hook)
+                .with_context(|| format!("Failed to remove {}", hook.display()))?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Returns whether the hook was installed by `cargo-breaking`, or `None` if
+/// there is no hook.
+fn is_ours(hook: &Path) -> AnyResult<Option<bool>> {
+    match fs::read_to_string(hook) {
+        Ok(content) => Ok(Some(content.contains(HOOK_MARKER))),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", hook.display())),
+    }
+}
+
+#[cfg(unix)]
+fn make_executable(hook: &Path) -> AnyResult<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(hook, fs::Permissions::from_mode(0o755))
+        .with_context(|| format!("Failed to make {} executable", hook.display()))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_: &Path) -> AnyResult<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn script_runs_from_crate_dir() {
+        let script = script(Path::new("crates/foo"), &["v1.0.0".to_owned()]);
+
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains(
+            "cd 'crates/foo' || exit 0\n\
+             cargo breaking --checkout temp-dir -a 'v1.0.0' >&2"
+        ));
+        assert!(script.ends_with("exit 0\n"));
+    }
+
+    #[test]
+    fn script_without_reference() {
+        let script = script(Path::new(""), &[]);
+
+        assert!(!script.contains("cd "));
+        assert!(script.contains("\ncargo breaking --checkout temp-dir >&2"));
+    }
+
+    #[test]
+    fn quotes_are_escaped() {
+        assert_eq!(quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn install_and_remove() {
+        let dir = env::temp_dir().join(format!("cb-test-hook-{}", process::id()));
+        let hook = dir.join("hooks").join(HOOK_NAME);
+
+        install(&hook, &script(Path::new(""), &[])).unwrap();
+        assert_eq!(is_ours(&hook).unwrap(), Some(true));
+
+        assert!(remove(&hook).unwrap());
+        assert_eq!(is_ours(&hook).unwrap(), None);
+        assert!(!remove(&hook).unwrap());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn foreign_hooks_are_kept() {
+        let dir = env::temp_dir().join(format!("cb-test-foreign-hook-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let hook = dir.join(HOOK_NAME);
+        fs::write(&hook, "#!/bin/sh\ncargo test\n").unwrap();
+
+        assert!(install(&hook, &script(Path::new(""), &[])).is_err());
+        assert!(!remove(&hook).unwrap());
+        assert_eq!(
+            fs::read_to_string(&hook).unwrap(),
+            "#!/bin/sh\ncargo test\n"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
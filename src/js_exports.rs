@@ -0,0 +1,436 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use syn::{
+    visit::Visit, Attribute, Fields, FnArg, Ident, ImplItem, ItemEnum, ItemFn, ItemForeignMod,
+    ItemImpl, ItemStruct, Type, Visibility,
+};
+
+use crate::{
+    ast::CrateAst,
+    diagnosis::DiagnosisItem,
+    public_api::{display_code, ItemPath, MacroArgs},
+    rules::RuleId,
+};
+
+/// What an item exported to JavaScript by wasm-bindgen is.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum JsExportKind {
+    Function,
+    Class,
+    Enum,
+    Constructor,
+    Method,
+    StaticMethod,
+    Property,
+    Getter,
+    Setter,
+}
+
+impl Display for JsExportKind {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let name = match self {
+            JsExportKind::Function => "function",
+            JsExportKind::Class => "class",
+            JsExportKind::Enum => "enum",
+            JsExportKind::Constructor => "constructor",
+            JsExportKind::Method => "method",
+            JsExportKind::StaticMethod => "static method",
+            JsExportKind::Property => "property",
+            JsExportKind::Getter => "getter",
+            JsExportKind::Setter => "setter",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// The items a crate exports to JavaScript, by JavaScript path and kind. The
+/// value is the Rust code they are generated from.
+pub(crate) type JsExports = BTreeMap<(Vec<String>, JsExportKind), String>;
+
+/// Finds the items exported to JavaScript by the `#[wasm_bindgen]` attributes
+/// of a crate. The attributes are gone once the crate is expanded, so the AST
+/// must be read from the source files.
+pub(crate) fn extract(ast: &CrateAst) -> JsExports {
+    let mut classes = ClassVisitor::default();
+    classes.visit_file(ast.ast());
+
+    let mut visitor = ExportVisitor {
+        classes: classes.0,
+        exports: BTreeMap::new(),
+    };
+    visitor.visit_file(ast.ast());
+
+    visitor.exports
+}
+
+// Returns the arguments of the `#[wasm_bindgen]` attribute of an item, if it
+// has one.
+fn wasm_bindgen(attrs: &[Attribute]) -> Option<MacroArgs> {
+    MacroArgs::find(attrs, "wasm_bindgen")
+}
+
+// The JavaScript name of the exported structs, by Rust name. The `impl`
+// blocks refer to them by their Rust name.
+#[derive(Default)]
+struct ClassVisitor(BTreeMap<String, String>);
+
+impl<'ast> Visit<'ast> for ClassVisitor {
+    fn visit_item_struct(&mut self, struct_: &'ast ItemStruct) {
+        if let Some(args) = wasm_bindgen(&struct_.attrs) {
+            let name = struct_.ident.to_string();
+            let js_name = args.value("js_name").unwrap_or(&name).to_owned();
+            self.0.insert(name, js_name);
+        }
+    }
+}
+
+struct ExportVisitor {
+    classes: BTreeMap<String, String>,
+    exports: JsExports,
+}
+
+impl ExportVisitor {
+    fn add(&mut self, path: Vec<String>, kind: JsExportKind, code: String) {
+        self.exports.insert((path, kind), code);
+    }
+}
+
+impl<'ast> Visit<'ast> for ExportVisitor {
+    fn visit_item_fn(&mut self, fn_: &'ast ItemFn) {
+        let args = match wasm_bindgen(&fn_.attrs) {
+            Some(args) if is_pub(&fn_.vis) && !args.has("start") => args,
+            _ => return,
+        };
+
+        let name = js_name(&args, &fn_.sig.ident);
+        self.add(vec![name], JsExportKind::Function, display_code(&fn_.sig));
+    }
+
+    fn visit_item_struct(&mut self, struct_: &'ast ItemStruct) {
+        let args = match wasm_bindgen(&struct_.attrs) {
+            Some(args) if is_pub(&struct_.vis) => args,
+            _ => return,
+        };
+
+        let class = js_name(&args, &struct_.ident);
+        self.add(
+            vec![class.clone()],
+            JsExportKind::Class,
+            format!("class {}", class),
+        );
+
+        let fields = match &struct_.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return,
+        };
+
+        for field in fields.iter().filter(|field| is_pub(&field.vis)) {
+            let field_args = wasm_bindgen(&field.attrs).unwrap_or_default();
+            if field_args.has("skip") {
+                continue;
+            }
+
+            let ident = field.ident.as_ref().unwrap();
+            let readonly = if field_args.has("readonly") {
+                "readonly "
+            } else {
+                ""
+            };
+            let code = format!("{}{}: {}", readonly, ident, display_code(&field.ty));
+
+            self.add(
+                vec![class.clone(), js_name(&field_args, ident)],
+                JsExportKind::Property,
+                code,
+            );
+        }
+    }
+
+    fn visit_item_enum(&mut self, enum_: &'ast ItemEnum) {
+        let args = match wasm_bindgen(&enum_.attrs) {
+            Some(args) if is_pub(&enum_.vis) => args,
+            _ => return,
+        };
+
+        let variants = enum_
+            .variants
+            .iter()
+            .map(|variant| match &variant.discriminant {
+                Some((_, value)) => format!("{} = {}", variant.ident, display_code(value)),
+                None => variant.ident.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        let name = js_name(&args, &enum_.ident);
+        let code = format!("enum {} {{ {} }}", name, variants.join(", "));
+        self.add(vec![name], JsExportKind::Enum, code);
+    }
+
+    fn visit_item_impl(&mut self, impl_: &'ast ItemImpl) {
+        let args = match wasm_bindgen(&impl_.attrs) {
+            Some(args) if impl_.trait_.is_none() => args,
+            _ => return,
+        };
+
+        let class = match (args.value("js_class"), &*impl_.self_ty) {
+            (Some(class), _) => class.to_owned(),
+            (None, Type::Path(path)) => match path.path.segments.last() {
+                Some(segment) => {
+                    let name = segment.ident.to_string();
+                    self.classes.get(&name).cloned().unwrap_or(name)
+                }
+                None => return,
+            },
+            _ => return,
+        };
+
+        for item in &impl_.items {
+            let method = match item {
+                ImplItem::Method(method) if is_pub(&method.vis) => method,
+                _ => continue,
+            };
+
+            let method_args = wasm_bindgen(&method.attrs).unwrap_or_default();
+            if method_args.has("skip") {
+                continue;
+            }
+
+            let has_receiver = matches!(method.sig.inputs.first(), Some(FnArg::Receiver(_)));
+            let ident = &method.sig.ident;
+
+            let (name, kind) = if method_args.has("constructor") {
+                ("constructor".to_owned(), JsExportKind::Constructor)
+            } else if method_args.has("getter") {
+                (
+                    accessor_name(&method_args, "getter", ident),
+                    JsExportKind::Getter,
+                )
+            } else if method_args.has("setter") {
+                (
+                    accessor_name(&method_args, "setter", ident),
+                    JsExportKind::Setter,
+                )
+            } else if has_receiver {
+                (js_name(&method_args, ident), JsExportKind::Method)
+            } else {
+                (js_name(&method_args, ident), JsExportKind::StaticMethod)
+            };
+
+            self.add(vec![class.clone(), name], kind, display_code(&method.sig));
+        }
+    }
+
+    // The functions of `extern` blocks are imported from JavaScript, not
+    // exported to it.
+    fn visit_item_foreign_mod(&mut self, _: &'ast ItemForeignMod) {}
+}
+
+fn is_pub(vis: &Visibility) -> bool {
+    matches!(vis, Visibility::Public(_))
+}
+
+fn js_name(args: &MacroArgs, ident: &Ident) -> String {
+    args.value("js_name")
+        .map_or_else(|| ident.to_string(), ToOwned::to_owned)
+}
+
+// The name of the property of a getter or a setter. It is given by the
+// argument, or by `js_name`, or it is the name of the method, without the
+// `set_` prefix of the setters.
+fn accessor_name(args: &MacroArgs, accessor: &str, ident: &Ident) -> String {
+    if let Some(name) = args.value(accessor).or_else(|| args.value("js_name")) {
+        return name.to_owned();
+    }
+
+    let name = ident.to_string();
+    match name.strip_prefix("set_") {
+        Some(property) if accessor == "setter" => property.to_owned(),
+        _ => name,
+    }
+}
+
+/// Finds the items exported to JavaScript which were removed, modified or
+/// added. They are reported at their JavaScript path, such as
+/// `Counter::increment`.
+pub(crate) fn changes(previous: &JsExports, current: &JsExports) -> Vec<DiagnosisItem> {
+    let diagnosis = |rule, (path, kind): &(Vec<String>, JsExportKind)| {
+        // JavaScript names may contain `$`, which Rust paths can't.
+        let segments = path
+            .iter()
+            .map(|segment| syn::parse_str::<Ident>(segment).ok())
+            .collect::<Option<Vec<_>>>();
+
+        match segments {
+            Some(segments) => DiagnosisItem::new(rule, ItemPath::from_segments(segments), None)
+                .with_message(format!("JS {}", kind)),
+            None => DiagnosisItem::new(rule, ItemPath::from_segments(Vec::new()), None)
+                .with_message(format!("JS {} `{}`", kind, path.join("."))),
+        }
+    };
+
+    let removals_and_modifications =
+        previous
+            .iter()
+            .filter_map(|(key, previous)| match current.get(key) {
+                None => Some(
+                    diagnosis(RuleId::JsExportRemoved, key)
+                        .with_signatures(Some(previous.clone()), None),
+                ),
+                Some(current) if current != previous => Some(
+                    diagnosis(RuleId::JsExportModified, key)
+                        .with_signatures(Some(previous.clone()), Some(current.clone())),
+                ),
+                Some(_) => None,
+            });
+
+    let additions = current
+        .iter()
+        .filter(|(key, _)| !previous.contains_key(*key))
+        .map(|(key, current)| {
+            diagnosis(RuleId::JsExportAdded, key).with_signatures(None, Some(current.clone()))
+        });
+
+    removals_and_modifications.chain(additions).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn exports(code: &str) -> Vec<(String, JsExportKind, String)> {
+        extract(&CrateAst::from_str(code).unwrap())
+            .into_iter()
+            .map(|((path, kind), code)| (path.join("."), kind, code))
+            .collect()
+    }
+
+    #[test]
+    fn exported_items() {
+        let exports = exports(
+            r#"
+            use wasm_bindgen::prelude::*;
+
+            #[wasm_bindgen]
+            extern "C" {
+                fn alert(s: &str);
+            }
+
+            #[wasm_bindgen(js_name = parseInput)]
+            pub fn parse_input(input: &str) -> u32 { 0 }
+
+            #[wasm_bindgen(start)]
+            pub fn main() {}
+
+            pub fn not_exported() {}
+
+            #[wasm_bindgen(js_name = "Counter")]
+            pub struct RawCounter {
+                pub step: u32,
+                #[wasm_bindgen(readonly)]
+                pub total: u32,
+                #[wasm_bindgen(skip)]
+                pub cache: Vec<u32>,
+            }
+
+            #[wasm_bindgen]
+            impl RawCounter {
+                #[wasm_bindgen(constructor)]
+                pub fn new() -> RawCounter {}
+                pub fn increment(&mut self) {}
+                pub fn zero() -> RawCounter {}
+                #[wasm_bindgen(setter)]
+                pub fn set_label(&mut self, label: String) {}
+                fn private(&self) {}
+            }
+
+            #[wasm_bindgen]
+            pub enum Mode { Fast = 1, Slow = 2 }
+            "#,
+        );
+
+        let expected = [
+            ("Counter", JsExportKind::Class, "class Counter"),
+            (
+                "Counter.constructor",
+                JsExportKind::Constructor,
+                "fn new() -> RawCounter",
+            ),
+            (
+                "Counter.increment",
+                JsExportKind::Method,
+                "fn increment(&mut self)",
+            ),
+            (
+                "Counter.label",
+                JsExportKind::Setter,
+                "fn set_label(&mut self, label: String)",
+            ),
+            ("Counter.step", JsExportKind::Property, "step: u32"),
+            (
+                "Counter.total",
+                JsExportKind::Property,
+                "readonly total: u32",
+            ),
+            (
+                "Counter.zero",
+                JsExportKind::StaticMethod,
+                "fn zero() -> RawCounter",
+            ),
+            (
+                "Mode",
+                JsExportKind::Enum,
+                "enum Mode { Fast = 1, Slow = 2 }",
+            ),
+            (
+                "parseInput",
+                JsExportKind::Function,
+                "fn parse_input(input: &str) -> u32",
+            ),
+        ]
+        .iter()
+        .map(|(path, kind, code)| (path.to_string(), *kind, code.to_string()))
+        .collect::<Vec<_>>();
+
+        assert_eq!(exports, expected);
+    }
+
+    #[test]
+    fn export_changes() {
+        let previous = extract(
+            &CrateAst::from_str(
+                "#[wasm_bindgen] pub fn run(n: u32) {}\n\
+                 #[wasm_bindgen] pub fn stop() {}\n\
+                 #[wasm_bindgen] pub struct Counter;",
+            )
+            .unwrap(),
+        );
+        let current = extract(
+            &CrateAst::from_str(
+                "#[wasm_bindgen] pub fn run(n: u64) {}\n\
+                 #[wasm_bindgen] pub struct Counter;\n\
+                 #[wasm_bindgen] impl Counter { pub fn reset(&mut self) {} }",
+            )
+            .unwrap(),
+        );
+
+        let changes = changes(&previous, &current)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            [
+                "≠ run (JS function)",
+                "- stop (JS function)",
+                "+ Counter::reset (JS method)"
+            ]
+        );
+    }
+}
@@ -1,44 +1,497 @@
+mod api_diff;
+mod api_lock;
+mod archive;
 mod ast;
+mod backport;
+mod c_header;
+mod ci;
 mod cli;
 mod comparator;
+mod config;
+mod corpus;
 mod diagnosis;
+mod display_examples;
+mod doctor;
+mod error;
+mod facade;
 mod git;
+mod glob;
 mod glue;
+mod hook;
+mod js_exports;
 mod manifest;
+mod metrics;
+mod package;
+mod probe;
 mod public_api;
+mod py_exports;
+mod release;
+mod report;
+mod revision;
+mod rules;
+mod serde_compat;
+mod symbols;
+pub mod tests;
+mod toolchain;
+mod verify;
+mod version_check;
+mod version_patch;
 
-use anyhow::{Context, Result as AnyResult};
-pub use comparator::ApiCompatibilityDiagnostics;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    time::Instant,
+};
+
+use anyhow::{anyhow, bail, Context, Result as AnyResult};
+pub use comparator::{ApiCompatibilityDiagnostics, SemverBump};
+pub use diagnosis::{DiagnosisItemKind, Diagnostic};
+pub use error::Error;
 pub use glue::compare;
+pub use rules::{Audience, Severity};
 
 use crate::{
+    api_lock::LockStatus,
+    backport::BackportGuard,
+    ci::BaseRef,
+    cli::ProgramConfig,
     comparator::ApiComparator,
-    git::{CrateRepo, GitBackend},
+    config::Config,
+    git::{CrateRepo, GitBackend, TempCheckout},
+    glue::{Backend, CheckoutStrategy, CrateCodeProvider, ExtractionConfig, Target},
+    public_api::PublicApi,
+    release::Release,
+    report::{BaselineSummary, OutputFormat, Report},
+    rules::RuleId,
+    toolchain::NightlyToolchain,
 };
 
-pub fn run() -> AnyResult<()> {
-    let config = cli::ProgramConfig::parse();
+/// Runs cargo-breaking with the command line arguments. When the output format
+/// is JSON, a failure is also printed as a JSON error object.
+pub fn run() -> Result<(), Error> {
+    let config = ProgramConfig::parse();
+    let format = config.format;
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| run_with(config)))
+        .map_err(|payload| Error::from_panic(&*payload))
+        .and_then(|result| result.map_err(Error::from));
+
+    if let Err(e) = &result {
+        if format == OutputFormat::Json {
+            println!("{:#}", e.to_json());
+        }
+    }
+
+    result
+}
+
+fn run_with(config: ProgramConfig) -> AnyResult<()> {
+    if let Some(rule_id) = config.explain.as_deref() {
+        let rule = RuleId::from_id(rule_id).ok_or_else(|| anyhow!("Unknown rule `{}`", rule_id))?;
+        println!("{}", rule.rule());
+        return Ok(());
+    }
+
+    if let Some(self_test) = &config.self_test {
+        if config.backend == Backend::Expanded {
+            NightlyToolchain::detect(config.install_nightly)?;
+        }
 
-    let mut repo = CrateRepo::current().context("Failed to fetch repository data")?;
+        let extraction = ExtractionConfig {
+            backend: config.backend,
+            display_build_output: config.display_build_output,
+            ..ExtractionConfig::default()
+        };
 
-    let version = manifest::get_crate_version().context("Failed to get crate version")?;
+        return corpus::run(self_test.corpus.as_deref(), &extraction);
+    }
+
+    let crate_config = Config::load(Path::new("."))?;
+
+    let comparaison_refs = if !config.comparaison_refs.is_empty() {
+        config.comparaison_refs.clone()
+    } else if let Some(against) = &crate_config.against {
+        vec![against.clone()]
+    } else if let Some(base) = BaseRef::detect() {
+        if config.verbose {
+            eprintln!(
+                "Comparing against {}, the base of the {} pull request",
+                base.revision(),
+                base.environment
+            );
+        }
+
+        vec![base.revision()]
+    } else {
+        vec![cli::DEFAULT_AGAINST.to_owned()]
+    };
+
+    if config.doctor {
+        return doctor::run(&comparaison_refs);
+    }
+
+    if let Some(command) = config.hook {
+        let repo = CrateRepo::current()
+            .context("Failed to fetch repository data")
+            .map_err(Error::Git)?;
+        println!("{}", hook::run(&repo, command, &config.comparaison_refs)?);
 
-    let current_api = glue::extract_api().context("Failed to get crate API")?;
+        return Ok(());
+    }
 
-    let previous_api = repo.run_in(config.comparaison_ref.as_str(), || {
-        glue::extract_api().context("Failed to get crate API")
-    })??;
+    if config.restore {
+        let mut repo = CrateRepo::current()
+            .context("Failed to fetch repository data")
+            .map_err(Error::Git)?;
 
-    let api_comparator = ApiComparator::new(previous_api, current_api);
+        match repo.restore_leftover_stash().map_err(Error::Git)? {
+            Some(branch) => println!("Restored the local changes stashed on {}", branch),
+            None => println!("No local changes were left stashed by cargo-breaking"),
+        }
+
+        return Ok(());
+    }
 
-    let diagnosis = api_comparator.run();
+    let start = Instant::now();
+
+    let uses_compiler = config.backend == Backend::Expanded || config.verify_backends;
+
+    if uses_compiler {
+        let toolchain = NightlyToolchain::detect(config.install_nightly)?;
+        if config.verbose {
+            eprintln!("Using {}", toolchain);
+        }
+    }
+
+    let mut repo = CrateRepo::current()
+        .context("Failed to fetch repository data")
+        .map_err(Error::Git)?;
+
+    // Kept until the end, as the archive is unpacked in a temporary
+    // directory.
+    let current_archive = config
+        .current_archive
+        .as_deref()
+        .map(archive::unpack)
+        .transpose()?;
+    let current_dir = current_archive
+        .as_ref()
+        .map_or(Path::new("."), TempCheckout::crate_dir);
+
+    let version =
+        manifest::get_crate_version_in(current_dir).context("Failed to get crate version")?;
+
+    let target = match &config.bin {
+        Some(name) => Target::Bin(name.clone()),
+        None => {
+            manifest::check_lib_target()?;
+            Target::Lib
+        }
+    };
+    let backport = if config.backport {
+        Some(BackportGuard::new(&repo, &crate_config.backport, &version)?)
+    } else {
+        None
+    };
+
+    let target_dir = if uses_compiler {
+        Some(glue::target_dir(Path::new(".")).context("Failed to find the target directory")?)
+    } else {
+        None
+    };
+
+    let extraction = ExtractionConfig {
+        backend: config.backend,
+        include_private: config.include_private,
+        display_build_output: config.display_build_output,
+        path_filters: config.path_filters.clone(),
+        ignored_paths: crate_config.ignore.clone(),
+        features: crate_config.features.clone(),
+        target_dir,
+        lockfile: config.lockfile,
+        target,
+        examples: config.examples.clone(),
+        probe_auto_traits: config.probe_auto_traits,
+        no_std_target: config.no_std_target.clone(),
+        c_header: config.c_header,
+        symbols: config.symbols,
+        wasm_bindgen: config.wasm_bindgen,
+        pyo3: config.pyo3,
+        serde: config.serde,
+        display_examples: config.display_examples,
+        foreign_depth: config
+            .foreign_depth
+            .or(crate_config.foreign_depth)
+            .unwrap_or_default(),
+    };
+
+    if config.package_verify {
+        let diagnosis = package::verify(current_dir, &extraction)?;
+
+        if diagnosis.is_empty() {
+            println!("The packaged crate has the same API as the working tree");
+            return Ok(());
+        }
 
-    if !diagnosis.is_empty() {
         println!("{}", diagnosis);
+        bail!("The API of the packaged crate differs from the working tree");
+    }
+
+    if config.lock_api || config.locked_api {
+        let api = glue::extract_api(&extraction, current_dir, "next")
+            .context("Failed to get crate API")?;
+
+        if config.lock_api {
+            api_lock::write(current_dir, &api)?;
+            println!("Wrote the public API to {}", api_lock::FILE_NAME);
+        } else {
+            match api_lock::check(current_dir, &api, config.update_lock)? {
+                LockStatus::Matches => println!("The public API matches {}", api_lock::FILE_NAME),
+                LockStatus::Updated { diff } => {
+                    print!("{}", diff);
+                    println!(
+                        "Updated {}, commit it along with the API change",
+                        api_lock::FILE_NAME
+                    );
+                }
+                LockStatus::Outdated { diff, lock } => {
+                    print!("{}", lock);
+                    bail!(api_lock::outdated_message(&diff));
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    let comparaison_refs = match &backport {
+        Some(guard) => vec![guard.tag().to_owned()],
+        None => comparaison_refs,
+    };
+
+    let providers = if let Some(spec) = config.against_repo.as_deref() {
+        vec![CrateCodeProvider::remote(spec)?]
+    } else if let Some(path) = &config.against_archive {
+        vec![CrateCodeProvider::Archive { path: path.clone() }]
+    } else {
+        comparaison_refs
+            .iter()
+            .map(|revision| CrateCodeProvider::Local {
+                revision: revision.clone(),
+                strategy: config.checkout,
+            })
+            .collect()
+    };
+
+    let checks_out_in_place = providers.iter().any(|provider| {
+        matches!(
+            provider,
+            CrateCodeProvider::Local {
+                strategy: CheckoutStrategy::InPlace,
+                ..
+            }
+        )
+    });
+
+    if config.no_stash && checks_out_in_place && repo.needs_stash() {
+        bail!(
+            "The working tree has local changes. Commit them, pass --autostash to stash them during the comparison, or use --checkout temp-dir"
+        );
+    }
+
+    if let Some(release) = config.release {
+        if providers.len() > 1 {
+            bail!("A release can only be prepared against a single reference");
+        }
+
+        if current_archive.is_some() {
+            bail!("A release can't be prepared from a crate archive");
+        }
+
+        if release.execute && repo.needs_stash() {
+            bail!("The working tree must be clean to make a release");
+        }
+    }
+
+    if config.verify_backends {
+        let provider = match providers.as_slice() {
+            [provider] => provider,
+            _ => bail!("The backends can only be verified against a single reference"),
+        };
+
+        let discrepancies = verify::run(&mut repo, provider, &extraction)?;
+        println!("{}", discrepancies.render(config.format, config.verbose)?);
+
+        if !discrepancies.is_empty() {
+            bail!(
+                "The backends disagree on {} diagnostics",
+                discrepancies.len()
+            );
+        }
+
+        return Ok(());
     }
 
-    let next_version = diagnosis.guess_next_version(version);
-    println!("Next version is: {}", next_version);
+    let wants_metrics = config.stats || config.format == OutputFormat::Metrics;
+
+    if let [provider] = providers.as_slice() {
+        let (mut diagnosis, comparator) =
+            if !wants_metrics && current_archive.is_none() && is_identical(&repo, provider)? {
+                eprintln!("The versions are identical, skipping the comparison");
+                (ApiCompatibilityDiagnostics::default(), None)
+            } else {
+                let current_api = glue::extract_api(&extraction, current_dir, "next")
+                    .context("Failed to get crate API")?;
+                let previous_api = glue::extract_previous_api(&mut repo, provider, &extraction)
+                    .context("Failed to get crate API")
+                    .map_err(|e| error::tag(e, Error::BaselineBuild))?;
+
+                let api_comparator = comparator(previous_api, current_api, &config, &crate_config);
+
+                (api_comparator.run(), Some(api_comparator))
+            };
+
+        let stale = diagnosis.mark_expected(&crate_config.expected_breaking);
+        let next_version = diagnosis.guess_next_version(version.clone());
+
+        // The tags of the repository say nothing about an archived crate.
+        if current_archive.is_none() {
+            let name = manifest::get_crate_name()
+                .context("Failed to read the crate name to check the published versions")?;
+            if let Some(warning) =
+                version_check::check(&repo, &name, &version, &next_version, config.check_registry)?
+            {
+                eprintln!("{}", warning);
+            }
+        }
+
+        match (config.format, &comparator) {
+            (OutputFormat::CargoPatch, _) => print!(
+                "{}",
+                version_patch::render(Path::new("."), &version, &next_version)?
+            ),
+            // Identical versions have the same API.
+            (OutputFormat::ApiDiff, None) => {}
+            (OutputFormat::ApiDiff, Some(comparator)) => print!("{}", comparator.api_diff()),
+            (format, comparator) => {
+                let metrics = comparator
+                    .as_ref()
+                    .filter(|_| wants_metrics)
+                    .map(|comparator| comparator.metrics());
+                let mut report =
+                    Report::new(&diagnosis, &next_version, metrics).with_duration(start.elapsed());
+
+                if let Some(comparator) = comparator {
+                    if matches!(format, OutputFormat::Json | OutputFormat::Html) {
+                        report = report.with_impact(comparator.impact(&diagnosis));
+                    }
+
+                    if format == OutputFormat::Html {
+                        report = report.with_signatures(comparator.signature_changes(&diagnosis));
+                    }
+                }
+
+                println!("{}", report.render(format, config.verbose)?);
+            }
+        }
+
+        if !stale.is_empty() {
+            let stale = stale.iter().map(ToString::to_string).collect::<Vec<_>>();
+            bail!(
+                "These expected breaking changes did not happen, remove them from `expected-breaking`: {}",
+                stale.join(", ")
+            );
+        }
+
+        if let Some(guard) = &backport {
+            guard.check(diagnosis.required_bump())?;
+        }
+
+        if let Some(release_config) = config.release {
+            let release = Release::new(Path::new("."), &diagnosis, version, next_version)?;
+            println!("{}", release.describe(release_config));
+
+            if release_config.execute {
+                release.execute(&repo, release_config)?;
+                println!("Released {}", release.tag());
+            } else {
+                println!("Nothing was changed, run again with --execute to release");
+            }
+        }
+
+        return Ok(());
+    }
+
+    let current_api =
+        glue::extract_api(&extraction, current_dir, "next").context("Failed to get crate API")?;
+
+    // An entry only needs to match a breaking change against one baseline, as
+    // the others may already have it.
+    let mut stale = crate_config.expected_breaking.iter().collect::<Vec<_>>();
+
+    let rows = comparaison_refs
+        .iter()
+        .zip(&providers)
+        .map(|(against, provider)| {
+            let mut diagnosis = if current_archive.is_none() && is_identical(&repo, provider)? {
+                ApiCompatibilityDiagnostics::default()
+            } else {
+                let previous_api = glue::extract_previous_api(&mut repo, provider, &extraction)
+                    .with_context(|| format!("Failed to get crate API for `{}`", against))
+                    .map_err(|e| error::tag(e, Error::BaselineBuild))?;
+
+                comparator(previous_api, current_api.clone(), &config, &crate_config).run()
+            };
+
+            let stale_here = diagnosis.mark_expected(&crate_config.expected_breaking);
+            stale.retain(|pattern| stale_here.contains(pattern));
+
+            Ok(BaselineSummary {
+                against: against.clone(),
+                change: diagnosis.required_bump(),
+                next_version: diagnosis.guess_next_version(version.clone()),
+            })
+        })
+        .collect::<AnyResult<Vec<_>>>()?;
+
+    println!("{}", report::render_matrix(&rows, config.format)?);
+
+    if !stale.is_empty() {
+        let stale = stale.iter().map(ToString::to_string).collect::<Vec<_>>();
+        bail!(
+            "These expected breaking changes did not happen against any reference, remove them from `expected-breaking`: {}",
+            stale.join(", ")
+        );
+    }
 
     Ok(())
 }
+
+/// Sets up the comparison of two versions of the crate with the options of
+/// the command line and of the configuration file, whatever the baseline.
+fn comparator(
+    previous: PublicApi,
+    current: PublicApi,
+    config: &ProgramConfig,
+    crate_config: &Config,
+) -> ApiComparator {
+    ApiComparator::new(previous, current)
+        .with_pedantic(config.pedantic)
+        .with_macro_internals(config.macro_internals)
+        .with_extensions(crate_config.extensions.unwrap_or_default())
+        .with_const_values(crate_config.const_values.unwrap_or_default())
+        .with_downstream_lints(crate_config.downstream_lints.unwrap_or_default())
+        .with_widening(crate_config.numeric_widening.unwrap_or_default())
+        .with_stability(crate_config.stability.clone())
+}
+
+/// Returns whether `provider` gives the same crate as the working tree, in
+/// which case there is no need to extract and compare both versions.
+fn is_identical(repo: &CrateRepo, provider: &CrateCodeProvider) -> AnyResult<bool> {
+    match provider {
+        CrateCodeProvider::Local { revision, .. } => repo
+            .is_identical_to(revision)
+            .map_err(|e| Error::Git(e).into()),
+        CrateCodeProvider::Remote { .. } | CrateCodeProvider::Archive { .. } => Ok(false),
+    }
+}
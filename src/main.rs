@@ -1,5 +1,8 @@
-use anyhow::Result as AnyResult;
+use std::process;
 
-fn main() -> AnyResult<()> {
-    cargo_breaking::run()
+fn main() {
+    if let Err(e) = cargo_breaking::run() {
+        eprintln!("Error: {:?}", e);
+        process::exit(e.exit_code());
+    }
 }
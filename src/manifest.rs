@@ -1,7 +1,7 @@
-use std::path::Path;
+use std::{collections::BTreeSet, path::Path};
 
 use anyhow::{bail, Context, Result as AnyResult};
-use cargo_toml::Manifest;
+use cargo_toml::{Edition, Manifest};
 use semver::Version;
 
 pub(crate) fn get_crate_version() -> AnyResult<Version> {
@@ -9,9 +9,160 @@ pub(crate) fn get_crate_version() -> AnyResult<Version> {
     get_version_from_manifest(&m).context("Failed to get version from crate manifest")
 }
 
+/// Returns the version of the crate located in `dir`.
+pub(crate) fn get_crate_version_in(dir: &Path) -> AnyResult<Version> {
+    let m = load_manifest_in(dir)?;
+    get_version_from_manifest(&m).context("Failed to get version from crate manifest")
+}
+
+/// Returns the package name of the crate, as published.
+pub(crate) fn get_crate_name() -> AnyResult<String> {
+    match load_manifest()?.package {
+        Some(package) => Ok(package.name),
+        None => bail!("Expected a package, found a workspace"),
+    }
+}
+
+pub(crate) fn has_lib_target() -> AnyResult<bool> {
+    load_manifest().map(|m| m.lib.is_some())
+}
+
+/// Fails if the crate has no library target, suggesting to compare one of
+/// its binaries instead.
+pub(crate) fn check_lib_target() -> AnyResult<()> {
+    let m = load_manifest()?;
+
+    if m.lib.is_some() {
+        return Ok(());
+    }
+
+    match bin_names(&m).as_slice() {
+        [] => bail!("The crate has no library target"),
+        bins => bail!(
+            "The crate has no library target. Pass --bin <name> to compare the public items of one of its binaries: {}",
+            bins.join(", ")
+        ),
+    }
+}
+
+fn bin_names(m: &Manifest) -> Vec<String> {
+    m.bin.iter().filter_map(|bin| bin.name.clone()).collect()
+}
+
+/// Returns the root file of the `name` binary, relative to the crate
+/// directory.
+pub(crate) fn bin_root(m: &Manifest, name: &str) -> AnyResult<String> {
+    let bin = match m.bin.iter().find(|bin| bin.name.as_deref() == Some(name)) {
+        Some(bin) => bin,
+        None => bail!(
+            "The crate has no binary named `{}`. Its binaries are: {}",
+            name,
+            bin_names(m).join(", ")
+        ),
+    };
+
+    if let Some(path) = &bin.path {
+        return Ok(path.clone());
+    }
+
+    let is_main = m.package.as_ref().is_some_and(|p| p.name == name);
+
+    Ok(if is_main {
+        "src/main.rs".to_owned()
+    } else {
+        format!("src/bin/{}.rs", name)
+    })
+}
+
+/// Fails if the library of the crate located in `dir` is not meant to be
+/// linked from C, as a `cdylib` or a `staticlib`.
+pub(crate) fn check_c_library(dir: &Path) -> AnyResult<()> {
+    let m = load_manifest_in(dir)?;
+    let crate_types = m.lib.and_then(|lib| lib.crate_type).unwrap_or_default();
+
+    if !crate_types
+        .iter()
+        .any(|crate_type| crate_type == "cdylib" || crate_type == "staticlib")
+    {
+        bail!("The crate is not a `cdylib` or a `staticlib`");
+    }
+
+    Ok(())
+}
+
 fn load_manifest() -> AnyResult<Manifest> {
-    let p = Path::new("Cargo.toml");
-    Manifest::from_path(p).context("Failed to load crate manifest")
+    load_manifest_in(Path::new("."))
+}
+
+pub(crate) fn load_manifest_in(dir: &Path) -> AnyResult<Manifest> {
+    let p = dir.join("Cargo.toml");
+    let manifest = Manifest::from_path(p);
+
+    // Packaged crates have a manifest normalized by cargo, which may use
+    // settings the parser doesn't know about. The original one is kept next
+    // to it.
+    let original = dir.join("Cargo.toml.orig");
+    if manifest.is_err() && original.is_file() {
+        return Manifest::from_path(original).context("Failed to load crate manifest");
+    }
+
+    manifest.context("Failed to load crate manifest")
+}
+
+/// Returns the features of the crate which are enabled when `requested` are,
+/// along with the default ones and the ones they imply.
+pub(crate) fn enabled_features(
+    m: &Manifest,
+    requested: impl IntoIterator<Item = String>,
+) -> BTreeSet<String> {
+    let mut enabled = BTreeSet::new();
+    let mut to_enable = requested.into_iter().collect::<Vec<_>>();
+
+    if m.features.contains_key("default") {
+        to_enable.push("default".to_owned());
+    }
+
+    while let Some(feature) = to_enable.pop() {
+        if !enabled.insert(feature.clone()) {
+            continue;
+        }
+
+        // Features enabling a feature of a dependency (`dep/feature`) are
+        // not relevant for the crate itself.
+        let implied = m.features.get(&feature).into_iter().flatten();
+        to_enable.extend(implied.filter(|f| !f.contains('/')).cloned());
+    }
+
+    enabled
+}
+
+/// Returns the root file of the `name` example, relative to the crate
+/// directory.
+pub(crate) fn example_root(m: &Manifest, name: &str) -> AnyResult<String> {
+    match m.example.iter().find(|ex| ex.name.as_deref() == Some(name)) {
+        Some(example) => Ok(example
+            .path
+            .clone()
+            .unwrap_or_else(|| format!("examples/{}.rs", name))),
+        None => bail!("The crate has no example named `{}`", name),
+    }
+}
+
+/// Returns the edition the library of the crate is compiled with, such as
+/// `2018`.
+pub(crate) fn edition(m: &Manifest) -> &'static str {
+    let edition = m
+        .lib
+        .as_ref()
+        .and_then(|lib| lib.edition)
+        .or_else(|| m.package.as_ref().map(|package| package.edition))
+        .unwrap_or_default();
+
+    match edition {
+        Edition::E2015 => "2015",
+        Edition::E2018 => "2018",
+        Edition::E2021 => "2021",
+    }
 }
 
 fn get_version_from_manifest(m: &Manifest) -> AnyResult<Version> {
@@ -22,3 +173,60 @@ fn get_version_from_manifest(m: &Manifest) -> AnyResult<Version> {
 
     Version::parse(unparsed_version.as_str()).context("Failed to parser version string")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(s: &str) -> Manifest {
+        Manifest::from_slice(s.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn bin_roots() {
+        let m = manifest(
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [[bin]]
+            name = "foo"
+
+            [[bin]]
+            name = "tool"
+
+            [[bin]]
+            name = "custom"
+            path = "tools/custom.rs"
+            "#,
+        );
+
+        assert_eq!(bin_root(&m, "foo").unwrap(), "src/main.rs");
+        assert_eq!(bin_root(&m, "tool").unwrap(), "src/bin/tool.rs");
+        assert_eq!(bin_root(&m, "custom").unwrap(), "tools/custom.rs");
+        assert!(bin_root(&m, "bar").is_err());
+    }
+
+    #[test]
+    fn example_roots() {
+        let m = manifest(
+            r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+
+            [[example]]
+            name = "plugin"
+
+            [[example]]
+            name = "template"
+            path = "templates/main.rs"
+            "#,
+        );
+
+        assert_eq!(example_root(&m, "plugin").unwrap(), "examples/plugin.rs");
+        assert_eq!(example_root(&m, "template").unwrap(), "templates/main.rs");
+        assert!(example_root(&m, "other").is_err());
+    }
+}
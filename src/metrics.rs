@@ -0,0 +1,304 @@
+use std::fmt::{Display, Formatter, Result as FmtResult, Write};
+
+use serde::Serialize;
+
+use crate::public_api::{ItemKind, PublicApi};
+
+/// Size of the public API of both versions, and how much of it changed.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct ApiMetrics {
+    previous: ItemCounts,
+    current: ItemCounts,
+    added: usize,
+    removed: usize,
+    modified: usize,
+    /// Added, removed and modified items, as a percentage of the items of the
+    /// previous version.
+    churn: f64,
+}
+
+impl ApiMetrics {
+    pub(crate) fn new(previous: &PublicApi, current: &PublicApi) -> ApiMetrics {
+        let (previous_items, current_items) = (previous.items(), current.items());
+
+        let added = current_items
+            .keys()
+            .filter(|path| !previous_items.contains_key(path))
+            .count();
+
+        let (removed, modified) =
+            previous_items
+                .iter()
+                .fold(
+                    (0, 0),
+                    |(removed, modified), (path, kind)| match current_items.get(path) {
+                        None => (removed + 1, modified),
+                        Some(other) if other != kind => (removed, modified + 1),
+                        Some(_) => (removed, modified),
+                    },
+                );
+
+        let previous = ItemCounts::new(previous);
+        let current = ItemCounts::new(current);
+
+        let changed = (added + removed + modified) as f64;
+        let churn = match previous.total() {
+            0 if changed == 0.0 => 0.0,
+            0 => 100.0,
+            total => changed * 100.0 / total as f64,
+        };
+
+        ApiMetrics {
+            previous,
+            current,
+            added,
+            removed,
+            modified,
+            churn,
+        }
+    }
+}
+
+impl ApiMetrics {
+    /// Writes the metrics in the Prometheus text exposition format.
+    pub(crate) fn write_prometheus(&self, out: &mut impl Write) -> FmtResult {
+        let items = [("previous", &self.previous), ("current", &self.current)]
+            .iter()
+            .flat_map(|(version, counts)| {
+                counts.per_kind().map(move |(kind, count)| {
+                    (
+                        format!("version=\"{}\",kind=\"{}\"", version, kind),
+                        count as f64,
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+
+        write_metric(
+            out,
+            "cargo_breaking_public_items",
+            MetricType::Gauge,
+            "Number of public items, per version and kind.",
+            &items,
+        )?;
+
+        write_metric(
+            out,
+            "cargo_breaking_changed_items",
+            MetricType::Gauge,
+            "Number of added, removed and modified public items.",
+            &[
+                ("change=\"added\"".to_owned(), self.added as f64),
+                ("change=\"removed\"".to_owned(), self.removed as f64),
+                ("change=\"modified\"".to_owned(), self.modified as f64),
+            ],
+        )?;
+
+        write_metric(
+            out,
+            "cargo_breaking_churn_ratio",
+            MetricType::Gauge,
+            "Changed public items, relative to the previous number of public items.",
+            &[(String::new(), self.churn / 100.0)],
+        )
+    }
+}
+
+impl Display for ApiMetrics {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        writeln!(f, "{:<12} {:>8} {:>8}", "", "previous", "current")?;
+
+        self.previous
+            .per_kind()
+            .zip(self.current.per_kind())
+            .try_for_each(|((name, previous), (_, current))| {
+                writeln!(f, "{:<12} {:>8} {:>8}", name, previous, current)
+            })?;
+
+        writeln!(
+            f,
+            "{:<12} {:>8} {:>8}",
+            "total",
+            self.previous.total(),
+            self.current.total()
+        )?;
+
+        write!(
+            f,
+            "{} added, {} removed, {} modified ({:.1}% churn)",
+            self.added, self.removed, self.modified, self.churn
+        )
+    }
+}
+
+/// Number of public items of each kind.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+struct ItemCounts {
+    functions: usize,
+    types: usize,
+    methods: usize,
+    traits: usize,
+    macros: usize,
+    // Trait implementations are not items by themselves: they are not counted
+    // in the total.
+    trait_impls: usize,
+}
+
+impl ItemCounts {
+    fn new(api: &PublicApi) -> ItemCounts {
+        let mut counts = ItemCounts::default();
+
+        api.items().values().for_each(|kind| match kind {
+            ItemKind::Fn(_) => counts.functions += 1,
+            ItemKind::Type(type_) => {
+                counts.types += 1;
+                counts.trait_impls += type_.traits().len();
+            }
+            ItemKind::Method(_) => counts.methods += 1,
+            ItemKind::TraitDef(_) => counts.traits += 1,
+            ItemKind::Macro(_) => counts.macros += 1,
+        });
+
+        counts
+    }
+
+    fn total(&self) -> usize {
+        self.functions + self.types + self.methods + self.traits + self.macros
+    }
+
+    fn per_kind(&self) -> impl Iterator<Item = (&'static str, usize)> {
+        vec![
+            ("functions", self.functions),
+            ("types", self.types),
+            ("methods", self.methods),
+            ("traits", self.traits),
+            ("macros", self.macros),
+            ("trait_impls", self.trait_impls),
+        ]
+        .into_iter()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum MetricType {
+    Counter,
+    Gauge,
+}
+
+impl Display for MetricType {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+        }
+        .fmt(f)
+    }
+}
+
+/// Writes a metric family in the Prometheus text exposition format. Each
+/// sample is made of its (possibly empty) labels and its value.
+pub(crate) fn write_metric(
+    out: &mut impl Write,
+    name: &str,
+    type_: MetricType,
+    help: &str,
+    samples: &[(String, f64)],
+) -> FmtResult {
+    writeln!(out, "# HELP {} {}", name, help)?;
+    writeln!(out, "# TYPE {} {}", name, type_)?;
+
+    samples.iter().try_for_each(|(labels, value)| {
+        if labels.is_empty() {
+            writeln!(out, "{} {}", name, value)
+        } else {
+            writeln!(out, "{}{{{}}} {}", name, labels, value)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn counts_items_per_kind() {
+        let api: PublicApi = parse_quote! {
+            pub fn f() {}
+            pub struct S;
+            impl S {
+                pub fn m() {}
+            }
+            impl Clone for S {
+                fn clone(&self) -> S {}
+            }
+            pub trait T {}
+            #[macro_export]
+            macro_rules! m {
+                () => {};
+            }
+        };
+
+        let expected = ItemCounts {
+            functions: 1,
+            types: 1,
+            methods: 1,
+            traits: 1,
+            macros: 1,
+            trait_impls: 1,
+        };
+
+        assert_eq!(ItemCounts::new(&api), expected);
+    }
+
+    #[test]
+    fn changes_and_churn() {
+        let previous: PublicApi = parse_quote! {
+            pub fn a() {}
+            pub fn b() {}
+            pub fn c() {}
+            pub fn d() {}
+        };
+        let current: PublicApi = parse_quote! {
+            pub fn a() {}
+            pub fn b(n: u8) {}
+            pub fn e() {}
+            pub fn f() {}
+        };
+
+        let metrics = ApiMetrics::new(&previous, &current);
+
+        assert_eq!(metrics.added, 2);
+        assert_eq!(metrics.removed, 2);
+        assert_eq!(metrics.modified, 1);
+        assert_eq!(metrics.churn, 125.0);
+    }
+
+    #[test]
+    fn prometheus_output() {
+        let previous: PublicApi = parse_quote! { pub fn a() {} };
+        let current: PublicApi = parse_quote! { pub fn a(n: u8) {} pub struct S; };
+
+        let mut output = String::new();
+        ApiMetrics::new(&previous, &current)
+            .write_prometheus(&mut output)
+            .unwrap();
+
+        assert!(output.contains("# TYPE cargo_breaking_public_items gauge\n"));
+        assert!(
+            output.contains("cargo_breaking_public_items{version=\"current\",kind=\"types\"} 1\n")
+        );
+        assert!(output.contains("cargo_breaking_changed_items{change=\"modified\"} 1\n"));
+        assert!(output.contains("cargo_breaking_churn_ratio 2\n"));
+    }
+
+    #[test]
+    fn churn_of_empty_previous_version() {
+        let empty: PublicApi = parse_quote! {};
+        let current: PublicApi = parse_quote! { pub fn f() {} };
+
+        assert_eq!(ApiMetrics::new(&empty, &empty).churn, 0.0);
+        assert_eq!(ApiMetrics::new(&empty, &current).churn, 100.0);
+    }
+}
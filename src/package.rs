@@ -0,0 +1,74 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, bail, Context, Result as AnyResult};
+
+use crate::{
+    archive,
+    comparator::{ApiComparator, ApiCompatibilityDiagnostics},
+    glue::{self, ExtractionConfig},
+    manifest,
+};
+
+/// Packages the crate located in `dir` with `cargo package`, and compares
+/// the API of the archive against the API of `dir`. Every diagnostic is an
+/// item which is lost, or changed, when the crate is published, usually
+/// because of the `include` or `exclude` settings of the manifest.
+pub(crate) fn verify(
+    dir: &Path,
+    extraction: &ExtractionConfig,
+) -> AnyResult<ApiCompatibilityDiagnostics> {
+    let archive = package(dir)?;
+
+    let worktree_api =
+        glue::extract_api(extraction, dir, "previous").context("Failed to get crate API")?;
+
+    let checkout = archive::unpack(&archive)?;
+    let packaged_api = glue::extract_api(extraction, checkout.crate_dir(), "next")
+        .context("Failed to get the API of the packaged crate, files it needs may be excluded from the package")?;
+
+    Ok(ApiComparator::new(worktree_api, packaged_api).run())
+}
+
+/// Runs `cargo package` and returns the path of the archive. The archive is
+/// not built, as its API is extracted afterwards anyway.
+fn package(dir: &Path) -> AnyResult<PathBuf> {
+    let status = Command::new("cargo")
+        .current_dir(dir)
+        .args(["package", "--no-verify", "--allow-dirty"])
+        .status()
+        .context("Failed to run `cargo package`")?;
+
+    if !status.success() {
+        bail!("`cargo package` failed");
+    }
+
+    let m = manifest::load_manifest_in(dir)?;
+    let package = m
+        .package
+        .ok_or_else(|| anyhow!("The manifest has no package section"))?;
+    let target_dir = glue::target_dir(dir).context("Failed to find the target directory")?;
+
+    Ok(archive_path(&target_dir, &package.name, &package.version))
+}
+
+fn archive_path(target_dir: &Path, name: &str, version: &str) -> PathBuf {
+    target_dir
+        .join("package")
+        .join(format!("{}-{}.crate", name, version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_is_in_target_dir() {
+        assert_eq!(
+            archive_path(Path::new("target"), "foo", "1.2.3"),
+            Path::new("target/package/foo-1.2.3.crate")
+        );
+    }
+}
@@ -0,0 +1,487 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+    process::{self, Command, Output, Stdio},
+};
+
+use anyhow::{bail, Context, Result as AnyResult};
+use serde_json::Value;
+use syn::{visit::Visit, GenericParam, ReturnType, Signature, Type, TypeImplTrait};
+
+use crate::{
+    glue::{ExtractionConfig, Target},
+    manifest,
+    public_api::{ItemKind, ItemPath, PublicApi},
+};
+
+/// The auto traits which an opaque type implements whenever the type it
+/// hides does, whatever its bounds say.
+const AUTO_TRAITS: &[&str] = &["Send", "Sync"];
+
+/// The name of the crate the probe is built as.
+const PROBE_NAME: &str = "cargo_breaking_probe";
+
+/// Finds which auto traits are implemented by the opaque type returned by
+/// each `-> impl Trait` and `async fn` function of the crate located in
+/// `dir`, by building a crate which depends on it and requires them.
+///
+/// The functions which are generic over a type, or whose call does not
+/// compile from outside of the crate, are left out.
+pub(crate) fn auto_traits(
+    config: &ExtractionConfig,
+    dir: &Path,
+    api: &PublicApi,
+) -> AnyResult<BTreeMap<ItemPath, Vec<String>>> {
+    if config.target != Target::Lib {
+        bail!("Auto traits can only be probed on libraries");
+    }
+
+    let candidates = opaque_returns(api);
+    if candidates.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    let (package, lib, edition) = crate_names(dir)?;
+    let probe = Probe::new(&lib, edition, &candidates);
+
+    let probe_dir =
+        std::env::temp_dir().join(format!("cargo-breaking-probe-{}-{}", process::id(), lib));
+    let dependency = Dependency {
+        package: &package,
+        lib: &lib,
+        dir,
+        features: &config.features,
+        default_features: true,
+        edition,
+    };
+
+    let output = check_probe(config, &probe_dir, &dependency, &probe.source, &[])
+        .context("Failed to run `cargo check` on the auto trait probe")?;
+
+    let failed = failed_lines(&output.stdout)?;
+
+    Ok(probe.results(&failed))
+}
+
+/// Finds whether the crate located in `dir` can be used without `std`, by
+/// building a `#![no_std]` crate which depends on it, with its default
+/// features disabled, for `target`. Such a target has no `std` to link.
+pub(crate) fn no_std(config: &ExtractionConfig, dir: &Path, target: &str) -> AnyResult<bool> {
+    if config.target != Target::Lib {
+        bail!("`no_std` support can only be probed on libraries");
+    }
+
+    let (package, lib, edition) = crate_names(dir)?;
+
+    let probe_dir = std::env::temp_dir().join(format!(
+        "cargo-breaking-no-std-probe-{}-{}",
+        process::id(),
+        lib
+    ));
+    let dependency = Dependency {
+        package: &package,
+        lib: &lib,
+        dir,
+        features: &[],
+        default_features: false,
+        edition,
+    };
+    // Dependencies are only in scope after `extern crate` in the 2015
+    // edition, and re-exporting them with `pub use` would then conflict.
+    let source = if edition == "2015" {
+        format!("#![no_std]\npub extern crate {};\n", lib)
+    } else {
+        format!("#![no_std]\npub use {};\n", lib)
+    };
+
+    let output = check_probe(
+        config,
+        &probe_dir,
+        &dependency,
+        &source,
+        &["--target", target],
+    )
+    .context("Failed to run `cargo check` on the `no_std` probe")?;
+
+    if output.status.success() {
+        return Ok(true);
+    }
+
+    let errors = compiler_errors(&output.stdout)?;
+
+    if errors.is_empty() {
+        bail!(
+            "`cargo check` failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let missing_core = errors.iter().any(|error| {
+        error["message"]["message"]
+            .as_str()
+            .unwrap_or_default()
+            .contains("can't find crate for `core`")
+    });
+
+    if missing_core {
+        bail!(
+            "The `{}` target is not installed, run `rustup target add {}`",
+            target,
+            target
+        );
+    }
+
+    Ok(false)
+}
+
+// The name of the package located in `dir`, the name its library is imported
+// with, and the edition it is written in.
+fn crate_names(dir: &Path) -> AnyResult<(String, String, &'static str)> {
+    let manifest = manifest::load_manifest_in(dir)?;
+    let package = match &manifest.package {
+        Some(package) => package.name.clone(),
+        None => bail!("The crate manifest has no package"),
+    };
+    let edition = manifest::edition(&manifest);
+    let lib = manifest
+        .lib
+        .and_then(|lib| lib.name)
+        .unwrap_or_else(|| package.replace('-', "_"));
+
+    Ok((package, lib, edition))
+}
+
+/// How a probe depends on the crate it checks.
+struct Dependency<'a> {
+    package: &'a str,
+    lib: &'a str,
+    dir: &'a Path,
+    features: &'a [String],
+    default_features: bool,
+    // The probe is written in the edition of the crate, so that the paths
+    // of its items are written the same way.
+    edition: &'a str,
+}
+
+// Writes a probe crate with `source` as its root in `probe_dir`, then runs
+// `cargo check` on it with `args`. The probe is removed afterwards.
+fn check_probe(
+    config: &ExtractionConfig,
+    probe_dir: &Path,
+    dependency: &Dependency,
+    source: &str,
+    args: &[&str],
+) -> AnyResult<Output> {
+    fs::create_dir_all(probe_dir.join("src"))
+        .with_context(|| format!("Failed to create {}", probe_dir.display()))?;
+
+    let path = fs::canonicalize(dependency.dir)
+        .with_context(|| format!("Failed to resolve {}", dependency.dir.display()))?;
+    fs::write(
+        probe_dir.join("Cargo.toml"),
+        probe_manifest(dependency, &path),
+    )?;
+    fs::write(probe_dir.join("src/lib.rs"), source)?;
+
+    let mut command = Command::new("cargo");
+    command
+        .current_dir(probe_dir)
+        .args(["check", "--message-format=json", "--quiet"])
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    if let Some(target_dir) = &config.target_dir {
+        command.env("CARGO_TARGET_DIR", target_dir);
+    }
+
+    let output = command.output();
+
+    fs::remove_dir_all(probe_dir).ok();
+
+    Ok(output?)
+}
+
+// The functions and methods returning an opaque type, along with their number
+// of arguments.
+fn opaque_returns(api: &PublicApi) -> Vec<(&ItemPath, usize)> {
+    api.items()
+        .iter()
+        .filter(|(path, _)| !api.is_private(path))
+        .filter_map(|(path, kind)| {
+            let sig = match kind {
+                ItemKind::Fn(f) => f.signature(),
+                ItemKind::Method(m) if !m.is_generic() => m.signature(),
+                _ => return None,
+            };
+
+            (returns_opaque_type(sig) && !is_generic(sig)).then(|| (path, sig.inputs.len()))
+        })
+        .collect()
+}
+
+fn returns_opaque_type(sig: &Signature) -> bool {
+    sig.asyncness.is_some()
+        || matches!(&sig.output, ReturnType::Type(_, ty) if matches!(**ty, Type::ImplTrait(_)))
+}
+
+// Type parameters, including the ones of `impl Trait` arguments, can't be
+// inferred from the placeholder arguments of the probe.
+fn is_generic(sig: &Signature) -> bool {
+    let has_params = sig
+        .generics
+        .params
+        .iter()
+        .any(|param| !matches!(param, GenericParam::Lifetime(_)));
+
+    let mut impl_args = ImplTraitArgs(false);
+    sig.inputs
+        .iter()
+        .for_each(|input| impl_args.visit_fn_arg(input));
+
+    has_params || impl_args.0
+}
+
+struct ImplTraitArgs(bool);
+
+impl<'ast> Visit<'ast> for ImplTraitArgs {
+    fn visit_type_impl_trait(&mut self, _: &'ast TypeImplTrait) {
+        self.0 = true;
+    }
+}
+
+fn probe_manifest(dependency: &Dependency, path: &Path) -> String {
+    format!(
+        "[package]\n\
+         name = \"{probe}\"\n\
+         version = \"0.0.0\"\n\
+         edition = \"{edition}\"\n\
+         \n\
+         [dependencies]\n\
+         {lib} = {{ package = \"{package}\", path = {path:?}, features = {features:?}, default-features = {default_features} }}\n\
+         \n\
+         [workspace]\n",
+        probe = PROBE_NAME,
+        edition = dependency.edition,
+        lib = dependency.lib,
+        package = dependency.package,
+        path = path.display().to_string(),
+        features = dependency.features,
+        default_features = dependency.default_features,
+    )
+}
+
+/// A crate calling each function, then requiring the auto traits from what it
+/// returns. Each call and requirement is on its own line, so that the errors
+/// tell which ones don't compile.
+struct Probe<'a> {
+    source: String,
+    // The line of each call, and of each requirement.
+    calls: BTreeMap<usize, &'a ItemPath>,
+    requirements: BTreeMap<usize, (&'a ItemPath, &'static str)>,
+}
+
+impl<'a> Probe<'a> {
+    fn new(lib: &str, edition: &str, candidates: &[(&'a ItemPath, usize)]) -> Probe<'a> {
+        let mut lines = vec!["#![allow(dead_code, unreachable_code, unused_variables)]".to_owned()];
+        if edition == "2015" {
+            lines.push(format!("extern crate {};", lib));
+        }
+        let mut calls = BTreeMap::new();
+        let mut requirements = BTreeMap::new();
+
+        for trait_ in AUTO_TRAITS {
+            lines.push(format!(
+                "fn require_{}<T: ?Sized + {}>(_: &T) {{}}",
+                trait_.to_lowercase(),
+                trait_
+            ));
+        }
+
+        for (idx, (path, arg_count)) in candidates.iter().enumerate() {
+            let args = vec!["todo!()"; *arg_count].join(", ");
+
+            lines.push(format!("fn probe_{}() {{", idx));
+            lines.push(format!("    let value = {}::{}({});", lib, path, args));
+            calls.insert(lines.len(), *path);
+
+            for trait_ in AUTO_TRAITS {
+                lines.push(format!("    require_{}(&value);", trait_.to_lowercase()));
+                requirements.insert(lines.len(), (*path, *trait_));
+            }
+
+            lines.push("}".to_owned());
+        }
+
+        let mut source = lines.join("\n");
+        source.push('\n');
+
+        Probe {
+            source,
+            calls,
+            requirements,
+        }
+    }
+
+    fn results(&self, failed: &BTreeSet<usize>) -> BTreeMap<ItemPath, Vec<String>> {
+        let mut results = self
+            .calls
+            .iter()
+            .filter(|(line, _)| !failed.contains(line))
+            .map(|(_, path)| ((*path).clone(), Vec::new()))
+            .collect::<BTreeMap<_, _>>();
+
+        for (line, (path, trait_)) in &self.requirements {
+            if failed.contains(line) {
+                continue;
+            }
+
+            if let Some(traits) = results.get_mut(*path) {
+                traits.push((*trait_).to_owned());
+            }
+        }
+
+        results
+    }
+}
+
+// The lines of the probe which have an error, read from the JSON messages of
+// cargo. Fails if the crate itself does not build.
+fn failed_lines(messages: &[u8]) -> AnyResult<BTreeSet<usize>> {
+    let mut lines = BTreeSet::new();
+
+    for message in compiler_errors(messages)? {
+        if message["target"]["name"] != PROBE_NAME {
+            bail!(
+                "The crate does not build, the auto traits can't be probed:\n{}",
+                message["message"]["rendered"].as_str().unwrap_or_default()
+            );
+        }
+
+        let spans = message["message"]["spans"].as_array().into_iter().flatten();
+        lines.extend(
+            spans
+                .filter(|span| span["is_primary"] == true)
+                .filter_map(|span| span["line_start"].as_u64())
+                .map(|line| line as usize),
+        );
+    }
+
+    Ok(lines)
+}
+
+// The error messages of the compiler, among the JSON messages of cargo.
+fn compiler_errors(messages: &[u8]) -> AnyResult<Vec<Value>> {
+    let mut errors = Vec::new();
+
+    for message in messages.split(|b| *b == b'\n').filter(|m| !m.is_empty()) {
+        let message = serde_json::from_slice::<Value>(message)
+            .context("Failed to parse `cargo check` output")?;
+
+        if message["reason"] == "compiler-message" && message["message"]["level"] == "error" {
+            errors.push(message);
+        }
+    }
+
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn probe_source() {
+        let api: PublicApi = parse_quote! {
+            pub fn iter() -> impl Iterator<Item = u8> {}
+            pub async fn fetch(url: &str, retries: u8) -> u8 {}
+            pub fn generic<T>(t: T) -> impl Clone {}
+            pub fn plain() -> u8 {}
+        };
+
+        let candidates = opaque_returns(&api);
+        let probe = Probe::new("my_crate", "2018", &candidates);
+
+        assert_eq!(
+            probe.source,
+            "#![allow(dead_code, unreachable_code, unused_variables)]\n\
+             fn require_send<T: ?Sized + Send>(_: &T) {}\n\
+             fn require_sync<T: ?Sized + Sync>(_: &T) {}\n\
+             fn probe_0() {\n\
+             \x20   let value = my_crate::fetch(todo!(), todo!());\n\
+             \x20   require_send(&value);\n\
+             \x20   require_sync(&value);\n\
+             }\n\
+             fn probe_1() {\n\
+             \x20   let value = my_crate::iter();\n\
+             \x20   require_send(&value);\n\
+             \x20   require_sync(&value);\n\
+             }\n"
+        );
+
+        // `fetch` is `Send` only, and `iter` can't be called.
+        let failed = failed_lines(
+            br#"{"reason":"compiler-artifact","target":{"name":"my_crate"}}
+{"reason":"compiler-message","target":{"name":"cargo_breaking_probe"},"message":{"level":"error","spans":[{"is_primary":true,"line_start":7}]}}
+{"reason":"compiler-message","target":{"name":"cargo_breaking_probe"},"message":{"level":"error","spans":[{"is_primary":true,"line_start":10}]}}
+{"reason":"compiler-message","target":{"name":"cargo_breaking_probe"},"message":{"level":"warning","spans":[{"is_primary":true,"line_start":11}]}}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            probe.results(&failed),
+            BTreeMap::from([(parse_quote! { fetch }, vec!["Send".to_owned()])])
+        );
+    }
+
+    #[test]
+    fn no_std_probe_manifest() {
+        let dependency = Dependency {
+            package: "my-crate",
+            lib: "my_crate",
+            dir: Path::new("."),
+            features: &[],
+            default_features: false,
+            edition: "2018",
+        };
+
+        assert_eq!(
+            probe_manifest(&dependency, Path::new("/src/my-crate")),
+            "[package]\n\
+             name = \"cargo_breaking_probe\"\n\
+             version = \"0.0.0\"\n\
+             edition = \"2018\"\n\
+             \n\
+             [dependencies]\n\
+             my_crate = { package = \"my-crate\", path = \"/src/my-crate\", features = [], default-features = false }\n\
+             \n\
+             [workspace]\n"
+        );
+    }
+
+    #[test]
+    fn probe_source_declares_2015_dependencies() {
+        let api: PublicApi = parse_quote! {
+            pub fn iter() -> impl Iterator<Item = u8> {}
+        };
+
+        let candidates = opaque_returns(&api);
+        let probe = Probe::new("my_crate", "2015", &candidates);
+
+        assert!(probe.source.starts_with(
+            "#![allow(dead_code, unreachable_code, unused_variables)]\nextern crate my_crate;\n"
+        ));
+        assert_eq!(probe.calls, BTreeMap::from([(6, &parse_quote! { iter })]));
+    }
+
+    #[test]
+    fn broken_crates_are_reported() {
+        let messages = br#"{"reason":"compiler-message","target":{"name":"my_crate"},"message":{"level":"error","rendered":"error[E0425]","spans":[]}}"#;
+
+        assert!(failed_lines(messages).is_err());
+    }
+}
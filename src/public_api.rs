@@ -1,20 +1,39 @@
+mod annotations;
+mod attributes;
+mod canonical;
+mod closures;
+mod constructors;
+mod consts;
 mod functions;
+mod hrtb;
+mod impl_trait;
 mod imports;
+mod macros;
 mod methods;
+mod private;
+mod sized;
 mod trait_defs;
 mod trait_impls;
+mod trait_objects;
+mod type_aliases;
 mod types;
 mod utils;
+mod variance;
 
 use std::{
-    collections::HashMap,
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    convert::TryFrom,
     fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
 };
 
+use anyhow::{bail, Error as AnyError, Result as AnyResult};
+use serde::Deserialize;
+
 use syn::{
     parse::{Parse, ParseStream, Result as ParseResult},
     visit::Visit,
-    Ident,
+    Ident, Signature,
 };
 
 #[cfg(test)]
@@ -24,50 +43,334 @@ use tap::Tap;
 
 use crate::{
     ast::CrateAst,
+    c_header::CDeclaration,
     diagnosis::{DiagnosisCollector, DiagnosticGenerator},
+    display_examples::DisplayExamples,
+    js_exports::JsExports,
+    py_exports::PyExports,
+    serde_compat::SerdeShape,
 };
 
 use self::{
+    annotations::AnnotationVisitor,
     functions::{FnPrototype, FnVisitor},
     imports::PathResolver,
+    macros::{MacroHelperVisitor, MacroMetadata, MacroVisitor},
     methods::{MethodMetadata, MethodVisitor},
     trait_defs::{TraitDefMetadata, TraitDefVisitor},
     trait_impls::TraitImplVisitor,
     types::{TypeMetadata, TypeVisitor},
 };
 
+pub(crate) use self::utils::{call_form, display_code, module_path, MacroArgs};
+
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct PublicApi {
-    items: HashMap<ItemPath, ItemKind>,
+    items: BTreeMap<ItemPath, ItemKind>,
+    // Items which are not reachable from outside of the crate. Always empty
+    // unless the API was extracted with `from_ast_including_private`.
+    private: BTreeSet<ItemPath>,
+    // The other public paths of the items which are re-exported.
+    aliases: BTreeMap<ItemPath, Vec<ItemPath>>,
+    // The hidden macros which the expansion of the public macros may use,
+    // along with their rules.
+    macro_helpers: BTreeMap<ItemPath, String>,
+    // The auto traits implemented by the opaque type each function returns.
+    // Only known when they were probed.
+    auto_traits: BTreeMap<ItemPath, Vec<String>>,
+    // Whether the crate builds without `std` and its default features. Only
+    // known when it was probed.
+    no_std: Option<bool>,
+    // The declarations of the C header generated for the crate, by name. Only
+    // known when it was generated.
+    c_header: BTreeMap<String, CDeclaration>,
+    // The symbols exported by the library built for C. Only known when it was
+    // built.
+    symbols: BTreeSet<String>,
+    // The items exported to JavaScript by wasm-bindgen. Only known when they
+    // were looked for.
+    js_exports: JsExports,
+    // The items exported to Python by PyO3. Only known when they were looked
+    // for.
+    py_exports: PyExports,
+    // The serialized shape of the types deriving serde traits. Only known
+    // when they were looked for.
+    serde_types: BTreeMap<ItemPath, SerdeShape>,
+    // The `Display` outputs asserted by the documentation of the types. Only
+    // known when they were looked for.
+    display_examples: BTreeMap<ItemPath, DisplayExamples>,
+    // The edition the crate is compiled with, read from its manifest. Only
+    // known when the crate was read from a directory.
+    edition: Option<String>,
+    // The items which could not be analyzed, along with the reason why. They
+    // are left out of `items`.
+    unanalyzable: BTreeMap<ItemPath, String>,
+    // The items annotated as intentionally broken, along with the reason the
+    // maintainers gave. The annotation covers what the item contains.
+    annotations: BTreeMap<ItemPath, String>,
 }
 
 impl PublicApi {
     pub(crate) fn from_ast(program: &CrateAst) -> PublicApi {
         let resolver = PathResolver::new(program);
+        let program = type_aliases::expand(program, &resolver);
+        let program = &hrtb::normalize(&closures::normalize(&program));
 
-        let mut type_visitor = TypeVisitor::new();
+        let mut unanalyzable = BTreeMap::new();
+
+        let mut type_visitor = TypeVisitor::new(&mut unanalyzable);
         type_visitor.visit_file(program.ast());
 
-        let mut method_visitor = MethodVisitor::new(type_visitor.types(), &resolver);
+        let mut method_visitor =
+            MethodVisitor::new(type_visitor.types(), &resolver, &mut unanalyzable);
         method_visitor.visit_file(program.ast());
 
-        let mut fn_visitor = FnVisitor::new(method_visitor.items());
+        let mut fn_visitor = FnVisitor::new(method_visitor.items(), &mut unanalyzable);
         fn_visitor.visit_file(program.ast());
 
-        let mut trait_impl_visitor = TraitImplVisitor::new(fn_visitor.items(), &resolver);
+        let mut trait_impl_visitor =
+            TraitImplVisitor::new(fn_visitor.items(), &resolver, &mut unanalyzable);
         trait_impl_visitor.visit_file(program.ast());
 
-        let mut trait_def_visitor = TraitDefVisitor::new(trait_impl_visitor.items(), &resolver);
+        let mut trait_def_visitor =
+            TraitDefVisitor::new(trait_impl_visitor.items(), &resolver, &mut unanalyzable);
         trait_def_visitor.visit_file(program.ast());
 
-        let items = trait_def_visitor.items();
+        let items = trait_def_visitor
+            .items()
+            .tap_mut(trait_impls::add_provided_consts);
+
+        let mut macro_visitor = MacroVisitor::new(items, &resolver);
+        macro_visitor.visit_file(program.ast());
+
+        let items = macro_visitor
+            .items()
+            .tap_mut(|items| items.retain(|path, _| !unanalyzable.contains_key(path)));
+        let (items, aliases) = canonical::canonicalize(items, &resolver);
+        let private = BTreeSet::new();
+
+        let mut helper_visitor = MacroHelperVisitor::new();
+        helper_visitor.visit_file(program.ast());
+        let macro_helpers = helper_visitor.helpers();
+
+        let mut annotation_visitor = AnnotationVisitor::new(&resolver);
+        annotation_visitor.visit_file(program.ast());
+        let annotations =
+            canonical::canonicalize_annotations(annotation_visitor.annotations(), &resolver);
+
+        PublicApi {
+            items,
+            private,
+            aliases,
+            macro_helpers,
+            auto_traits: BTreeMap::new(),
+            no_std: None,
+            c_header: BTreeMap::new(),
+            symbols: BTreeSet::new(),
+            js_exports: BTreeMap::new(),
+            py_exports: BTreeMap::new(),
+            serde_types: BTreeMap::new(),
+            display_examples: BTreeMap::new(),
+            edition: None,
+            unanalyzable,
+            annotations,
+        }
+    }
+
+    /// Extracts both the public and the private items of a crate.
+    ///
+    /// Items which can't be reached from outside of the crate are marked as
+    /// private.
+    pub(crate) fn from_ast_including_private(program: &CrateAst) -> PublicApi {
+        let public = PublicApi::from_ast(program);
+
+        let mut exposed = program.clone();
+        private::expose_private_items(&mut exposed.0);
+
+        let mut api = PublicApi::from_ast(&exposed);
+        api.private = api
+            .items
+            .keys()
+            .filter(|path| !public.items.contains_key(path))
+            .cloned()
+            .collect();
+
+        api
+    }
 
-        PublicApi { items }
+    pub(crate) fn is_private(&self, path: &ItemPath) -> bool {
+        self.private.contains(path)
     }
 
-    pub(crate) fn items(&self) -> &HashMap<ItemPath, ItemKind> {
+    pub(crate) fn items(&self) -> &BTreeMap<ItemPath, ItemKind> {
         &self.items
     }
+
+    /// Renders each public item as pseudo-Rust, in the order of their path.
+    pub(crate) fn render(&self) -> BTreeMap<&ItemPath, Vec<String>> {
+        self.items
+            .iter()
+            .filter(|(path, _)| !self.is_private(path))
+            .map(|(path, kind)| (path, kind.render(path)))
+            .collect()
+    }
+
+    pub(crate) fn macro_helpers(&self) -> &BTreeMap<ItemPath, String> {
+        &self.macro_helpers
+    }
+
+    pub(crate) fn auto_traits(&self) -> &BTreeMap<ItemPath, Vec<String>> {
+        &self.auto_traits
+    }
+
+    pub(crate) fn set_auto_traits(&mut self, auto_traits: BTreeMap<ItemPath, Vec<String>>) {
+        self.auto_traits = auto_traits;
+    }
+
+    pub(crate) fn no_std(&self) -> Option<bool> {
+        self.no_std
+    }
+
+    pub(crate) fn set_no_std(&mut self, no_std: bool) {
+        self.no_std = Some(no_std);
+    }
+
+    pub(crate) fn c_header(&self) -> &BTreeMap<String, CDeclaration> {
+        &self.c_header
+    }
+
+    pub(crate) fn set_c_header(&mut self, c_header: BTreeMap<String, CDeclaration>) {
+        self.c_header = c_header;
+    }
+
+    pub(crate) fn symbols(&self) -> &BTreeSet<String> {
+        &self.symbols
+    }
+
+    pub(crate) fn set_symbols(&mut self, symbols: BTreeSet<String>) {
+        self.symbols = symbols;
+    }
+
+    pub(crate) fn js_exports(&self) -> &JsExports {
+        &self.js_exports
+    }
+
+    pub(crate) fn set_js_exports(&mut self, js_exports: JsExports) {
+        self.js_exports = js_exports;
+    }
+
+    pub(crate) fn py_exports(&self) -> &PyExports {
+        &self.py_exports
+    }
+
+    pub(crate) fn set_py_exports(&mut self, py_exports: PyExports) {
+        self.py_exports = py_exports;
+    }
+
+    pub(crate) fn serde_types(&self) -> &BTreeMap<ItemPath, SerdeShape> {
+        &self.serde_types
+    }
+
+    pub(crate) fn set_serde_types(&mut self, serde_types: BTreeMap<ItemPath, SerdeShape>) {
+        self.serde_types = serde_types;
+    }
+
+    pub(crate) fn display_examples(&self) -> &BTreeMap<ItemPath, DisplayExamples> {
+        &self.display_examples
+    }
+
+    pub(crate) fn set_display_examples(
+        &mut self,
+        display_examples: BTreeMap<ItemPath, DisplayExamples>,
+    ) {
+        self.display_examples = display_examples;
+    }
+
+    pub(crate) fn edition(&self) -> Option<&str> {
+        self.edition.as_deref()
+    }
+
+    pub(crate) fn set_edition(&mut self, edition: &str) {
+        self.edition = Some(edition.to_owned());
+    }
+
+    pub(crate) fn unanalyzable(&self) -> &BTreeMap<ItemPath, String> {
+        &self.unanalyzable
+    }
+
+    /// Returns the reason given by the annotation covering `path`, if the
+    /// item or one of its parents is annotated as intentionally broken.
+    pub(crate) fn breaking_reason(&self, path: &ItemPath) -> Option<&str> {
+        (0..=path.path.len())
+            .rev()
+            .map(|len| ItemPath::from_segments(path.path[..len].to_vec()))
+            .find_map(|prefix| self.annotations.get(&prefix))
+            .map(String::as_str)
+    }
+
+    /// The other public paths of an item, which is stored at its canonical
+    /// path.
+    pub(crate) fn aliases(&self, path: &ItemPath) -> &[ItemPath] {
+        self.aliases.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    /// Removes the items which are not in any of the subtrees described by
+    /// `prefixes`. Nothing is removed if `prefixes` is empty.
+    pub(crate) fn retain_prefixes(&mut self, prefixes: &[PathPrefix]) {
+        if prefixes.is_empty() {
+            return;
+        }
+
+        let keep = |path: &ItemPath| prefixes.iter().any(|prefix| prefix.matches(path));
+
+        self.items.retain(|path, _| keep(path));
+        self.private.retain(|path| keep(path));
+        self.aliases.retain(|path, _| keep(path));
+        self.macro_helpers.retain(|path, _| keep(path));
+        self.auto_traits.retain(|path, _| keep(path));
+        self.unanalyzable.retain(|path, _| keep(path));
+        self.annotations.retain(|path, _| keep(path));
+    }
+
+    /// Removes the items which are in any of the subtrees described by
+    /// `prefixes`.
+    pub(crate) fn remove_prefixes(&mut self, prefixes: &[PathPrefix]) {
+        let keep = |path: &ItemPath| !prefixes.iter().any(|prefix| prefix.matches(path));
+
+        self.items.retain(|path, _| keep(path));
+        self.private.retain(|path| keep(path));
+        self.aliases.retain(|path, _| keep(path));
+        self.macro_helpers.retain(|path, _| keep(path));
+        self.auto_traits.retain(|path, _| keep(path));
+        self.unanalyzable.retain(|path, _| keep(path));
+        self.annotations.retain(|path, _| keep(path));
+    }
+}
+
+// Adds an item to `items`. A type and a value, such as a braced struct and a
+// function, may share a path, as they live in different namespaces: only the
+// type is kept then. Items of the same namespace can share a path when they
+// are enabled on different platforms: none of them can be analyzed then.
+fn add_item(
+    items: &mut BTreeMap<ItemPath, ItemKind>,
+    unanalyzable: &mut BTreeMap<ItemPath, String>,
+    path: ItemPath,
+    kind: ItemKind,
+) {
+    match items.entry(path) {
+        Entry::Occupied(mut entry) if !entry.get().shares_namespace(&kind) => {
+            if kind.defines_type() {
+                entry.insert(kind);
+            }
+        }
+        Entry::Occupied(entry) => {
+            let reason = "several items are defined at this path".to_owned();
+            unanalyzable.insert(entry.key().clone(), reason);
+        }
+        Entry::Vacant(entry) => {
+            entry.insert(kind);
+        }
+    }
 }
 
 impl Parse for PublicApi {
@@ -88,31 +391,146 @@ impl ItemPath {
         ItemPath { path }
     }
 
-    fn concat_both(left: Vec<Ident>, right: Vec<Ident>) -> ItemPath {
-        let path = left.tap_mut(|v| v.extend(right));
+    fn extend(initial: ItemPath, last: Ident) -> ItemPath {
+        initial.tap_mut(|initial| initial.path.push(last))
+    }
+
+    pub(crate) fn from_segments(path: Vec<Ident>) -> ItemPath {
         ItemPath { path }
     }
 
-    fn extend(initial: ItemPath, last: Ident) -> ItemPath {
-        initial.tap_mut(|initial| initial.path.push(last))
+    pub(crate) fn segments(&self) -> &[Ident] {
+        self.path.as_slice()
     }
 }
 
 impl Display for ItemPath {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        if let Some(first) = self.path.first() {
-            write!(f, "{}", first)?;
+        // The crate root, which items such as re-exports of the crate itself
+        // resolve to.
+        let first = match self.path.first() {
+            Some(first) => first,
+            None => return write!(f, "crate"),
+        };
+
+        write!(f, "{}", first)?;
+
+        self.path
+            .iter()
+            .skip(1)
+            .try_for_each(|segment| write!(f, "::{}", segment))
+    }
+}
+
+/// The path of a module or an item, such as `api::v2`. It matches the item
+/// itself and everything it contains.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct PathPrefix {
+    segments: Vec<String>,
+}
+
+impl PathPrefix {
+    /// The number of segments of the prefix.
+    pub(crate) fn depth(&self) -> usize {
+        self.segments.len()
+    }
 
-            self.path
+    pub(crate) fn matches(&self, path: &ItemPath) -> bool {
+        path.path.len() >= self.segments.len()
+            && self
+                .segments
                 .iter()
-                .skip(1)
-                .try_for_each(|segment| write!(f, "::{}", segment))?;
+                .zip(&path.path)
+                .all(|(expected, segment)| segment == expected)
+    }
+}
+
+impl FromStr for PathPrefix {
+    type Err = AnyError;
+
+    fn from_str(s: &str) -> AnyResult<PathPrefix> {
+        // The crate root is implied.
+        let path = s.strip_prefix("crate::").unwrap_or(s);
+
+        let segments = path
+            .split("::")
+            .map(|segment| syn::parse_str::<Ident>(segment).map(|ident| ident.to_string()))
+            .collect::<Result<Vec<_>, _>>();
+
+        match segments {
+            Ok(segments) => Ok(PathPrefix { segments }),
+            Err(_) => bail!("`{}` is not a valid path", s),
+        }
+    }
+}
+
+impl TryFrom<String> for PathPrefix {
+    type Error = AnyError;
+
+    fn try_from(s: String) -> AnyResult<PathPrefix> {
+        s.parse()
+    }
+}
+
+/// The path of an item, such as `foo::Bar`, or of every item located in a
+/// module, such as `baz::*`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct ItemPattern {
+    prefix: PathPrefix,
+    subtree: bool,
+}
+
+impl ItemPattern {
+    pub(crate) fn matches(&self, path: &ItemPath) -> bool {
+        let depth = self.prefix.segments.len();
+
+        self.prefix.matches(path)
+            && if self.subtree {
+                path.path.len() > depth
+            } else {
+                path.path.len() == depth
+            }
+    }
+}
+
+impl Display for ItemPattern {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(&self.prefix.segments.join("::"))?;
+
+        if self.subtree {
+            f.write_str("::*")?;
         }
 
         Ok(())
     }
 }
 
+impl FromStr for ItemPattern {
+    type Err = AnyError;
+
+    fn from_str(s: &str) -> AnyResult<ItemPattern> {
+        let (prefix, subtree) = match s.strip_suffix("::*") {
+            Some(prefix) => (prefix, true),
+            None => (s, false),
+        };
+
+        Ok(ItemPattern {
+            prefix: prefix.parse()?,
+            subtree,
+        })
+    }
+}
+
+impl TryFrom<String> for ItemPattern {
+    type Error = AnyError;
+
+    fn try_from(s: String) -> AnyResult<ItemPattern> {
+        s.parse()
+    }
+}
+
 #[cfg(test)]
 impl Parse for ItemPath {
     fn parse(input: ParseStream) -> ParseResult<ItemPath> {
@@ -136,9 +554,18 @@ pub(crate) enum ItemKind {
     Type(TypeMetadata),
     Method(MethodMetadata),
     TraitDef(TraitDefMetadata),
+    Macro(MacroMetadata),
 }
 
 impl ItemKind {
+    pub(crate) fn as_type(&self) -> Option<&TypeMetadata> {
+        if let ItemKind::Type(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
     pub(crate) fn as_type_mut(&mut self) -> Option<&mut TypeMetadata> {
         if let Self::Type(v) = self {
             Some(v)
@@ -146,17 +573,83 @@ impl ItemKind {
             None
         }
     }
-}
 
-#[cfg(test)]
-impl ItemKind {
-    fn as_type(&self) -> Option<&TypeMetadata> {
-        if let ItemKind::Type(v) = self {
+    pub(crate) fn as_method(&self) -> Option<&MethodMetadata> {
+        if let ItemKind::Method(v) = self {
             Some(v)
         } else {
             None
         }
     }
+
+    pub(crate) fn as_macro(&self) -> Option<&MacroMetadata> {
+        if let ItemKind::Macro(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    // Whether the item is in the type namespace.
+    fn defines_type(&self) -> bool {
+        matches!(self, ItemKind::Type(_) | ItemKind::TraitDef(_))
+    }
+
+    // Whether the item is in the value namespace.
+    fn defines_value(&self) -> bool {
+        match self {
+            ItemKind::Fn(_) | ItemKind::Method(_) => true,
+            ItemKind::Type(t) => t.has_constructor(),
+            ItemKind::TraitDef(_) | ItemKind::Macro(_) => false,
+        }
+    }
+
+    // Whether both items can't be defined at the same path.
+    fn shares_namespace(&self, other: &ItemKind) -> bool {
+        (self.defines_type() && other.defines_type())
+            || (self.defines_value() && other.defines_value())
+            || (self.as_macro().is_some() && other.as_macro().is_some())
+    }
+
+    /// Returns the signature of functions and methods.
+    pub(crate) fn signature(&self) -> Option<&Signature> {
+        match self {
+            ItemKind::Fn(f) => Some(f.signature()),
+            ItemKind::Method(m) => Some(m.signature()),
+            ItemKind::Type(_) | ItemKind::TraitDef(_) | ItemKind::Macro(_) => None,
+        }
+    }
+
+    /// Visits the types mentioned by the signature of functions and methods,
+    /// and by the public fields of types.
+    pub(crate) fn visit_types<'a>(&'a self, visitor: &mut impl Visit<'a>) {
+        match self {
+            ItemKind::Fn(f) => visitor.visit_signature(f.signature()),
+            ItemKind::Method(m) => visitor.visit_signature(m.signature()),
+            ItemKind::Type(t) => t.visit_fields(visitor),
+            ItemKind::TraitDef(_) | ItemKind::Macro(_) => {}
+        }
+    }
+
+    /// Renders the item as pseudo-Rust, named by its full path.
+    pub(crate) fn render(&self, path: &ItemPath) -> Vec<String> {
+        match self {
+            ItemKind::Fn(f) => utils::render_fn(f.attributes(), f.signature(), path),
+            ItemKind::Method(m) => utils::render_fn(m.attributes(), m.signature(), path),
+            ItemKind::Type(t) => t.render(path),
+            ItemKind::TraitDef(t) => t.render(path),
+            ItemKind::Macro(m) => m.render(path),
+        }
+    }
+
+    /// Returns the `#[inline]` hint of functions and methods.
+    pub(crate) fn inline(&self) -> Option<&str> {
+        match self {
+            ItemKind::Fn(f) => f.attributes().inline(),
+            ItemKind::Method(m) => m.attributes().inline(),
+            ItemKind::Type(_) | ItemKind::TraitDef(_) | ItemKind::Macro(_) => None,
+        }
+    }
 }
 
 impl DiagnosticGenerator for ItemKind {
@@ -166,6 +659,7 @@ impl DiagnosticGenerator for ItemKind {
             ItemKind::Type(t) => t.removal_diagnosis(path, collector),
             ItemKind::Method(m) => m.removal_diagnosis(path, collector),
             ItemKind::TraitDef(t) => t.removal_diagnosis(path, collector),
+            ItemKind::Macro(m) => m.removal_diagnosis(path, collector),
         }
     }
 
@@ -186,6 +680,9 @@ impl DiagnosticGenerator for ItemKind {
             (ItemKind::TraitDef(ta), ItemKind::TraitDef(tb)) => {
                 ta.modification_diagnosis(tb, path, collector)
             }
+            (ItemKind::Macro(ma), ItemKind::Macro(mb)) => {
+                ma.modification_diagnosis(mb, path, collector)
+            }
             (a, b) => {
                 a.removal_diagnosis(path, collector);
                 b.addition_diagnosis(path, collector);
@@ -199,6 +696,7 @@ impl DiagnosticGenerator for ItemKind {
             ItemKind::Type(t) => t.addition_diagnosis(path, collector),
             ItemKind::Method(m) => m.addition_diagnosis(path, collector),
             ItemKind::TraitDef(t) => t.addition_diagnosis(path, collector),
+            ItemKind::Macro(m) => m.addition_diagnosis(path, collector),
         }
     }
 }
@@ -258,6 +756,50 @@ impl From<MethodMetadata> for ItemKind {
 mod tests {
     use super::*;
 
+    mod path_prefix {
+        use syn::parse_quote;
+
+        use super::*;
+
+        #[test]
+        fn matches_item_and_children() {
+            let prefix = PathPrefix::from_str("api::v2").unwrap();
+
+            assert!(prefix.matches(&parse_quote! { api::v2 }));
+            assert!(prefix.matches(&parse_quote! { api::v2::S::new }));
+            assert!(!prefix.matches(&parse_quote! { api::v20 }));
+            assert!(!prefix.matches(&parse_quote! { api }));
+        }
+
+        #[test]
+        fn crate_root_is_ignored() {
+            assert_eq!(
+                PathPrefix::from_str("crate::api").unwrap(),
+                PathPrefix::from_str("api").unwrap()
+            );
+        }
+
+        #[test]
+        fn invalid_paths() {
+            assert!(PathPrefix::from_str("").is_err());
+            assert!(PathPrefix::from_str("api::").is_err());
+            assert!(PathPrefix::from_str("api::v2::*").is_err());
+        }
+
+        #[test]
+        fn item_patterns() {
+            let item = ItemPattern::from_str("foo::Bar").unwrap();
+            let module = ItemPattern::from_str("crate::baz::*").unwrap();
+
+            assert!(item.matches(&parse_quote! { foo::Bar }));
+            assert!(!item.matches(&parse_quote! { foo::Bar::new }));
+            assert!(module.matches(&parse_quote! { baz::S::new }));
+            assert!(!module.matches(&parse_quote! { baz }));
+            assert_eq!(module.to_string(), "baz::*");
+            assert!(ItemPattern::from_str("*").is_err());
+        }
+    }
+
     mod public_api {
         use syn::parse_quote;
 
@@ -284,6 +826,118 @@ mod tests {
             assert_eq!(left, right);
         }
 
+        #[test]
+        fn marks_unreachable_items_as_private() {
+            let ast: CrateAst = parse_quote! {
+                pub fn f() {}
+                fn g() {}
+                mod m {
+                    pub struct S;
+                }
+            };
+
+            let public_api = PublicApi::from_ast_including_private(&ast);
+
+            assert_eq!(public_api.items.len(), 3);
+            assert!(!public_api.is_private(&parse_quote! { f }));
+            assert!(public_api.is_private(&parse_quote! { g }));
+            assert!(public_api.is_private(&parse_quote! { m::S }));
+        }
+
+        #[test]
+        fn retains_prefixes() {
+            let mut public_api: PublicApi = parse_quote! {
+                pub fn f() {}
+                pub mod api {
+                    pub mod v1 {
+                        pub fn g() {}
+                    }
+                    pub mod v2 {
+                        pub struct S;
+                    }
+                    pub mod v20 {
+                        pub fn h() {}
+                    }
+                }
+            };
+
+            public_api.retain_prefixes(&["api::v2".parse().unwrap(), "f".parse().unwrap()]);
+
+            let paths = public_api.items.keys().collect::<Vec<_>>();
+            let expected: [ItemPath; 2] = [parse_quote! { api::v2::S }, parse_quote! { f }];
+            assert_eq!(paths, expected.iter().collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn removes_prefixes() {
+            let mut public_api: PublicApi = parse_quote! {
+                pub fn f() {}
+                pub mod internal {
+                    pub struct S;
+                }
+            };
+
+            public_api.remove_prefixes(&["internal".parse().unwrap()]);
+
+            let paths = public_api.items.keys().collect::<Vec<_>>();
+            let expected: [ItemPath; 1] = [parse_quote! { f }];
+            assert_eq!(paths, expected.iter().collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn no_prefix_retains_everything() {
+            let mut public_api: PublicApi = parse_quote! {
+                pub fn f() {}
+                pub struct S;
+            };
+
+            public_api.retain_prefixes(&[]);
+
+            assert_eq!(public_api.items.len(), 2);
+        }
+
+        #[test]
+        fn items_defined_twice_are_unanalyzable() {
+            let public_api: PublicApi = parse_quote! {
+                pub struct S;
+                pub fn S() {}
+                pub fn f() {}
+            };
+
+            let paths = public_api.items.keys().collect::<Vec<_>>();
+            let expected: [ItemPath; 1] = [parse_quote! { f }];
+            assert_eq!(paths, expected.iter().collect::<Vec<_>>());
+
+            let s = parse_quote! { S };
+            assert_eq!(
+                public_api.unanalyzable.get(&s).map(String::as_str),
+                Some("several items are defined at this path")
+            );
+        }
+
+        #[test]
+        fn type_and_value_can_share_a_path() {
+            let public_api: PublicApi = parse_quote! {
+                pub struct A {}
+                pub fn A() {}
+                pub fn T() {}
+                pub trait T {}
+            };
+
+            assert!(public_api.unanalyzable.is_empty());
+
+            let a = parse_quote! { A };
+            assert!(public_api.items[&a].as_type().is_some());
+
+            let t = parse_quote! { T };
+            assert!(matches!(public_api.items[&t], ItemKind::TraitDef(_)));
+        }
+
+        #[test]
+        fn root_path_is_displayed_as_crate() {
+            assert_eq!(ItemPath::from_segments(Vec::new()).to_string(), "crate");
+        }
+
         #[test]
         fn adds_structure() {
             let public_api: PublicApi = parse_quote! { pub struct A; };
@@ -400,21 +1054,25 @@ mod tests {
         }
 
         #[test]
-        #[should_panic(expected = "Duplicate item definition")]
-        fn panics_on_redefinition_1() {
-            let _: PublicApi = parse_quote! {
+        fn redefinition_is_unanalyzable_1() {
+            let public_api: PublicApi = parse_quote! {
                 pub fn a () {}
                 pub fn a() {}
             };
+
+            assert!(public_api.items.is_empty());
+            assert_eq!(public_api.unanalyzable.len(), 1);
         }
 
         #[test]
-        #[should_panic(expected = "Duplicate item definition")]
-        fn panics_on_redefinition_2() {
-            let _: PublicApi = parse_quote! {
+        fn redefinition_is_unanalyzable_2() {
+            let public_api: PublicApi = parse_quote! {
                 pub struct A;
                 pub struct A;
             };
+
+            assert!(public_api.items.is_empty());
+            assert_eq!(public_api.unanalyzable.len(), 1);
         }
 
         #[test]
@@ -430,7 +1088,7 @@ mod tests {
             assert_eq!(public_api.items.len(), 2);
 
             let struct_key = parse_quote! { A };
-            assert!(public_api.items.get(&struct_key).is_some());
+            assert!(public_api.items.contains_key(&struct_key));
 
             let item = parse_quote! {
                 impl A {
@@ -507,12 +1165,14 @@ mod tests {
         }
 
         #[test]
-        #[should_panic(expected = "Duplicate item definition")]
-        fn panics_on_redefinition_3() {
-            let _: PublicApi = parse_quote! {
+        fn redefinition_is_unanalyzable_3() {
+            let public_api: PublicApi = parse_quote! {
                 pub trait T {}
                 pub trait T {}
             };
+
+            assert!(public_api.items.is_empty());
+            assert_eq!(public_api.unanalyzable.len(), 1);
         }
     }
 }
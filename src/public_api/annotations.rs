@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+
+use syn::{
+    visit::{self, Visit},
+    Attribute, Ident, ImplItem, Item, ItemImpl, ItemMod, ItemTrait, Lit, Meta, NestedMeta,
+    TraitItem,
+};
+
+use super::{imports::PathResolver, utils, ItemPath};
+
+/// The `cfg` predicate of the annotation attribute. It is never set, so the
+/// compiler ignores the attribute.
+const CFG_PREDICATE: &str = "cargo_breaking";
+
+/// The prefix of the documentation lines holding an annotation, which is kept
+/// by the expansion unlike the attribute.
+const DOC_PREFIX: &str = "cargo-breaking:";
+
+/// Collects the items which the maintainers annotated as intentionally
+/// broken, along with the reason they gave:
+///
+/// ```rust,ignore
+/// #[cfg_attr(cargo_breaking, breaking(reason = "the error type is now opaque"))]
+/// pub fn parse() {}
+///
+/// /// cargo-breaking: breaking(reason = "the error type is now opaque")
+/// pub fn parse() {}
+/// ```
+pub(crate) struct AnnotationVisitor<'a> {
+    annotations: BTreeMap<ItemPath, String>,
+    resolver: &'a PathResolver,
+    path: Vec<Ident>,
+}
+
+impl<'a> AnnotationVisitor<'a> {
+    pub(crate) fn new(resolver: &'a PathResolver) -> AnnotationVisitor<'a> {
+        AnnotationVisitor {
+            annotations: BTreeMap::new(),
+            resolver,
+            path: Vec::new(),
+        }
+    }
+
+    pub(crate) fn annotations(self) -> BTreeMap<ItemPath, String> {
+        self.annotations
+    }
+
+    fn add(&mut self, parent: &[Ident], name: &Ident, attrs: &[Attribute]) {
+        if let Some(reason) = attrs.iter().find_map(breaking_reason) {
+            let path = ItemPath::new(parent.to_vec(), name.clone());
+            self.annotations.insert(path, reason);
+        }
+    }
+}
+
+impl<'a, 'ast> Visit<'ast> for AnnotationVisitor<'a> {
+    fn visit_item(&mut self, item: &'ast Item) {
+        let path = self.path.clone();
+        let named = match item {
+            Item::Const(i) => Some((&i.ident, &i.attrs)),
+            Item::Enum(i) => Some((&i.ident, &i.attrs)),
+            Item::Fn(i) => Some((&i.sig.ident, &i.attrs)),
+            Item::Static(i) => Some((&i.ident, &i.attrs)),
+            Item::Struct(i) => Some((&i.ident, &i.attrs)),
+            Item::Trait(i) => Some((&i.ident, &i.attrs)),
+            Item::Type(i) => Some((&i.ident, &i.attrs)),
+            Item::Union(i) => Some((&i.ident, &i.attrs)),
+            Item::Mod(i) => Some((&i.ident, &i.attrs)),
+            _ => None,
+        };
+
+        if let Some((name, attrs)) = named {
+            self.add(&path, name, attrs);
+        }
+
+        visit::visit_item(self, item);
+    }
+
+    fn visit_item_mod(&mut self, mod_: &'ast ItemMod) {
+        self.path.push(mod_.ident.clone());
+        visit::visit_item_mod(self, mod_);
+        self.path.pop().unwrap();
+    }
+
+    fn visit_item_trait(&mut self, trait_: &'ast ItemTrait) {
+        let mut path = self.path.clone();
+        path.push(trait_.ident.clone());
+
+        for item in &trait_.items {
+            match item {
+                TraitItem::Const(i) => self.add(&path, &i.ident, &i.attrs),
+                TraitItem::Method(i) => self.add(&path, &i.sig.ident, &i.attrs),
+                TraitItem::Type(i) => self.add(&path, &i.ident, &i.attrs),
+                _ => {}
+            }
+        }
+    }
+
+    fn visit_item_impl(&mut self, impl_: &'ast ItemImpl) {
+        if impl_.trait_.is_some() {
+            return;
+        }
+
+        let type_path = match utils::extract_name_and_generic_args(&impl_.self_ty) {
+            Some((type_path, _)) => type_path,
+            None => return,
+        };
+
+        let path = match self.resolver.resolve(&self.path, type_path) {
+            Some(resolved) => resolved.to_vec(),
+            None => return,
+        };
+
+        for item in &impl_.items {
+            match item {
+                ImplItem::Const(i) => self.add(&path, &i.ident, &i.attrs),
+                ImplItem::Method(i) => self.add(&path, &i.sig.ident, &i.attrs),
+                _ => {}
+            }
+        }
+    }
+}
+
+// Returns the reason given by an annotation, if `attr` is one.
+fn breaking_reason(attr: &Attribute) -> Option<String> {
+    match attr.parse_meta().ok()? {
+        Meta::List(list) if list.path.is_ident("cfg_attr") && list.nested.len() == 2 => {
+            match (&list.nested[0], &list.nested[1]) {
+                (NestedMeta::Meta(Meta::Path(cfg)), NestedMeta::Meta(annotation))
+                    if cfg.is_ident(CFG_PREDICATE) =>
+                {
+                    reason(annotation)
+                }
+                _ => None,
+            }
+        }
+
+        Meta::NameValue(doc) if doc.path.is_ident("doc") => match doc.lit {
+            Lit::Str(line) => {
+                let annotation = line.value().trim().strip_prefix(DOC_PREFIX)?.to_owned();
+                reason(&syn::parse_str(&annotation).ok()?)
+            }
+            _ => None,
+        },
+
+        _ => None,
+    }
+}
+
+// Returns the reason of `breaking(reason = "...")`.
+fn reason(annotation: &Meta) -> Option<String> {
+    let list = match annotation {
+        Meta::List(list) if list.path.is_ident("breaking") => list,
+        _ => return None,
+    };
+
+    list.nested.iter().find_map(|nested| match nested {
+        NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("reason") => match &nv.lit {
+            Lit::Str(reason) => Some(reason.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::public_api::PublicApi;
+
+    fn annotations(api: &PublicApi) -> Vec<(String, &str)> {
+        api.annotations
+            .iter()
+            .map(|(path, reason)| (path.to_string(), reason.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn attribute_and_doc_annotations() {
+        let api: PublicApi = parse_quote! {
+            pub mod m {
+                #[cfg_attr(cargo_breaking, breaking(reason = "renamed"))]
+                pub fn f() {}
+
+                /// Does things.
+                ///
+                /// cargo-breaking: breaking(reason = "now fallible")
+                pub fn g() {}
+            }
+
+            pub struct S;
+
+            impl S {
+                #[cfg_attr(cargo_breaking, breaking(reason = "takes a reference"))]
+                pub fn h() {}
+            }
+        };
+
+        assert_eq!(
+            annotations(&api),
+            [
+                ("S::h".to_owned(), "takes a reference"),
+                ("m::f".to_owned(), "renamed"),
+                ("m::g".to_owned(), "now fallible"),
+            ]
+        );
+    }
+
+    #[test]
+    fn other_attributes_are_not_annotations() {
+        let api: PublicApi = parse_quote! {
+            #[cfg_attr(feature = "serde", breaking(reason = "other predicate"))]
+            pub fn f() {}
+
+            #[cfg_attr(cargo_breaking, breaking)]
+            pub fn g() {}
+
+            /// cargo-breaking is great.
+            pub fn h() {}
+        };
+
+        assert!(api.annotations.is_empty());
+    }
+
+    #[test]
+    fn annotations_follow_reexports() {
+        let api: PublicApi = parse_quote! {
+            mod inner {
+                #[cfg_attr(cargo_breaking, breaking(reason = "renamed"))]
+                pub struct S;
+            }
+
+            pub use inner::S;
+        };
+
+        assert_eq!(annotations(&api), [("S".to_owned(), "renamed")]);
+    }
+}
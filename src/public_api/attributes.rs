@@ -0,0 +1,633 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use quote::ToTokens;
+use syn::{
+    visit::{self, Visit},
+    Attribute, Lit, Meta, NestedMeta, ReturnType, Signature, Type, TypeNever,
+};
+
+use crate::{
+    diagnosis::{DiagnosisCollector, DiagnosisItem, DiagnosisRules},
+    rules::RuleId,
+};
+
+use super::ItemPath;
+
+/// The attributes of an item which are part of its API.
+///
+/// Other attributes, such as documentation, are not recorded: adding or
+/// removing them does not affect the users of the item.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ItemAttributes {
+    // The value is the list of arguments of the attribute, for the
+    // attributes whose arguments are compared.
+    attrs: BTreeMap<TrackedAttribute, String>,
+    // Set with `#[doc(alias = "...")]`. They are used by rustdoc search and
+    // IDEs to find the item under another name.
+    aliases: BTreeSet<String>,
+    // The `#[inline]` hint, such as `inline(always)`. It only affects code
+    // generation, so it is not compared.
+    inline: Option<String>,
+    // The unstable features the definition of the item depends on, such as
+    // `#[marker]` or the `!` type in its signature.
+    nightly: BTreeSet<String>,
+}
+
+impl PartialEq for ItemAttributes {
+    fn eq(&self, other: &ItemAttributes) -> bool {
+        self.attrs == other.attrs && self.aliases == other.aliases && self.nightly == other.nightly
+    }
+}
+
+impl ItemAttributes {
+    pub(crate) fn new(attrs: &[Attribute]) -> ItemAttributes {
+        let mut recorded = BTreeMap::new();
+        let mut aliases = BTreeSet::new();
+        let mut inline = None;
+        let mut nightly = BTreeSet::new();
+
+        for attr in attrs {
+            if let Some(feature) = unstable_attribute(attr) {
+                nightly.insert(feature);
+                continue;
+            }
+
+            if attr.path.is_ident("doc") {
+                aliases.extend(doc_aliases(attr));
+                record(
+                    &mut recorded,
+                    TrackedAttribute::DocCfg,
+                    doc_cfg_predicates(attr),
+                );
+                continue;
+            }
+
+            if attr.path.is_ident("inline") {
+                inline = Some(inline_hint(attr));
+                continue;
+            }
+
+            let tracked = match TrackedAttribute::ALL
+                .iter()
+                .find(|tracked| attr.path.is_ident(tracked.name()))
+            {
+                Some(tracked) => *tracked,
+                None => continue,
+            };
+
+            record(&mut recorded, tracked, tracked.arguments(attr));
+        }
+
+        ItemAttributes {
+            attrs: recorded,
+            aliases,
+            inline,
+            nightly,
+        }
+    }
+
+    /// Records the unstable syntax used in the signature of a function or a
+    /// method.
+    pub(crate) fn with_signature(mut self, sig: &Signature) -> ItemAttributes {
+        self.nightly.extend(unstable_syntax(sig));
+        self
+    }
+
+    pub(crate) fn is_non_exhaustive(&self) -> bool {
+        self.attrs.contains_key(&TrackedAttribute::NonExhaustive)
+    }
+
+    /// Renders the compared attributes, one per line.
+    pub(crate) fn render(&self) -> Vec<String> {
+        let attrs = self
+            .attrs
+            .iter()
+            .map(|(tracked, arguments)| Displayed(*tracked, arguments).to_string());
+        let aliases = self
+            .aliases
+            .iter()
+            .map(|alias| format!("#[doc(alias = {:?})]", alias));
+
+        attrs.chain(aliases).collect()
+    }
+
+    /// Returns the `#[inline]` hint of the item, such as `inline(always)`.
+    pub(crate) fn inline(&self) -> Option<&str> {
+        self.inline.as_deref()
+    }
+
+    /// Reports every attribute which was added, removed or modified between
+    /// `self` and `other`.
+    pub(crate) fn diagnosis(
+        &self,
+        other: &ItemAttributes,
+        path: &ItemPath,
+        collector: &mut DiagnosisCollector,
+    ) {
+        for tracked in TrackedAttribute::ALL {
+            let rules = tracked.rules();
+
+            let (rule, message) = match (self.attrs.get(tracked), other.attrs.get(tracked)) {
+                (Some(a), Some(b)) if a == b => continue,
+                (None, None) => continue,
+
+                (Some(a), Some(b)) => (
+                    rules.modification,
+                    format!(
+                        "{} changed to {}",
+                        Displayed(*tracked, a),
+                        Displayed(*tracked, b)
+                    ),
+                ),
+                (Some(a), None) => (
+                    rules.removal,
+                    format!("no longer {}", Displayed(*tracked, a)),
+                ),
+                (None, Some(b)) => (rules.addition, format!("now {}", Displayed(*tracked, b))),
+            };
+
+            collector.add(DiagnosisItem::new(rule, path.clone(), None).with_message(message));
+        }
+
+        self.aliases.difference(&other.aliases).for_each(|alias| {
+            collector.add(
+                DiagnosisItem::new(RuleId::DocAliasRemoved, path.clone(), None)
+                    .with_message(format!("doc alias `{}` removed", alias)),
+            )
+        });
+
+        // Stabilizing an item is not a breaking change, so only the newly
+        // required features are reported.
+        other.nightly.difference(&self.nightly).for_each(|feature| {
+            collector.add(
+                DiagnosisItem::new(RuleId::NightlyRequired, path.clone(), None)
+                    .with_message(format!("now requires a nightly compiler: {}", feature)),
+            )
+        });
+    }
+}
+
+/// The attributes which can't be used on a stable compiler, and don't need
+/// any argument to be recognized.
+const UNSTABLE_ATTRIBUTES: &[&str] = &["fundamental", "marker", "unstable", "const_trait"];
+
+/// The ABIs which can't be used on a stable compiler.
+const UNSTABLE_ABIS: &[&str] = &[
+    "rust-call",
+    "rust-intrinsic",
+    "platform-intrinsic",
+    "x86-interrupt",
+    "ptx-kernel",
+    "msp430-interrupt",
+    "avr-interrupt",
+];
+
+/// Returns the unstable feature `attr` depends on, if any. The attributes
+/// reserved to the standard library start with `rustc_`.
+fn unstable_attribute(attr: &Attribute) -> Option<String> {
+    let name = attr.path.get_ident()?.to_string();
+
+    if UNSTABLE_ATTRIBUTES.contains(&name.as_str()) || name.starts_with("rustc_") {
+        Some(format!("#[{}]", name))
+    } else {
+        None
+    }
+}
+
+/// Returns the unstable syntax used by a signature: an unstable ABI, or the
+/// `!` type anywhere but as the bare return type, which is stable.
+fn unstable_syntax(sig: &Signature) -> Vec<String> {
+    struct NeverVisitor(bool);
+
+    impl<'ast> Visit<'ast> for NeverVisitor {
+        fn visit_type_never(&mut self, _: &'ast TypeNever) {
+            self.0 = true;
+        }
+    }
+
+    let mut syntax = Vec::new();
+
+    if let Some(abi) = sig.abi.as_ref().and_then(|abi| abi.name.as_ref()) {
+        if UNSTABLE_ABIS.contains(&abi.value().as_str()) {
+            syntax.push(format!("the `{}` ABI", abi.value()));
+        }
+    }
+
+    let mut never = NeverVisitor(false);
+    visit::visit_generics(&mut never, &sig.generics);
+    sig.inputs.iter().for_each(|arg| never.visit_fn_arg(arg));
+    match &sig.output {
+        ReturnType::Type(_, ty) if matches!(**ty, Type::Never(_)) => {}
+        output => never.visit_return_type(output),
+    }
+
+    if never.0 {
+        syntax.push("the `!` type".to_owned());
+    }
+
+    syntax
+}
+
+// Records the arguments of an attribute, along with the ones of the previous
+// occurrences of the same attribute.
+fn record(
+    recorded: &mut BTreeMap<TrackedAttribute, String>,
+    tracked: TrackedAttribute,
+    arguments: Vec<String>,
+) {
+    // A `#[doc]` attribute is only tracked when it has a `cfg`.
+    if tracked == TrackedAttribute::DocCfg && arguments.is_empty() {
+        return;
+    }
+
+    let value = recorded.entry(tracked).or_default();
+
+    // `#[repr(C)] #[repr(u8)]` is the same as `#[repr(C, u8)]`.
+    if !arguments.is_empty() {
+        let mut all = value
+            .split(", ")
+            .filter(|arg| !arg.is_empty())
+            .chain(arguments.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+        all.sort_unstable();
+        all.dedup();
+
+        *value = all.join(", ");
+    }
+}
+
+/// Returns the `cfg(...)` predicates of a `#[doc]` attribute, which tell
+/// rustdoc the platforms and features the item is available with.
+fn doc_cfg_predicates(attr: &Attribute) -> Vec<String> {
+    match attr.parse_meta() {
+        Ok(Meta::List(list)) => list
+            .nested
+            .iter()
+            .filter_map(|nested| match nested {
+                NestedMeta::Meta(Meta::List(cfg)) if cfg.path.is_ident("cfg") => {
+                    Some(cfg.to_token_stream().to_string().replace(' ', ""))
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns `inline`, `inline(always)` or `inline(never)`.
+fn inline_hint(attr: &Attribute) -> String {
+    match attr.parse_meta() {
+        Ok(Meta::List(list)) => format!("inline({})", list.nested.to_token_stream()),
+        _ => "inline".to_owned(),
+    }
+}
+
+/// Returns the aliases declared by a `#[doc]` attribute, either as
+/// `#[doc(alias = "a")]` or as `#[doc(alias("a", "b"))]`.
+fn doc_aliases(attr: &Attribute) -> Vec<String> {
+    let nested = match attr.parse_meta() {
+        Ok(Meta::List(list)) => list.nested,
+        _ => return Vec::new(),
+    };
+
+    let string = |lit: &Lit| match lit {
+        Lit::Str(s) => Some(s.value()),
+        _ => None,
+    };
+
+    nested
+        .iter()
+        .flat_map(|nested| match nested {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("alias") => {
+                string(&nv.lit).into_iter().collect()
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("alias") => list
+                .nested
+                .iter()
+                .filter_map(|nested| match nested {
+                    NestedMeta::Lit(lit) => string(lit),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum TrackedAttribute {
+    Deprecated,
+    MustUse,
+    NonExhaustive,
+    Repr,
+    // `#[doc(cfg(...))]`, which is kept by the expansion unlike `#[cfg]`.
+    DocCfg,
+}
+
+impl TrackedAttribute {
+    const ALL: &'static [TrackedAttribute] = &[
+        TrackedAttribute::Deprecated,
+        TrackedAttribute::MustUse,
+        TrackedAttribute::NonExhaustive,
+        TrackedAttribute::Repr,
+        TrackedAttribute::DocCfg,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            TrackedAttribute::Deprecated => "deprecated",
+            TrackedAttribute::MustUse => "must_use",
+            TrackedAttribute::NonExhaustive => "non_exhaustive",
+            TrackedAttribute::Repr => "repr",
+            TrackedAttribute::DocCfg => "doc",
+        }
+    }
+
+    fn rules(self) -> DiagnosisRules {
+        // The arguments of the attributes other than `repr` are not compared,
+        // so their `modification` rule is never used.
+        match self {
+            TrackedAttribute::Deprecated => DiagnosisRules {
+                removal: RuleId::DeprecationRemoved,
+                modification: RuleId::DeprecationAdded,
+                addition: RuleId::DeprecationAdded,
+            },
+            TrackedAttribute::MustUse => DiagnosisRules {
+                removal: RuleId::MustUseRemoved,
+                modification: RuleId::MustUseAdded,
+                addition: RuleId::MustUseAdded,
+            },
+            TrackedAttribute::NonExhaustive => DiagnosisRules {
+                removal: RuleId::NonExhaustiveRemoved,
+                modification: RuleId::NonExhaustiveAdded,
+                addition: RuleId::NonExhaustiveAdded,
+            },
+            TrackedAttribute::Repr => DiagnosisRules {
+                removal: RuleId::ReprRemoved,
+                modification: RuleId::ReprModified,
+                addition: RuleId::ReprAdded,
+            },
+            TrackedAttribute::DocCfg => DiagnosisRules {
+                removal: RuleId::PlatformExtended,
+                modification: RuleId::PlatformRestricted,
+                addition: RuleId::PlatformRestricted,
+            },
+        }
+    }
+
+    /// Returns the arguments of `attr` which are part of the API, normalized
+    /// so that formatting changes are not reported.
+    fn arguments(self, attr: &Attribute) -> Vec<String> {
+        if self != TrackedAttribute::Repr {
+            // Deprecation notes and `must_use` messages are only hints.
+            return Vec::new();
+        }
+
+        match attr.parse_meta() {
+            Ok(Meta::List(list)) => list
+                .nested
+                .iter()
+                .map(|nested| match nested {
+                    NestedMeta::Meta(meta) => meta.to_token_stream().to_string(),
+                    NestedMeta::Lit(lit) => lit.to_token_stream().to_string(),
+                })
+                .map(|arg| arg.replace(' ', ""))
+                .collect(),
+            _ => vec![attr.tokens.to_string().replace(' ', "")],
+        }
+    }
+}
+
+struct Displayed<'a>(TrackedAttribute, &'a str);
+
+impl Display for Displayed<'_> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let Displayed(attr, arguments) = self;
+
+        if arguments.is_empty() {
+            write!(f, "#[{}]", attr.name())
+        } else {
+            write!(f, "#[{}({})]", attr.name(), arguments)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, ItemStruct};
+
+    use super::*;
+
+    fn attributes(item: ItemStruct) -> ItemAttributes {
+        ItemAttributes::new(&item.attrs)
+    }
+
+    fn diagnosis(a: ItemStruct, b: ItemStruct) -> Vec<String> {
+        let mut collector = DiagnosisCollector::new();
+        let path = parse_quote! { A };
+
+        attributes(a).diagnosis(&attributes(b), &path, &mut collector);
+
+        collector
+            .finalize()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn untracked_attributes_are_ignored() {
+        let attrs = attributes(parse_quote! {
+            #[doc = "A"]
+            #[inline(always)]
+            struct A;
+        });
+
+        assert_eq!(attrs, ItemAttributes::default());
+        assert_eq!(attrs.inline(), Some("inline(always)"));
+    }
+
+    #[test]
+    fn doc_cfg_changes() {
+        let a: ItemStruct = parse_quote! {
+            /// Docs.
+            #[doc(alias = "B")]
+            struct A;
+        };
+        let b: ItemStruct = parse_quote! {
+            #[doc(cfg(unix))]
+            #[doc(alias = "B")]
+            struct A;
+        };
+        let c: ItemStruct = parse_quote! {
+            #[doc(cfg(target_os = "linux"))]
+            #[doc(alias = "B")]
+            struct A;
+        };
+
+        assert_eq!(
+            diagnosis(a.clone(), b.clone()),
+            ["≠ A (now #[doc(cfg(unix))])"]
+        );
+        assert_eq!(
+            diagnosis(b.clone(), c),
+            ["≠ A (#[doc(cfg(unix))] changed to #[doc(cfg(target_os=\"linux\"))])"]
+        );
+        assert_eq!(diagnosis(b, a), ["≠ A (no longer #[doc(cfg(unix))])"]);
+    }
+
+    #[test]
+    fn unstable_features() {
+        let a: ItemStruct = parse_quote! {
+            #[rustc_layout_scalar_valid_range_start(1)]
+            struct A;
+        };
+        let b: ItemStruct = parse_quote! {
+            #[fundamental]
+            #[rustc_layout_scalar_valid_range_start(1)]
+            struct A;
+        };
+
+        assert_eq!(
+            diagnosis(a.clone(), b.clone()),
+            ["≠ A (now requires a nightly compiler: #[fundamental])"]
+        );
+        assert!(diagnosis(b, a).is_empty());
+    }
+
+    #[test]
+    fn unstable_syntax_in_signatures() {
+        let features = |sig: Signature| unstable_syntax(&sig);
+
+        assert!(features(parse_quote! { fn f() -> ! }).is_empty());
+        assert!(features(parse_quote! { extern "C" fn f(a: u8) }).is_empty());
+        assert_eq!(
+            features(parse_quote! { fn f(a: Result<u8, !>) }),
+            ["the `!` type"]
+        );
+        assert_eq!(
+            features(parse_quote! { extern "rust-call" fn f() -> Box<dyn Fn() -> !> }),
+            ["the `rust-call` ABI", "the `!` type"]
+        );
+    }
+
+    #[test]
+    fn repr_arguments_are_merged_and_sorted() {
+        let a = attributes(parse_quote! {
+            #[repr(u8)]
+            #[repr(C)]
+            struct A;
+        });
+        let b = attributes(parse_quote! {
+            #[repr(C, u8)]
+            struct A;
+        });
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn deprecation_notes_are_ignored() {
+        let a = attributes(parse_quote! {
+            #[deprecated]
+            struct A;
+        });
+        let b = attributes(parse_quote! {
+            #[deprecated(since = "1.2.0", note = "use B")]
+            struct A;
+        });
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn additions() {
+        let diags = diagnosis(
+            parse_quote! { struct A; },
+            parse_quote! {
+                #[non_exhaustive]
+                #[repr(C)]
+                struct A;
+            },
+        );
+
+        assert_eq!(
+            diags,
+            ["≠ A (now #[non_exhaustive])", "≠ A (now #[repr(C)])"]
+        );
+    }
+
+    #[test]
+    fn removals() {
+        let diags = diagnosis(
+            parse_quote! {
+                #[must_use]
+                #[deprecated]
+                struct A;
+            },
+            parse_quote! { struct A; },
+        );
+
+        assert_eq!(
+            diags,
+            [
+                "≠ A (no longer #[deprecated])",
+                "≠ A (no longer #[must_use])"
+            ]
+        );
+    }
+
+    #[test]
+    fn doc_aliases_are_recorded() {
+        let a = attributes(parse_quote! {
+            #[doc = "A"]
+            #[doc(alias = "b")]
+            #[doc(alias("c", "d"))]
+            struct A;
+        });
+
+        assert_eq!(
+            a.aliases.iter().map(String::as_str).collect::<Vec<_>>(),
+            ["b", "c", "d"]
+        );
+    }
+
+    #[test]
+    fn removed_doc_aliases() {
+        let diags = diagnosis(
+            parse_quote! {
+                #[doc(alias("b", "c"))]
+                struct A;
+            },
+            parse_quote! {
+                #[doc(alias = "c")]
+                #[doc(alias = "d")]
+                struct A;
+            },
+        );
+
+        assert_eq!(diags, ["≠ A (doc alias `b` removed)"]);
+    }
+
+    #[test]
+    fn repr_modification() {
+        let diags = diagnosis(
+            parse_quote! {
+                #[repr(align(8))]
+                struct A;
+            },
+            parse_quote! {
+                #[repr(transparent)]
+                struct A;
+            },
+        );
+
+        assert_eq!(
+            diags,
+            ["≠ A (#[repr(align(8))] changed to #[repr(transparent)])"]
+        );
+    }
+}
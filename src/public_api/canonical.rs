@@ -0,0 +1,172 @@
+use std::{collections::BTreeMap, iter};
+
+use super::{imports::PathResolver, ItemKind, ItemPath};
+
+/// Moves the items which can be named with several public paths to their
+/// canonical path: the shortest one, then the first in lexicographic order.
+///
+/// Returns the items, along with the other paths of each item which has
+/// some.
+pub(crate) fn canonicalize(
+    items: BTreeMap<ItemPath, ItemKind>,
+    resolver: &PathResolver,
+) -> (
+    BTreeMap<ItemPath, ItemKind>,
+    BTreeMap<ItemPath, Vec<ItemPath>>,
+) {
+    let mut renames = BTreeMap::new();
+    let mut aliases = BTreeMap::new();
+
+    // Methods are named after their type, they follow it.
+    for path in items
+        .iter()
+        .filter(|(_, kind)| kind.as_method().is_none())
+        .map(|(path, _)| path)
+    {
+        let mut paths = match public_paths(path, resolver) {
+            Some(paths) => paths,
+            None => continue,
+        };
+        let canonical = paths.remove(0);
+
+        if &canonical != path {
+            renames.insert(path.clone(), canonical.clone());
+        }
+
+        aliases.insert(canonical, paths);
+    }
+
+    let items = items
+        .into_iter()
+        .map(|(path, mut kind)| {
+            if let Some(type_) = kind.as_type_mut() {
+                type_.rename_deref_target(&renames);
+            }
+
+            (rename(&renames, path), kind)
+        })
+        .collect();
+
+    (items, aliases)
+}
+
+/// Moves the annotations to the canonical path of the item they are attached
+/// to.
+pub(crate) fn canonicalize_annotations(
+    annotations: BTreeMap<ItemPath, String>,
+    resolver: &PathResolver,
+) -> BTreeMap<ItemPath, String> {
+    let renames = annotations
+        .keys()
+        .flat_map(|path| {
+            let parent = path
+                .segments()
+                .split_last()
+                .map(|(_, parent)| ItemPath::from_segments(parent.to_vec()));
+
+            iter::once(path.clone()).chain(parent)
+        })
+        .filter_map(|path| {
+            let canonical = public_paths(&path, resolver)?.remove(0);
+            Some((path, canonical))
+        })
+        .collect();
+
+    annotations
+        .into_iter()
+        .map(|(path, reason)| (rename(&renames, path), reason))
+        .collect()
+}
+
+// Returns the paths an item can be named with, the canonical one first, if
+// it is re-exported.
+fn public_paths(path: &ItemPath, resolver: &PathResolver) -> Option<Vec<ItemPath>> {
+    let reexports = resolver.reexports(path.segments());
+    if reexports.is_empty() {
+        return None;
+    }
+
+    let mut paths = reexports
+        .into_iter()
+        .map(ItemPath::from_segments)
+        .chain(iter::once(path.clone()))
+        .collect::<Vec<_>>();
+
+    paths.sort_by_cached_key(|path| (path.segments().len(), path.to_string()));
+    Some(paths)
+}
+
+fn rename(renames: &BTreeMap<ItemPath, ItemPath>, path: ItemPath) -> ItemPath {
+    if let Some(canonical) = renames.get(&path) {
+        return canonical.clone();
+    }
+
+    match path.path.split_last() {
+        Some((last, parent)) => match renames.get(&ItemPath::from_segments(parent.to_vec())) {
+            Some(canonical) => ItemPath::extend(canonical.clone(), last.clone()),
+            None => path,
+        },
+        None => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::public_api::PublicApi;
+
+    #[test]
+    fn shortest_path_is_canonical() {
+        let api: PublicApi = parse_quote! {
+            pub mod a {
+                pub mod b {
+                    pub struct S;
+
+                    impl S {
+                        pub fn f(&self) {}
+                    }
+                }
+            }
+
+            pub mod prelude {
+                pub use crate::a::b::S;
+            }
+        };
+
+        assert!(api.items().contains_key(&parse_quote! { prelude::S }));
+        assert!(api.items().contains_key(&parse_quote! { prelude::S::f }));
+        assert_eq!(
+            api.aliases(&parse_quote! { prelude::S }),
+            [parse_quote! { a::b::S }]
+        );
+    }
+
+    #[test]
+    fn ties_are_broken_lexicographically() {
+        let api: PublicApi = parse_quote! {
+            pub mod b {
+                pub fn f() {}
+            }
+
+            pub mod a {
+                pub use crate::b::f;
+            }
+        };
+
+        assert!(api.items().contains_key(&parse_quote! { a::f }));
+        assert_eq!(api.aliases(&parse_quote! { a::f }), [parse_quote! { b::f }]);
+    }
+
+    #[test]
+    fn items_without_reexports_are_kept() {
+        let api: PublicApi = parse_quote! {
+            pub mod a {
+                pub fn f() {}
+            }
+        };
+
+        assert!(api.items().contains_key(&parse_quote! { a::f }));
+        assert!(api.aliases(&parse_quote! { a::f }).is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+use syn::{
+    visit_mut::{self, VisitMut},
+    GenericArgument, Ident, ParenthesizedGenericArguments, PathArguments, PathSegment, ReturnType,
+    Signature, TraitBound, Type, TypeTraitObject,
+};
+
+use crate::{ast::CrateAst, diagnosis::DiagnosisItem, rules::RuleId};
+
+use super::{utils::display_code, ItemPath};
+
+/// The traits implemented by closures, from the one which the fewest
+/// closures implement to the one which they all implement.
+const CLOSURE_TRAITS: &[&str] = &["Fn", "FnMut", "FnOnce"];
+
+/// Rewrites the closure traits written as `Fn<(A,), Output = B>` with the
+/// parenthesized notation, `Fn(A) -> B`, and removes the `-> ()` of the
+/// parenthesized notation, so that both spellings are the same.
+pub(crate) fn normalize(program: &CrateAst) -> CrateAst {
+    let mut normalized = program.clone();
+    Sugar.visit_file_mut(&mut normalized.0);
+    normalized
+}
+
+struct Sugar;
+
+impl VisitMut for Sugar {
+    fn visit_path_segment_mut(&mut self, segment: &mut PathSegment) {
+        visit_mut::visit_path_segment_mut(self, segment);
+
+        if !CLOSURE_TRAITS.iter().any(|name| segment.ident == name) {
+            return;
+        }
+
+        if let PathArguments::AngleBracketed(args) = &segment.arguments {
+            let (inputs, output) = match args.args.iter().collect::<Vec<_>>().as_slice() {
+                [GenericArgument::Type(Type::Tuple(inputs))] => (inputs, None),
+                [GenericArgument::Type(Type::Tuple(inputs)), GenericArgument::Binding(output)]
+                    if output.ident == "Output" =>
+                {
+                    (inputs, Some(&output.ty))
+                }
+                _ => return,
+            };
+
+            segment.arguments = PathArguments::Parenthesized(ParenthesizedGenericArguments {
+                paren_token: inputs.paren_token,
+                inputs: inputs.elems.iter().cloned().collect(),
+                output: match output {
+                    Some(ty) => ReturnType::Type(Default::default(), Box::new(ty.clone())),
+                    None => ReturnType::Default,
+                },
+            });
+        }
+
+        if let PathArguments::Parenthesized(args) = &mut segment.arguments {
+            if matches!(&args.output, ReturnType::Type(_, ty) if is_unit(ty)) {
+                args.output = ReturnType::Default;
+            }
+        }
+    }
+}
+
+fn is_unit(ty: &Type) -> bool {
+    matches!(ty, Type::Tuple(tuple) if tuple.elems.is_empty())
+}
+
+/// Reports the closure trait bounds of the generic parameters and of the
+/// `impl Trait` arguments of a function which were swapped for another
+/// closure trait, when nothing else changed in its signature.
+///
+/// Requiring `Fn` instead of `FnMut` rejects the closures which mutate their
+/// environment, while requiring `FnMut` instead of `Fn` accepts more closures.
+pub(crate) fn signature_diagnosis(
+    previous: &Signature,
+    current: &Signature,
+    path: &ItemPath,
+) -> Option<DiagnosisItem> {
+    let (previous_bounds, previous) = strip(previous);
+    let (current_bounds, current) = strip(current);
+
+    if previous != current || previous_bounds == current_bounds {
+        return None;
+    }
+
+    let changes = previous_bounds
+        .iter()
+        .zip(&current_bounds)
+        .filter(|((a, _), (b, _))| a != b)
+        .collect::<Vec<_>>();
+
+    let rule = if changes.iter().any(|((a, _), (b, _))| b < a) {
+        RuleId::ClosureBoundRestricted
+    } else {
+        RuleId::ClosureBoundRelaxed
+    };
+
+    let message = changes
+        .iter()
+        .map(|((_, a), (_, b))| format!("`{}` is now `{}`", a, b))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Some(DiagnosisItem::new(rule, path.clone(), None).with_message(message))
+}
+
+// Replaces the closure traits used as bounds by the generics and the
+// arguments with `Fn`, and returns the position of each one in
+// `CLOSURE_TRAITS`, along with its rendering.
+fn strip(sig: &Signature) -> (Vec<(usize, String)>, Signature) {
+    let mut sig = sig.clone();
+    let mut bounds = ClosureBounds(Vec::new());
+
+    bounds.visit_generics_mut(&mut sig.generics);
+    sig.inputs
+        .iter_mut()
+        .for_each(|input| bounds.visit_fn_arg_mut(input));
+
+    (bounds.0, sig)
+}
+
+struct ClosureBounds(Vec<(usize, String)>);
+
+impl VisitMut for ClosureBounds {
+    fn visit_trait_bound_mut(&mut self, bound: &mut TraitBound) {
+        let rendered = display_code(&*bound);
+
+        visit_mut::visit_trait_bound_mut(self, bound);
+
+        let last = match bound.path.segments.last_mut() {
+            Some(last) => last,
+            None => return,
+        };
+
+        if let Some(idx) = CLOSURE_TRAITS.iter().position(|name| last.ident == name) {
+            self.0.push((idx, rendered));
+            last.ident = Ident::new(CLOSURE_TRAITS[0], last.ident.span());
+        }
+    }
+
+    // Trait objects are types on their own, a caller can't pass a
+    // `Box<dyn FnMut()>` where a `Box<dyn Fn()>` is expected.
+    fn visit_type_trait_object_mut(&mut self, _: &mut TypeTraitObject) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::comparator::ApiComparator;
+
+    #[test]
+    fn desugared_closure_traits() {
+        let comparator: ApiComparator = parse_quote! {
+            {
+                pub fn f<F: Fn<(u8,), Output = u16>>(f: F) {}
+                pub fn g(f: impl FnMut<(u8, u16)>) {}
+            },
+            {
+                pub fn f<F: Fn(u8) -> u16>(f: F) {}
+                pub fn g(f: impl FnMut(u8, u16) -> ()) {}
+            },
+        };
+
+        assert!(comparator.run().is_empty());
+    }
+}
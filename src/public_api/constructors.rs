@@ -0,0 +1,184 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use syn::{GenericParam, Generics, Signature, WherePredicate};
+
+use crate::{diagnosis::DiagnosisItem, rules::RuleId};
+
+use super::{utils::display_code, ItemPath};
+
+/// Reports the bounds added to the generic parameters of a constructor, such
+/// as `new` or `from_path`, when nothing else changed in its signature. The
+/// code building the type with its own types may no longer compile.
+pub(crate) fn signature_diagnosis(
+    previous: &Signature,
+    current: &Signature,
+    path: &ItemPath,
+) -> Option<DiagnosisItem> {
+    method_diagnosis(
+        previous,
+        current,
+        &Generics::default(),
+        &Generics::default(),
+        path,
+    )
+}
+
+/// Same as `signature_diagnosis`, for a method whose impl block has generics
+/// of its own, which may have gained bounds too.
+pub(crate) fn method_diagnosis(
+    previous: &Signature,
+    current: &Signature,
+    previous_impl: &Generics,
+    current_impl: &Generics,
+    path: &ItemPath,
+) -> Option<DiagnosisItem> {
+    if !is_constructor(current) {
+        return None;
+    }
+
+    let (previous_sig, previous_bounds) = strip_signature(previous, previous_impl);
+    let (current_sig, current_bounds) = strip_signature(current, current_impl);
+
+    if previous_sig != current_sig {
+        return None;
+    }
+
+    let added = current_bounds
+        .iter()
+        .filter_map(|(bounded, bounds)| {
+            let previous = previous_bounds.get(bounded);
+            let added = bounds
+                .iter()
+                .filter(|bound| !previous.is_some_and(|previous| previous.contains(*bound)))
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+
+            (!added.is_empty())
+                .then(|| format!("`{}` now requires `{}`", bounded, added.join(" + ")))
+        })
+        .collect::<Vec<_>>();
+
+    if added.is_empty() {
+        return None;
+    }
+
+    Some(
+        DiagnosisItem::new(RuleId::ConstructionRequirementsChanged, path.clone(), None)
+            .with_message(added.join(", ")),
+    )
+}
+
+fn is_constructor(sig: &Signature) -> bool {
+    let name = sig.ident.to_string();
+    name == "new" || name.starts_with("from_")
+}
+
+// Splits the generics of a signature and of its impl block into generics
+// without trait bounds and the bounds of each parameter, wherever they are
+// written.
+fn strip_signature(
+    sig: &Signature,
+    impl_generics: &Generics,
+) -> ((Signature, Generics), BTreeMap<String, BTreeSet<String>>) {
+    let mut bounds = BTreeMap::new();
+
+    let mut sig = sig.clone();
+    sig.generics = strip(&sig.generics, &mut bounds);
+    let impl_generics = strip(impl_generics, &mut bounds);
+
+    ((sig, impl_generics), bounds)
+}
+
+fn strip(generics: &Generics, bounds: &mut BTreeMap<String, BTreeSet<String>>) -> Generics {
+    let mut generics = generics.clone();
+
+    for param in generics.params.iter_mut() {
+        if let GenericParam::Type(ty) = param {
+            bounds
+                .entry(ty.ident.to_string())
+                .or_default()
+                .extend(ty.bounds.iter().map(display_code));
+
+            ty.bounds.clear();
+            ty.colon_token = None;
+        }
+    }
+
+    if let Some(where_clause) = generics.where_clause.take() {
+        for predicate in where_clause.predicates {
+            match predicate {
+                WherePredicate::Type(ty) => bounds
+                    .entry(display_code(&ty.bounded_ty))
+                    .or_default()
+                    .extend(ty.bounds.iter().map(display_code)),
+                // Lifetime bounds are compared as any other change.
+                predicate => generics.make_where_clause().predicates.push(predicate),
+            }
+        }
+    }
+
+    generics
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn changes(previous: Signature, current: Signature) -> Option<String> {
+        let path = ItemPath::new(Vec::new(), previous.ident.clone());
+
+        signature_diagnosis(&previous, &current, &path)
+            .map(|diag| diag.message().unwrap().to_owned())
+    }
+
+    #[test]
+    fn added_bounds() {
+        assert_eq!(
+            changes(
+                parse_quote! { fn new<T: Read>(reader: T) -> Self },
+                parse_quote! { fn new<T>(reader: T) -> Self where T: Read + Send + 'static },
+            ),
+            Some("`T` now requires `'static + Send`".to_owned())
+        );
+
+        assert_eq!(
+            changes(
+                parse_quote! { fn from_parts<K, V>(k: K, v: V) -> Self },
+                parse_quote! { fn from_parts<K: Hash, V>(k: K, v: V) -> Self where Vec<V>: Debug },
+            ),
+            Some("`K` now requires `Hash`, `Vec<V>` now requires `Debug`".to_owned())
+        );
+    }
+
+    #[test]
+    fn other_changes_are_not_construction_requirements() {
+        // Not a constructor.
+        assert_eq!(
+            changes(
+                parse_quote! { fn build<T>(t: T) },
+                parse_quote! { fn build<T: Clone>(t: T) },
+            ),
+            None
+        );
+
+        // Relaxed bounds.
+        assert_eq!(
+            changes(
+                parse_quote! { fn new<T: Clone>(t: T) },
+                parse_quote! { fn new<T>(t: T) },
+            ),
+            None
+        );
+
+        // Other changes of the signature.
+        assert_eq!(
+            changes(
+                parse_quote! { fn new<T>(t: T) },
+                parse_quote! { fn new<T: Clone>(t: T, u: u8) },
+            ),
+            None
+        );
+    }
+}
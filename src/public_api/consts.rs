@@ -0,0 +1,258 @@
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use quote::ToTokens;
+use syn::{Attribute, BinOp, Expr, Ident, ImplItemConst, Lit, TraitItemConst, Type, UnOp};
+
+use super::utils::display_code;
+
+/// An associated constant, of a trait definition or of a trait
+/// implementation.
+///
+/// Its value is evaluated when it is a simple expression, so that writing it
+/// differently, such as `1 << 4` instead of `16`, is not a change.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct AssocConstMetadata {
+    attrs: Vec<Attribute>,
+    ident: Ident,
+    ty: Type,
+    // The constants of trait definitions may have no default value.
+    value: Option<ConstValue>,
+}
+
+impl AssocConstMetadata {
+    pub(crate) fn name(&self) -> &Ident {
+        &self.ident
+    }
+
+    /// The constant a trait implementation gets when it does not specify
+    /// this one, if it has a default value. The attributes, such as the
+    /// documentation, stay with the trait.
+    pub(crate) fn provided(&self) -> Option<AssocConstMetadata> {
+        self.value.as_ref()?;
+
+        Some(AssocConstMetadata {
+            attrs: Vec::new(),
+            ..self.clone()
+        })
+    }
+
+    /// Renders the constant as pseudo-Rust, with its evaluated value.
+    pub(crate) fn render(&self) -> String {
+        let ty = display_code(&self.ty);
+
+        match &self.value {
+            Some(value) => format!("const {}: {} = {};", self.ident, ty, value),
+            None => format!("const {}: {};", self.ident, ty),
+        }
+    }
+
+    /// Returns whether a default value was added (`true`) or removed
+    /// (`false`), when it is the only thing which changed.
+    pub(crate) fn default_change(&self, other: &AssocConstMetadata) -> Option<bool> {
+        let same_declaration =
+            self.attrs == other.attrs && self.ident == other.ident && self.ty == other.ty;
+
+        match (&self.value, &other.value) {
+            (None, Some(_)) if same_declaration => Some(true),
+            (Some(_), None) if same_declaration => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Returns the previous and the current value of the constant, when its
+    /// value is the only thing which changed.
+    pub(crate) fn value_change<'a>(
+        &'a self,
+        other: &'a AssocConstMetadata,
+    ) -> Option<(&'a ConstValue, &'a ConstValue)> {
+        if self.attrs != other.attrs || self.ident != other.ident || self.ty != other.ty {
+            return None;
+        }
+
+        match (&self.value, &other.value) {
+            (Some(previous), Some(current)) if previous != current => Some((previous, current)),
+            _ => None,
+        }
+    }
+}
+
+impl From<&TraitItemConst> for AssocConstMetadata {
+    fn from(c: &TraitItemConst) -> AssocConstMetadata {
+        AssocConstMetadata {
+            attrs: c.attrs.clone(),
+            ident: c.ident.clone(),
+            ty: c.ty.clone(),
+            value: c.default.as_ref().map(|(_, expr)| ConstValue::new(expr)),
+        }
+    }
+}
+
+impl From<&ImplItemConst> for AssocConstMetadata {
+    fn from(c: &ImplItemConst) -> AssocConstMetadata {
+        AssocConstMetadata {
+            attrs: c.attrs.clone(),
+            ident: c.ident.clone(),
+            ty: c.ty.clone(),
+            value: Some(ConstValue::new(&c.expr)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ConstValue {
+    Int(i128),
+    Bool(bool),
+    Char(char),
+    Str(String),
+    // An expression which can't be evaluated, such as a call to a `const fn`.
+    // It is compared by its tokens.
+    Tokens(String),
+}
+
+impl ConstValue {
+    fn new(expr: &Expr) -> ConstValue {
+        evaluate(expr).unwrap_or_else(|| ConstValue::Tokens(expr.to_token_stream().to_string()))
+    }
+}
+
+impl Display for ConstValue {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            ConstValue::Int(i) => write!(f, "{}", i),
+            ConstValue::Bool(b) => write!(f, "{}", b),
+            ConstValue::Char(c) => write!(f, "{:?}", c),
+            ConstValue::Str(s) => write!(f, "{:?}", s),
+            ConstValue::Tokens(e) => f.write_str(e),
+        }
+    }
+}
+
+/// Evaluates literals, and the arithmetic, bitwise, logic and comparison
+/// operators applied to them. The type of integers is not known, so
+/// operations whose result depends on it, such as `!0`, are not evaluated.
+fn evaluate(expr: &Expr) -> Option<ConstValue> {
+    use ConstValue::*;
+
+    match expr {
+        Expr::Lit(lit) => match &lit.lit {
+            Lit::Int(i) => i.base10_parse().ok().map(Int),
+            Lit::Byte(b) => Some(Int(b.value().into())),
+            Lit::Bool(b) => Some(Bool(b.value)),
+            Lit::Char(c) => Some(Char(c.value())),
+            Lit::Str(s) => Some(Str(s.value())),
+            _ => None,
+        },
+
+        Expr::Paren(paren) => evaluate(&paren.expr),
+        Expr::Group(group) => evaluate(&group.expr),
+
+        Expr::Unary(unary) => match (unary.op, evaluate(&unary.expr)?) {
+            (UnOp::Neg(_), Int(i)) => i.checked_neg().map(Int),
+            (UnOp::Not(_), Bool(b)) => Some(Bool(!b)),
+            _ => None,
+        },
+
+        Expr::Binary(binary) => {
+            let (left, right) = (evaluate(&binary.left)?, evaluate(&binary.right)?);
+
+            match (left, right) {
+                (Int(a), Int(b)) => int_operation(binary.op, a, b),
+                (Bool(a), Bool(b)) => bool_operation(binary.op, a, b),
+                _ => None,
+            }
+        }
+
+        _ => None,
+    }
+}
+
+fn int_operation(op: BinOp, a: i128, b: i128) -> Option<ConstValue> {
+    use ConstValue::*;
+
+    let shift = || u32::try_from(b).ok();
+
+    match op {
+        BinOp::Add(_) => a.checked_add(b).map(Int),
+        BinOp::Sub(_) => a.checked_sub(b).map(Int),
+        BinOp::Mul(_) => a.checked_mul(b).map(Int),
+        BinOp::Div(_) => a.checked_div(b).map(Int),
+        BinOp::Rem(_) => a.checked_rem(b).map(Int),
+        BinOp::BitAnd(_) => Some(Int(a & b)),
+        BinOp::BitOr(_) => Some(Int(a | b)),
+        BinOp::BitXor(_) => Some(Int(a ^ b)),
+        BinOp::Shl(_) => a.checked_shl(shift()?).map(Int),
+        BinOp::Shr(_) => a.checked_shr(shift()?).map(Int),
+        BinOp::Eq(_) => Some(Bool(a == b)),
+        BinOp::Ne(_) => Some(Bool(a != b)),
+        BinOp::Lt(_) => Some(Bool(a < b)),
+        BinOp::Le(_) => Some(Bool(a <= b)),
+        BinOp::Gt(_) => Some(Bool(a > b)),
+        BinOp::Ge(_) => Some(Bool(a >= b)),
+        _ => None,
+    }
+}
+
+fn bool_operation(op: BinOp, a: bool, b: bool) -> Option<ConstValue> {
+    let value = match op {
+        BinOp::And(_) | BinOp::BitAnd(_) => a && b,
+        BinOp::Or(_) | BinOp::BitOr(_) => a || b,
+        BinOp::BitXor(_) | BinOp::Ne(_) => a != b,
+        BinOp::Eq(_) => a == b,
+        _ => return None,
+    };
+
+    Some(ConstValue::Bool(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn value(expr: Expr) -> String {
+        ConstValue::new(&expr).to_string()
+    }
+
+    #[test]
+    fn simple_expressions_are_evaluated() {
+        assert_eq!(value(parse_quote! { 1 << 4 }), "16");
+        assert_eq!(value(parse_quote! { (2 + 3) * 4 - 0x1 }), "19");
+        assert_eq!(value(parse_quote! { -(5 % 3) }), "-2");
+        assert_eq!(value(parse_quote! { 1_000u32 }), "1000");
+        assert_eq!(value(parse_quote! { b'a' }), "97");
+        assert_eq!(value(parse_quote! { 3 > 2 && !false }), "true");
+        assert_eq!(value(parse_quote! { "a" }), "\"a\"");
+    }
+
+    #[test]
+    fn other_expressions_are_compared_by_tokens() {
+        assert_eq!(value(parse_quote! { !0 }), "! 0");
+        assert_eq!(value(parse_quote! { 1 / 0 }), "1 / 0");
+        assert_eq!(value(parse_quote! { u8::MAX }), "u8 :: MAX");
+    }
+
+    fn assoc_const(c: ImplItemConst) -> AssocConstMetadata {
+        AssocConstMetadata::from(&c)
+    }
+
+    #[test]
+    fn value_changes() {
+        let a = assoc_const(parse_quote! { const A: u8 = 1 << 4; });
+        let b = assoc_const(parse_quote! { const A: u8 = 16; });
+        let c = assoc_const(parse_quote! { const A: u8 = 17; });
+        let d = assoc_const(parse_quote! { const A: u16 = 17; });
+
+        let describe = |(a, b): (&ConstValue, &ConstValue)| format!("{} -> {}", a, b);
+
+        assert_eq!(a, b);
+        assert_eq!(
+            a.value_change(&c).map(describe).as_deref(),
+            Some("16 -> 17")
+        );
+        assert_eq!(a.value_change(&d), None);
+    }
+}
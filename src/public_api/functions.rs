@@ -1,31 +1,42 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use syn::{
     visit::{self, Visit},
-    Ident, ItemFn, ItemMod, Signature, Visibility,
+    Attribute, Ident, ItemFn, ItemMod, Signature, Visibility,
 };
 
 #[cfg(test)]
 use syn::parse::{Error as ParseError, Parse, ParseStream, Result as ParseResult};
 
-use crate::diagnosis::DiagnosticGenerator;
+use crate::diagnosis::{DiagnosisCollector, DiagnosisItem, DiagnosticGenerator};
 
-use super::{ItemKind, ItemPath};
+use super::{
+    attributes::ItemAttributes, closures, constructors, impl_trait, sized, trait_objects, ItemKind,
+    ItemPath,
+};
 
-#[derive(Clone, Debug, PartialEq)]
-pub(crate) struct FnVisitor {
-    items: HashMap<ItemPath, ItemKind>,
+#[derive(Debug, PartialEq)]
+pub(crate) struct FnVisitor<'a> {
+    items: BTreeMap<ItemPath, ItemKind>,
     path: Vec<Ident>,
+    unanalyzable: &'a mut BTreeMap<ItemPath, String>,
 }
 
-impl FnVisitor {
-    pub(crate) fn new(items: HashMap<ItemPath, ItemKind>) -> FnVisitor {
+impl<'a> FnVisitor<'a> {
+    pub(crate) fn new(
+        items: BTreeMap<ItemPath, ItemKind>,
+        unanalyzable: &'a mut BTreeMap<ItemPath, String>,
+    ) -> FnVisitor<'a> {
         let path = Vec::new();
 
-        FnVisitor { items, path }
+        FnVisitor {
+            items,
+            path,
+            unanalyzable,
+        }
     }
 
-    pub(crate) fn items(self) -> HashMap<ItemPath, ItemKind> {
+    pub(crate) fn items(self) -> BTreeMap<ItemPath, ItemKind> {
         self.items
     }
 
@@ -38,13 +49,11 @@ impl FnVisitor {
     }
 
     fn add_fn(&mut self, path: ItemPath, fn_: FnPrototype) {
-        let tmp = self.items.insert(path, fn_.into());
-
-        assert!(tmp.is_none(), "Duplicate item definition");
+        super::add_item(&mut self.items, self.unanalyzable, path, fn_.into());
     }
 }
 
-impl<'ast> Visit<'ast> for FnVisitor {
+impl<'a, 'ast> Visit<'ast> for FnVisitor<'a> {
     fn visit_item_mod(&mut self, mod_: &'ast ItemMod) {
         self.add_path_segment(mod_.ident.clone());
         visit::visit_item_mod(self, mod_);
@@ -57,7 +66,7 @@ impl<'ast> Visit<'ast> for FnVisitor {
         }
 
         let path = ItemPath::new(self.path.clone(), fn_.sig.ident.clone());
-        let fn_ = FnPrototype::new(fn_.sig.clone());
+        let fn_ = FnPrototype::new(fn_.sig.clone(), &fn_.attrs);
 
         self.add_fn(path, fn_);
     }
@@ -66,22 +75,67 @@ impl<'ast> Visit<'ast> for FnVisitor {
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct FnPrototype {
     sig: Signature,
+    attrs: ItemAttributes,
 }
 
 impl FnPrototype {
-    fn new(mut sig: Signature) -> FnPrototype {
+    fn new(mut sig: Signature, attrs: &[Attribute]) -> FnPrototype {
         if let Some(last) = sig.inputs.pop() {
             sig.inputs.push(last.value().clone());
         }
-        FnPrototype { sig }
+
+        let attrs = ItemAttributes::new(attrs).with_signature(&sig);
+        FnPrototype { sig, attrs }
+    }
+
+    pub(crate) fn attributes(&self) -> &ItemAttributes {
+        &self.attrs
+    }
+
+    pub(crate) fn signature(&self) -> &Signature {
+        &self.sig
+    }
+}
+
+impl DiagnosticGenerator for FnPrototype {
+    fn modification_diagnosis(
+        &self,
+        other: &Self,
+        path: &ItemPath,
+        collector: &mut DiagnosisCollector,
+    ) {
+        if let Some(diagnosis) = generics_diagnosis(&self.sig, &other.sig, path) {
+            collector.add(diagnosis);
+        } else if self.sig != other.sig {
+            collector.add(trait_objects::modification_diagnosis(
+                &self.sig,
+                &other.sig,
+                Self::RULES.modification,
+                path,
+            ));
+        }
+
+        self.attrs.diagnosis(&other.attrs, path, collector);
     }
 }
 
-impl DiagnosticGenerator for FnPrototype {}
+/// Reports the changes of the generics of a function which have a rule of
+/// their own, when nothing else changed in its signature.
+pub(crate) fn generics_diagnosis(
+    previous: &Signature,
+    current: &Signature,
+    path: &ItemPath,
+) -> Option<DiagnosisItem> {
+    sized::signature_diagnosis(previous, current, path)
+        .or_else(|| closures::signature_diagnosis(previous, current, path))
+        .or_else(|| impl_trait::signature_diagnosis(previous, current, path))
+        .or_else(|| constructors::signature_diagnosis(previous, current, path))
+}
 
 #[cfg(test)]
 impl Parse for FnPrototype {
     fn parse(input: ParseStream) -> ParseResult<FnPrototype> {
+        let attrs = input.call(Attribute::parse_outer)?;
         let vis = input.parse()?;
 
         if !matches!(vis, Visibility::Public(_)) {
@@ -93,6 +147,7 @@ impl Parse for FnPrototype {
         }
 
         let sig = input.parse()?;
-        Ok(FnPrototype { sig })
+        let attrs = ItemAttributes::new(&attrs).with_signature(&sig);
+        Ok(FnPrototype { sig, attrs })
     }
 }
@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+
+use syn::{
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+    BoundLifetimes, Lifetime, PredicateType, TraitBound, TypeBareFn,
+};
+
+use crate::ast::CrateAst;
+
+/// Rewrites the higher-ranked lifetimes (`for<'a>`) of function pointers,
+/// where clauses and trait bounds, so that spelling them differently is not
+/// reported as a change: the lifetimes which are not used are removed, and the
+/// other ones are sorted by name.
+pub(crate) fn normalize(program: &CrateAst) -> CrateAst {
+    let mut normalized = program.clone();
+    Normalizer.visit_file_mut(&mut normalized.0);
+    normalized
+}
+
+struct Normalizer;
+
+impl VisitMut for Normalizer {
+    fn visit_type_bare_fn_mut(&mut self, f: &mut TypeBareFn) {
+        visit_mut::visit_type_bare_fn_mut(self, f);
+
+        let mut used = UsedLifetimes::default();
+        f.inputs
+            .iter()
+            .for_each(|input| used.visit_bare_fn_arg(input));
+        used.visit_return_type(&f.output);
+
+        normalize_binder(&mut f.lifetimes, &used.0);
+    }
+
+    fn visit_predicate_type_mut(&mut self, predicate: &mut PredicateType) {
+        visit_mut::visit_predicate_type_mut(self, predicate);
+
+        let mut used = UsedLifetimes::default();
+        used.visit_type(&predicate.bounded_ty);
+        predicate
+            .bounds
+            .iter()
+            .for_each(|bound| used.visit_type_param_bound(bound));
+
+        normalize_binder(&mut predicate.lifetimes, &used.0);
+    }
+
+    fn visit_trait_bound_mut(&mut self, bound: &mut TraitBound) {
+        visit_mut::visit_trait_bound_mut(self, bound);
+
+        let mut used = UsedLifetimes::default();
+        used.visit_path(&bound.path);
+
+        normalize_binder(&mut bound.lifetimes, &used.0);
+    }
+}
+
+fn normalize_binder(binder: &mut Option<BoundLifetimes>, used: &BTreeSet<String>) {
+    let bound = match binder {
+        Some(bound) => bound,
+        None => return,
+    };
+
+    let mut lifetimes = bound
+        .lifetimes
+        .iter()
+        .filter(|def| used.contains(&def.lifetime.to_string()))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if lifetimes.is_empty() {
+        *binder = None;
+        return;
+    }
+
+    lifetimes.sort_by_cached_key(|def| def.lifetime.to_string());
+    bound.lifetimes = lifetimes.into_iter().collect();
+}
+
+// The lifetimes mentioned in the scope of a binder.
+#[derive(Default)]
+struct UsedLifetimes(BTreeSet<String>);
+
+impl<'ast> Visit<'ast> for UsedLifetimes {
+    fn visit_lifetime(&mut self, lifetime: &'ast Lifetime) {
+        self.0.insert(lifetime.to_string());
+        visit::visit_lifetime(self, lifetime);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::comparator::ApiComparator;
+
+    #[test]
+    fn respelled_binders_are_not_reported() {
+        let comparator: ApiComparator = parse_quote! {
+            {
+                pub fn a(f: for<'a, 'b> fn(&'a u8, &'b u8)) {}
+                pub fn b<F>(f: F) where for<'a> F: Fn(&u8) {}
+                pub fn c<F: for<'b, 'a> Fn(&'a u8) -> &'b u8>(f: F) {}
+            },
+            {
+                pub fn a(f: for<'b, 'a> fn(&'a u8, &'b u8)) {}
+                pub fn b<F>(f: F) where F: Fn(&u8) {}
+                pub fn c<F: for<'a, 'b, 'c> Fn(&'a u8) -> &'b u8>(f: F) {}
+            },
+        };
+
+        assert!(comparator.run().is_empty());
+    }
+
+    #[test]
+    fn used_binders_are_kept() {
+        let comparator: ApiComparator = parse_quote! {
+            {
+                pub fn f<F: for<'a> Fn(&'a u8) -> &'a u8>(f: F) {}
+            },
+            {
+                pub fn f<F: Fn(&u8) -> &'static u8>(f: F) {}
+            },
+        };
+
+        assert_eq!(comparator.run().to_string(), "≠ f\n");
+    }
+}
@@ -0,0 +1,124 @@
+use std::{collections::BTreeMap, mem};
+
+use proc_macro2::Span;
+use syn::{
+    visit_mut::{self, VisitMut},
+    FnArg, GenericParam, Ident, Path, Signature, Type, TypeParam, TypePath,
+};
+
+use crate::{diagnosis::DiagnosisItem, rules::RuleId};
+
+use super::{utils::display_code, ItemPath};
+
+/// Reports the `impl Trait` arguments of a function which were replaced with
+/// an equivalent generic parameter, or the opposite, when nothing else changed
+/// in its signature.
+///
+/// Both are the same for callers, except the ones which name the generic
+/// parameters with a turbofish, as the type of an `impl Trait` argument can't
+/// be named.
+pub(crate) fn signature_diagnosis(
+    previous: &Signature,
+    current: &Signature,
+    path: &ItemPath,
+) -> Option<DiagnosisItem> {
+    let (previous_count, previous_desugared) = desugar(previous);
+    let (current_count, current_desugared) = desugar(current);
+
+    if previous_count == current_count || previous_desugared != current_desugared {
+        return None;
+    }
+
+    let message = previous
+        .inputs
+        .iter()
+        .zip(&current.inputs)
+        .filter(|(a, b)| impl_trait_count(a) != impl_trait_count(b))
+        .map(|(a, b)| format!("`{}` is now `{}`", display_code(a), display_code(b)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Some(
+        DiagnosisItem::new(RuleId::ImplTraitArgumentChanged, path.clone(), None)
+            .with_message(message),
+    )
+}
+
+// Replaces the `impl Trait` arguments with generic parameters appended to the
+// other ones, as the compiler does, then gives every type parameter a name
+// which only depends on its position. Returns the number of `impl Trait`
+// arguments.
+fn desugar(sig: &Signature) -> (usize, Signature) {
+    let mut sig = sig.clone();
+
+    let mut anonymous = AnonymousParams(Vec::new());
+    sig.inputs
+        .iter_mut()
+        .for_each(|input| anonymous.visit_fn_arg_mut(input));
+
+    let count = anonymous.0.len();
+    sig.generics
+        .params
+        .extend(anonymous.0.into_iter().map(GenericParam::Type));
+
+    let names = sig
+        .generics
+        .params
+        .iter_mut()
+        .filter_map(|param| match param {
+            GenericParam::Type(ty) => Some(&mut ty.ident),
+            _ => None,
+        })
+        .enumerate()
+        .map(|(idx, ident)| {
+            let positional = Ident::new(&format!("__T{}", idx), ident.span());
+            (mem::replace(ident, positional.clone()), positional)
+        })
+        .collect();
+
+    Rename(names).visit_signature_mut(&mut sig);
+
+    (count, sig)
+}
+
+fn impl_trait_count(arg: &FnArg) -> usize {
+    let mut anonymous = AnonymousParams(Vec::new());
+    anonymous.visit_fn_arg_mut(&mut arg.clone());
+    anonymous.0.len()
+}
+
+struct AnonymousParams(Vec<TypeParam>);
+
+impl VisitMut for AnonymousParams {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        let bounds = match ty {
+            Type::ImplTrait(impl_trait) => impl_trait.bounds.clone(),
+            _ => return visit_mut::visit_type_mut(self, ty),
+        };
+
+        let ident = Ident::new(&format!("__Impl{}", self.0.len()), Span::call_site());
+        *ty = Type::Path(TypePath {
+            qself: None,
+            path: ident.clone().into(),
+        });
+
+        let mut param = TypeParam::from(ident);
+        param.colon_token = Some(Default::default());
+        param.bounds = bounds;
+        self.0.push(param);
+    }
+}
+
+struct Rename(BTreeMap<Ident, Ident>);
+
+impl VisitMut for Rename {
+    fn visit_path_mut(&mut self, path: &mut Path) {
+        if path.leading_colon.is_none() {
+            if let Some(name) = self.0.get(&path.segments[0].ident) {
+                path.segments[0].ident = name.clone();
+            }
+        }
+
+        visit_mut::visit_path_mut(self, path);
+    }
+}
@@ -4,7 +4,6 @@ use std::{
 };
 
 use syn::{
-    parse_quote,
     visit::{self, Visit},
     Ident, ItemEnum, ItemFn, ItemMod, ItemStruct, ItemUse, Path, UseTree, Visibility,
 };
@@ -37,6 +36,15 @@ impl PathResolver {
     }
 
     pub(crate) fn resolve(&self, current_path: &[Ident], item_path: &Path) -> Option<&[Ident]> {
+        let full_path = self.absolute_path(current_path, item_path);
+
+        self.items.get(full_path.as_slice()).map(Vec::as_slice)
+    }
+
+    /// Returns the path, relative to the crate root, that `item_path` refers
+    /// to when it is written in the module at `current_path`. The item may
+    /// not exist, or not be public.
+    pub(crate) fn absolute_path(&self, current_path: &[Ident], item_path: &Path) -> Vec<Ident> {
         let mut item_idents = item_path
             .segments
             .iter()
@@ -56,13 +64,56 @@ impl PathResolver {
 
         for item in full_path_iter {
             if item == "super" {
-                full_path.pop().unwrap();
-            } else {
+                full_path.pop();
+            } else if item != "self" {
                 full_path.push(item.clone());
             }
         }
 
-        self.items.get(full_path.as_slice()).map(Vec::as_slice)
+        full_path
+    }
+
+    /// Returns the other paths the item defined at `path` can be named with,
+    /// through the `pub use` of modules which are reachable from outside of
+    /// the crate.
+    pub(crate) fn reexports(&self, path: &[Ident]) -> Vec<Vec<Ident>> {
+        let mut reexports = self
+            .uses
+            .iter()
+            .filter(|(module, _)| module.is_empty() || self.items.contains(*module))
+            .flat_map(|(module, imports)| {
+                imports
+                    .iter()
+                    .filter(|(_, vis)| *vis == UseVisibility::Pub)
+                    .filter_map(move |(import, _)| {
+                        // `self::` imports are relative to the module, other
+                        // ones are stored relative to the crate root.
+                        let imported = match import.path().split_first() {
+                            Some((first, rest)) if first == "self" => {
+                                module.iter().chain(rest).cloned().collect()
+                            }
+                            _ => import.path().to_owned(),
+                        };
+
+                        let inner = path.strip_prefix(imported.as_slice())?;
+
+                        Some(
+                            module
+                                .iter()
+                                .chain(iter::once(import.name()))
+                                .chain(inner)
+                                .cloned()
+                                .collect::<Vec<_>>(),
+                        )
+                    })
+            })
+            .filter(|reexport| reexport != path)
+            .collect::<Vec<_>>();
+
+        reexports.sort();
+        reexports.dedup();
+
+        reexports
     }
 
     // Note: item_path is taken by mutable reference because it is expected to
@@ -107,6 +158,9 @@ struct ExportedItemsVisitor<'a> {
     items: &'a mut HashSet<Vec<Ident>>,
     uses: &'a mut HashMap<Vec<Ident>, Vec<(Import, UseVisibility)>>,
     path: Vec<Ident>,
+    // Private modules are visited too, as impl blocks they contain may refer
+    // to public types through their imports. Their items are not exported.
+    in_private_module: bool,
 }
 
 impl<'a> ExportedItemsVisitor<'a> {
@@ -115,6 +169,7 @@ impl<'a> ExportedItemsVisitor<'a> {
             items: &mut resolver.items,
             uses: &mut resolver.uses,
             path: Vec::new(),
+            in_private_module: false,
         }
     }
 
@@ -130,6 +185,13 @@ impl<'a> ExportedItemsVisitor<'a> {
         self.path.clone().tap_mut(|p| p.push(item_ident))
     }
 
+    fn add_item(&mut self, vis: &Visibility, item_ident: Ident) {
+        if !self.in_private_module && matches!(vis, Visibility::Public(_)) {
+            let item_path = self.create_full_path(item_ident);
+            self.items.insert(item_path);
+        }
+    }
+
     fn add_import(&mut self, path: Vec<Ident>, import: Import, vis: UseVisibility) {
         let uses_at_path = self.uses.entry(path).or_default();
 
@@ -137,53 +199,43 @@ impl<'a> ExportedItemsVisitor<'a> {
     }
 }
 
-impl<'a, 'ast> Visit<'ast> for ExportedItemsVisitor<'ast> {
+impl<'ast> Visit<'ast> for ExportedItemsVisitor<'ast> {
     fn visit_item_mod(&mut self, i: &'ast ItemMod) {
-        if !matches!(i.vis, Visibility::Public(_)) {
-            return;
-        }
+        self.add_item(&i.vis, i.ident.clone());
 
-        let module_path = self.create_full_path(i.ident.clone());
-        self.items.insert(module_path);
+        let in_private_module = self.in_private_module;
+        self.in_private_module |= !matches!(i.vis, Visibility::Public(_));
 
         self.add_path_segment(i.ident.clone());
         visit::visit_item_mod(self, i);
         self.remove_path_segment();
+
+        self.in_private_module = in_private_module;
     }
 
     fn visit_item_fn(&mut self, i: &'ast ItemFn) {
-        if !matches!(i.vis, Visibility::Public(_)) {
-            return;
-        }
-
-        let fn_path = self.create_full_path(i.sig.ident.clone());
-        self.items.insert(fn_path);
+        self.add_item(&i.vis, i.sig.ident.clone());
     }
 
     fn visit_item_struct(&mut self, i: &'ast ItemStruct) {
-        if !matches!(i.vis, Visibility::Public(_)) {
-            return;
-        }
-
-        let struct_path = self.create_full_path(i.ident.clone());
-        self.items.insert(struct_path);
+        self.add_item(&i.vis, i.ident.clone());
     }
 
     fn visit_item_enum(&mut self, i: &'ast ItemEnum) {
-        if !matches!(i.vis, Visibility::Public(_)) {
-            return;
-        }
-
-        let enum_path = self.create_full_path(i.ident.clone());
-        self.items.insert(enum_path);
+        self.add_item(&i.vis, i.ident.clone());
     }
 
+    // `#[doc(inline)]` and `#[doc(no_inline)]` only change whether rustdoc
+    // documents the item at the re-export or links to it, so the attributes
+    // of `use` declarations are not looked at: toggling them changes no path.
     fn visit_item_use(&mut self, i: &'ast ItemUse) {
         let vis = match &i.vis {
             Visibility::Inherited => UseVisibility::Private,
             Visibility::Crate(_) => UseVisibility::PubCrate,
             Visibility::Public(_) => UseVisibility::Pub,
-            _ => todo!(),
+            // `pub(super)` and `pub(in path)` are never visible from outside of
+            // the crate.
+            Visibility::Restricted(_) => UseVisibility::PubCrate,
         };
 
         for imported_item in flatten_use_tree(&i.tree) {
@@ -197,6 +249,11 @@ fn flatten_use_tree(tree: &UseTree) -> Vec<Import> {
         match tree {
             UseTree::Path(p) if p.ident == "super" => flatten_use_tree_inner(&p.tree, current),
 
+            // Imports are stored relative to the crate root.
+            UseTree::Path(p) if p.ident == "crate" && current.is_empty() => {
+                flatten_use_tree_inner(&p.tree, current)
+            }
+
             UseTree::Path(p) => {
                 let current = current
                     .iter()
@@ -234,11 +291,8 @@ fn flatten_use_tree(tree: &UseTree) -> Vec<Import> {
                 vec![Import::renamed(path, name)]
             }
 
-            _ if current.starts_with(&[parse_quote! { std }, parse_quote! { prelude }]) => {
-                Vec::new()
-            }
-
-            _ => todo!(),
+            // Glob imports are not resolved.
+            UseTree::Glob(_) => Vec::new(),
         }
     }
 
@@ -524,7 +578,7 @@ mod tests {
     }
 
     // Yes I do want to name my variable foo or baz.
-    #[allow(clippy::blacklisted_name)]
+    #[allow(clippy::disallowed_names)]
     #[test]
     fn handles_super_anywhere_in_path() {
         let resolver: PathResolver = parse_quote! {
@@ -546,4 +600,85 @@ mod tests {
 
         assert_eq!(left, right);
     }
+
+    #[test]
+    fn handles_crate_on_import() {
+        let resolver: PathResolver = parse_quote! {
+            use crate::foo::Bar;
+
+            pub mod foo {
+                pub struct Bar;
+            }
+        };
+
+        let tmp = [parse_quote! { foo }, parse_quote! { Bar }];
+
+        let left = resolver.resolve(&[], &parse_quote! { Bar });
+        let right = Some(&tmp as _);
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn resolves_imports_of_private_modules() {
+        let resolver: PathResolver = parse_quote! {
+            pub mod foo {
+                pub struct Bar;
+            }
+
+            mod baz {
+                use crate::foo::Bar;
+            }
+        };
+
+        let tmp = [parse_quote! { foo }, parse_quote! { Bar }];
+
+        let left = resolver.resolve(&[parse_quote! { baz }], &parse_quote! { Bar });
+        let right = Some(&tmp as _);
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn does_not_export_items_of_private_modules() {
+        let resolver: PathResolver = parse_quote! {
+            mod foo {
+                pub struct Bar;
+            }
+        };
+
+        assert_eq!(resolver.resolve(&[], &parse_quote! { foo::Bar }), None);
+    }
+
+    #[test]
+    fn finds_reexports() {
+        let resolver: PathResolver = parse_quote! {
+            pub mod foo {
+                pub struct Bar;
+            }
+
+            pub mod prelude {
+                pub use crate::foo::Bar;
+            }
+
+            pub use foo::Bar;
+            pub use foo as renamed;
+            use foo::Bar as PrivateBar;
+
+            mod private {
+                pub use crate::foo::Bar;
+            }
+        };
+
+        let path = [parse_quote! { foo }, parse_quote! { Bar }];
+
+        let left = resolver.reexports(&path);
+        let right: Vec<Vec<Ident>> = vec![
+            vec![parse_quote! { Bar }],
+            vec![parse_quote! { prelude }, parse_quote! { Bar }],
+            vec![parse_quote! { renamed }, parse_quote! { Bar }],
+        ];
+
+        assert_eq!(left, right);
+    }
 }
@@ -0,0 +1,456 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use syn::{
+    visit::{self, Visit},
+    Attribute, Ident, ItemMacro, ItemMod, Meta, NestedMeta,
+};
+
+use crate::{
+    diagnosis::{DiagnosisCollector, DiagnosisItem, DiagnosticGenerator},
+    rules::RuleId,
+};
+
+use super::{
+    imports::PathResolver,
+    utils::{module_path, render_block},
+    ItemKind, ItemPath,
+};
+
+/// Collects the exported macros of a crate, except the helper ones, which are
+/// collected by `MacroHelperVisitor`. A helper macro which is re-exported with
+/// `pub use` is public under its new path.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MacroVisitor<'a> {
+    items: BTreeMap<ItemPath, ItemKind>,
+    path: Vec<Ident>,
+    resolver: &'a PathResolver,
+}
+
+impl<'a> MacroVisitor<'a> {
+    pub(crate) fn new(
+        items: BTreeMap<ItemPath, ItemKind>,
+        resolver: &'a PathResolver,
+    ) -> MacroVisitor<'a> {
+        let path = Vec::new();
+
+        MacroVisitor {
+            items,
+            path,
+            resolver,
+        }
+    }
+
+    pub(crate) fn items(self) -> BTreeMap<ItemPath, ItemKind> {
+        self.items
+    }
+}
+
+impl<'a, 'ast> Visit<'ast> for MacroVisitor<'a> {
+    fn visit_item_mod(&mut self, mod_: &'ast ItemMod) {
+        self.path.push(mod_.ident.clone());
+        visit::visit_item_mod(self, mod_);
+        self.path.pop().unwrap();
+    }
+
+    fn visit_item_macro(&mut self, item: &'ast ItemMacro) {
+        let path = match exported_macro(item, &self.path) {
+            Some((path, false)) => path,
+            Some((path, true)) => {
+                // `#[doc(hidden)] #[macro_export] macro_rules! __m` and
+                // `pub use crate::__m as m;` in a module, so that the macro
+                // is documented in that module.
+                let mut reexports = self.resolver.reexports(path.segments());
+                reexports.sort_by_cached_key(|path| (path.len(), module_path(path)));
+
+                match reexports.into_iter().next() {
+                    Some(reexport) => ItemPath::from_segments(reexport),
+                    None => return,
+                }
+            }
+            None => return,
+        };
+
+        // Macros live in their own namespace, so a function or a type may
+        // have the same path. The other item is kept.
+        self.items
+            .entry(path)
+            .or_insert_with(|| MacroMetadata::new(&item.attrs, item.mac.tokens.clone()).into());
+    }
+}
+
+/// Collects the helper macros of a crate: the `#[macro_export]` macros which
+/// are hidden from the documentation with `#[doc(hidden)]`, or which are
+/// defined in a `__private` module.
+///
+/// They are not part of the documented API, but the expansion of the public
+/// macros of the crate may refer to them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct MacroHelperVisitor {
+    // The value is the body of the macro, as the rules are compared.
+    helpers: BTreeMap<ItemPath, String>,
+    path: Vec<Ident>,
+}
+
+impl MacroHelperVisitor {
+    pub(crate) fn new() -> MacroHelperVisitor {
+        MacroHelperVisitor::default()
+    }
+
+    pub(crate) fn helpers(self) -> BTreeMap<ItemPath, String> {
+        self.helpers
+    }
+}
+
+impl<'ast> Visit<'ast> for MacroHelperVisitor {
+    fn visit_item_mod(&mut self, mod_: &'ast ItemMod) {
+        self.path.push(mod_.ident.clone());
+        visit::visit_item_mod(self, mod_);
+        self.path.pop().unwrap();
+    }
+
+    fn visit_item_macro(&mut self, item: &'ast ItemMacro) {
+        if let Some((path, true)) = exported_macro(item, &self.path) {
+            self.helpers.insert(path, item.mac.tokens.to_string());
+        }
+    }
+}
+
+/// Returns the path of a `#[macro_export]` macro, and whether it is a helper
+/// macro.
+fn exported_macro(item: &ItemMacro, module: &[Ident]) -> Option<(ItemPath, bool)> {
+    let name = match &item.ident {
+        Some(name) if item.mac.path.is_ident("macro_rules") => name,
+        _ => return None,
+    };
+
+    let is_exported = item
+        .attrs
+        .iter()
+        .any(|attr| attr.path.is_ident("macro_export"));
+    if !is_exported {
+        return None;
+    }
+
+    let is_helper = item
+        .attrs
+        .iter()
+        .any(|attr| has_argument(attr, "doc", "hidden"))
+        || module.iter().any(|segment| segment == "__private");
+
+    // Exported macros are always located at the crate root.
+    let path = ItemPath::from_segments(vec![name.clone()]);
+    Some((path, is_helper))
+}
+
+/// Returns whether `attr` is `#[name(.., argument, ..)]`.
+fn has_argument(attr: &Attribute, name: &str, argument: &str) -> bool {
+    match attr.parse_meta() {
+        Ok(Meta::List(list)) if list.path.is_ident(name) => list.nested.iter().any(
+            |nested| matches!(nested, NestedMeta::Meta(Meta::Path(path)) if path.is_ident(argument)),
+        ),
+        _ => false,
+    }
+}
+
+/// The rules of an exported macro.
+///
+/// Only the matchers are compared: they decide which invocations are
+/// accepted, while the transcribers are an implementation detail.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MacroMetadata {
+    matchers: Vec<Matcher>,
+    // Set with `#[macro_export(local_inner_macros)]`: the macros invoked by
+    // the expansion are looked up in the crate, rather than at the call
+    // site.
+    local_inner_macros: bool,
+}
+
+impl MacroMetadata {
+    /// Renders the macro as pseudo-Rust, named by its full path. Only the
+    /// matchers are compared, so the transcribers are elided.
+    pub(crate) fn render(&self, path: &ItemPath) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.local_inner_macros {
+            lines.push("#[macro_export(local_inner_macros)]".to_owned());
+        }
+
+        let arms = self
+            .matchers
+            .iter()
+            .map(|matcher| format!("{} => {{ .. }};", matcher))
+            .collect();
+        lines.extend(render_block(format!("macro_rules! {}", path), arms));
+
+        lines
+    }
+
+    fn new(attrs: &[Attribute], tokens: TokenStream) -> MacroMetadata {
+        let local_inner_macros = attrs
+            .iter()
+            .any(|attr| has_argument(attr, "macro_export", "local_inner_macros"));
+        let mut tokens = tokens.into_iter();
+        let mut matchers = Vec::new();
+
+        // The rules are written as `(matcher) => { transcriber };`.
+        while let Some(tree) = tokens.next() {
+            if let TokenTree::Group(group) = tree {
+                matchers.push(Matcher::new(group.stream()));
+            }
+
+            tokens
+                .by_ref()
+                .take_while(|tree| !matches!(tree, TokenTree::Punct(p) if p.as_char() == ';'))
+                .for_each(drop);
+        }
+
+        MacroMetadata {
+            matchers,
+            local_inner_macros,
+        }
+    }
+
+    /// Returns the changes of the matchers of `self` which are missing from
+    /// `other`. A matcher whose metavariables only changed their fragment
+    /// specifier is reported as such.
+    fn matcher_changes(&self, other: &MacroMetadata) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        for (idx, matcher) in self.matchers.iter().enumerate() {
+            if other.matchers.contains(matcher) {
+                continue;
+            }
+
+            match other.matchers.get(idx).filter(|m| m.shape == matcher.shape) {
+                Some(current) => changes.extend(matcher.fragment_changes(current)),
+                None => changes.push(format!("arm `{}` removed", matcher)),
+            }
+        }
+
+        changes
+    }
+
+    fn added_matchers(&self, other: &MacroMetadata) -> Vec<String> {
+        other
+            .matchers
+            .iter()
+            .filter(|matcher| {
+                !self
+                    .matchers
+                    .iter()
+                    .any(|previous| previous.shape == matcher.shape)
+            })
+            .map(|matcher| format!("arm `{}` added", matcher))
+            .collect()
+    }
+}
+
+impl From<MacroMetadata> for ItemKind {
+    fn from(macro_: MacroMetadata) -> ItemKind {
+        ItemKind::Macro(macro_)
+    }
+}
+
+impl DiagnosticGenerator for MacroMetadata {
+    fn modification_diagnosis(
+        &self,
+        other: &Self,
+        path: &ItemPath,
+        collector: &mut DiagnosisCollector,
+    ) {
+        let mut changes = self.matcher_changes(other);
+
+        // The macros the expansion invokes must now be in scope where the
+        // macro is invoked.
+        if self.local_inner_macros && !other.local_inner_macros {
+            changes.push("no longer `local_inner_macros`".to_owned());
+        }
+
+        if !changes.is_empty() {
+            collector.add(
+                DiagnosisItem::new(Self::RULES.modification, path.clone(), None)
+                    .with_message(changes.join(", ")),
+            );
+        }
+
+        let added = self.added_matchers(other);
+        if !added.is_empty() {
+            collector.add(
+                DiagnosisItem::new(RuleId::MacroArmAdded, path.clone(), None)
+                    .with_message(added.join(", ")),
+            );
+        }
+    }
+}
+
+/// The matcher of a macro rule, without its outer delimiters.
+#[derive(Clone, Debug, PartialEq)]
+struct Matcher {
+    text: String,
+    // The matcher, with the fragment specifiers replaced by `_`.
+    shape: String,
+    // The metavariables, along with their fragment specifier.
+    fragments: Vec<(String, String)>,
+}
+
+impl Matcher {
+    fn new(tokens: TokenStream) -> Matcher {
+        let mut matcher = Matcher {
+            text: String::new(),
+            shape: String::new(),
+            fragments: Vec::new(),
+        };
+        matcher.push_tokens(tokens);
+
+        matcher
+    }
+
+    fn push_tokens(&mut self, tokens: TokenStream) {
+        let trees = tokens.into_iter().collect::<Vec<_>>();
+        let mut rest = trees.as_slice();
+        // Set after a punctuation which is part of a multi-character
+        // operator, such as the `=` of `=>`.
+        let mut joint = false;
+
+        while let [first, ..] = rest {
+            if !joint && self.needs_space_before(first) {
+                self.push_str(" ");
+            }
+            joint = matches!(first, TokenTree::Punct(p) if p.spacing() == Spacing::Joint);
+
+            rest = match rest {
+                [TokenTree::Punct(dollar), TokenTree::Ident(name), TokenTree::Punct(colon), TokenTree::Ident(specifier), rest @ ..]
+                    if dollar.as_char() == '$' && colon.as_char() == ':' =>
+                {
+                    self.text.push_str(&format!("${}:{}", name, specifier));
+                    self.shape.push_str(&format!("${}:_", name));
+                    self.fragments
+                        .push((name.to_string(), specifier.to_string()));
+                    rest
+                }
+                [TokenTree::Group(group), rest @ ..] => {
+                    self.push_group(group.delimiter(), group.stream());
+                    rest
+                }
+                [tree, rest @ ..] => {
+                    self.push_str(&tree.to_string());
+                    rest
+                }
+                [] => unreachable!(),
+            };
+        }
+    }
+
+    // Spaces are written the way matchers are usually written, such as
+    // `$($e:expr),* $(,)?`.
+    fn needs_space_before(&self, tree: &TokenTree) -> bool {
+        let previous = match self.text.chars().last() {
+            Some(previous) => previous,
+            None => return false,
+        };
+
+        if matches!(previous, '(' | '[' | '{' | '$') {
+            return false;
+        }
+
+        match tree {
+            TokenTree::Punct(p) => match p.as_char() {
+                ',' | ';' | '?' => false,
+                // Repetition operators.
+                '*' | '+' => !matches!(previous, ')' | ','),
+                _ => true,
+            },
+            _ => true,
+        }
+    }
+
+    fn push_group(&mut self, delimiter: Delimiter, tokens: TokenStream) {
+        let (open, close) = match delimiter {
+            Delimiter::Parenthesis => ("(", ")"),
+            Delimiter::Bracket => ("[", "]"),
+            Delimiter::Brace => ("{", "}"),
+            Delimiter::None => ("", ""),
+        };
+
+        self.push_str(open);
+        self.push_tokens(tokens);
+        self.push_str(close);
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.text.push_str(s);
+        self.shape.push_str(s);
+    }
+
+    fn fragment_changes(&self, current: &Matcher) -> Vec<String> {
+        self.fragments
+            .iter()
+            .zip(&current.fragments)
+            .filter(|(previous, current)| previous.1 != current.1)
+            .map(|((name, previous), (_, current))| {
+                format!("`${}` is now `{}` instead of `{}`", name, current, previous)
+            })
+            .collect()
+    }
+}
+
+impl Display for Matcher {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "({})", self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, File};
+
+    use super::*;
+
+    fn helpers(file: File) -> Vec<String> {
+        let mut visitor = MacroHelperVisitor::new();
+        visitor.visit_file(&file);
+
+        visitor.helpers().keys().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn hidden_exported_macros() {
+        let file = parse_quote! {
+            #[doc(hidden)]
+            #[macro_export]
+            macro_rules! __helper {
+                () => {};
+            }
+
+            #[macro_export]
+            macro_rules! public {
+                () => {};
+            }
+
+            #[doc(hidden)]
+            macro_rules! local {
+                () => {};
+            }
+        };
+
+        assert_eq!(helpers(file), ["__helper"]);
+    }
+
+    #[test]
+    fn macros_of_private_modules() {
+        let file = parse_quote! {
+            #[doc(hidden)]
+            pub mod __private {
+                #[macro_export]
+                macro_rules! expand {
+                    () => {};
+                }
+            }
+        };
+
+        assert_eq!(helpers(file), ["expand"]);
+    }
+}
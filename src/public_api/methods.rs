@@ -1,9 +1,9 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use syn::{
     visit::{self, Visit},
-    AngleBracketedGenericArguments, Generics, Ident, ImplItemMethod, ItemImpl, ItemMod, Signature,
-    Visibility,
+    AngleBracketedGenericArguments, Attribute, FnArg, GenericParam, Generics, Ident,
+    ImplItemMethod, ItemImpl, ItemMod, ReturnType, Signature, Type, Visibility,
 };
 
 #[cfg(test)]
@@ -12,21 +12,29 @@ use syn::{
     spanned::Spanned,
 };
 
-use crate::diagnosis::DiagnosticGenerator;
+use crate::{
+    diagnosis::{DiagnosisCollector, DiagnosisItem, DiagnosticGenerator},
+    rules::RuleId,
+};
 
-use super::{imports::PathResolver, utils, ItemKind, ItemPath};
+use super::{
+    attributes::ItemAttributes, constructors, functions, imports::PathResolver, trait_objects,
+    utils, ItemKind, ItemPath,
+};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub(crate) struct MethodVisitor<'a> {
-    items: HashMap<ItemPath, ItemKind>,
+    items: BTreeMap<ItemPath, ItemKind>,
     path: Vec<Ident>,
     resolver: &'a PathResolver,
+    unanalyzable: &'a mut BTreeMap<ItemPath, String>,
 }
 
 impl<'a> MethodVisitor<'a> {
     pub(crate) fn new(
-        types: HashMap<ItemPath, ItemKind>,
+        types: BTreeMap<ItemPath, ItemKind>,
         resolver: &'a PathResolver,
+        unanalyzable: &'a mut BTreeMap<ItemPath, String>,
     ) -> MethodVisitor<'a> {
         let items = types;
         let path = Vec::new();
@@ -35,10 +43,11 @@ impl<'a> MethodVisitor<'a> {
             items,
             path,
             resolver,
+            unanalyzable,
         }
     }
 
-    pub(crate) fn items(self) -> HashMap<ItemPath, ItemKind> {
+    pub(crate) fn items(self) -> BTreeMap<ItemPath, ItemKind> {
         self.items
     }
 
@@ -79,7 +88,9 @@ impl<'a, 'ast> Visit<'ast> for MethodVisitor<'a> {
 
         let mut impl_block_visitor = ImplBlockVisitor {
             items: &mut self.items,
+            unanalyzable: self.unanalyzable,
             path: resolved_type_path,
+            module: self.path.as_slice(),
             parent_generic_params: generic_params,
             parent_generic_args: &generic_args,
         };
@@ -89,17 +100,17 @@ impl<'a, 'ast> Visit<'ast> for MethodVisitor<'a> {
 
 #[derive(Debug, PartialEq)]
 struct ImplBlockVisitor<'a> {
-    items: &'a mut HashMap<ItemPath, ItemKind>,
+    items: &'a mut BTreeMap<ItemPath, ItemKind>,
+    unanalyzable: &'a mut BTreeMap<ItemPath, String>,
     path: &'a [Ident],
+    module: &'a [Ident],
     parent_generic_params: &'a Generics,
     parent_generic_args: &'a Option<AngleBracketedGenericArguments>,
 }
 
 impl<'a> ImplBlockVisitor<'a> {
     fn add_method(&mut self, path: ItemPath, method: MethodMetadata) {
-        let tmp = self.items.insert(path, method.into());
-
-        assert!(tmp.is_none(), "Duplicate item definition");
+        super::add_item(self.items, self.unanalyzable, path, method.into());
     }
 }
 
@@ -112,36 +123,266 @@ impl<'a, 'ast> Visit<'ast> for ImplBlockVisitor<'a> {
         let path = ItemPath::new(self.path.to_owned(), method.sig.ident.clone());
         let method = MethodMetadata::new(
             method.sig.clone(),
+            &method.attrs,
             self.parent_generic_params.clone(),
             self.parent_generic_args.clone(),
-        );
+        )
+        .defined_in(self.module);
 
         self.add_method(path, method);
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub(crate) struct MethodMetadata {
     signature: Signature,
+    attrs: ItemAttributes,
     parent_generic_params: Generics,
     parent_generic_args: Option<AngleBracketedGenericArguments>,
+    // The module containing the impl block. Moving the impl block does not
+    // change the API, so it is not compared.
+    module: Vec<Ident>,
+}
+
+impl PartialEq for MethodMetadata {
+    fn eq(&self, other: &MethodMetadata) -> bool {
+        self.signature == other.signature
+            && self.attrs == other.attrs
+            && self.parent_generic_params == other.parent_generic_params
+            && self.parent_generic_args == other.parent_generic_args
+    }
 }
 
 impl MethodMetadata {
     fn new(
         signature: Signature,
+        attrs: &[Attribute],
         parent_generic_params: Generics,
         parent_generic_args: Option<AngleBracketedGenericArguments>,
     ) -> MethodMetadata {
+        let attrs = ItemAttributes::new(attrs).with_signature(&signature);
+
         MethodMetadata {
             signature,
+            attrs,
             parent_generic_params,
             parent_generic_args,
+            module: Vec::new(),
         }
     }
+
+    fn defined_in(mut self, module: &[Ident]) -> MethodMetadata {
+        self.module = module.to_owned();
+        self
+    }
+
+    pub(crate) fn attributes(&self) -> &ItemAttributes {
+        &self.attrs
+    }
+
+    pub(crate) fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The module containing the impl block which defines the method.
+    pub(crate) fn module(&self) -> &[Ident] {
+        &self.module
+    }
+
+    /// Whether the impl block defining the method is generic over a type or
+    /// a constant.
+    pub(crate) fn is_generic(&self) -> bool {
+        self.parent_generic_params
+            .params
+            .iter()
+            .any(|param| !matches!(param, GenericParam::Lifetime(_)))
+    }
+
+    /// Methods taking `self` can be called through a `Deref` implementation,
+    /// unlike associated functions.
+    pub(crate) fn has_receiver(&self) -> bool {
+        self.signature.receiver().is_some()
+    }
 }
 
-impl DiagnosticGenerator for MethodMetadata {}
+impl DiagnosticGenerator for MethodMetadata {
+    fn removal_diagnosis(&self, path: &ItemPath, collector: &mut DiagnosisCollector) {
+        collector.add(
+            DiagnosisItem::new(Self::RULES.removal, path.clone(), None)
+                .with_details(vec![utils::defined_in(&self.module)]),
+        );
+    }
+
+    fn modification_diagnosis(
+        &self,
+        other: &Self,
+        path: &ItemPath,
+        collector: &mut DiagnosisCollector,
+    ) {
+        if self.parent_generic_params != other.parent_generic_params {
+            collector.add(
+                constructors::method_diagnosis(
+                    &self.signature,
+                    &other.signature,
+                    &self.parent_generic_params,
+                    &other.parent_generic_params,
+                    path,
+                )
+                .unwrap_or_else(|| {
+                    DiagnosisItem::new(Self::RULES.modification, path.clone(), None)
+                }),
+            );
+        } else if self.has_receiver() != other.has_receiver() {
+            collector.add(receiver_change(&self.signature, &other.signature, path));
+        } else if let Some(diagnosis) =
+            functions::generics_diagnosis(&self.signature, &other.signature, path)
+        {
+            collector.add(diagnosis);
+        } else if let Some(message) =
+            builder_chaining_change(&self.signature, &other.signature, path)
+        {
+            collector.add(
+                DiagnosisItem::new(RuleId::BuilderChainingChanged, path.clone(), None)
+                    .with_message(message),
+            );
+        } else if self.signature != other.signature {
+            collector.add(trait_objects::modification_diagnosis(
+                &self.signature,
+                &other.signature,
+                Self::RULES.modification,
+                path,
+            ));
+        } else if self.parent_generic_args != other.parent_generic_args {
+            // The method is the same, but it is available on another
+            // instantiation of the type.
+            let type_name = &path.segments()[path.segments().len() - 2];
+            let message = utils::impl_target_change(
+                type_name,
+                &self.parent_generic_args,
+                &other.parent_generic_args,
+            );
+
+            collector.add(
+                DiagnosisItem::new(Self::RULES.modification, path.clone(), None)
+                    .with_message(message),
+            );
+        }
+
+        self.attrs.diagnosis(&other.attrs, path, collector);
+    }
+
+    fn addition_diagnosis(&self, path: &ItemPath, collector: &mut DiagnosisCollector) {
+        collector.add(
+            DiagnosisItem::new(Self::RULES.addition, path.clone(), None)
+                .with_details(vec![utils::defined_in(&self.module)]),
+        );
+    }
+}
+
+// Describes an associated function becoming a method, or the opposite, with
+// the way it is called before and after.
+fn receiver_change(previous: &Signature, current: &Signature, path: &ItemPath) -> DiagnosisItem {
+    let type_name = &path.segments()[path.segments().len() - 2];
+    let calls = format!(
+        "`{}` becomes `{}`",
+        utils::call_form(type_name, previous, None),
+        utils::call_form(type_name, current, None)
+    );
+
+    let (rule, message) = if current.receiver().is_some() {
+        (
+            RuleId::MethodReceiverAdded,
+            format!("now a method: {}", calls),
+        )
+    } else {
+        (
+            RuleId::MethodReceiverRemoved,
+            format!("now an associated function: {}", calls),
+        )
+    };
+
+    DiagnosisItem::new(rule, path.clone(), None).with_message(message)
+}
+
+/// How a setter of a builder lets calls be chained.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Chaining {
+    /// `fn f(self) -> Self`, for `Builder::new().f().build()`.
+    Owned,
+    /// `fn f(&mut self) -> &mut Self`, for `builder.f().g();`.
+    MutRef,
+}
+
+// Describes the change of a setter of a `*Builder` type from one chaining
+// style to the other, when nothing else changed in its signature. Both keep
+// compiling on their own, but break the calls written for the other style.
+fn builder_chaining_change(
+    previous: &Signature,
+    current: &Signature,
+    path: &ItemPath,
+) -> Option<&'static str> {
+    let type_name = path.segments().iter().rev().nth(1)?;
+    if !type_name.to_string().ends_with("Builder") {
+        return None;
+    }
+
+    let unchanged = previous.generics == current.generics
+        && previous
+            .inputs
+            .iter()
+            .skip(1)
+            .eq(current.inputs.iter().skip(1));
+
+    if !unchanged {
+        return None;
+    }
+
+    match (
+        chaining(previous, type_name)?,
+        chaining(current, type_name)?,
+    ) {
+        (Chaining::Owned, Chaining::MutRef) => Some(
+            "now takes and returns `&mut Self`: \
+             chains ending with a method taking `self` no longer compile",
+        ),
+        (Chaining::MutRef, Chaining::Owned) => Some(
+            "now takes and returns `Self`: \
+             it can no longer be called on a builder behind a `&mut`",
+        ),
+        _ => None,
+    }
+}
+
+fn chaining(sig: &Signature, type_name: &Ident) -> Option<Chaining> {
+    let is_self = |ty: &Type| match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Self" || segment.ident == *type_name),
+        _ => false,
+    };
+
+    let output = match &sig.output {
+        ReturnType::Type(_, ty) => ty.as_ref(),
+        ReturnType::Default => return None,
+    };
+
+    match (sig.receiver()?, output) {
+        (FnArg::Receiver(receiver), output) if receiver.reference.is_none() && is_self(output) => {
+            Some(Chaining::Owned)
+        }
+        (FnArg::Receiver(receiver), Type::Reference(output))
+            if receiver.mutability.is_some()
+                && receiver.reference.is_some()
+                && output.mutability.is_some()
+                && is_self(&output.elem) =>
+        {
+            Some(Chaining::MutRef)
+        }
+        _ => None,
+    }
+}
 
 #[cfg(test)]
 impl Parse for MethodMetadata {
@@ -171,6 +412,7 @@ impl Parse for MethodMetadata {
 
         Ok(MethodMetadata::new(
             sig.clone(),
+            &method.attrs,
             parent_generc_params.clone(),
             parent_generic_arguments.cloned(),
         ))
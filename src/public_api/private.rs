@@ -0,0 +1,117 @@
+use syn::{
+    token::Pub,
+    visit_mut::{self, VisitMut},
+    File, ImplItemMethod, ItemEnum, ItemFn, ItemImpl, ItemMod, ItemStruct, ItemTrait, VisPublic,
+    Visibility,
+};
+
+/// Makes every function, type, trait, module and inherent method of the crate
+/// public, so that the visitors extract them as well.
+///
+/// Fields and `use` declarations are left untouched: changing a private field
+/// of a public type must not be reported as a change of the type itself.
+pub(crate) fn expose_private_items(file: &mut File) {
+    PrivateItemsExposer.visit_file_mut(file);
+}
+
+struct PrivateItemsExposer;
+
+impl VisitMut for PrivateItemsExposer {
+    fn visit_item_fn_mut(&mut self, i: &mut ItemFn) {
+        make_public(&mut i.vis);
+    }
+
+    fn visit_item_struct_mut(&mut self, i: &mut ItemStruct) {
+        make_public(&mut i.vis);
+    }
+
+    fn visit_item_enum_mut(&mut self, i: &mut ItemEnum) {
+        make_public(&mut i.vis);
+    }
+
+    fn visit_item_trait_mut(&mut self, i: &mut ItemTrait) {
+        make_public(&mut i.vis);
+    }
+
+    fn visit_item_mod_mut(&mut self, i: &mut ItemMod) {
+        make_public(&mut i.vis);
+        visit_mut::visit_item_mod_mut(self, i);
+    }
+
+    fn visit_item_impl_mut(&mut self, i: &mut ItemImpl) {
+        // Items of trait implementations have no visibility of their own.
+        if i.trait_.is_none() {
+            visit_mut::visit_item_impl_mut(self, i);
+        }
+    }
+
+    fn visit_impl_item_method_mut(&mut self, i: &mut ImplItemMethod) {
+        make_public(&mut i.vis);
+    }
+}
+
+fn make_public(vis: &mut Visibility) {
+    if !matches!(vis, Visibility::Public(_)) {
+        *vis = Visibility::Public(VisPublic {
+            pub_token: Pub::default(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn exposes_items_modules_and_inherent_methods() {
+        let mut file: File = parse_quote! {
+            fn f() {}
+
+            pub(crate) mod m {
+                struct S {
+                    a: u8,
+                }
+
+                impl S {
+                    pub(super) fn g() {}
+                }
+
+                impl Default for S {
+                    fn default() -> S {
+                        S { a: 0 }
+                    }
+                }
+            }
+        };
+
+        expose_private_items(&mut file);
+
+        let expected: File = parse_quote! {
+            pub fn f() {}
+
+            pub mod m {
+                pub struct S {
+                    a: u8,
+                }
+
+                impl S {
+                    pub fn g() {}
+                }
+
+                impl Default for S {
+                    fn default() -> S {
+                        S { a: 0 }
+                    }
+                }
+            }
+        };
+
+        assert_eq!(
+            file.into_token_stream().to_string(),
+            expected.into_token_stream().to_string()
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use std::{collections::BTreeSet, mem};
+
+use quote::ToTokens;
+use syn::{
+    punctuated::Punctuated, token::Add, GenericParam, Generics, Signature, TraitBoundModifier,
+    TypeParamBound, WherePredicate,
+};
+
+use crate::{diagnosis::DiagnosisItem, rules::RuleId};
+
+use super::ItemPath;
+
+/// Reports the type parameters which became `?Sized`, or which lost their
+/// `?Sized` bound, when nothing else changed in the generics.
+///
+/// Returns `None` if anything else changed.
+pub(crate) fn diagnosis(
+    previous: &Generics,
+    current: &Generics,
+    path: &ItemPath,
+) -> Option<DiagnosisItem> {
+    let (previous_relaxed, previous) = strip(previous);
+    let (current_relaxed, current) = strip(current);
+
+    if previous != current || previous_relaxed == current_relaxed {
+        return None;
+    }
+
+    let required = previous_relaxed
+        .difference(&current_relaxed)
+        .map(|param| format!("`{}` now requires `Sized`", param));
+    let relaxed = current_relaxed
+        .difference(&previous_relaxed)
+        .map(|param| format!("`{}` is now `?Sized`", param));
+
+    // Relaxing a parameter does not make up for requiring another one.
+    let rule = if previous_relaxed.is_subset(&current_relaxed) {
+        RuleId::SizedBoundRelaxed
+    } else {
+        RuleId::SizedBoundAdded
+    };
+
+    let message = required.chain(relaxed).collect::<Vec<_>>().join(", ");
+
+    Some(DiagnosisItem::new(rule, path.clone(), None).with_message(message))
+}
+
+/// Reports the `?Sized` bounds added to or removed from the generics of a
+/// function, when nothing else changed in its signature.
+pub(crate) fn signature_diagnosis(
+    previous: &Signature,
+    current: &Signature,
+    path: &ItemPath,
+) -> Option<DiagnosisItem> {
+    let same_generics = |sig: &Signature| Signature {
+        generics: Generics::default(),
+        ..sig.clone()
+    };
+
+    if same_generics(previous) != same_generics(current) {
+        return None;
+    }
+
+    diagnosis(&previous.generics, &current.generics, path)
+}
+
+// Removes the `?Sized` bounds of the generics, either on the parameters or in
+// the where clause, and returns the parameters they were on.
+fn strip(generics: &Generics) -> (BTreeSet<String>, Generics) {
+    let mut relaxed = BTreeSet::new();
+    let mut generics = generics.clone();
+
+    for param in generics.params.iter_mut() {
+        if let GenericParam::Type(ty) = param {
+            if strip_bounds(&mut ty.bounds) {
+                relaxed.insert(ty.ident.to_string());
+            }
+
+            if ty.bounds.is_empty() {
+                ty.colon_token = None;
+            }
+        }
+    }
+
+    if let Some(where_clause) = &mut generics.where_clause {
+        let predicates = mem::take(&mut where_clause.predicates);
+
+        where_clause.predicates = predicates
+            .into_iter()
+            .filter_map(|mut predicate| {
+                if let WherePredicate::Type(ty) = &mut predicate {
+                    if strip_bounds(&mut ty.bounds) {
+                        relaxed.insert(ty.bounded_ty.to_token_stream().to_string());
+                    }
+
+                    if ty.bounds.is_empty() {
+                        return None;
+                    }
+                }
+
+                Some(predicate)
+            })
+            .collect();
+
+        if where_clause.predicates.is_empty() {
+            generics.where_clause = None;
+        }
+    }
+
+    (relaxed, generics)
+}
+
+// Removes the `?Sized` bounds, and returns whether there was one.
+fn strip_bounds(bounds: &mut Punctuated<TypeParamBound, Add>) -> bool {
+    let previous = mem::take(bounds);
+    let len = previous.len();
+
+    *bounds = previous
+        .into_iter()
+        .filter(|bound| !is_maybe_sized(bound))
+        .collect();
+
+    bounds.len() != len
+}
+
+fn is_maybe_sized(bound: &TypeParamBound) -> bool {
+    match bound {
+        TypeParamBound::Trait(bound) => {
+            matches!(bound.modifier, TraitBoundModifier::Maybe(_)) && bound.path.is_ident("Sized")
+        }
+        TypeParamBound::Lifetime(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn changes(previous: Signature, current: Signature) -> Option<(RuleId, String)> {
+        let path = ItemPath::new(Vec::new(), previous.ident.clone());
+
+        signature_diagnosis(&previous, &current, &path)
+            .map(|diag| (diag.rule(), diag.message().unwrap().to_owned()))
+    }
+
+    #[test]
+    fn sized_bounds() {
+        assert_eq!(
+            changes(
+                parse_quote! { fn f<T: ?Sized + Clone>() },
+                parse_quote! { fn f<T: Clone>() }
+            ),
+            Some((
+                RuleId::SizedBoundAdded,
+                "`T` now requires `Sized`".to_owned()
+            ))
+        );
+
+        assert_eq!(
+            changes(
+                parse_quote! { fn f<T, U>() },
+                parse_quote! { fn f<T, U>() where U: ?Sized }
+            ),
+            Some((RuleId::SizedBoundRelaxed, "`U` is now `?Sized`".to_owned()))
+        );
+
+        assert_eq!(
+            changes(
+                parse_quote! { fn f<T: ?Sized>() },
+                parse_quote! { fn f<T>() where T: ?Sized }
+            ),
+            None
+        );
+
+        assert_eq!(
+            changes(
+                parse_quote! { fn f<T>() },
+                parse_quote! { fn f<T: ?Sized + Clone>() }
+            ),
+            None
+        );
+    }
+}
@@ -1,47 +1,61 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use syn::{
     punctuated::Punctuated,
     token::Add,
     visit::{self, Visit},
-    Generics, Ident, ItemMod, ItemTrait, TraitItem, TraitItemConst, TraitItemMethod, TraitItemType,
-    TypeParamBound, Visibility,
+    Generics, Ident, ItemMod, ItemTrait, TraitItem, TraitItemMethod, TraitItemType, TypeParamBound,
+    Visibility,
 };
 
 #[cfg(test)]
-use syn::parse::{Parse, ParseStream, Result as ParseResult};
+use syn::{
+    parse::{Error as ParseError, Parse, ParseStream, Result as ParseResult},
+    spanned::Spanned,
+};
 
-use crate::diagnosis::{DiagnosisCollector, DiagnosisItem, DiagnosticGenerator};
+use crate::{
+    diagnosis::{DiagnosisCollector, DiagnosisItem, DiagnosisRules, DiagnosticGenerator},
+    rules::RuleId,
+};
 
-use super::{imports::PathResolver, ItemKind, ItemPath};
+use super::{
+    attributes::ItemAttributes,
+    consts::AssocConstMetadata,
+    imports::PathResolver,
+    utils::{display_code, display_where_clause, render_block},
+    ItemKind, ItemPath,
+};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub(crate) struct TraitDefVisitor<'a> {
-    items: HashMap<ItemPath, ItemKind>,
+    items: BTreeMap<ItemPath, ItemKind>,
     path: Vec<Ident>,
     resolver: &'a PathResolver,
+    unanalyzable: &'a mut BTreeMap<ItemPath, String>,
 }
 
 impl<'a> TraitDefVisitor<'a> {
     pub(crate) fn new(
-        items: HashMap<ItemPath, ItemKind>,
+        items: BTreeMap<ItemPath, ItemKind>,
         resolver: &'a PathResolver,
+        unanalyzable: &'a mut BTreeMap<ItemPath, String>,
     ) -> TraitDefVisitor<'a> {
         let path = Vec::new();
         TraitDefVisitor {
             items,
             path,
             resolver,
+            unanalyzable,
         }
     }
 
-    pub(crate) fn items(self) -> HashMap<ItemPath, ItemKind> {
+    pub(crate) fn items(self) -> BTreeMap<ItemPath, ItemKind> {
         self.items
     }
 
     pub(crate) fn add_trait_def(&mut self, path: ItemPath, metadata: TraitDefMetadata) {
-        let tmp = self.items.insert(path, metadata.into());
-        assert!(tmp.is_none(), "Duplicate item definition");
+        super::add_item(&mut self.items, self.unanalyzable, path, metadata.into());
     }
 }
 
@@ -52,9 +66,12 @@ impl<'a, 'ast> Visit<'ast> for TraitDefVisitor<'a> {
         }
 
         let path = ItemPath::new(self.path.clone(), i.ident.clone());
-        let metadata = extract_def_trait_metadata(i);
-
-        self.add_trait_def(path, metadata);
+        match extract_def_trait_metadata(i) {
+            Ok(metadata) => self.add_trait_def(path, metadata),
+            Err(reason) => {
+                self.unanalyzable.insert(path, reason);
+            }
+        }
     }
 
     fn visit_item_mod(&mut self, i: &'ast ItemMod) {
@@ -68,37 +85,90 @@ impl<'a, 'ast> Visit<'ast> for TraitDefVisitor<'a> {
     }
 }
 
-fn extract_def_trait_metadata(i: &ItemTrait) -> TraitDefMetadata {
+// Fails when the trait contains items which can't be compared, such as
+// macro invocations.
+fn extract_def_trait_metadata(i: &ItemTrait) -> Result<TraitDefMetadata, String> {
     let generics = i.generics.clone();
     let supertraits = i.supertraits.clone();
+    let attrs = ItemAttributes::new(&i.attrs);
 
     let (mut consts, mut methods, mut types) = (Vec::new(), Vec::new(), Vec::new());
 
-    i.items.iter().for_each(|item| match item {
-        TraitItem::Const(c) => consts.push(c.clone()),
-        TraitItem::Method(m) => methods.push(m.clone()),
-        TraitItem::Type(t) => types.push(t.clone()),
-        other => panic!("Found unexcepted trait item: `{:?}`", other),
-    });
+    for item in &i.items {
+        match item {
+            TraitItem::Const(c) => consts.push(AssocConstMetadata::from(c)),
+            TraitItem::Method(m) => methods.push(m.clone()),
+            TraitItem::Type(t) => types.push(t.clone()),
+            TraitItem::Macro(m) => {
+                return Err(format!(
+                    "the trait contains a `{}!` invocation",
+                    display_code(&m.mac.path)
+                ))
+            }
+            _ => return Err("the trait contains an unsupported item".to_owned()),
+        }
+    }
 
-    TraitDefMetadata {
+    Ok(TraitDefMetadata {
         generics,
         supertraits,
+        attrs,
         consts,
         methods,
         types,
-    }
+    })
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct TraitDefMetadata {
     generics: Generics,
     supertraits: Punctuated<TypeParamBound, Add>,
-    consts: Vec<TraitItemConst>,
+    attrs: ItemAttributes,
+    consts: Vec<AssocConstMetadata>,
     methods: Vec<TraitItemMethod>,
     types: Vec<TraitItemType>,
 }
 
+impl TraitDefMetadata {
+    pub(crate) fn consts(&self) -> &[AssocConstMetadata] {
+        &self.consts
+    }
+
+    /// Renders the trait as pseudo-Rust, named by its full path.
+    pub(crate) fn render(&self, path: &ItemPath) -> Vec<String> {
+        let supertraits = if self.supertraits.is_empty() {
+            String::new()
+        } else {
+            format!(": {}", display_code(&self.supertraits))
+        };
+        let header = format!(
+            "pub trait {}{}{}{}",
+            path,
+            display_code(&self.generics),
+            supertraits,
+            display_where_clause(&self.generics),
+        );
+
+        let consts = self.consts.iter().map(AssocConstMetadata::render);
+        let types = self.types.iter().map(|ty| {
+            let mut ty = ty.clone();
+            ty.attrs.clear();
+            display_code(ty)
+        });
+        let methods = self.methods.iter().map(|method| match method.default {
+            Some(_) => format!("{} {{ .. }}", display_code(&method.sig)),
+            None => format!("{};", display_code(&method.sig)),
+        });
+
+        let mut lines = self.attrs.render();
+        lines.extend(render_block(
+            header,
+            consts.chain(types).chain(methods).collect(),
+        ));
+        lines
+    }
+}
+
 impl From<TraitDefMetadata> for ItemKind {
     fn from(metadata: TraitDefMetadata) -> ItemKind {
         ItemKind::TraitDef(metadata)
@@ -113,9 +183,15 @@ impl DiagnosticGenerator for TraitDefMetadata {
         collector: &mut DiagnosisCollector,
     ) {
         if self.generics != other.generics || self.supertraits != other.supertraits {
-            collector.add(DiagnosisItem::modification(path.clone(), None));
+            collector.add(DiagnosisItem::new(
+                Self::RULES.modification,
+                path.clone(),
+                None,
+            ));
         }
 
+        self.attrs.diagnosis(&other.attrs, path, collector);
+
         diagnosis_for_nameable(
             self.consts.as_slice(),
             other.consts.as_slice(),
@@ -142,9 +218,10 @@ impl DiagnosticGenerator for TraitDefMetadata {
 #[cfg(test)]
 impl Parse for TraitDefMetadata {
     fn parse(input: ParseStream) -> ParseResult<TraitDefMetadata> {
-        input
-            .parse()
-            .map(|trait_def| extract_def_trait_metadata(&trait_def))
+        let trait_def = input.parse::<ItemTrait>()?;
+
+        extract_def_trait_metadata(&trait_def)
+            .map_err(|reason| ParseError::new(trait_def.span(), reason))
     }
 }
 
@@ -154,25 +231,23 @@ fn diagnosis_for_nameable<Item>(
     path: &ItemPath,
     collector: &mut DiagnosisCollector,
 ) where
-    Item: Nameable + PartialEq,
+    Item: Nameable,
 {
     for left_item in left {
         let left_item_name = left_item.name();
+        let path = ItemPath::extend(path.clone(), left_item_name.clone());
 
         match Item::find_named(right, left_item_name) {
-            Some(right_item) if left_item == right_item => {}
-
-            altered => {
-                let path = ItemPath::extend(path.clone(), left_item_name.clone());
-                let diagnostic_creator = if altered.is_some() {
-                    DiagnosisItem::modification
-                } else {
-                    DiagnosisItem::removal
-                };
-
-                let diagnosis = diagnostic_creator(path, None);
-                collector.add(diagnosis);
+            Some(right_item) => {
+                if let Some(diagnosis) = left_item.modification(right_item, path) {
+                    collector.add(diagnosis);
+                }
             }
+            None => collector.add(DiagnosisItem::new(
+                DiagnosisRules::TRAIT_ITEM.removal,
+                path,
+                None,
+            )),
         }
     }
 
@@ -181,15 +256,22 @@ fn diagnosis_for_nameable<Item>(
 
         if Item::find_named(left, right_item_name).is_none() {
             let path = ItemPath::extend(path.clone(), right_item_name.clone());
-            let diagnosis = DiagnosisItem::addition(path, None);
+            let diagnosis = DiagnosisItem::new(DiagnosisRules::TRAIT_ITEM.addition, path, None);
             collector.add(diagnosis)
         }
     }
 }
 
-trait Nameable: Sized {
+trait Nameable: Sized + PartialEq {
     fn name(&self) -> &Ident;
 
+    /// Returns the diagnosis describing the changes between two versions of
+    /// the item, if there is any.
+    fn modification(&self, other: &Self, path: ItemPath) -> Option<DiagnosisItem> {
+        (self != other)
+            .then(|| DiagnosisItem::new(DiagnosisRules::TRAIT_ITEM.modification, path, None))
+    }
+
     fn find_named<'a>(items: &'a [Self], name: &Ident) -> Option<&'a Self> {
         items.iter().find(|item| item.name() == name)
     }
@@ -206,9 +288,37 @@ impl Nameable for TraitItem {
     }
 }
 
-impl Nameable for TraitItemConst {
+impl Nameable for AssocConstMetadata {
     fn name(&self) -> &Ident {
-        &self.ident
+        AssocConstMetadata::name(self)
+    }
+
+    fn modification(&self, other: &Self, path: ItemPath) -> Option<DiagnosisItem> {
+        if self == other {
+            return None;
+        }
+
+        // The implementations relying on the default value get it as their
+        // own, so that moving the value between the trait and them is only
+        // a change of the trait.
+        if let Some(added) = self.default_change(other) {
+            let (rule, message) = if added {
+                (RuleId::TraitConstDefaultAdded, "default value added")
+            } else {
+                (RuleId::TraitConstDefaultRemoved, "default value removed")
+            };
+
+            return Some(DiagnosisItem::new(rule, path, None).with_message(message));
+        }
+
+        Some(match self.value_change(other) {
+            Some((previous, current)) => {
+                DiagnosisItem::new(RuleId::AssocConstValueModified, path, None).with_message(
+                    format!("default value changed from `{}` to `{}`", previous, current),
+                )
+            }
+            None => DiagnosisItem::new(DiagnosisRules::TRAIT_ITEM.modification, path, None),
+        })
     }
 }
 
@@ -216,6 +326,32 @@ impl Nameable for TraitItemMethod {
     fn name(&self) -> &Ident {
         &self.sig.ident
     }
+
+    // The content of the default implementation is not part of the API,
+    // only whether there is one.
+    fn modification(&self, other: &Self, path: ItemPath) -> Option<DiagnosisItem> {
+        if self.attrs != other.attrs || self.sig != other.sig {
+            return Some(DiagnosisItem::new(
+                DiagnosisRules::TRAIT_ITEM.modification,
+                path,
+                None,
+            ));
+        }
+
+        let (rule, message) = match (self.default.is_some(), other.default.is_some()) {
+            (true, false) => (
+                RuleId::TraitMethodDefaultRemoved,
+                "default implementation removed",
+            ),
+            (false, true) => (
+                RuleId::TraitMethodDefaultAdded,
+                "default implementation added",
+            ),
+            _ => return None,
+        };
+
+        Some(DiagnosisItem::new(rule, path, None).with_message(message))
+    }
 }
 
 impl Nameable for TraitItemType {
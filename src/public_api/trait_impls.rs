@@ -1,45 +1,50 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+
+use quote::ToTokens;
 
 use syn::{
     visit::{self, Visit},
-    AngleBracketedGenericArguments, Generics, Ident, ImplItemConst, ImplItemType, ItemImpl,
-    ItemMod,
+    AngleBracketedGenericArguments, Generics, Ident, ImplItemType, ItemImpl, ItemMod,
 };
 
 #[cfg(test)]
 use syn::parse::{Parse, ParseStream, Result as ParseResult};
 
 use crate::{
-    diagnosis::{DiagnosisCollector, DiagnosisItem, DiagnosticGenerator},
+    diagnosis::{DiagnosisCollector, DiagnosisItem, DiagnosisRules, DiagnosticGenerator},
     public_api::utils,
+    rules::RuleId,
 };
 
 #[cfg(test)]
 use crate::ast::CrateAst;
 
-use super::{imports::PathResolver, ItemKind, ItemPath};
+use super::{consts::AssocConstMetadata, imports::PathResolver, ItemKind, ItemPath};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub(crate) struct TraitImplVisitor<'a> {
-    items: HashMap<ItemPath, ItemKind>,
+    items: BTreeMap<ItemPath, ItemKind>,
     path: Vec<Ident>,
     resolver: &'a PathResolver,
+    unanalyzable: &'a mut BTreeMap<ItemPath, String>,
 }
 
 impl<'a> TraitImplVisitor<'a> {
     pub(crate) fn new(
-        items: HashMap<ItemPath, ItemKind>,
+        items: BTreeMap<ItemPath, ItemKind>,
         resolver: &'a PathResolver,
+        unanalyzable: &'a mut BTreeMap<ItemPath, String>,
     ) -> TraitImplVisitor<'a> {
         let path = Vec::new();
         TraitImplVisitor {
             items,
             path,
             resolver,
+            unanalyzable,
         }
     }
 
-    pub(crate) fn items(self) -> HashMap<ItemPath, ItemKind> {
+    pub(crate) fn items(self) -> BTreeMap<ItemPath, ItemKind> {
         self.items
     }
 
@@ -52,14 +57,23 @@ impl<'a> TraitImplVisitor<'a> {
     }
 
     fn add_trait_impl(&mut self, type_path: &ItemPath, impl_: TraitImplMetadata) {
-        let type_ = self
-            .items
-            .get_mut(type_path)
-            .expect("Type not found")
-            .as_type_mut()
-            .expect("Can't impl a trait for a non-type item");
-
-        type_.add_trait_impl(impl_);
+        match self.items.get_mut(type_path).map(ItemKind::as_type_mut) {
+            Some(Some(type_)) => type_.add_trait_impl(impl_),
+            Some(None) => {
+                let reason = format!(
+                    "`{}` is implemented for an item which is not a type",
+                    impl_.trait_name()
+                );
+                self.unanalyzable.insert(type_path.clone(), reason);
+            }
+            None => {
+                let reason = format!(
+                    "`{}` is implemented for an unknown type",
+                    impl_.trait_name()
+                );
+                self.unanalyzable.insert(type_path.clone(), reason);
+            }
+        }
     }
 }
 
@@ -77,8 +91,10 @@ impl<'a, 'ast> Visit<'ast> for TraitImplVisitor<'a> {
                 None => return,
             };
 
+        // The resolved path is absolute: the implementation is attributed to
+        // the type, wherever the impl block is.
         self.add_trait_impl(
-            &ItemPath::concat_both(self.path.clone(), type_name.to_owned()),
+            &ItemPath::from_segments(type_name.to_owned()),
             trait_impl_metadata,
         );
     }
@@ -108,72 +124,512 @@ fn extract_impl_trait_metadata<'a>(
 
     let mut consts = Vec::new();
     let mut types = Vec::new();
+    let mut methods = Vec::new();
 
     for item in &impl_.items {
         match item {
-            syn::ImplItem::Const(c) => consts.push(c.clone()),
+            syn::ImplItem::Const(c) => consts.push(AssocConstMetadata::from(c)),
             syn::ImplItem::Type(t) => types.push(t.clone()),
+            syn::ImplItem::Method(m) => methods.push(m.sig.ident.clone()),
             _ => {}
         }
     }
 
+    consts.sort_by(|a, b| a.name().cmp(b.name()));
+    methods.sort();
+
     let generic_parameters = impl_.generics.clone();
 
+    let deref_target = if trait_name == "Deref" {
+        types
+            .iter()
+            .find(|type_| type_.ident == "Target")
+            .and_then(|target| utils::extract_name_and_generic_args(&target.ty))
+            .and_then(|(target_path, _)| resolver.resolve(current_path, target_path))
+            .map(|target_path| ItemPath::from_segments(target_path.to_owned()))
+    } else {
+        None
+    };
+
     let trait_impl_metadata = TraitImplMetadata {
         trait_name,
         generic_parameters,
         trait_generic_args,
         type_generic_args,
         consts,
+        provided: Vec::new(),
         types,
+        methods,
+        module: current_path.to_owned(),
+        deref_target,
     };
 
     Some((resolved_path, trait_impl_metadata))
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub(crate) struct TraitImplMetadata {
     trait_name: Ident,
     generic_parameters: Generics,
     trait_generic_args: Option<AngleBracketedGenericArguments>,
     type_generic_args: Option<AngleBracketedGenericArguments>,
 
-    consts: Vec<ImplItemConst>,
+    // Sorted by name. They include the constants the trait provides a
+    // default value for, as they are part of the implementation wherever
+    // their value is written.
+    consts: Vec<AssocConstMetadata>,
     types: Vec<ImplItemType>,
+
+    // The names of the constants whose value is the default of the trait.
+    // Where the value is written does not change the API, so they are not
+    // compared.
+    provided: Vec<Ident>,
+
+    // The names of the methods defined in the impl block, sorted. Overriding
+    // a provided method does not change the API, so they are not compared.
+    methods: Vec<Ident>,
+
+    // The module containing the impl block. Moving the impl block does not
+    // change the API, so it is not compared.
+    module: Vec<Ident>,
+
+    // The resolved path of `Target`, for `Deref` implementations. It is
+    // derived from `types`, which is compared instead.
+    deref_target: Option<ItemPath>,
+}
+
+impl PartialEq for TraitImplMetadata {
+    fn eq(&self, other: &TraitImplMetadata) -> bool {
+        self.trait_name == other.trait_name
+            && self.generic_parameters == other.generic_parameters
+            && self.trait_generic_args == other.trait_generic_args
+            && self.type_generic_args == other.type_generic_args
+            && self.consts == other.consts
+            && self.types == other.types
+    }
 }
 
 impl TraitImplMetadata {
     pub(crate) fn trait_name(&self) -> &Ident {
         &self.trait_name
     }
+
+    pub(crate) fn trait_generic_args(&self) -> Option<&AngleBracketedGenericArguments> {
+        self.trait_generic_args.as_ref()
+    }
+
+    /// The names of the methods the implementation defines, including the
+    /// provided methods it overrides.
+    pub(crate) fn methods(&self) -> &[Ident] {
+        &self.methods
+    }
+
+    /// The module containing the impl block.
+    pub(crate) fn module(&self) -> &[Ident] {
+        &self.module
+    }
+
+    /// The type whose methods can be called on the implementing type, if
+    /// this is a `Deref` implementation.
+    pub(crate) fn deref_target(&self) -> Option<&ItemPath> {
+        self.deref_target.as_ref()
+    }
+
+    /// Updates the deref target if it was moved to its canonical path.
+    pub(crate) fn rename_deref_target(&mut self, renames: &BTreeMap<ItemPath, ItemPath>) {
+        if let Some(canonical) = self
+            .deref_target
+            .as_ref()
+            .and_then(|target| renames.get(target))
+        {
+            self.deref_target = Some(canonical.clone());
+        }
+    }
+
+    // Adds the constants whose default value the implementation relies on.
+    fn add_provided_consts(&mut self, trait_consts: &[AssocConstMetadata]) {
+        for provided in trait_consts.iter().filter_map(AssocConstMetadata::provided) {
+            if self.consts.iter().all(|c| c.name() != provided.name()) {
+                self.provided.push(provided.name().clone());
+                self.consts.push(provided);
+            }
+        }
+
+        self.consts.sort_by(|a, b| a.name().cmp(b.name()));
+    }
+
+    // Describes where the value of a constant comes from, when it moved
+    // between the implementation and the trait.
+    fn value_origin_change(&self, other: &TraitImplMetadata, name: &Ident) -> &'static str {
+        match (self.provided.contains(name), other.provided.contains(name)) {
+            (false, true) => ", now the default value of the trait",
+            (true, false) => ", no longer the default value of the trait",
+            _ => "",
+        }
+    }
+
+    /// Describes the changes of the values of the associated constants, when
+    /// they are the only changes of the implementation.
+    fn const_value_changes(&self, other: &TraitImplMetadata) -> Option<Vec<String>> {
+        let only_consts_changed = self.trait_name == other.trait_name
+            && self.generic_parameters == other.generic_parameters
+            && self.trait_generic_args == other.trait_generic_args
+            && self.type_generic_args == other.type_generic_args
+            && self.types == other.types
+            && self.consts.len() == other.consts.len();
+
+        if !only_consts_changed {
+            return None;
+        }
+
+        self.consts
+            .iter()
+            .zip(&other.consts)
+            .filter(|(previous, current)| previous != current)
+            .map(|(previous, current)| {
+                previous
+                    .value_change(current)
+                    .map(|(previous_value, current_value)| {
+                        format!(
+                            "`{}` changed from `{}` to `{}`{}",
+                            previous.name(),
+                            previous_value,
+                            current_value,
+                            self.value_origin_change(other, previous.name())
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Renders the implementation as pseudo-Rust, for the type at `path`.
+    pub(crate) fn render(&self, path: &ItemPath) -> Vec<String> {
+        let header = format!(
+            "impl{} {}{} for {}{}{}",
+            utils::display_code(&self.generic_parameters),
+            self.trait_name,
+            utils::display_code(&self.trait_generic_args),
+            path,
+            utils::display_code(&self.type_generic_args),
+            utils::display_where_clause(&self.generic_parameters),
+        );
+
+        let consts = self.consts.iter().map(AssocConstMetadata::render);
+        let types = self.types.iter().map(|ty| {
+            let mut ty = ty.clone();
+            ty.attrs.clear();
+            utils::display_code(ty)
+        });
+
+        utils::render_block(header, consts.chain(types).collect())
+    }
+
+    pub(crate) fn sort_key(&self) -> (String, String, String) {
+        (
+            self.trait_name.to_string(),
+            self.trait_generic_args.to_token_stream().to_string(),
+            self.type_generic_args.to_token_stream().to_string(),
+        )
+    }
 }
 
 impl DiagnosticGenerator for TraitImplMetadata {
+    const RULES: DiagnosisRules = DiagnosisRules::TRAIT_IMPL;
+
     fn removal_diagnosis(&self, path: &ItemPath, collector: &mut DiagnosisCollector) {
-        collector.add(DiagnosisItem::removal(
+        let diagnosis = DiagnosisItem::new(
+            Self::RULES.removal,
             path.clone(),
             Some(self.trait_name.clone()),
-        ));
+        )
+        .with_details(vec![utils::defined_in(&self.module)]);
+
+        let text_conversion = match self.trait_name.to_string().as_str() {
+            "Display" => Some((
+                RuleId::DisplayImplRemoved,
+                "`to_string()` and `format!(\"{}\")` no longer work".to_owned(),
+            )),
+            "FromStr" => Some((
+                RuleId::FromStrImplRemoved,
+                "`parse()` can no longer build it".to_owned(),
+            )),
+            _ => None,
+        };
+
+        let dedicated = text_conversion.or_else(|| {
+            self.operator_removal(path)
+                .map(|message| (RuleId::OperatorImplRemoved, message))
+        });
+
+        if let Some((rule, message)) = dedicated {
+            collector.add(
+                DiagnosisItem::new(rule, path.clone(), Some(self.trait_name.clone()))
+                    .with_message(message)
+                    .with_details(vec![utils::defined_in(&self.module)]),
+            );
+
+            return;
+        }
+
+        let diagnosis = match removal_consequence(&self.trait_name) {
+            Some(consequence) => diagnosis.with_message(consequence),
+            None => diagnosis,
+        };
+
+        collector.add(diagnosis);
     }
 
     fn modification_diagnosis(
         &self,
-        _other: &Self,
+        other: &Self,
         path: &ItemPath,
         collector: &mut DiagnosisCollector,
     ) {
-        collector.add(DiagnosisItem::modification(
+        if let Some(changes) = self.const_value_changes(other) {
+            collector.add(
+                DiagnosisItem::new(
+                    RuleId::AssocConstValueModified,
+                    path.clone(),
+                    Some(self.trait_name.clone()),
+                )
+                .with_message(changes.join(", ")),
+            );
+
+            return;
+        }
+
+        if let Some(message) = self.operator_modification(other, path) {
+            collector.add(
+                DiagnosisItem::new(
+                    RuleId::OperatorImplModified,
+                    path.clone(),
+                    Some(self.trait_name.clone()),
+                )
+                .with_message(message),
+            );
+
+            return;
+        }
+
+        let diagnosis = DiagnosisItem::new(
+            Self::RULES.modification,
             path.clone(),
             Some(self.trait_name.clone()),
-        ));
+        );
+
+        let target_changed_only = self.type_generic_args != other.type_generic_args
+            && self.generic_parameters == other.generic_parameters
+            && self.trait_generic_args == other.trait_generic_args
+            && self.consts == other.consts
+            && self.types == other.types;
+
+        let diagnosis = match path.segments().last() {
+            Some(type_name) if target_changed_only => {
+                diagnosis.with_message(utils::impl_target_change(
+                    type_name,
+                    &self.type_generic_args,
+                    &other.type_generic_args,
+                ))
+            }
+            _ => diagnosis,
+        };
+
+        collector.add(diagnosis);
     }
 
     fn addition_diagnosis(&self, path: &ItemPath, collector: &mut DiagnosisCollector) {
-        collector.add(DiagnosisItem::addition(
-            path.clone(),
-            Some(self.trait_name.clone()),
-        ));
+        collector.add(
+            DiagnosisItem::new(
+                Self::RULES.addition,
+                path.clone(),
+                Some(self.trait_name.clone()),
+            )
+            .with_details(vec![utils::defined_in(&self.module)]),
+        );
+    }
+}
+
+/// Adds to the trait implementations the constants they get from the
+/// definition of their trait, when the trait is defined in the crate, so that
+/// `S::[impl T]::C` and `T::C` are compared as the same constant of `S`.
+/// Traits are matched by name, and left out when several public traits have
+/// the same name.
+pub(crate) fn add_provided_consts(items: &mut BTreeMap<ItemPath, ItemKind>) {
+    let mut trait_consts: BTreeMap<Ident, Option<Vec<AssocConstMetadata>>> = BTreeMap::new();
+
+    for (path, kind) in items.iter() {
+        if let ItemKind::TraitDef(trait_def) = kind {
+            let name = path.segments().last().unwrap().clone();
+            trait_consts
+                .entry(name)
+                .and_modify(|consts| *consts = None)
+                .or_insert_with(|| Some(trait_def.consts().to_owned()));
+        }
+    }
+
+    for type_ in items.values_mut().filter_map(ItemKind::as_type_mut) {
+        for impl_ in type_.traits_mut() {
+            if let Some(Some(consts)) = trait_consts.get(impl_.trait_name()) {
+                impl_.add_provided_consts(consts);
+            }
+        }
+    }
+}
+
+/// Describes what stops compiling when an implementation of a trait backing
+/// some syntax (`for` loops, `*`) is removed, as these removals are easy to
+/// underestimate. The operators have rules of their own.
+fn removal_consequence(trait_name: &Ident) -> Option<String> {
+    let trait_name = trait_name.to_string();
+
+    let consequence = match trait_name.as_str() {
+        "Iterator" => "can no longer be iterated or used in `for` loops",
+        "IntoIterator" => "can no longer be used in `for` loops",
+        "Deref" => "`*` and auto-deref method calls no longer work",
+        "DerefMut" => "mutable `*` and auto-deref method calls no longer work",
+        _ => return None,
+    };
+
+    Some(consequence.to_owned())
+}
+
+/// An operator backed by a trait, such as `+` for `Add`.
+enum Operator {
+    /// `a + b`, `a += b`, `a == b`...
+    Binary(&'static str),
+    /// `-a` and `!a`.
+    Unary(&'static str),
+    /// `a[i]`, or `&mut a[i]`.
+    Index { mutable: bool },
+    /// `a.cmp(&b)`, which has no operator but sorts and compares.
+    Cmp,
+}
+
+impl Operator {
+    fn of(trait_name: &Ident) -> Option<Operator> {
+        let trait_name = trait_name.to_string();
+
+        let operator = match trait_name.as_str() {
+            "Neg" => Operator::Unary("-"),
+            "Not" => Operator::Unary("!"),
+            "Index" => Operator::Index { mutable: false },
+            "IndexMut" => Operator::Index { mutable: true },
+            "PartialEq" => Operator::Binary("=="),
+            "PartialOrd" => Operator::Binary("<"),
+            "Ord" => Operator::Cmp,
+            name => match name.strip_suffix("Assign") {
+                Some(name) => Operator::Binary(binary_operator(name)?.1),
+                None => Operator::Binary(binary_operator(name)?.0),
+            },
+        };
+
+        Some(operator)
+    }
+
+    /// The operator applied to `a` and `b`.
+    fn syntax(&self) -> String {
+        self.apply("a", "b")
     }
+
+    /// The operator applied to values of the types `lhs` and `rhs`.
+    fn apply(&self, lhs: &str, rhs: &str) -> String {
+        match self {
+            Operator::Binary(operator) => format!("{} {} {}", lhs, operator, rhs),
+            Operator::Unary(operator) => format!("{}{}", operator, lhs),
+            Operator::Index { mutable: false } => format!("{}[{}]", lhs, rhs),
+            Operator::Index { mutable: true } => format!("&mut {}[{}]", lhs, rhs),
+            Operator::Cmp => format!("{}.cmp(&{})", lhs, rhs),
+        }
+    }
+
+    /// The usual name of the indexes, which `Index` has no default for.
+    fn default_rhs(&self) -> Option<&'static str> {
+        match self {
+            Operator::Index { .. } => Some("i"),
+            _ => None,
+        }
+    }
+}
+
+impl TraitImplMetadata {
+    // The types the operator is applied to: the implementing type, and the
+    // first generic argument of the trait, which defaults to `Self`.
+    fn operands(&self, path: &ItemPath) -> (String, String) {
+        let lhs = match path.segments().last() {
+            Some(type_name) => format!(
+                "{}{}",
+                type_name,
+                utils::display_code(&self.type_generic_args)
+            ),
+            None => path.to_string(),
+        };
+
+        let rhs = self
+            .trait_generic_args
+            .as_ref()
+            .and_then(|args| args.args.first())
+            .map_or_else(|| lhs.clone(), utils::display_code);
+
+        (lhs, rhs)
+    }
+
+    fn output(&self) -> Option<&ImplItemType> {
+        self.types.iter().find(|ty| ty.ident == "Output")
+    }
+
+    fn operator_removal(&self, path: &ItemPath) -> Option<String> {
+        let operator = Operator::of(&self.trait_name)?;
+        let (lhs, rhs) = self.operands(path);
+        let syntax = match operator.default_rhs() {
+            Some(index) => operator.apply("a", index),
+            None => operator.syntax(),
+        };
+
+        Some(format!(
+            "`{}` no longer compiles for `{}`",
+            syntax,
+            operator.apply(&lhs, &rhs)
+        ))
+    }
+
+    fn operator_modification(&self, other: &TraitImplMetadata, path: &ItemPath) -> Option<String> {
+        let operator = Operator::of(&self.trait_name)?;
+
+        if self.trait_generic_args != other.trait_generic_args
+            || self.type_generic_args != other.type_generic_args
+        {
+            return self.operator_removal(path);
+        }
+
+        let output = other.output().filter(|_| self.output() != other.output())?;
+        let (lhs, rhs) = self.operands(path);
+
+        Some(format!(
+            "`{}` now gives `{}`",
+            operator.apply(&lhs, &rhs),
+            utils::display_code(&output.ty)
+        ))
+    }
+}
+
+// The operator of a binary operator trait, and the one of its `*Assign`
+// counterpart.
+fn binary_operator(trait_name: &str) -> Option<(&'static str, &'static str)> {
+    let operators = match trait_name {
+        "Add" => ("+", "+="),
+        "Sub" => ("-", "-="),
+        "Mul" => ("*", "*="),
+        "Div" => ("/", "/="),
+        "Rem" => ("%", "%="),
+        "BitAnd" => ("&", "&="),
+        "BitOr" => ("|", "|="),
+        "BitXor" => ("^", "^="),
+        "Shl" => ("<<", "<<="),
+        "Shr" => (">>", ">>="),
+        _ => return None,
+    };
+
+    Some(operators)
 }
 
 #[cfg(test)]
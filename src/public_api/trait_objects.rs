@@ -0,0 +1,236 @@
+use quote::ToTokens;
+use syn::{
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+    Fields, Signature, TypeParamBound, TypeTraitObject, Variant,
+};
+
+use crate::{diagnosis::DiagnosisItem, rules::RuleId};
+
+use super::ItemPath;
+
+/// Traits which are implemented automatically, and which can be added to any
+/// trait object.
+const AUTO_TRAITS: &[&str] = &["Send", "Sync", "Unpin", "UnwindSafe", "RefUnwindSafe"];
+
+/// Reports the modification of an item, with a specific rule when only the
+/// bounds of its trait objects changed.
+pub(crate) fn modification_diagnosis<T: ContainsTypes>(
+    previous: &T,
+    current: &T,
+    fallback: RuleId,
+    path: &ItemPath,
+) -> DiagnosisItem {
+    match bound_changes(previous, current) {
+        Some(changes) => DiagnosisItem::new(RuleId::TraitObjectBoundsModified, path.clone(), None)
+            .with_message(changes),
+        None => DiagnosisItem::new(fallback, path.clone(), None),
+    }
+}
+
+/// Describes how the auto-trait and lifetime bounds of the trait objects of
+/// `previous` changed in `current`, such as `Box<dyn Error>` becoming
+/// `Box<dyn Error + Send + Sync>`.
+///
+/// Returns `None` if anything else changed.
+pub(crate) fn bound_changes<T: ContainsTypes>(previous: &T, current: &T) -> Option<String> {
+    let mut stripped = (previous.clone(), current.clone());
+    stripped.0.visit_types_mut(&mut StripExtraBounds);
+    stripped.1.visit_types_mut(&mut StripExtraBounds);
+
+    if stripped.0 != stripped.1 {
+        return None;
+    }
+
+    let (mut previous_objects, mut current_objects) =
+        (TraitObjects(Vec::new()), TraitObjects(Vec::new()));
+    previous.visit_types(&mut previous_objects);
+    current.visit_types(&mut current_objects);
+
+    let changes = previous_objects
+        .0
+        .iter()
+        .zip(current_objects.0.iter())
+        .flat_map(|(a, b)| describe_changes(a, b))
+        .collect::<Vec<_>>();
+
+    if changes.is_empty() {
+        None
+    } else {
+        Some(changes.join(", "))
+    }
+}
+
+fn describe_changes(previous: &TypeTraitObject, current: &TypeTraitObject) -> Vec<String> {
+    let (previous_extra, current_extra) = (extra_bounds(previous), extra_bounds(current));
+    let object = principal(current);
+
+    let removed = previous_extra
+        .iter()
+        .filter(|bound| !current_extra.contains(bound))
+        .map(|bound| format!("`+ {}` removed from `dyn {}`", bound, object));
+    let added = current_extra
+        .iter()
+        .filter(|bound| !previous_extra.contains(bound))
+        .map(|bound| format!("`+ {}` added to `dyn {}`", bound, object));
+
+    removed.chain(added).collect()
+}
+
+fn extra_bounds(object: &TypeTraitObject) -> Vec<String> {
+    object
+        .bounds
+        .iter()
+        .filter(|bound| is_extra(bound))
+        .map(|bound| bound.to_token_stream().to_string().replace(' ', ""))
+        .collect()
+}
+
+fn principal(object: &TypeTraitObject) -> String {
+    object
+        .bounds
+        .iter()
+        .filter(|bound| !is_extra(bound))
+        .map(|bound| bound.to_token_stream().to_string().replace(' ', ""))
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+/// Returns whether `bound` is an auto-trait or a lifetime.
+fn is_extra(bound: &TypeParamBound) -> bool {
+    match bound {
+        TypeParamBound::Lifetime(_) => true,
+        TypeParamBound::Trait(trait_) => trait_
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| AUTO_TRAITS.iter().any(|auto| segment.ident == auto)),
+    }
+}
+
+struct StripExtraBounds;
+
+impl VisitMut for StripExtraBounds {
+    fn visit_type_trait_object_mut(&mut self, object: &mut TypeTraitObject) {
+        object.bounds = object
+            .bounds
+            .iter()
+            .filter(|bound| !is_extra(bound))
+            .cloned()
+            .collect();
+
+        visit_mut::visit_type_trait_object_mut(self, object);
+    }
+}
+
+struct TraitObjects<'a>(Vec<&'a TypeTraitObject>);
+
+impl<'a> Visit<'a> for TraitObjects<'a> {
+    fn visit_type_trait_object(&mut self, object: &'a TypeTraitObject) {
+        self.0.push(object);
+        visit::visit_type_trait_object(self, object);
+    }
+}
+
+/// The parts of the API in which trait objects are compared.
+pub(crate) trait ContainsTypes: Clone + PartialEq {
+    fn visit_types<'a>(&'a self, visitor: &mut impl Visit<'a>);
+    fn visit_types_mut(&mut self, visitor: &mut impl VisitMut);
+}
+
+impl ContainsTypes for Signature {
+    fn visit_types<'a>(&'a self, visitor: &mut impl Visit<'a>) {
+        visitor.visit_signature(self);
+    }
+
+    fn visit_types_mut(&mut self, visitor: &mut impl VisitMut) {
+        visitor.visit_signature_mut(self);
+    }
+}
+
+impl ContainsTypes for Fields {
+    fn visit_types<'a>(&'a self, visitor: &mut impl Visit<'a>) {
+        visitor.visit_fields(self);
+    }
+
+    fn visit_types_mut(&mut self, visitor: &mut impl VisitMut) {
+        visitor.visit_fields_mut(self);
+    }
+}
+
+impl ContainsTypes for Vec<Variant> {
+    fn visit_types<'a>(&'a self, visitor: &mut impl Visit<'a>) {
+        self.iter()
+            .for_each(|variant| visitor.visit_variant(variant));
+    }
+
+    fn visit_types_mut(&mut self, visitor: &mut impl VisitMut) {
+        self.iter_mut()
+            .for_each(|variant| visitor.visit_variant_mut(variant));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, ItemFn, ItemStruct};
+
+    use super::*;
+
+    fn signature_changes(a: ItemFn, b: ItemFn) -> Option<String> {
+        bound_changes(&a.sig, &b.sig)
+    }
+
+    #[test]
+    fn added_auto_traits() {
+        let changes = signature_changes(
+            parse_quote! { fn f(e: Box<dyn Error>) {} },
+            parse_quote! { fn f(e: Box<dyn Error + Send + Sync>) {} },
+        );
+
+        assert_eq!(
+            changes.unwrap(),
+            "`+ Send` added to `dyn Error`, `+ Sync` added to `dyn Error`"
+        );
+    }
+
+    #[test]
+    fn removed_lifetime() {
+        let changes = signature_changes(
+            parse_quote! { fn f() -> Box<dyn Fn() + 'static> {} },
+            parse_quote! { fn f() -> Box<dyn Fn()> {} },
+        );
+
+        assert_eq!(changes.unwrap(), "`+ 'static` removed from `dyn Fn()`");
+    }
+
+    #[test]
+    fn fields() {
+        let a: ItemStruct = parse_quote! { struct A(pub Box<dyn Any + Send>, pub u8); };
+        let b: ItemStruct = parse_quote! { struct A(pub Box<dyn Any + Sync>, pub u8); };
+
+        assert_eq!(
+            bound_changes(&a.fields, &b.fields).unwrap(),
+            "`+ Send` removed from `dyn Any`, `+ Sync` added to `dyn Any`"
+        );
+    }
+
+    #[test]
+    fn other_changes() {
+        let changes = signature_changes(
+            parse_quote! { fn f(e: Box<dyn Error>) {} },
+            parse_quote! { fn f(e: Box<dyn Error + Send>, other: u8) {} },
+        );
+
+        assert_eq!(changes, None);
+    }
+
+    #[test]
+    fn principal_trait_change() {
+        let changes = signature_changes(
+            parse_quote! { fn f(e: Box<dyn Error + Send>) {} },
+            parse_quote! { fn f(e: Box<dyn Display + Send>) {} },
+        );
+
+        assert_eq!(changes, None);
+    }
+}
@@ -0,0 +1,272 @@
+use std::{collections::HashMap, iter};
+
+use syn::{
+    visit::{self, Visit},
+    visit_mut::{self, VisitMut},
+    GenericArgument, Generics, Ident, ItemMod, ItemType, Lifetime, Path, PathArguments, Type,
+    TypePath,
+};
+
+use crate::ast::CrateAst;
+
+use super::imports::PathResolver;
+
+// Aliases which refer to other aliases are expanded up to this depth.
+const MAX_DEPTH: usize = 8;
+
+/// Replaces the uses of the type aliases defined in the crate with the type
+/// they stand for, so that switching between an alias and its definition is
+/// not reported as a change.
+///
+/// Alias definitions are kept as they are written.
+pub(crate) fn expand(program: &CrateAst, resolver: &PathResolver) -> CrateAst {
+    let mut collector = AliasCollector::default();
+    collector.visit_file(program.ast());
+
+    let mut expanded = program.clone();
+
+    if !collector.aliases.is_empty() {
+        AliasExpander {
+            aliases: &collector.aliases,
+            resolver,
+            path: Vec::new(),
+            depth: 0,
+        }
+        .visit_file_mut(&mut expanded.0);
+    }
+
+    expanded
+}
+
+struct Alias {
+    // The module the alias is defined in, which the paths of its definition
+    // are relative to.
+    module: Vec<Ident>,
+    generics: Generics,
+    ty: Type,
+}
+
+impl Alias {
+    /// Returns the definition of the alias, with its generic parameters
+    /// replaced by the arguments of `use_path`. Missing arguments are
+    /// replaced by the parameter default, if any.
+    fn instantiate(&self, use_path: &Path, definition: Type) -> Type {
+        let args = match &use_path.segments.last().unwrap().arguments {
+            PathArguments::AngleBracketed(args) => args.args.iter().collect(),
+            _ => Vec::new(),
+        };
+
+        let types = args.iter().filter_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        });
+        let lifetimes = args.iter().filter_map(|arg| match arg {
+            GenericArgument::Lifetime(lifetime) => Some(lifetime),
+            _ => None,
+        });
+
+        let mut substitution = Substitution::default();
+
+        for (param, arg) in self
+            .generics
+            .type_params()
+            .zip(types.map(Some).chain(iter::repeat(None)))
+        {
+            if let Some(ty) = arg.or(param.default.as_ref()) {
+                substitution.types.insert(param.ident.clone(), ty.clone());
+            }
+        }
+
+        for (param, arg) in self.generics.lifetimes().zip(lifetimes) {
+            substitution
+                .lifetimes
+                .insert(param.lifetime.ident.clone(), arg.clone());
+        }
+
+        let mut ty = definition;
+        substitution.visit_type_mut(&mut ty);
+
+        ty
+    }
+}
+
+#[derive(Default)]
+struct AliasCollector {
+    aliases: HashMap<Vec<Ident>, Alias>,
+    path: Vec<Ident>,
+}
+
+impl<'ast> Visit<'ast> for AliasCollector {
+    fn visit_item_mod(&mut self, mod_: &'ast ItemMod) {
+        self.path.push(mod_.ident.clone());
+        visit::visit_item_mod(self, mod_);
+        self.path.pop().unwrap();
+    }
+
+    fn visit_item_type(&mut self, i: &'ast ItemType) {
+        let path = self
+            .path
+            .iter()
+            .chain(iter::once(&i.ident))
+            .cloned()
+            .collect();
+
+        let alias = Alias {
+            module: self.path.clone(),
+            generics: i.generics.clone(),
+            ty: (*i.ty).clone(),
+        };
+
+        self.aliases.insert(path, alias);
+    }
+}
+
+struct AliasExpander<'a> {
+    aliases: &'a HashMap<Vec<Ident>, Alias>,
+    resolver: &'a PathResolver,
+    path: Vec<Ident>,
+    depth: usize,
+}
+
+impl<'a> VisitMut for AliasExpander<'a> {
+    fn visit_item_mod_mut(&mut self, mod_: &mut ItemMod) {
+        self.path.push(mod_.ident.clone());
+        visit_mut::visit_item_mod_mut(self, mod_);
+        self.path.pop().unwrap();
+    }
+
+    fn visit_item_type_mut(&mut self, _: &mut ItemType) {}
+
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        // Generic arguments may be aliases too.
+        visit_mut::visit_type_mut(self, ty);
+
+        let path = match ty {
+            Type::Path(TypePath { qself: None, path }) => path,
+            _ => return,
+        };
+
+        let alias = match self
+            .aliases
+            .get(&self.resolver.absolute_path(&self.path, path))
+        {
+            Some(alias) if self.depth < MAX_DEPTH => alias,
+            _ => return,
+        };
+
+        // The definition is expanded where it is written, then instantiated.
+        let mut definition = alias.ty.clone();
+        AliasExpander {
+            aliases: self.aliases,
+            resolver: self.resolver,
+            path: alias.module.clone(),
+            depth: self.depth + 1,
+        }
+        .visit_type_mut(&mut definition);
+
+        *ty = alias.instantiate(path, definition);
+    }
+}
+
+#[derive(Default)]
+struct Substitution {
+    types: HashMap<Ident, Type>,
+    lifetimes: HashMap<Ident, Lifetime>,
+}
+
+impl VisitMut for Substitution {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::Path(TypePath { qself: None, path }) = ty {
+            if let Some(substitute) = path.get_ident().and_then(|ident| self.types.get(ident)) {
+                *ty = substitute.clone();
+                return;
+            }
+        }
+
+        visit_mut::visit_type_mut(self, ty);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if let Some(substitute) = self.lifetimes.get(&lifetime.ident) {
+            *lifetime = substitute.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn expand_str(program: CrateAst) -> String {
+        let resolver = PathResolver::new(&program);
+        expand(&program, &resolver).0.to_token_stream().to_string()
+    }
+
+    #[test]
+    fn expands_simple_alias() {
+        let left = expand_str(parse_quote! {
+            type Id = u32;
+            pub fn f(id: Id) {}
+        });
+        let right = expand_str(parse_quote! {
+            type Id = u32;
+            pub fn f(id: u32) {}
+        });
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn substitutes_generic_arguments() {
+        let left = expand_str(parse_quote! {
+            pub mod error {
+                pub struct Error;
+                pub type Result<T, E = crate::error::Error> = std::result::Result<T, E>;
+            }
+
+            use crate::error::Result;
+
+            pub fn f<'a>() -> Result<&'a u8> {}
+        });
+        let right = expand_str(parse_quote! {
+            pub mod error {
+                pub struct Error;
+                pub type Result<T, E = crate::error::Error> = std::result::Result<T, E>;
+            }
+
+            use crate::error::Result;
+
+            pub fn f<'a>() -> std::result::Result<&'a u8, crate::error::Error> {}
+        });
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn expands_nested_aliases() {
+        let left = expand_str(parse_quote! {
+            type A = u8;
+            type B = Vec<A>;
+            pub struct S(pub B);
+        });
+        let right = expand_str(parse_quote! {
+            type A = u8;
+            type B = Vec<A>;
+            pub struct S(pub Vec<u8>);
+        });
+
+        assert_eq!(left, right);
+    }
+
+    #[test]
+    fn cyclic_aliases_terminate() {
+        expand_str(parse_quote! {
+            type A = B;
+            type B = A;
+            pub fn f(a: A) {}
+        });
+    }
+}
@@ -1,34 +1,52 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use syn::{
     punctuated::Punctuated,
     token::Comma,
     visit::{self, Visit},
-    Field, Fields, FieldsNamed, FieldsUnnamed, Generics, Ident, ItemEnum, ItemMod, ItemStruct,
-    Variant, Visibility,
+    Attribute, Field, Fields, FieldsNamed, FieldsUnnamed, GenericParam, Generics, Ident, ItemEnum,
+    ItemMod, ItemStruct, Variant, Visibility,
 };
 
+use quote::ToTokens;
+
 use tap::Conv;
 
 #[cfg(test)]
 use syn::parse::{Parse, ParseStream, Result as ParseResult};
 
-use crate::diagnosis::{DiagnosisCollector, DiagnosisItem, DiagnosticGenerator};
+use crate::{
+    diagnosis::{DiagnosisCollector, DiagnosisItem, DiagnosisRules, DiagnosticGenerator},
+    rules::RuleId,
+};
 
-use super::{trait_impls::TraitImplMetadata, ItemKind, ItemPath};
+use super::{
+    attributes::ItemAttributes,
+    sized,
+    trait_impls::TraitImplMetadata,
+    trait_objects::{self, ContainsTypes},
+    utils::{self, display_code, display_where_clause, render_block},
+    variance::{self, Variance},
+    ItemKind, ItemPath,
+};
 
-#[derive(Clone, Debug, Default, PartialEq)]
-pub(crate) struct TypeVisitor {
-    types: HashMap<ItemPath, ItemKind>,
+#[derive(Debug, PartialEq)]
+pub(crate) struct TypeVisitor<'a> {
+    types: BTreeMap<ItemPath, ItemKind>,
     path: Vec<Ident>,
+    unanalyzable: &'a mut BTreeMap<ItemPath, String>,
 }
 
-impl TypeVisitor {
-    pub(crate) fn new() -> TypeVisitor {
-        TypeVisitor::default()
+impl<'a> TypeVisitor<'a> {
+    pub(crate) fn new(unanalyzable: &'a mut BTreeMap<ItemPath, String>) -> TypeVisitor<'a> {
+        TypeVisitor {
+            types: BTreeMap::new(),
+            path: Vec::new(),
+            unanalyzable,
+        }
     }
 
-    pub(crate) fn types(self) -> HashMap<ItemPath, ItemKind> {
+    pub(crate) fn types(self) -> BTreeMap<ItemPath, ItemKind> {
         self.types
     }
 
@@ -41,12 +59,11 @@ impl TypeVisitor {
     }
 
     fn add_type(&mut self, path: ItemPath, kind: ItemKind) {
-        let tmp = self.types.insert(path, kind);
-        assert!(tmp.is_none(), "Duplicate item definition");
+        super::add_item(&mut self.types, self.unanalyzable, path, kind);
     }
 }
 
-impl<'ast> Visit<'ast> for TypeVisitor {
+impl<'a, 'ast> Visit<'ast> for TypeVisitor<'a> {
     fn visit_item_mod(&mut self, mod_: &'ast ItemMod) {
         if matches!(mod_.vis, Visibility::Public(_)) {
             self.add_path_segment(mod_.ident.clone());
@@ -61,8 +78,11 @@ impl<'ast> Visit<'ast> for TypeVisitor {
         }
 
         let k = ItemPath::new(self.path.clone(), i.ident.clone());
+        let variances = variance::infer(&i.generics, i.fields.iter().map(|field| &field.ty));
         let v = StructMetadata::new(i.generics.clone(), i.fields.clone())
             .conv::<TypeMetadata>()
+            .with_attributes(&i.attrs)
+            .with_variances(variances)
             .into();
 
         self.add_type(k, v);
@@ -74,8 +94,12 @@ impl<'ast> Visit<'ast> for TypeVisitor {
         }
 
         let k = ItemPath::new(self.path.clone(), i.ident.clone());
+        let fields = i.variants.iter().flat_map(|variant| &variant.fields);
+        let variances = variance::infer(&i.generics, fields.map(|field| &field.ty));
         let v = EnumMetadata::new(i.generics.clone(), i.variants.clone())
             .conv::<TypeMetadata>()
+            .with_attributes(&i.attrs)
+            .with_variances(variances)
             .into();
 
         self.add_type(k, v);
@@ -85,35 +109,211 @@ impl<'ast> Visit<'ast> for TypeVisitor {
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct TypeMetadata {
     inner: InnerTypeMetadata,
+    attrs: ItemAttributes,
     traits: Vec<TraitImplMetadata>,
-}
-
-#[cfg(test)]
-impl TypeMetadata {
-    pub(crate) fn traits(&self) -> &[TraitImplMetadata] {
-        &self.traits
-    }
+    // The variance of each lifetime and type parameter, inferred from every
+    // field, including the private ones.
+    variances: Vec<(String, Variance)>,
 }
 
 impl TypeMetadata {
     fn new(inner: InnerTypeMetadata) -> TypeMetadata {
         TypeMetadata {
             inner,
+            attrs: ItemAttributes::default(),
             traits: Vec::new(),
+            variances: Vec::new(),
         }
     }
 
+    fn with_attributes(mut self, attrs: &[Attribute]) -> TypeMetadata {
+        self.attrs = ItemAttributes::new(attrs);
+        self
+    }
+
+    fn with_variances(mut self, variances: Vec<(String, Variance)>) -> TypeMetadata {
+        self.variances = variances;
+        self
+    }
+
+    // Describes the parameters whose variance now rejects conversions which
+    // were allowed.
+    fn variance_restrictions(&self, other: &TypeMetadata) -> Option<String> {
+        let restrictions = self
+            .variances
+            .iter()
+            .filter_map(|(name, previous)| {
+                let (_, current) = other.variances.iter().find(|(other, _)| other == name)?;
+
+                previous
+                    .is_restricted_by(*current)
+                    .then(|| format!("`{}` is now {} instead of {}", name, current, previous))
+            })
+            .collect::<Vec<_>>();
+
+        (!restrictions.is_empty()).then(|| restrictions.join(", "))
+    }
+
+    pub(crate) fn traits(&self) -> &[TraitImplMetadata] {
+        &self.traits
+    }
+
+    /// Whether the type also defines a value, as tuple and unit structs have
+    /// a constructor with their name.
+    pub(crate) fn has_constructor(&self) -> bool {
+        match &self.inner {
+            InnerTypeMetadata::Struct(s) => !matches!(s.fields, Fields::Named(_)),
+            InnerTypeMetadata::Enum(_) => false,
+        }
+    }
+
+    pub(crate) fn traits_mut(&mut self) -> &mut [TraitImplMetadata] {
+        &mut self.traits
+    }
+
     pub(crate) fn add_trait_impl(&mut self, impl_: TraitImplMetadata) {
-        self.traits.push(impl_);
+        // Implementations are kept sorted so that reordering impl blocks in
+        // the source code does not change the metadata.
+        let key = impl_.sort_key();
+        let idx = self
+            .traits
+            .partition_point(|trait_| trait_.sort_key() <= key);
+
+        self.traits.insert(idx, impl_);
+    }
+
+    /// Renders the type and its trait implementations as pseudo-Rust, named
+    /// by its full path.
+    pub(crate) fn render(&self, path: &ItemPath) -> Vec<String> {
+        let mut lines = self.attrs.render();
+        lines.extend(self.inner.render(path));
+
+        for trait_ in &self.traits {
+            lines.extend(trait_.render(path));
+        }
+
+        lines
+    }
+
+    /// The number of generic arguments which must be given when naming the
+    /// type: its type and const parameters which have no default value.
+    pub(crate) fn required_generic_args(&self) -> usize {
+        self.inner
+            .generics()
+            .params
+            .iter()
+            .filter(|param| match param {
+                GenericParam::Type(ty) => ty.default.is_none(),
+                GenericParam::Const(c) => c.default.is_none(),
+                GenericParam::Lifetime(_) => false,
+            })
+            .count()
+    }
+
+    pub(crate) fn visit_fields<'a>(&'a self, visitor: &mut impl Visit<'a>) {
+        match &self.inner {
+            InnerTypeMetadata::Struct(s) => s.fields.visit_types(visitor),
+            InnerTypeMetadata::Enum(e) => e.variants.visit_types(visitor),
+        }
+    }
+
+    /// The type this type dereferences to, if it implements `Deref`.
+    pub(crate) fn deref_target(&self) -> Option<&ItemPath> {
+        self.traits.iter().find_map(TraitImplMetadata::deref_target)
     }
 
-    fn find_trait(&self, name: &Ident) -> Option<&TraitImplMetadata> {
+    pub(crate) fn rename_deref_target(&mut self, renames: &BTreeMap<ItemPath, ItemPath>) {
+        self.traits
+            .iter_mut()
+            .for_each(|trait_| trait_.rename_deref_target(renames));
+    }
+
+    // Finds the implementation matching `impl_`, which `owner` defines. A
+    // type may implement a trait several times with different arguments, such
+    // as `From<A>` and `From<B>`: the implementation with the same arguments
+    // is looked for, or the only one when both types implement the trait once.
+    fn find_trait(
+        &self,
+        impl_: &TraitImplMetadata,
+        owner: &TypeMetadata,
+    ) -> Option<&TraitImplMetadata> {
+        let name = impl_.trait_name();
+        let same_trait = || {
+            self.traits
+                .iter()
+                .filter(move |trait_| trait_.trait_name() == name)
+        };
+
+        same_trait()
+            .find(|trait_| trait_.trait_generic_args() == impl_.trait_generic_args())
+            .or_else(|| {
+                let implemented_once = same_trait().count() == 1
+                    && owner
+                        .traits
+                        .iter()
+                        .filter(|t| t.trait_name() == name)
+                        .count()
+                        == 1;
+
+                implemented_once.then(|| same_trait().next()).flatten()
+            })
+    }
+
+    /// Whether the type implements `std::error::Error`.
+    fn is_error(&self) -> bool {
         self.traits
             .iter()
-            .find(|trait_| trait_.trait_name() == name)
+            .any(|trait_| trait_.trait_name() == "Error")
     }
 }
 
+// Reports the removal of an implementation of an error type. Error types are
+// mostly used through `?`, which relies on `Error` and `From`.
+fn error_impl_removal(
+    impl_: &TraitImplMetadata,
+    path: &ItemPath,
+    collector: &mut DiagnosisCollector,
+) {
+    let (rule, message) = match impl_.trait_name().to_string().as_str() {
+        "Error" => (
+            RuleId::ErrorImplRemoved,
+            "`?` can no longer convert it into `Box<dyn Error>`".to_owned(),
+        ),
+        "From" => (
+            RuleId::ErrorConversionRemoved,
+            format!(
+                "`?` can no longer convert `{}` into it",
+                display_code(impl_.trait_generic_args().map(|args| &args.args))
+            ),
+        ),
+        _ => return impl_.removal_diagnosis(path, collector),
+    };
+
+    collector.add(
+        DiagnosisItem::new(rule, path.clone(), Some(impl_.trait_name().clone()))
+            .with_message(message)
+            .with_details(vec![utils::defined_in(impl_.module())]),
+    );
+}
+
+// Reports the `#[non_exhaustive]` changes of an error type with their own
+// rules, as error types are the enums most often matched on.
+fn error_attribute_diagnosis(diag: DiagnosisItem, path: &ItemPath) -> DiagnosisItem {
+    let (rule, message) = match diag.rule() {
+        RuleId::NonExhaustiveAdded => (
+            RuleId::ErrorNonExhaustiveAdded,
+            "now #[non_exhaustive]: matching on it requires a wildcard arm",
+        ),
+        RuleId::NonExhaustiveRemoved => (
+            RuleId::ErrorNonExhaustiveRemoved,
+            "no longer #[non_exhaustive]: adding a variant will be a breaking change",
+        ),
+        _ => return diag,
+    };
+
+    DiagnosisItem::new(rule, path.clone(), None).with_message(message)
+}
+
 impl DiagnosticGenerator for TypeMetadata {
     fn modification_diagnosis(
         &self,
@@ -122,34 +322,55 @@ impl DiagnosticGenerator for TypeMetadata {
         collector: &mut DiagnosisCollector,
     ) {
         if self.inner != other.inner {
-            collector.add(DiagnosisItem::modification(path.clone(), None));
+            // Code outside of the crate can't build or exhaustively match
+            // types which are non-exhaustive in both versions.
+            let non_exhaustive = self.attrs.is_non_exhaustive() && other.attrs.is_non_exhaustive();
+            collector.add(
+                self.inner
+                    .modification_diagnosis(&other.inner, path, non_exhaustive),
+            );
+        }
+
+        if let Some(restrictions) = self.variance_restrictions(other) {
+            collector.add(
+                DiagnosisItem::new(RuleId::VarianceRestricted, path.clone(), None)
+                    .with_message(restrictions),
+            );
+        }
+
+        let is_error = self.is_error();
+
+        if is_error {
+            let mut attr_collector = DiagnosisCollector::new();
+            self.attrs
+                .diagnosis(&other.attrs, path, &mut attr_collector);
+            attr_collector
+                .finalize()
+                .into_iter()
+                .map(|diag| error_attribute_diagnosis(diag, path))
+                .for_each(|diag| collector.add(diag));
+        } else {
+            self.attrs.diagnosis(&other.attrs, path, collector);
         }
 
         // TODO: replace these O(n²) zone with a faster implentation, perhaps by
         // using an ordered list or a HashMap.
 
         for trait_1 in self.traits.iter() {
-            match other.find_trait(trait_1.trait_name()) {
+            match other.find_trait(trait_1, self) {
                 Some(trait_2) if trait_1 == trait_2 => {}
 
-                Some(_) => collector.add(DiagnosisItem::modification(
-                    path.clone(),
-                    Some(trait_1.trait_name().clone()),
-                )),
+                Some(trait_2) => trait_1.modification_diagnosis(trait_2, path, collector),
+
+                None if is_error => error_impl_removal(trait_1, path, collector),
 
-                None => collector.add(DiagnosisItem::removal(
-                    path.clone(),
-                    Some(trait_1.trait_name().clone()),
-                )),
+                None => trait_1.removal_diagnosis(path, collector),
             }
         }
 
         for trait_2 in other.traits.iter() {
-            if self.find_trait(trait_2.trait_name()).is_none() {
-                collector.add(DiagnosisItem::addition(
-                    path.clone(),
-                    Some(trait_2.trait_name().clone()),
-                ));
+            if self.find_trait(trait_2, other).is_none() {
+                trait_2.addition_diagnosis(path, collector);
             }
         }
     }
@@ -167,10 +388,18 @@ impl From<EnumMetadata> for TypeMetadata {
     }
 }
 
+fn render_field(field: &Field) -> String {
+    let mut field = field.clone();
+    field.attrs.clear();
+    display_code(field)
+}
+
 #[cfg(test)]
 impl Parse for TypeMetadata {
     fn parse(input: ParseStream) -> ParseResult<TypeMetadata> {
-        Ok(TypeMetadata::new(input.parse()?))
+        let attrs = input.fork().call(Attribute::parse_outer)?;
+
+        Ok(TypeMetadata::new(input.parse()?).with_attributes(&attrs))
     }
 }
 
@@ -180,6 +409,218 @@ pub(crate) enum InnerTypeMetadata {
     Enum(EnumMetadata),
 }
 
+impl InnerTypeMetadata {
+    fn render(&self, path: &ItemPath) -> Vec<String> {
+        match self {
+            InnerTypeMetadata::Struct(s) => {
+                let header = format!("pub struct {}{}", path, display_code(&s.generics));
+                let where_clause = display_where_clause(&s.generics);
+
+                match &s.fields {
+                    Fields::Named(fields) => render_block(
+                        format!("{}{}", header, where_clause),
+                        fields
+                            .named
+                            .iter()
+                            .map(|field| format!("{},", render_field(field)))
+                            .collect(),
+                    ),
+                    Fields::Unnamed(fields) => {
+                        let fields = fields.unnamed.iter().map(render_field).collect::<Vec<_>>();
+                        vec![format!(
+                            "{}({}){};",
+                            header,
+                            fields.join(", "),
+                            where_clause
+                        )]
+                    }
+                    Fields::Unit => vec![format!("{}{};", header, where_clause)],
+                }
+            }
+
+            InnerTypeMetadata::Enum(e) => {
+                let header = format!(
+                    "pub enum {}{}{}",
+                    path,
+                    display_code(&e.generics),
+                    display_where_clause(&e.generics)
+                );
+                let variants = e
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        let mut variant = variant.clone();
+                        variant.attrs.clear();
+                        variant
+                            .fields
+                            .iter_mut()
+                            .for_each(|field| field.attrs.clear());
+
+                        format!("{},", display_code(variant))
+                    })
+                    .collect();
+
+                render_block(header, variants)
+            }
+        }
+    }
+
+    fn modification_diagnosis(
+        &self,
+        other: &InnerTypeMetadata,
+        path: &ItemPath,
+        non_exhaustive: bool,
+    ) -> DiagnosisItem {
+        let rule = DiagnosisRules::ITEM.modification;
+
+        if let Some(message) = self.extension(other, non_exhaustive) {
+            return DiagnosisItem::new(RuleId::NonExhaustiveExtended, path.clone(), None)
+                .with_message(message);
+        }
+
+        if let Some(message) = self.defaulted_generics_addition(other) {
+            return DiagnosisItem::new(RuleId::DefaultedGenericAdded, path.clone(), None)
+                .with_message(message);
+        }
+
+        if self.same_shape(other) {
+            if let Some(diagnosis) = sized::diagnosis(self.generics(), other.generics(), path) {
+                return diagnosis;
+            }
+        }
+
+        match (self, other) {
+            (InnerTypeMetadata::Struct(a), InnerTypeMetadata::Struct(b))
+                if a.generics == b.generics =>
+            {
+                trait_objects::modification_diagnosis(&a.fields, &b.fields, rule, path)
+            }
+
+            (InnerTypeMetadata::Enum(a), InnerTypeMetadata::Enum(b))
+                if a.generics == b.generics =>
+            {
+                trait_objects::modification_diagnosis(&a.variants, &b.variants, rule, path)
+            }
+
+            _ => DiagnosisItem::new(rule, path.clone(), None),
+        }
+    }
+
+    fn generics(&self) -> &Generics {
+        match self {
+            InnerTypeMetadata::Struct(s) => &s.generics,
+            InnerTypeMetadata::Enum(e) => &e.generics,
+        }
+    }
+
+    // Whether both types have the same fields or variants.
+    fn same_shape(&self, other: &InnerTypeMetadata) -> bool {
+        match (self, other) {
+            (InnerTypeMetadata::Struct(a), InnerTypeMetadata::Struct(b)) => a.fields == b.fields,
+            (InnerTypeMetadata::Enum(a), InnerTypeMetadata::Enum(b)) => a.variants == b.variants,
+            _ => false,
+        }
+    }
+
+    /// Describes the variants or public fields added to a non-exhaustive
+    /// type, if nothing else changed.
+    fn extension(&self, other: &InnerTypeMetadata, non_exhaustive: bool) -> Option<String> {
+        if !non_exhaustive || self.generics() != other.generics() {
+            return None;
+        }
+
+        let (kind, added) = match (self, other) {
+            (InnerTypeMetadata::Enum(a), InnerTypeMetadata::Enum(b)) => {
+                let added = b
+                    .variants
+                    .iter()
+                    .filter(|variant| !a.variants.contains(variant))
+                    .map(|variant| variant.ident.to_string())
+                    .collect::<Vec<_>>();
+
+                // Every previous variant must be kept as is.
+                if b.variants.len() != a.variants.len() + added.len() {
+                    return None;
+                }
+
+                ("variant", added)
+            }
+
+            (InnerTypeMetadata::Struct(a), InnerTypeMetadata::Struct(b)) => {
+                let (previous, current) = match (&a.fields, &b.fields) {
+                    (Fields::Named(a), Fields::Named(b)) => (&a.named, &b.named),
+                    (Fields::Unnamed(a), Fields::Unnamed(b)) => (&a.unnamed, &b.unnamed),
+                    _ => return None,
+                };
+
+                // Tuple fields are named by their position, so they can only
+                // be appended.
+                if current.len() < previous.len()
+                    || previous.iter().zip(current).any(|(a, b)| a != b)
+                {
+                    return None;
+                }
+
+                let added = current
+                    .iter()
+                    .enumerate()
+                    .skip(previous.len())
+                    .map(|(idx, field)| match &field.ident {
+                        Some(ident) => ident.to_string(),
+                        None => idx.to_string(),
+                    })
+                    .collect();
+
+                ("field", added)
+            }
+
+            _ => return None,
+        };
+
+        Some(added_items(kind, &added))
+    }
+
+    /// Describes the generic parameters with a default value appended to the
+    /// type, if nothing else changed. Existing code keeps naming the type
+    /// without them.
+    fn defaulted_generics_addition(&self, other: &InnerTypeMetadata) -> Option<String> {
+        let (a, b) = (self.generics(), other.generics());
+
+        if !self.same_shape(other)
+            || a.where_clause != b.where_clause
+            || b.params.len() <= a.params.len()
+            || a.params.iter().zip(&b.params).any(|(a, b)| a != b)
+        {
+            return None;
+        }
+
+        let added = b
+            .params
+            .iter()
+            .skip(a.params.len())
+            .map(|param| match param {
+                GenericParam::Type(ty) if ty.default.is_some() => {
+                    Some(ty.to_token_stream().to_string())
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(added_items("generic parameter", &added))
+    }
+}
+
+fn added_items(kind: &str, names: &[String]) -> String {
+    let plural = if names.len() > 1 { "s" } else { "" };
+    let names = names
+        .iter()
+        .map(|name| format!("`{}`", name))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("{}{} {} added", kind, plural, names)
+}
+
 impl From<StructMetadata> for InnerTypeMetadata {
     fn from(v: StructMetadata) -> InnerTypeMetadata {
         InnerTypeMetadata::Struct(v)
@@ -1,4 +1,91 @@
-use syn::{AngleBracketedGenericArguments, Ident, Path, PathArguments, Type, TypePath};
+use std::{collections::BTreeMap, iter};
+
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+
+use syn::{
+    ext::IdentExt,
+    parse::{ParseStream, Parser, Result as ParseResult},
+    AngleBracketedGenericArguments, Attribute, FnArg, Generics, Ident, Lit, Path, PathArguments,
+    Receiver, Signature, Token, Type, TypePath,
+};
+
+use super::{attributes::ItemAttributes, ItemPath};
+
+/// The arguments of an attribute macro, such as `js_name = parseInput` or
+/// `constructor` in `#[wasm_bindgen(js_name = parseInput, constructor)]`.
+/// Their value is kept as written, without the quotes of string literals.
+#[derive(Debug, Default)]
+pub(crate) struct MacroArgs(BTreeMap<String, Option<String>>);
+
+impl MacroArgs {
+    /// Returns the arguments of the `name` attribute, if there is one. The
+    /// attribute may be written with its full path, such as
+    /// `#[pyo3::pyfunction]`.
+    pub(crate) fn find(attrs: &[Attribute], name: &str) -> Option<MacroArgs> {
+        let attr = attrs.iter().find(|attr| {
+            attr.path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == name)
+        })?;
+
+        let group = match attr.tokens.clone().into_iter().next() {
+            Some(TokenTree::Group(group)) => group.stream(),
+            _ => return Some(MacroArgs::default()),
+        };
+
+        Some(MacroArgs(
+            parse_macro_args.parse2(group).unwrap_or_default(),
+        ))
+    }
+
+    pub(crate) fn has(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    pub(crate) fn value(&self, name: &str) -> Option<&str> {
+        self.0.get(name)?.as_deref()
+    }
+
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+}
+
+fn parse_macro_args(input: ParseStream) -> ParseResult<BTreeMap<String, Option<String>>> {
+    let mut args = BTreeMap::new();
+
+    while !input.is_empty() {
+        // Some arguments are keywords, such as `final`.
+        let name = Ident::parse_any(input)?.to_string();
+
+        let value = if input.parse::<Option<Token![=]>>()?.is_some() {
+            let mut value = TokenStream::new();
+            while !input.is_empty() && !input.peek(Token![,]) {
+                value.extend(Some(input.parse::<TokenTree>()?));
+            }
+
+            let value = match syn::parse2::<Lit>(value.clone()) {
+                Ok(Lit::Str(s)) => s.value(),
+                _ => display_code(value),
+            };
+            Some(value)
+        } else if input.peek(syn::token::Paren) {
+            // `getter(name)`, or `signature(a, b = 1)` in older versions.
+            let content;
+            syn::parenthesized!(content in input);
+            Some(display_code(content.parse::<TokenStream>()?))
+        } else {
+            None
+        };
+
+        args.insert(name, value);
+        input.parse::<Option<Token![,]>>()?;
+    }
+
+    Ok(args)
+}
 
 pub(crate) fn extract_name_and_generic_args(
     ty: &Type,
@@ -23,15 +110,21 @@ fn extract_ending_generics(path: &Path) -> Option<&AngleBracketedGenericArgument
 pub(crate) fn extract_name_and_generic_args_from_path(
     p: &Path,
 ) -> Option<(&Ident, Option<&AngleBracketedGenericArguments>)> {
-    let unique_segment = match p.segments.len() {
-        1 => p.segments.first().unwrap(),
-        // TODO: handle paths with more than one segment in them
-        _ => return None,
-    };
+    // Traits are identified by their name, such as `Error` for
+    // `std::error::Error`.
+    let last_segment = p.segments.last()?;
+    if p.segments
+        .iter()
+        .rev()
+        .skip(1)
+        .any(|segment| !segment.arguments.is_empty())
+    {
+        return None;
+    }
 
-    let name = &unique_segment.ident;
+    let name = &last_segment.ident;
 
-    let generics = match &unique_segment.arguments {
+    let generics = match &last_segment.arguments {
         syn::PathArguments::None => None,
         syn::PathArguments::AngleBracketed(args) => Some(args),
         // TODO: handle paths with parenthesis (for instance Fn(T) -> U).
@@ -40,3 +133,173 @@ pub(crate) fn extract_name_and_generic_args_from_path(
 
     Some((name, generics))
 }
+
+/// Describes the module an impl block is located in, for verbose diagnostics.
+pub(crate) fn defined_in(module: &[Ident]) -> String {
+    format!("defined in `{}`", module_path(module))
+}
+
+/// Returns the path of a module, starting with `crate`.
+pub(crate) fn module_path(module: &[Ident]) -> String {
+    iter::once("crate".to_owned())
+        .chain(module.iter().map(ToString::to_string))
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// Describes the change of the generic arguments of the type an impl block
+/// is written for, such as `A<u8>` becoming `A<u16>`.
+pub(crate) fn impl_target_change(
+    type_name: &Ident,
+    previous: &Option<AngleBracketedGenericArguments>,
+    current: &Option<AngleBracketedGenericArguments>,
+) -> String {
+    let target = |args: &Option<AngleBracketedGenericArguments>| {
+        format!("{}{}", type_name, display_tokens(args))
+    };
+
+    format!(
+        "impl target changed from {} to {}",
+        target(previous),
+        target(current)
+    )
+}
+
+/// Prints code the way it is usually written, for reports.
+pub(crate) fn display_code(tokens: impl ToTokens) -> String {
+    let mut output = display_tokens(tokens);
+
+    for (from, to) in [
+        (" :: ", "::"),
+        (" : ", ": "),
+        ("( ", "("),
+        (" )", ")"),
+        (" ;", ";"),
+    ] {
+        output = output.replace(from, to);
+    }
+
+    // The parentheses of calls and tuple structs, but not the ones of tuples,
+    // as in `-> (u8, u8)`.
+    let mut parts = output.split(" (");
+    let mut joined = parts.next().unwrap_or_default().to_owned();
+    for part in parts {
+        let is_name_char = |c: char| c.is_alphanumeric() || c == '_';
+        let after_name = joined
+            .chars()
+            .last()
+            .is_some_and(|c| is_name_char(c) || c == '>')
+            && !joined.ends_with("->")
+            && joined.rsplit(|c| !is_name_char(c)).next() != Some("mut");
+
+        joined.push_str(if after_name { "(" } else { " (" });
+        joined.push_str(part);
+    }
+
+    joined
+}
+
+/// Writes a call to a function or a method of `type_name`, the way users
+/// write it: `value.f(..)` for methods, `Type::f(..)` for associated
+/// functions, and `<Type as Trait>::f(..)` when it is defined by an
+/// implementation of `trait_name`.
+pub(crate) fn call_form(type_name: &Ident, sig: &Signature, trait_name: Option<&Ident>) -> String {
+    let has_args = sig.inputs.iter().any(|arg| matches!(arg, FnArg::Typed(_)));
+    let args = if has_args { ".." } else { "" };
+
+    let value = value_name(type_name);
+    let receiver = sig.receiver().map(|receiver| match receiver {
+        FnArg::Receiver(Receiver {
+            reference: Some(_),
+            mutability: Some(_),
+            ..
+        }) => format!("&mut {}", value),
+        FnArg::Receiver(Receiver {
+            reference: Some(_), ..
+        }) => format!("&{}", value),
+        _ => value.clone(),
+    });
+
+    match (trait_name, receiver) {
+        (None, Some(_)) => format!("{}.{}({})", value, sig.ident, args),
+        (None, None) => format!("{}::{}({})", type_name, sig.ident, args),
+        (Some(trait_name), receiver) => {
+            let args = receiver
+                .into_iter()
+                .chain(has_args.then(|| args.to_owned()))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            format!("<{} as {}>::{}({})", type_name, trait_name, sig.ident, args)
+        }
+    }
+}
+
+// The name of a variable holding a value of a type, such as `http_client` for
+// `HttpClient`.
+fn value_name(type_name: &Ident) -> String {
+    let mut name = String::new();
+
+    for (idx, c) in type_name.to_string().chars().enumerate() {
+        if c.is_uppercase() && idx > 0 {
+            name.push('_');
+        }
+        name.extend(c.to_lowercase());
+    }
+
+    name
+}
+
+/// Prints the `where` clause of an item, preceded by a space, if it has one.
+pub(crate) fn display_where_clause(generics: &Generics) -> String {
+    match &generics.where_clause {
+        Some(where_clause) => format!(" {}", display_code(where_clause)),
+        None => String::new(),
+    }
+}
+
+/// Renders an item whose members are written between braces, one per line.
+pub(crate) fn render_block(header: String, members: Vec<String>) -> Vec<String> {
+    if members.is_empty() {
+        return vec![format!("{} {{}}", header)];
+    }
+
+    iter::once(format!("{} {{", header))
+        .chain(members.into_iter().map(|member| format!("    {}", member)))
+        .chain(iter::once("}".to_owned()))
+        .collect()
+}
+
+/// Renders a function or a method as pseudo-Rust, named by its full path.
+pub(crate) fn render_fn(
+    attrs: &ItemAttributes,
+    signature: &Signature,
+    path: &ItemPath,
+) -> Vec<String> {
+    let signature = display_code(signature).replacen(
+        &format!("fn {}", signature.ident),
+        &format!("fn {}", path),
+        1,
+    );
+
+    let mut lines = attrs.render();
+    lines.push(format!("pub {};", signature));
+    lines
+}
+
+/// Prints tokens the way they are usually written, for messages.
+fn display_tokens(tokens: impl ToTokens) -> String {
+    let mut output = tokens.to_token_stream().to_string();
+
+    for (from, to) in [
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" ,", ","),
+        ("& ", "&"),
+    ] {
+        output = output.replace(from, to);
+    }
+
+    output
+}
@@ -0,0 +1,330 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use syn::{
+    GenericArgument, GenericParam, Generics, Lifetime, Path, PathArguments, ReturnType, Type,
+    TypeParamBound,
+};
+
+/// How the subtyping of a generic type follows the subtyping of one of its
+/// parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Variance {
+    /// The parameter is not used.
+    Bivariant,
+    Covariant,
+    Contravariant,
+    Invariant,
+}
+
+impl Variance {
+    // The variance of a parameter used both with `self` and `other`.
+    fn join(self, other: Variance) -> Variance {
+        match (self, other) {
+            (Variance::Bivariant, v) | (v, Variance::Bivariant) => v,
+            (a, b) if a == b => a,
+            _ => Variance::Invariant,
+        }
+    }
+
+    // The variance of a position which is `inner` relative to a position of
+    // variance `self`.
+    fn then(self, inner: Variance) -> Variance {
+        match (self, inner) {
+            (Variance::Bivariant, _) | (_, Variance::Bivariant) => Variance::Bivariant,
+            (Variance::Covariant, v) => v,
+            (Variance::Contravariant, Variance::Covariant) => Variance::Contravariant,
+            (Variance::Contravariant, Variance::Contravariant) => Variance::Covariant,
+            (Variance::Invariant, _) | (_, Variance::Invariant) => Variance::Invariant,
+        }
+    }
+
+    // Whether a type with `self` as variance can be used as a subtype, and as
+    // a supertype.
+    fn conversions(self) -> (bool, bool) {
+        match self {
+            Variance::Bivariant => (true, true),
+            Variance::Covariant => (true, false),
+            Variance::Contravariant => (false, true),
+            Variance::Invariant => (false, false),
+        }
+    }
+
+    /// Whether going from `self` to `other` rejects conversions which were
+    /// allowed.
+    pub(crate) fn is_restricted_by(self, other: Variance) -> bool {
+        let (sub, sup) = self.conversions();
+        let (other_sub, other_sup) = other.conversions();
+
+        (sub && !other_sub) || (sup && !other_sup)
+    }
+}
+
+impl Display for Variance {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Variance::Bivariant => "unused",
+            Variance::Covariant => "covariant",
+            Variance::Contravariant => "contravariant",
+            Variance::Invariant => "invariant",
+        }
+        .fmt(f)
+    }
+}
+
+// The types of the standard library which are invariant in their parameters,
+// because they allow mutation through a shared reference. Other types are
+// assumed to be covariant.
+const INVARIANT_TYPES: &[&str] = &[
+    "Cell",
+    "RefCell",
+    "UnsafeCell",
+    "OnceCell",
+    "Mutex",
+    "RwLock",
+    "AtomicPtr",
+];
+
+/// Infers the variance of the lifetime and type parameters of a type from the
+/// types of its fields, which must include the private ones.
+pub(crate) fn infer<'a>(
+    generics: &Generics,
+    fields: impl IntoIterator<Item = &'a Type>,
+) -> Vec<(String, Variance)> {
+    let names = generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(ty) => Some(ty.ident.to_string()),
+            GenericParam::Lifetime(lt) => Some(lt.lifetime.to_string()),
+            GenericParam::Const(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut inference = Inference {
+        params: names
+            .iter()
+            .map(|name| (name.clone(), Variance::Bivariant))
+            .collect(),
+    };
+
+    for ty in fields {
+        inference.visit_type(ty, Variance::Covariant);
+    }
+
+    // Kept in the order of declaration.
+    names
+        .into_iter()
+        .map(|name| {
+            let variance = inference.params[&name];
+            (name, variance)
+        })
+        .collect()
+}
+
+struct Inference {
+    params: BTreeMap<String, Variance>,
+}
+
+impl Inference {
+    fn record(&mut self, name: String, variance: Variance) {
+        if let Some(param) = self.params.get_mut(&name) {
+            *param = param.join(variance);
+        }
+    }
+
+    fn visit_lifetime(&mut self, lifetime: &Lifetime, position: Variance) {
+        self.record(lifetime.to_string(), position);
+    }
+
+    fn visit_type(&mut self, ty: &Type, position: Variance) {
+        match ty {
+            Type::Path(path) => {
+                // `<T as Trait>::Output` can be any type.
+                if let Some(qself) = &path.qself {
+                    self.visit_type(&qself.ty, position.then(Variance::Invariant));
+                    self.visit_path_arguments(&path.path, position.then(Variance::Invariant));
+                    return;
+                }
+
+                self.visit_path(&path.path, position);
+            }
+
+            Type::Reference(reference) => {
+                if let Some(lifetime) = &reference.lifetime {
+                    self.visit_lifetime(lifetime, position);
+                }
+
+                let elem = if reference.mutability.is_some() {
+                    position.then(Variance::Invariant)
+                } else {
+                    position
+                };
+                self.visit_type(&reference.elem, elem);
+            }
+
+            Type::Ptr(ptr) => {
+                let elem = if ptr.mutability.is_some() {
+                    position.then(Variance::Invariant)
+                } else {
+                    position
+                };
+                self.visit_type(&ptr.elem, elem);
+            }
+
+            Type::Array(array) => self.visit_type(&array.elem, position),
+            Type::Slice(slice) => self.visit_type(&slice.elem, position),
+            Type::Paren(paren) => self.visit_type(&paren.elem, position),
+            Type::Group(group) => self.visit_type(&group.elem, position),
+            Type::Tuple(tuple) => tuple
+                .elems
+                .iter()
+                .for_each(|elem| self.visit_type(elem, position)),
+
+            Type::BareFn(f) => {
+                f.inputs.iter().for_each(|input| {
+                    self.visit_type(&input.ty, position.then(Variance::Contravariant))
+                });
+                if let ReturnType::Type(_, output) = &f.output {
+                    self.visit_type(output, position);
+                }
+            }
+
+            Type::TraitObject(object) => self.visit_bounds(object.bounds.iter(), position),
+            Type::ImplTrait(impl_trait) => self.visit_bounds(impl_trait.bounds.iter(), position),
+
+            _ => {}
+        }
+    }
+
+    fn visit_path(&mut self, path: &Path, position: Variance) {
+        let first = &path.segments[0].ident;
+
+        if path.segments.len() == 1 && path.segments[0].arguments.is_empty() {
+            self.record(first.to_string(), position);
+            return;
+        }
+
+        // `T::Item` can be any type.
+        if self.params.contains_key(&first.to_string()) {
+            self.record(first.to_string(), position.then(Variance::Invariant));
+        }
+
+        let name = path.segments.last().unwrap().ident.to_string();
+        let container = if INVARIANT_TYPES.contains(&name.as_str()) {
+            Variance::Invariant
+        } else {
+            Variance::Covariant
+        };
+
+        self.visit_path_arguments(path, position.then(container));
+    }
+
+    fn visit_path_arguments(&mut self, path: &Path, position: Variance) {
+        for segment in &path.segments {
+            match &segment.arguments {
+                PathArguments::None => {}
+                PathArguments::AngleBracketed(args) => {
+                    for arg in &args.args {
+                        match arg {
+                            GenericArgument::Type(ty) => self.visit_type(ty, position),
+                            GenericArgument::Lifetime(lt) => self.visit_lifetime(lt, position),
+                            GenericArgument::Binding(binding) => {
+                                self.visit_type(&binding.ty, position)
+                            }
+                            GenericArgument::Constraint(_) | GenericArgument::Const(_) => {}
+                        }
+                    }
+                }
+                PathArguments::Parenthesized(args) => {
+                    args.inputs
+                        .iter()
+                        .for_each(|input| self.visit_type(input, position));
+                    if let ReturnType::Type(_, output) = &args.output {
+                        self.visit_type(output, position);
+                    }
+                }
+            }
+        }
+    }
+
+    // The parameters of traits are invariant, unlike the lifetime bound of a
+    // trait object.
+    fn visit_bounds<'a>(
+        &mut self,
+        bounds: impl Iterator<Item = &'a TypeParamBound>,
+        position: Variance,
+    ) {
+        for bound in bounds {
+            match bound {
+                TypeParamBound::Trait(bound) => {
+                    self.visit_path_arguments(&bound.path, position.then(Variance::Invariant))
+                }
+                TypeParamBound::Lifetime(lifetime) => self.visit_lifetime(lifetime, position),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, Fields, ItemStruct};
+
+    use super::*;
+
+    fn variances(s: ItemStruct) -> String {
+        let fields = match &s.fields {
+            Fields::Named(fields) => fields.named.iter().map(|f| &f.ty).collect::<Vec<_>>(),
+            Fields::Unnamed(fields) => fields.unnamed.iter().map(|f| &f.ty).collect(),
+            Fields::Unit => Vec::new(),
+        };
+
+        infer(&s.generics, fields)
+            .iter()
+            .map(|(name, variance)| format!("{}: {}", name, variance))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    #[test]
+    fn inferred_variances() {
+        assert_eq!(
+            variances(parse_quote! {
+                struct S<'a, 'b, T, U, V> {
+                    a: &'a T,
+                    b: &'b mut U,
+                    v: fn(V),
+                }
+            }),
+            "'a: covariant, 'b: covariant, T: covariant, U: invariant, V: contravariant"
+        );
+
+        assert_eq!(
+            variances(parse_quote! {
+                struct S<'a, T, U>(PhantomData<&'a ()>, Cell<T>, Box<dyn Fn(U) + 'a>, fn() -> T);
+            }),
+            "'a: covariant, T: invariant, U: invariant"
+        );
+
+        assert_eq!(
+            variances(parse_quote! {
+                struct S<T: Iterator>(Vec<T::Item>, fn(fn(T)));
+            }),
+            "T: invariant"
+        );
+    }
+
+    #[test]
+    fn restrictions() {
+        use Variance::*;
+
+        assert!(Covariant.is_restricted_by(Invariant));
+        assert!(Covariant.is_restricted_by(Contravariant));
+        assert!(Bivariant.is_restricted_by(Covariant));
+        assert!(!Invariant.is_restricted_by(Covariant));
+        assert!(!Covariant.is_restricted_by(Covariant));
+        assert!(!Covariant.is_restricted_by(Bivariant));
+    }
+}
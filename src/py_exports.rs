@@ -0,0 +1,500 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+use syn::{
+    visit::Visit, Attribute, Fields, FnArg, Ident, ImplItem, ItemEnum, ItemFn, ItemImpl,
+    ItemStruct, Pat, ReturnType, Signature, Type,
+};
+
+use crate::{
+    ast::CrateAst,
+    diagnosis::DiagnosisItem,
+    public_api::{display_code, ItemPath, MacroArgs},
+    rules::RuleId,
+};
+
+/// What an item exported to Python by PyO3 is.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum PyExportKind {
+    Function,
+    Class,
+    Constructor,
+    Method,
+    StaticMethod,
+    ClassMethod,
+    ClassAttribute,
+    Attribute,
+    Getter,
+    Setter,
+}
+
+impl Display for PyExportKind {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let name = match self {
+            PyExportKind::Function => "function",
+            PyExportKind::Class => "class",
+            PyExportKind::Constructor => "constructor",
+            PyExportKind::Method => "method",
+            PyExportKind::StaticMethod => "static method",
+            PyExportKind::ClassMethod => "class method",
+            PyExportKind::ClassAttribute => "class attribute",
+            PyExportKind::Attribute => "attribute",
+            PyExportKind::Getter => "getter",
+            PyExportKind::Setter => "setter",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// The items a crate exports to Python, by Python path and kind. The value
+/// describes what Python code sees, such as the parameters of a function.
+pub(crate) type PyExports = BTreeMap<(Vec<String>, PyExportKind), String>;
+
+/// Finds the items exported to Python by the `#[pyfunction]`, `#[pyclass]`
+/// and `#[pymethods]` attributes of a crate. The attributes are gone once the
+/// crate is expanded, so the AST must be read from the source files.
+///
+/// The functions are compared whether or not a `#[pymodule]` adds them to a
+/// module, which can't be known without running its code.
+pub(crate) fn extract(ast: &CrateAst) -> PyExports {
+    let mut classes = ClassVisitor::default();
+    classes.visit_file(ast.ast());
+
+    let mut visitor = ExportVisitor {
+        classes: classes.0,
+        exports: BTreeMap::new(),
+    };
+    visitor.visit_file(ast.ast());
+
+    visitor.exports
+}
+
+// The Python name of the classes, by Rust name. The `#[pymethods]` blocks
+// refer to them by their Rust name.
+#[derive(Default)]
+struct ClassVisitor(BTreeMap<String, String>);
+
+impl<'ast> Visit<'ast> for ClassVisitor {
+    fn visit_item_struct(&mut self, struct_: &'ast ItemStruct) {
+        if let Some(args) = MacroArgs::find(&struct_.attrs, "pyclass") {
+            let name = py_name(&struct_.attrs, &args, &struct_.ident);
+            self.0.insert(struct_.ident.to_string(), name);
+        }
+    }
+
+    fn visit_item_enum(&mut self, enum_: &'ast ItemEnum) {
+        if let Some(args) = MacroArgs::find(&enum_.attrs, "pyclass") {
+            let name = py_name(&enum_.attrs, &args, &enum_.ident);
+            self.0.insert(enum_.ident.to_string(), name);
+        }
+    }
+}
+
+struct ExportVisitor {
+    classes: BTreeMap<String, String>,
+    exports: PyExports,
+}
+
+impl ExportVisitor {
+    fn add(&mut self, path: Vec<String>, kind: PyExportKind, code: String) {
+        self.exports.insert((path, kind), code);
+    }
+}
+
+impl<'ast> Visit<'ast> for ExportVisitor {
+    fn visit_item_fn(&mut self, fn_: &'ast ItemFn) {
+        let args = match MacroArgs::find(&fn_.attrs, "pyfunction") {
+            Some(args) => args,
+            None => return,
+        };
+
+        let name = py_name(&fn_.attrs, &args, &fn_.sig.ident);
+        let code = python_signature(&name, &fn_.sig, &fn_.attrs, &args, 0);
+        self.add(vec![name], PyExportKind::Function, code);
+    }
+
+    fn visit_item_struct(&mut self, struct_: &'ast ItemStruct) {
+        let args = match MacroArgs::find(&struct_.attrs, "pyclass") {
+            Some(args) => args,
+            None => return,
+        };
+
+        let class = py_name(&struct_.attrs, &args, &struct_.ident);
+        let code = match args.value("extends") {
+            Some(base) => format!("class {}({})", class, base),
+            None => format!("class {}", class),
+        };
+        self.add(vec![class.clone()], PyExportKind::Class, code);
+
+        let fields = match &struct_.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return,
+        };
+
+        for field in fields {
+            let field_args = MacroArgs::find(&field.attrs, "pyo3").unwrap_or_default();
+            let get = args.has("get_all") || field_args.has("get");
+            let set = args.has("set_all") || field_args.has("set");
+
+            let access = match (get, set) {
+                (true, true) => "get, set",
+                (true, false) => "get",
+                (false, true) => "set",
+                (false, false) => continue,
+            };
+
+            let ident = field.ident.as_ref().unwrap();
+            let name = field_args
+                .value("name")
+                .map_or_else(|| ident.to_string(), ToOwned::to_owned);
+            let code = format!("{}: {} ({})", name, display_code(&field.ty), access);
+
+            self.add(vec![class.clone(), name], PyExportKind::Attribute, code);
+        }
+    }
+
+    fn visit_item_enum(&mut self, enum_: &'ast ItemEnum) {
+        let args = match MacroArgs::find(&enum_.attrs, "pyclass") {
+            Some(args) => args,
+            None => return,
+        };
+
+        let class = py_name(&enum_.attrs, &args, &enum_.ident);
+        let variants = enum_
+            .variants
+            .iter()
+            .map(|variant| variant.ident.to_string())
+            .collect::<Vec<_>>();
+        let code = format!("class {} {{ {} }}", class, variants.join(", "));
+
+        self.add(vec![class], PyExportKind::Class, code);
+    }
+
+    fn visit_item_impl(&mut self, impl_: &'ast ItemImpl) {
+        if MacroArgs::find(&impl_.attrs, "pymethods").is_none() {
+            return;
+        }
+
+        let class = match &*impl_.self_ty {
+            Type::Path(path) => match path.path.segments.last() {
+                Some(segment) => {
+                    let name = segment.ident.to_string();
+                    self.classes.get(&name).cloned().unwrap_or(name)
+                }
+                None => return,
+            },
+            _ => return,
+        };
+
+        // Every method of a `#[pymethods]` block is exported, whatever its
+        // visibility.
+        for item in &impl_.items {
+            let method = match item {
+                ImplItem::Method(method) => method,
+                _ => continue,
+            };
+
+            let attrs = &method.attrs;
+            let ident = &method.sig.ident;
+            let has = |name: &str| MacroArgs::find(attrs, name);
+            let no_args = MacroArgs::default();
+
+            let (name, kind, skipped) = if has("new").is_some() {
+                ("__new__".to_owned(), PyExportKind::Constructor, 0)
+            } else if let Some(args) = has("getter") {
+                let name = accessor_name(attrs, &args, "get_", ident);
+                (name, PyExportKind::Getter, 0)
+            } else if let Some(args) = has("setter") {
+                let name = accessor_name(attrs, &args, "set_", ident);
+                (name, PyExportKind::Setter, 0)
+            } else if has("staticmethod").is_some() {
+                let name = py_name(attrs, &no_args, ident);
+                (name, PyExportKind::StaticMethod, 0)
+            } else if has("classmethod").is_some() {
+                // The first argument is the class.
+                let name = py_name(attrs, &no_args, ident);
+                (name, PyExportKind::ClassMethod, 1)
+            } else if has("classattr").is_some() {
+                let name = py_name(attrs, &no_args, ident);
+                (name, PyExportKind::ClassAttribute, 0)
+            } else {
+                let name = py_name(attrs, &no_args, ident);
+                (name, PyExportKind::Method, 0)
+            };
+
+            let code = python_signature(&name, &method.sig, attrs, &no_args, skipped);
+            self.add(vec![class.clone(), name], kind, code);
+        }
+    }
+}
+
+// The Python name of an item: the `name` argument of its PyO3 attribute, or of
+// `#[pyo3(...)]`, or its Rust name.
+fn py_name(attrs: &[Attribute], args: &MacroArgs, ident: &Ident) -> String {
+    let pyo3 = MacroArgs::find(attrs, "pyo3").unwrap_or_default();
+
+    args.value("name")
+        .or_else(|| pyo3.value("name"))
+        .map_or_else(|| ident.to_string(), ToOwned::to_owned)
+}
+
+// The name of the attribute of a getter or a setter. It is given by the
+// argument of the attribute, or it is the name of the method, without its
+// `get_` or `set_` prefix.
+fn accessor_name(attrs: &[Attribute], args: &MacroArgs, prefix: &str, ident: &Ident) -> String {
+    if let Some(name) = args.names().next() {
+        return name.to_owned();
+    }
+
+    let name = py_name(attrs, &MacroArgs::default(), ident);
+    match name.strip_prefix(prefix) {
+        Some(attribute) => attribute.to_owned(),
+        None => name,
+    }
+}
+
+// Renders what Python code sees of a function: its name, its parameters and
+// what it returns. The parameters are the ones of its `signature` argument
+// when there is one, as it sets their defaults. Otherwise, they are the Rust
+// arguments, except the receiver, the `Python` token and the `skipped` first
+// ones.
+fn python_signature(
+    name: &str,
+    sig: &Signature,
+    attrs: &[Attribute],
+    args: &MacroArgs,
+    skipped: usize,
+) -> String {
+    let pyo3 = MacroArgs::find(attrs, "pyo3").unwrap_or_default();
+    let signature = args
+        .value("signature")
+        .or_else(|| pyo3.value("signature"))
+        .map(|signature| {
+            signature
+                .trim_start_matches('(')
+                .trim_end_matches(')')
+                .to_owned()
+        });
+
+    let parameters = signature.unwrap_or_else(|| {
+        sig.inputs
+            .iter()
+            .filter_map(|input| match input {
+                FnArg::Typed(arg) if !is_python_token(&arg.ty) => Some(arg),
+                _ => None,
+            })
+            .skip(skipped)
+            .map(|arg| match &*arg.pat {
+                Pat::Ident(pat) => format!("{}: {}", pat.ident, display_code(&arg.ty)),
+                pat => format!("{}: {}", display_code(pat), display_code(&arg.ty)),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    });
+
+    match &sig.output {
+        ReturnType::Default => format!("def {}({})", name, parameters),
+        ReturnType::Type(_, ty) => {
+            format!("def {}({}) -> {}", name, parameters, display_code(ty))
+        }
+    }
+}
+
+fn is_python_token(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Python"),
+        _ => false,
+    }
+}
+
+/// Finds the items exported to Python which were removed, modified or added.
+/// They are reported at their Python path, such as `Client::connect`.
+pub(crate) fn changes(previous: &PyExports, current: &PyExports) -> Vec<DiagnosisItem> {
+    let diagnosis = |rule, (path, kind): &(Vec<String>, PyExportKind)| {
+        // Python names may be Rust keywords, which Rust paths can't contain.
+        let segments = path
+            .iter()
+            .map(|segment| syn::parse_str::<Ident>(segment).ok())
+            .collect::<Option<Vec<_>>>();
+
+        match segments {
+            Some(segments) => DiagnosisItem::new(rule, ItemPath::from_segments(segments), None)
+                .with_message(format!("Python {}", kind)),
+            None => DiagnosisItem::new(rule, ItemPath::from_segments(Vec::new()), None)
+                .with_message(format!("Python {} `{}`", kind, path.join("."))),
+        }
+    };
+
+    let removals_and_modifications =
+        previous
+            .iter()
+            .filter_map(|(key, previous)| match current.get(key) {
+                None => Some(
+                    diagnosis(RuleId::PyExportRemoved, key)
+                        .with_signatures(Some(previous.clone()), None),
+                ),
+                Some(current) if current != previous => Some(
+                    diagnosis(RuleId::PyExportModified, key)
+                        .with_signatures(Some(previous.clone()), Some(current.clone())),
+                ),
+                Some(_) => None,
+            });
+
+    let additions = current
+        .iter()
+        .filter(|(key, _)| !previous.contains_key(*key))
+        .map(|(key, current)| {
+            diagnosis(RuleId::PyExportAdded, key).with_signatures(None, Some(current.clone()))
+        });
+
+    removals_and_modifications.chain(additions).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn exports(code: &str) -> Vec<(String, PyExportKind, String)> {
+        extract(&CrateAst::from_str(code).unwrap())
+            .into_iter()
+            .map(|((path, kind), code)| (path.join("."), kind, code))
+            .collect()
+    }
+
+    #[test]
+    fn exported_items() {
+        let exports = exports(
+            r#"
+            use pyo3::prelude::*;
+
+            #[pyfunction]
+            #[pyo3(signature = (text, strict = false))]
+            fn parse(py: Python<'_>, text: &str, strict: bool) -> PyResult<u32> {}
+
+            #[pyfunction(name = "run")]
+            fn run_py(n: u32) {}
+
+            fn not_exported() {}
+
+            #[pyclass(name = "Client", get_all)]
+            struct RawClient {
+                url: String,
+                #[pyo3(set)]
+                timeout: u32,
+            }
+
+            #[pymethods]
+            impl RawClient {
+                #[new]
+                fn new(url: String) -> Self {}
+                fn connect(&self, py: Python<'_>) -> PyResult<()> {}
+                #[staticmethod]
+                fn default_port() -> u16 {}
+                #[classmethod]
+                fn from_env(cls: &PyType, prefix: &str) -> Self {}
+                #[getter]
+                fn get_host(&self) -> String {}
+                #[setter(host)]
+                fn change_host(&mut self, host: String) {}
+                #[pyo3(name = "close")]
+                fn close_py(&mut self) {}
+            }
+
+            #[pyclass]
+            enum Mode { Fast, Slow }
+            "#,
+        );
+
+        let expected = [
+            ("Client", PyExportKind::Class, "class Client"),
+            (
+                "Client.__new__",
+                PyExportKind::Constructor,
+                "def __new__(url: String) -> Self",
+            ),
+            ("Client.close", PyExportKind::Method, "def close()"),
+            (
+                "Client.connect",
+                PyExportKind::Method,
+                "def connect() -> PyResult<()>",
+            ),
+            (
+                "Client.default_port",
+                PyExportKind::StaticMethod,
+                "def default_port() -> u16",
+            ),
+            (
+                "Client.from_env",
+                PyExportKind::ClassMethod,
+                "def from_env(prefix: &str) -> Self",
+            ),
+            ("Client.host", PyExportKind::Getter, "def host() -> String"),
+            (
+                "Client.host",
+                PyExportKind::Setter,
+                "def host(host: String)",
+            ),
+            (
+                "Client.timeout",
+                PyExportKind::Attribute,
+                "timeout: u32 (get, set)",
+            ),
+            ("Client.url", PyExportKind::Attribute, "url: String (get)"),
+            ("Mode", PyExportKind::Class, "class Mode { Fast, Slow }"),
+            (
+                "parse",
+                PyExportKind::Function,
+                "def parse(text, strict = false) -> PyResult<u32>",
+            ),
+            ("run", PyExportKind::Function, "def run(n: u32)"),
+        ]
+        .iter()
+        .map(|(path, kind, code)| (path.to_string(), *kind, code.to_string()))
+        .collect::<Vec<_>>();
+
+        assert_eq!(exports, expected);
+    }
+
+    #[test]
+    fn export_changes() {
+        let previous = extract(
+            &CrateAst::from_str(
+                "#[pyfunction] fn parse(text: &str) {}\n\
+                 #[pyfunction] fn stop() {}\n\
+                 #[pyclass] struct Client;",
+            )
+            .unwrap(),
+        );
+        let current = extract(
+            &CrateAst::from_str(
+                "#[pyfunction] fn parse(input: &str) {}\n\
+                 #[pyclass] struct Client;\n\
+                 #[pymethods] impl Client { fn close(&mut self) {} }",
+            )
+            .unwrap(),
+        );
+
+        let changes = changes(&previous, &current)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            [
+                "≠ parse (Python function)",
+                "- stop (Python function)",
+                "+ Client::close (Python method)"
+            ]
+        );
+    }
+}
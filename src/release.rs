@@ -0,0 +1,291 @@
+use std::{
+    fmt::Write,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context, Result as AnyResult};
+use semver::Version;
+
+use crate::{
+    comparator::ApiCompatibilityDiagnostics,
+    diagnosis::{DiagnosisItem, DiagnosisItemKind},
+    git::CrateRepo,
+    rules::Severity,
+    version_patch::{self, VersionedFile},
+};
+
+const CHANGELOG: &str = "CHANGELOG.md";
+
+/// How `cargo breaking release` behaves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ReleaseConfig {
+    /// Actually release. Otherwise, only print what would be done.
+    pub execute: bool,
+    /// Sign the release commit and tag with the git signing key.
+    pub sign: bool,
+}
+
+/// Everything a release changes, computed before anything is written.
+pub(crate) struct Release {
+    dir: PathBuf,
+    current: Version,
+    next: Version,
+    files: Vec<VersionedFile>,
+    // The changelog path and its new content.
+    changelog: Option<(PathBuf, String)>,
+}
+
+impl Release {
+    /// Prepares the release of the crate located in `dir`.
+    pub(crate) fn new(
+        dir: &Path,
+        diagnostics: &ApiCompatibilityDiagnostics,
+        current: Version,
+        next: Version,
+    ) -> AnyResult<Release> {
+        let files = version_patch::versioned_files(dir, &current)?;
+
+        let path = dir.join(CHANGELOG);
+        let changelog = match fs::read_to_string(&path) {
+            Ok(content) => {
+                let section = changelog_section(&next, diagnostics);
+                Some((path, insert_section(&content, &section)))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+
+        Ok(Release {
+            dir: dir.to_owned(),
+            current,
+            next,
+            files,
+            changelog,
+        })
+    }
+
+    pub(crate) fn tag(&self) -> String {
+        format!("v{}", self.next)
+    }
+
+    fn commit_message(&self) -> String {
+        format!("Release {}", self.next)
+    }
+
+    /// Returns what the release does.
+    pub(crate) fn describe(&self, config: ReleaseConfig) -> String {
+        let mut output = String::new();
+        let signed = if config.sign { "signed " } else { "" };
+
+        let paths = self.files.iter().map(|file| file.path).collect::<Vec<_>>();
+        writeln!(
+            output,
+            "Set the version from {} to {} in {}",
+            self.current,
+            self.next,
+            paths.join(", ")
+        )
+        .unwrap();
+
+        if self.changelog.is_some() {
+            writeln!(output, "Add a {} section to {}", self.next, CHANGELOG).unwrap();
+        }
+
+        writeln!(
+            output,
+            "Create the {}commit \"{}\"",
+            signed,
+            self.commit_message()
+        )
+        .unwrap();
+        write!(output, "Create the {}tag {}", signed, self.tag()).unwrap();
+
+        output
+    }
+
+    /// Writes the release changes, then commits and tags them.
+    pub(crate) fn execute(&self, repo: &CrateRepo, config: ReleaseConfig) -> AnyResult<()> {
+        if repo.tag_names()?.contains(&self.tag()) {
+            bail!("The tag {} already exists", self.tag());
+        }
+
+        let mut paths = Vec::new();
+
+        for file in &self.files {
+            let path = self.dir.join(file.path);
+            fs::write(&path, file.bumped(&self.current, &self.next))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            paths.push(path);
+        }
+
+        if let Some((path, content)) = &self.changelog {
+            fs::write(path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            paths.push(path.clone());
+        }
+
+        git(Command::new("git").arg("add").arg("--").args(&paths))?;
+
+        let mut commit = Command::new("git");
+        commit.arg("commit").args(["-m", &self.commit_message()]);
+        if config.sign {
+            commit.arg("-S");
+        }
+        git(&mut commit)?;
+
+        let mut tag = Command::new("git");
+        tag.arg("tag")
+            .arg(if config.sign { "-s" } else { "-a" })
+            .arg(self.tag())
+            .args(["-m", &self.commit_message()]);
+        git(&mut tag)
+    }
+}
+
+fn git(command: &mut Command) -> AnyResult<()> {
+    let status = command.status().context("Failed to run git")?;
+
+    if !status.success() {
+        bail!("`{:?}` failed", command);
+    }
+
+    Ok(())
+}
+
+/// Returns the changelog section of a release, which lists the breaking and
+/// minor changes. Informational changes are left out.
+fn changelog_section(next: &Version, diagnostics: &ApiCompatibilityDiagnostics) -> String {
+    let mut section = format!("## {}\n", next);
+
+    for (severity, title) in [
+        (Severity::Breaking, "Breaking changes"),
+        (Severity::Minor, "Minor changes"),
+    ] {
+        let entries = diagnostics
+            .items()
+            .filter(|diag| diag.severity() == severity)
+            .map(changelog_entry)
+            .collect::<Vec<_>>();
+
+        if entries.is_empty() {
+            continue;
+        }
+
+        write!(section, "\n### {}\n\n", title).unwrap();
+        entries
+            .iter()
+            .for_each(|entry| writeln!(section, "- {}", entry).unwrap());
+    }
+
+    section
+}
+
+fn changelog_entry(diag: &DiagnosisItem) -> String {
+    let change = match diag.kind() {
+        DiagnosisItemKind::Removal => "removed",
+        DiagnosisItemKind::Modification => "changed",
+        DiagnosisItemKind::Addition => "added",
+    };
+
+    let mut entry = match diag.trait_impl() {
+        Some(trait_) => format!(
+            "`{}` implementation for `{}` {}",
+            trait_,
+            diag.path(),
+            change
+        ),
+        None => format!("`{}` {}", diag.path(), change),
+    };
+
+    if let Some(message) = diag.message() {
+        write!(entry, " ({})", message).unwrap();
+    }
+
+    entry
+}
+
+/// Inserts `section` before the first release of the changelog, or at its
+/// end if it has none.
+fn insert_section(changelog: &str, section: &str) -> String {
+    let mut offset = 0;
+
+    for line in changelog.split_inclusive('\n') {
+        if line.starts_with("## ") {
+            return format!(
+                "{}{}\n{}",
+                &changelog[..offset],
+                section,
+                &changelog[offset..]
+            );
+        }
+
+        offset += line.len();
+    }
+
+    let separator = if changelog.is_empty() || changelog.ends_with("\n\n") {
+        ""
+    } else if changelog.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+
+    format!("{}{}{}", changelog, separator, section)
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn section_lists_breaking_and_minor_changes() {
+        let diagnostics: ApiCompatibilityDiagnostics = parse_quote! {
+            {
+                pub fn f() {}
+                pub struct S;
+            },
+            {
+                pub struct S;
+                pub fn g() {}
+                impl Clone for S {}
+            },
+        };
+
+        assert_eq!(
+            changelog_section(&Version::new(2, 0, 0), &diagnostics),
+            "## 2.0.0\n\
+             \n\
+             ### Breaking changes\n\
+             \n\
+             - `f` removed\n\
+             \n\
+             ### Minor changes\n\
+             \n\
+             - `Clone` implementation for `S` added\n\
+             - `g` added\n"
+        );
+    }
+
+    #[test]
+    fn section_is_inserted_before_the_latest_release() {
+        let changelog = "# Changelog\n\n## 1.0.0\n\nFirst release.\n";
+
+        assert_eq!(
+            insert_section(changelog, "## 1.1.0\n"),
+            "# Changelog\n\n## 1.1.0\n\n## 1.0.0\n\nFirst release.\n"
+        );
+    }
+
+    #[test]
+    fn section_is_appended_to_changelog_without_release() {
+        assert_eq!(
+            insert_section("# Changelog\n", "## 0.1.0\n"),
+            "# Changelog\n\n## 0.1.0\n"
+        );
+    }
+}
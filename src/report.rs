@@ -0,0 +1,494 @@
+mod html;
+
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as FmtResult, Write},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Error as AnyError, Result as AnyResult};
+use semver::Version;
+use serde::Serialize;
+
+use crate::{
+    comparator::{ApiCompatibilityDiagnostics, ImpactEdge, SemverBump},
+    diagnosis::{DiagnosisItem, DiagnosisItemKind},
+    metrics::{self, ApiMetrics, MetricType},
+    public_api::ItemPath,
+    rules::Severity,
+};
+
+/// How the result of a comparison is printed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum OutputFormat {
+    Text,
+    Json,
+    /// Prometheus text exposition format, suitable for a Pushgateway.
+    Metrics,
+    /// A diff which sets the crate version to the next one.
+    CargoPatch,
+    /// A standalone page, with filters.
+    Html,
+    /// A unified diff of both APIs, rendered as pseudo-Rust.
+    ApiDiff,
+}
+
+impl OutputFormat {
+    pub(crate) const VARIANTS: &'static [&'static str] =
+        &["text", "json", "metrics", "cargo-patch", "html", "api-diff"];
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Metrics => "metrics",
+            OutputFormat::CargoPatch => "cargo-patch",
+            OutputFormat::Html => "html",
+            OutputFormat::ApiDiff => "api-diff",
+        }
+        .fmt(f)
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = AnyError;
+
+    fn from_str(s: &str) -> AnyResult<OutputFormat> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "metrics" => Ok(OutputFormat::Metrics),
+            "cargo-patch" => Ok(OutputFormat::CargoPatch),
+            "html" => Ok(OutputFormat::Html),
+            "api-diff" => Ok(OutputFormat::ApiDiff),
+            other => bail!("Unknown output format `{}`", other),
+        }
+    }
+}
+
+/// Everything a comparison produces.
+#[derive(Serialize)]
+pub(crate) struct Report<'a> {
+    diagnostics: &'a ApiCompatibilityDiagnostics,
+    #[serde(serialize_with = "serialize_version")]
+    next_version: &'a Version,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metrics: Option<ApiMetrics>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    impact: Vec<ImpactEdge>,
+    #[serde(skip)]
+    signatures: BTreeMap<ItemPath, (String, String)>,
+    #[serde(skip)]
+    duration: Option<Duration>,
+}
+
+impl<'a> Report<'a> {
+    pub(crate) fn new(
+        diagnostics: &'a ApiCompatibilityDiagnostics,
+        next_version: &'a Version,
+        metrics: Option<ApiMetrics>,
+    ) -> Report<'a> {
+        Report {
+            diagnostics,
+            next_version,
+            metrics,
+            impact: Vec::new(),
+            signatures: BTreeMap::new(),
+            duration: None,
+        }
+    }
+
+    /// Sets the edges linking the changed items to the items which mention
+    /// them. They are only part of the JSON and HTML reports.
+    pub(crate) fn with_impact(mut self, impact: Vec<ImpactEdge>) -> Report<'a> {
+        self.impact = impact;
+        self
+    }
+
+    /// Sets the previous and the current signature of the modified functions
+    /// and methods. They are only part of the HTML report.
+    pub(crate) fn with_signatures(
+        mut self,
+        signatures: BTreeMap<ItemPath, (String, String)>,
+    ) -> Report<'a> {
+        self.signatures = signatures;
+        self
+    }
+
+    /// Sets how long the comparison took.
+    pub(crate) fn with_duration(mut self, duration: Duration) -> Report<'a> {
+        self.duration = Some(duration);
+        self
+    }
+
+    pub(crate) fn render(&self, format: OutputFormat, verbose: bool) -> AnyResult<String> {
+        match format {
+            OutputFormat::Text => Ok(self.render_text(verbose)),
+            OutputFormat::Json => {
+                serde_json::to_string_pretty(self).context("Failed to serialize report")
+            }
+            OutputFormat::Metrics => Ok(self.render_metrics()),
+            OutputFormat::Html => Ok(html::render(self)),
+            // They are rendered from the crate files and from the APIs.
+            OutputFormat::CargoPatch | OutputFormat::ApiDiff => {
+                bail!("The {} format is not a report", format)
+            }
+        }
+    }
+
+    fn render_text(&self, verbose: bool) -> String {
+        let mut output = String::new();
+
+        // The changes which give new warnings or errors to the code using the
+        // items are grouped, for the users who deny warnings.
+        let (lints, others): (Vec<_>, Vec<_>) = self
+            .diagnostics
+            .items()
+            .partition(|diag| diag.rule().is_downstream_lint());
+
+        let write_diags = |output: &mut String, diags: &[&DiagnosisItem]| {
+            for diag in diags {
+                if verbose {
+                    writeln!(output, "{:#}", diag).unwrap();
+                } else {
+                    writeln!(output, "{}", diag).unwrap();
+                }
+            }
+        };
+
+        write_diags(&mut output, &others);
+
+        if !lints.is_empty() {
+            if !others.is_empty() {
+                writeln!(output).unwrap();
+            }
+
+            writeln!(
+                output,
+                "New warnings or errors in the code using these items:"
+            )
+            .unwrap();
+            write_diags(&mut output, &lints);
+        }
+
+        if !self.diagnostics.is_empty() {
+            writeln!(output).unwrap();
+        }
+
+        write!(output, "Next version is: {}", self.next_version).unwrap();
+
+        if let Some(metrics) = &self.metrics {
+            write!(output, "\n\n{}", metrics).unwrap();
+        }
+
+        output
+    }
+
+    fn render_metrics(&self) -> String {
+        let mut output = String::new();
+        self.write_metrics(&mut output).unwrap();
+
+        // The last line break is added when printing.
+        output.pop();
+        output
+    }
+
+    fn write_metrics(&self, out: &mut String) -> std::fmt::Result {
+        let count = |severity| {
+            self.diagnostics
+                .items()
+                .filter(|diag| diag.severity() == severity)
+                .count() as f64
+        };
+
+        let severities = [
+            (
+                "breaking",
+                Severity::Breaking,
+                "Number of breaking changes.",
+            ),
+            ("minor", Severity::Minor, "Number of minor changes."),
+            (
+                "informational",
+                Severity::Informational,
+                "Number of changes which don't affect the next version.",
+            ),
+        ];
+
+        severities.iter().try_for_each(|(name, severity, help)| {
+            metrics::write_metric(
+                out,
+                &format!("cargo_breaking_{}_changes_total", name),
+                MetricType::Counter,
+                help,
+                &[(String::new(), count(*severity))],
+            )
+        })?;
+
+        let per_kind = [
+            ("removal", DiagnosisItemKind::Removal),
+            ("modification", DiagnosisItemKind::Modification),
+            ("addition", DiagnosisItemKind::Addition),
+        ]
+        .iter()
+        .map(|(name, kind)| {
+            let count = self
+                .diagnostics
+                .items()
+                .filter(|diag| diag.kind() == *kind)
+                .count();
+
+            (format!("kind=\"{}\"", name), count as f64)
+        })
+        .collect::<Vec<_>>();
+
+        metrics::write_metric(
+            out,
+            "cargo_breaking_diagnostics_total",
+            MetricType::Counter,
+            "Number of diagnostics, per kind.",
+            &per_kind,
+        )?;
+
+        if let Some(metrics) = &self.metrics {
+            metrics.write_prometheus(out)?;
+        }
+
+        if let Some(duration) = self.duration {
+            metrics::write_metric(
+                out,
+                "cargo_breaking_duration_seconds",
+                MetricType::Gauge,
+                "Time spent comparing both versions.",
+                &[(String::new(), duration.as_secs_f64())],
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn serialize_version<S: serde::Serializer>(v: &&Version, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(v)
+}
+
+/// The result of the comparison against one of several baselines.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(crate) struct BaselineSummary {
+    pub against: String,
+    #[serde(serialize_with = "serialize_display")]
+    pub change: SemverBump,
+    #[serde(serialize_with = "serialize_display")]
+    pub next_version: Version,
+}
+
+fn serialize_display<T, S>(v: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: std::fmt::Display,
+    S: serde::Serializer,
+{
+    serializer.collect_str(v)
+}
+
+/// Renders the comparisons against several baselines, one row per baseline.
+pub(crate) fn render_matrix(rows: &[BaselineSummary], format: OutputFormat) -> AnyResult<String> {
+    match format {
+        OutputFormat::Text => Ok(render_matrix_text(rows)),
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(rows).context("Failed to serialize report")
+        }
+        OutputFormat::Metrics
+        | OutputFormat::CargoPatch
+        | OutputFormat::Html
+        | OutputFormat::ApiDiff => bail!(
+            "The {} format can't be used when comparing against several baselines",
+            format
+        ),
+    }
+}
+
+fn render_matrix_text(rows: &[BaselineSummary]) -> String {
+    const HEADER: [&str; 3] = ["against", "change", "next version"];
+
+    let cells = rows
+        .iter()
+        .map(|row| {
+            [
+                row.against.clone(),
+                row.change.to_string(),
+                row.next_version.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let width = |column: usize| {
+        cells
+            .iter()
+            .map(|row| row[column].chars().count())
+            .chain(std::iter::once(HEADER[column].len()))
+            .max()
+            .unwrap()
+    };
+    let (against_width, change_width) = (width(0), width(1));
+
+    let mut output = format!(
+        "{:aw$}  {:cw$}  {}",
+        HEADER[0],
+        HEADER[1],
+        HEADER[2],
+        aw = against_width,
+        cw = change_width,
+    );
+
+    for [against, change, next_version] in &cells {
+        write!(
+            output,
+            "\n{:aw$}  {:cw$}  {}",
+            against,
+            change,
+            next_version,
+            aw = against_width,
+            cw = change_width,
+        )
+        .unwrap();
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::comparator::ApiComparator;
+
+    use super::*;
+
+    #[test]
+    fn parse_output_format() {
+        for format in OutputFormat::VARIANTS {
+            assert!(OutputFormat::from_str(format).is_ok());
+        }
+
+        assert!(OutputFormat::from_str("yaml").is_err());
+    }
+
+    #[test]
+    fn text_report() {
+        let diagnostics = ApiCompatibilityDiagnostics::default();
+        let version = Version::new(1, 2, 3);
+
+        let report = Report::new(&diagnostics, &version, None);
+
+        assert_eq!(
+            report.render(OutputFormat::Text, false).unwrap(),
+            "Next version is: 1.2.3"
+        );
+    }
+
+    #[test]
+    fn downstream_lints_are_grouped() {
+        let comparator: ApiComparator = parse_quote! {
+            {
+                pub fn a() {}
+                pub fn b() -> u8 {}
+            },
+            {
+                #[deprecated]
+                pub fn a() {}
+                pub fn b() -> u16 {}
+            },
+        };
+
+        let diagnostics = comparator.run();
+        let version = Version::new(1, 2, 3);
+
+        assert_eq!(
+            Report::new(&diagnostics, &version, None)
+                .render(OutputFormat::Text, false)
+                .unwrap(),
+            "≠ b\n\
+             \n\
+             New warnings or errors in the code using these items:\n\
+             ≠ a (now #[deprecated])\n\
+             \n\
+             Next version is: 1.2.3"
+        );
+    }
+
+    #[test]
+    fn metrics_report() {
+        let diagnostics = ApiCompatibilityDiagnostics::default();
+        let version = Version::new(1, 2, 3);
+
+        let report =
+            Report::new(&diagnostics, &version, None).with_duration(Duration::from_millis(1500));
+        let output = report.render(OutputFormat::Metrics, false).unwrap();
+
+        assert!(output.starts_with(
+            "# HELP cargo_breaking_breaking_changes_total Number of breaking changes.\n\
+             # TYPE cargo_breaking_breaking_changes_total counter\n\
+             cargo_breaking_breaking_changes_total 0\n"
+        ));
+        assert!(output.contains("cargo_breaking_diagnostics_total{kind=\"removal\"} 0\n"));
+        assert!(output.ends_with("cargo_breaking_duration_seconds 1.5"));
+    }
+
+    #[test]
+    fn json_report_without_metrics() {
+        let diagnostics = ApiCompatibilityDiagnostics::default();
+        let version = Version::new(1, 2, 3);
+
+        let report = Report::new(&diagnostics, &version, None);
+
+        assert_eq!(
+            report.render(OutputFormat::Json, false).unwrap(),
+            "{\n  \"diagnostics\": [],\n  \"next_version\": \"1.2.3\"\n}"
+        );
+    }
+
+    fn baseline(against: &str, change: SemverBump, next_version: &str) -> BaselineSummary {
+        BaselineSummary {
+            against: against.to_owned(),
+            change,
+            next_version: Version::parse(next_version).unwrap(),
+        }
+    }
+
+    #[test]
+    fn text_matrix() {
+        let rows = [
+            baseline("v1.0.0", SemverBump::Major, "2.0.0"),
+            baseline("release-1.1", SemverBump::Minor, "1.2.0"),
+            baseline("main", SemverBump::Patch, "1.1.1"),
+        ];
+
+        assert_eq!(
+            render_matrix(&rows, OutputFormat::Text).unwrap(),
+            "against      change    next version\n\
+             v1.0.0       breaking  2.0.0\n\
+             release-1.1  minor     1.2.0\n\
+             main         patch     1.1.1"
+        );
+    }
+
+    #[test]
+    fn json_matrix() {
+        let rows = [baseline("main", SemverBump::Minor, "1.2.0")];
+
+        assert_eq!(
+            render_matrix(&rows, OutputFormat::Json).unwrap(),
+            "[\n  {\n    \"against\": \"main\",\n    \"change\": \"minor\",\n    \"next_version\": \"1.2.0\"\n  }\n]"
+        );
+    }
+
+    #[test]
+    fn metrics_matrix_is_an_error() {
+        let rows = [baseline("main", SemverBump::Minor, "1.2.0")];
+
+        assert!(render_matrix(&rows, OutputFormat::Metrics).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::diagnosis::{DiagnosisItem, DiagnosisItemKind};
+
+use super::Report;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em auto; max-width: 60em; }
+form { margin-bottom: 1em; }
+label { margin-right: 1em; }
+ul { list-style: none; padding: 0; }
+li { border-left: 4px solid #999; margin: 0.3em 0; padding: 0.3em 0.6em; }
+li.breaking { border-color: #c62828; }
+li.minor { border-color: #1565c0; }
+summary { cursor: pointer; }
+.diagnostic { font-family: monospace; }
+.rule { color: #666; font-size: 0.9em; margin-left: 0.5em; }
+pre { background: #f5f5f5; padding: 0.5em; }
+del { color: #c62828; text-decoration: none; }
+ins { color: #2e7d32; text-decoration: none; }
+";
+
+const SCRIPT: &str = "\
+const filters = document.querySelectorAll('#filters select');
+function update() {
+  document.querySelectorAll('#diagnostics > li').forEach(item => {
+    item.hidden = !Array.from(filters)
+      .every(filter => filter.value === '' || item.dataset[filter.name] === filter.value);
+  });
+}
+filters.forEach(filter => filter.addEventListener('change', update));
+";
+
+/// Renders the report as a standalone page, whose diagnostics can be
+/// filtered by severity, kind and module. The diagnostics which have more to
+/// say, such as the signature of a modified function, can be expanded.
+pub(super) fn render(report: &Report) -> String {
+    let mut output = String::new();
+    write_page(report, &mut output).unwrap();
+    output
+}
+
+fn write_page(report: &Report, out: &mut String) -> std::fmt::Result {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>cargo-breaking report</title>")?;
+    writeln!(out, "<style>\n{}</style>", STYLE)?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<h1>API changes</h1>")?;
+    writeln!(
+        out,
+        "<p>Next version is: <strong>{}</strong></p>",
+        report.next_version
+    )?;
+
+    let diags = report.diagnostics.items().collect::<Vec<_>>();
+
+    let severities = diags.iter().map(|diag| diag.severity().to_string());
+    let kinds = diags.iter().map(|diag| kind(diag).to_owned());
+    let modules = diags.iter().map(|diag| module(diag));
+
+    writeln!(out, "<form id=\"filters\">")?;
+    write_filter(out, "severity", severities)?;
+    write_filter(out, "kind", kinds)?;
+    write_filter(out, "module", modules)?;
+    writeln!(out, "</form>")?;
+
+    let mut users = BTreeMap::<&str, Vec<&str>>::new();
+    for edge in &report.impact {
+        users
+            .entry(edge.changed())
+            .or_default()
+            .push(edge.used_by());
+    }
+
+    writeln!(out, "<ul id=\"diagnostics\">")?;
+    for diag in diags {
+        let path = diag.path().to_string();
+        let users = users.get(path.as_str()).map_or(&[][..], Vec::as_slice);
+
+        write_diagnostic(report, diag, users, out)?;
+    }
+    writeln!(out, "</ul>")?;
+
+    writeln!(out, "<script>\n{}</script>", SCRIPT)?;
+    writeln!(out, "</body>")?;
+    write!(out, "</html>")
+}
+
+fn write_filter(
+    out: &mut String,
+    name: &str,
+    values: impl Iterator<Item = String>,
+) -> std::fmt::Result {
+    writeln!(out, "<label>{} <select name=\"{}\">", name, name)?;
+    writeln!(out, "<option value=\"\">all</option>")?;
+
+    for value in values.collect::<BTreeSet<_>>() {
+        let value = escape(&value);
+        writeln!(out, "<option value=\"{}\">{}</option>", value, value)?;
+    }
+
+    writeln!(out, "</select></label>")
+}
+
+fn write_diagnostic(
+    report: &Report,
+    diag: &DiagnosisItem,
+    users: &[&str],
+    out: &mut String,
+) -> std::fmt::Result {
+    writeln!(
+        out,
+        "<li class=\"{severity}\" data-severity=\"{severity}\" data-kind=\"{}\" data-module=\"{}\">",
+        kind(diag),
+        escape(&module(diag)),
+        severity = diag.severity(),
+    )?;
+
+    let summary = format!(
+        "<span class=\"diagnostic\">{}</span><span class=\"rule\">{}</span>",
+        escape(&diag.to_string()),
+        diag.rule()
+    );
+
+    let signatures = diag
+        .trait_impl()
+        .is_none()
+        .then(|| report.signatures.get(diag.path()))
+        .flatten();
+
+    if diag.details().is_empty() && signatures.is_none() && users.is_empty() {
+        writeln!(out, "{}", summary)?;
+        return writeln!(out, "</li>");
+    }
+
+    writeln!(out, "<details>")?;
+    writeln!(out, "<summary>{}</summary>", summary)?;
+
+    if let Some((previous, current)) = signatures {
+        writeln!(
+            out,
+            "<pre><del>- {}</del>\n<ins>+ {}</ins></pre>",
+            escape(previous),
+            escape(current)
+        )?;
+    }
+
+    if !diag.details().is_empty() {
+        writeln!(out, "<ul>")?;
+        for detail in diag.details() {
+            writeln!(out, "<li>{}</li>", escape(detail))?;
+        }
+        writeln!(out, "</ul>")?;
+    }
+
+    if !users.is_empty() {
+        writeln!(out, "<p>Used by:</p>")?;
+        writeln!(out, "<ul>")?;
+        for user in users {
+            writeln!(out, "<li><code>{}</code></li>", escape(user))?;
+        }
+        writeln!(out, "</ul>")?;
+    }
+
+    writeln!(out, "</details>")?;
+    writeln!(out, "</li>")
+}
+
+fn kind(diag: &DiagnosisItem) -> &'static str {
+    match diag.kind() {
+        DiagnosisItemKind::Removal => "removal",
+        DiagnosisItemKind::Modification => "modification",
+        DiagnosisItemKind::Addition => "addition",
+    }
+}
+
+// The path the item is defined in, which is the type for methods.
+fn module(diag: &DiagnosisItem) -> String {
+    match diag.path().segments() {
+        [] | [_] => "crate".to_owned(),
+        [parent @ .., _] => parent
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("::"),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use semver::Version;
+    use syn::parse_quote;
+
+    use crate::{
+        comparator::ApiComparator,
+        report::{OutputFormat, Report},
+    };
+
+    #[test]
+    fn html_report() {
+        let comparator: ApiComparator = parse_quote! {
+            {
+                pub mod io {
+                    pub fn read(buf: &mut Vec<u8>) {}
+                }
+            },
+            {
+                pub mod io {
+                    pub fn read(buf: &mut [u8]) -> usize {}
+                }
+            },
+        };
+
+        let diagnostics = comparator.run();
+        let version = Version::new(1, 2, 3);
+
+        let report = Report::new(&diagnostics, &version, None)
+            .with_signatures(comparator.signature_changes(&diagnostics));
+        let output = report.render(OutputFormat::Html, false).unwrap();
+
+        assert!(output.starts_with("<!DOCTYPE html>\n"));
+        assert!(output.contains("<p>Next version is: <strong>1.2.3</strong></p>"));
+        assert!(output.contains("<option value=\"io\">io</option>"));
+        assert!(output.contains(
+            "<li class=\"breaking\" data-severity=\"breaking\" \
+             data-kind=\"modification\" data-module=\"io\">"
+        ));
+        assert!(output.contains(
+            "<pre><del>- fn read(buf: &amp;mut Vec&lt;u8&gt;)</del>\n\
+             <ins>+ fn read(buf: &amp;mut [u8]) -&gt; usize</ins></pre>"
+        ));
+        assert!(output.ends_with("</html>"));
+    }
+}
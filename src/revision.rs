@@ -0,0 +1,98 @@
+/// A `<reference>@{<date>}` revision, such as `main@{2024-01-01}`: the
+/// state of the reference at the beginning of the date.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct DatedRevision {
+    pub reference: String,
+    pub date: String,
+    /// The beginning of the date, as a UTC timestamp.
+    pub timestamp: i64,
+}
+
+impl DatedRevision {
+    /// Parses a dated revision. The date must be written as `YYYY-MM-DD`,
+    /// the other forms are left to git. The reference defaults to `HEAD`.
+    pub(crate) fn parse(spec: &str) -> Option<DatedRevision> {
+        let (reference, date) = spec.strip_suffix('}')?.rsplit_once("@{")?;
+        let timestamp = parse_date(date)?;
+
+        let reference = if reference.is_empty() {
+            "HEAD"
+        } else {
+            reference
+        };
+
+        Some(DatedRevision {
+            reference: reference.to_owned(),
+            date: date.to_owned(),
+            timestamp,
+        })
+    }
+}
+
+/// Returns the timestamp of the beginning of a `YYYY-MM-DD` date, in UTC.
+fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.split('-');
+    let mut next = |len: usize| {
+        parts
+            .next()
+            .filter(|part| part.len() == len && part.bytes().all(|b| b.is_ascii_digit()))
+            .and_then(|part| part.parse::<i64>().ok())
+    };
+
+    let (year, month, day) = (next(4)?, next(2)?, next(2)?);
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    Some(days_from_civil(year, month, day) * 24 * 60 * 60)
+}
+
+/// Returns the number of days between 1970-01-01 and a date of the proleptic
+/// Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dated_revision() {
+        assert_eq!(
+            DatedRevision::parse("main@{2024-01-01}"),
+            Some(DatedRevision {
+                reference: "main".to_owned(),
+                date: "2024-01-01".to_owned(),
+                timestamp: 1_704_067_200,
+            })
+        );
+        assert_eq!(
+            DatedRevision::parse("@{1970-01-02}").map(|rev| (rev.reference, rev.timestamp)),
+            Some(("HEAD".to_owned(), 86_400))
+        );
+    }
+
+    #[test]
+    fn other_revisions_are_left_to_git() {
+        assert_eq!(DatedRevision::parse("main"), None);
+        assert_eq!(DatedRevision::parse("main@{1}"), None);
+        assert_eq!(DatedRevision::parse("main@{yesterday}"), None);
+        assert_eq!(DatedRevision::parse("main@{2024-13-01}"), None);
+        assert_eq!(DatedRevision::parse("main@{2024-1-1}"), None);
+    }
+
+    #[test]
+    fn leap_years() {
+        assert_eq!(
+            parse_date("2000-03-01").unwrap() - parse_date("2000-02-28").unwrap(),
+            2 * 86_400
+        );
+    }
+}
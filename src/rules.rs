@@ -0,0 +1,854 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use serde::Serialize;
+
+use crate::diagnosis::DiagnosisItemKind;
+
+// Every rule the comparator can emit is declared here, along with its
+// metadata. Diagnostic generators only refer to rules by their `RuleId`, so
+// that severity, description and audience live in a single place.
+macro_rules! rules {
+    (
+        $(
+            $( #[$meta:meta] )*
+            $variant:ident => {
+                id: $id:literal,
+                kind: $kind:ident,
+                severity: $severity:ident,
+                audience: $audience:ident,
+                description: $description:literal $(,)?
+            }
+        ),* $(,)?
+    ) => {
+        #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        pub(crate) enum RuleId {
+            $(
+                $( #[$meta] )*
+                $variant,
+            )*
+        }
+
+        impl RuleId {
+            pub(crate) const ALL: &'static [RuleId] = &[ $( RuleId::$variant, )* ];
+
+            pub(crate) fn rule(self) -> &'static Rule {
+                match self {
+                    $(
+                        RuleId::$variant => &Rule {
+                            id: $id,
+                            kind: DiagnosisItemKind::$kind,
+                            default_severity: Severity::$severity,
+                            audience: Audience::$audience,
+                            description: $description,
+                        },
+                    )*
+                }
+            }
+        }
+    };
+}
+
+rules! {
+    ItemRemoved => {
+        id: "item-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: Users,
+        description: "A public item (function, type, method or trait) was removed.",
+    },
+
+    ItemModified => {
+        id: "item-modified",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The definition or signature of a public item changed.",
+    },
+
+    ItemAdded => {
+        id: "item-added",
+        kind: Addition,
+        severity: Minor,
+        audience: Users,
+        description: "A new public item was added.",
+    },
+
+    NonExhaustiveExtended => {
+        id: "non-exhaustive-extended",
+        kind: Modification,
+        severity: Minor,
+        audience: Users,
+        description: "Variants or public fields were added to a `#[non_exhaustive]` type. Code outside of the crate can't build it or match on it exhaustively, so it is not affected. The `extensions` setting of the configuration can make it a patch-level change.",
+    },
+
+    GenericCountChangedUse => {
+        id: "generic-count-changed-use",
+        kind: Modification,
+        severity: Informational,
+        audience: Users,
+        description: "A public function, method or type mentions a type whose number of required generic arguments changed. The type itself is reported on its own, this shows where the change spreads.",
+    },
+
+    VarianceRestricted => {
+        id: "variance-restricted",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A generic parameter of a public type became invariant, or its variance otherwise changed, as inferred from the fields of the type, including the private ones. Code relying on the previous subtyping, such as passing a `Foo<'static>` where a `Foo<'a>` is expected, no longer compiles.",
+    },
+
+    SizedBoundAdded => {
+        id: "sized-bound-added",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A generic parameter of a public item lost its `?Sized` bound, so it must now be `Sized`. Code using it with `str`, a slice or a trait object no longer compiles.",
+    },
+
+    SizedBoundRelaxed => {
+        id: "sized-bound-relaxed",
+        kind: Modification,
+        severity: Minor,
+        audience: Users,
+        description: "A generic parameter of a public item became `?Sized`, so it also accepts `str`, slices and trait objects. Existing code keeps compiling.",
+    },
+
+    ClosureBoundRestricted => {
+        id: "closure-bound-restricted",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A closure parameter of a public function requires a more restrictive closure trait, such as `Fn` instead of `FnMut`, or `FnMut` instead of `FnOnce`. Closures which mutate or consume what they capture are no longer accepted.",
+    },
+
+    ClosureBoundRelaxed => {
+        id: "closure-bound-relaxed",
+        kind: Modification,
+        severity: Minor,
+        audience: Users,
+        description: "A closure parameter of a public function requires a less restrictive closure trait, such as `FnMut` instead of `Fn`. Every closure which was accepted still is.",
+    },
+
+    ImplTraitArgumentChanged => {
+        id: "impl-trait-argument-changed",
+        kind: Modification,
+        severity: Informational,
+        audience: Users,
+        description: "An `impl Trait` argument of a public function was replaced with an equivalent generic parameter, or the opposite. Calls which let the compiler infer the generic parameters keep compiling, but the ones naming them with a turbofish (`f::<File>(...)`) may not, as the type of an `impl Trait` argument can't be named.",
+    },
+
+    AutoTraitLost => {
+        id: "auto-trait-lost",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The opaque type returned by a public `-> impl Trait` or `async fn` function no longer implements `Send` or `Sync`, although its bounds did not change. Auto traits leak through opaque types, so code sending the returned value to another thread no longer compiles. Only reported with `--probe-auto-traits`.",
+    },
+
+    EditionChanged => {
+        id: "edition-changed",
+        kind: Modification,
+        severity: Informational,
+        audience: Users,
+        description: "The crate is compiled with another edition. It does not change the API, but a newer edition requires a newer compiler, which may raise the minimum supported Rust version.",
+    },
+
+    StdRequired => {
+        id: "std-required",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The crate no longer builds for a target without `std` when its default features are disabled. The users building it for embedded targets can no longer use it. Only reported with `--probe-no-std`.",
+    },
+
+    CItemRemoved => {
+        id: "c-item-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: Users,
+        description: "A function, type or variable was removed from the C header generated by cbindgen. C code using it no longer compiles or links. Only reported with `--c-header`.",
+    },
+
+    CItemModified => {
+        id: "c-item-modified",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The declaration of a function, type or variable of the C header generated by cbindgen changed, such as the parameters of a function or the fields of a struct. C code using it may no longer compile, or may read memory with the wrong layout. Only reported with `--c-header`.",
+    },
+
+    CItemAdded => {
+        id: "c-item-added",
+        kind: Addition,
+        severity: Minor,
+        audience: Users,
+        description: "A function, type or variable was added to the C header generated by cbindgen. Only reported with `--c-header`.",
+    },
+
+    SymbolRemoved => {
+        id: "symbol-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: Users,
+        description: "The `cdylib` or `staticlib` library no longer exports a symbol, such as a `#[no_mangle]` function, possibly generated by a macro. Programs linking to it no longer link. Only reported with `--symbols`.",
+    },
+
+    SymbolAdded => {
+        id: "symbol-added",
+        kind: Addition,
+        severity: Minor,
+        audience: Users,
+        description: "The `cdylib` or `staticlib` library exports a new symbol. Only reported with `--symbols`.",
+    },
+
+    JsExportRemoved => {
+        id: "js-export-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: Users,
+        description: "A function, class, enum, method or property exported to JavaScript by `#[wasm_bindgen]` was removed or renamed. JavaScript code using it breaks. Only reported with `--wasm-bindgen`.",
+    },
+
+    JsExportModified => {
+        id: "js-export-modified",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The Rust signature of a function, method or property exported to JavaScript by `#[wasm_bindgen]` changed, or the variants of an exported enum did. The values JavaScript code passes or receives may no longer be valid. Only reported with `--wasm-bindgen`.",
+    },
+
+    JsExportAdded => {
+        id: "js-export-added",
+        kind: Addition,
+        severity: Minor,
+        audience: Users,
+        description: "A function, class, enum, method or property is newly exported to JavaScript by `#[wasm_bindgen]`. Only reported with `--wasm-bindgen`.",
+    },
+
+    PyExportRemoved => {
+        id: "py-export-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: Users,
+        description: "A function, class, method or attribute exported to Python by PyO3 was removed or renamed. Python code using it breaks. Only reported with `--pyo3`.",
+    },
+
+    PyExportModified => {
+        id: "py-export-modified",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The Python signature of a function or method exported by PyO3 changed, or the base class of a class, the variants of an enum or the access to an attribute did. Python code calling it may no longer work. Only reported with `--pyo3`.",
+    },
+
+    PyExportAdded => {
+        id: "py-export-added",
+        kind: Addition,
+        severity: Minor,
+        audience: Users,
+        description: "A function, class, method or attribute is newly exported to Python by PyO3. Only reported with `--pyo3`.",
+    },
+
+    SerdeFieldRenamed => {
+        id: "serde-field-renamed",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A field of a type deriving `Serialize` or `Deserialize` is no longer serialized under the same name, because it was renamed without `#[serde(rename)]`, or removed. The data serialized by one version can't be read by the other. Only reported with `--serde`.",
+    },
+
+    SerdeVariantRemoved => {
+        id: "serde-variant-removed",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A variant of an enum deriving `Serialize` or `Deserialize` is no longer serialized under the same name, because it was renamed or removed. The data serialized with it can no longer be read. Only reported with `--serde`.",
+    },
+
+    SerdeFieldOrderChanged => {
+        id: "serde-field-order-changed",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The fields of a tuple struct deriving `Serialize` or `Deserialize` were reordered. Tuple structs are serialized as sequences, so the data serialized by one version can't be read by the other. Only reported with `--serde`.",
+    },
+
+    DisplayOutputModified => {
+        id: "display-output-modified",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The documentation of a type asserts another `Display` output for the same value, with `to_string()` or `format!(\"{}\")`. The code parsing or comparing the output may no longer work. Only reported with `--display-examples`.",
+    },
+
+    UnanalyzableItem => {
+        id: "unanalyzable-item",
+        kind: Modification,
+        severity: Informational,
+        audience: Maintainers,
+        description: "An item of either version could not be analyzed, for instance because several items are defined at the same path, or because a trait contains macro invocations. Its changes are not reported, the rest of the crate is still compared.",
+    },
+
+    DefaultedGenericAdded => {
+        id: "defaulted-generic-added",
+        kind: Modification,
+        severity: Minor,
+        audience: Users,
+        description: "Generic parameters with a default value were appended to a type. Code which names the type without them keeps compiling. The `extensions` setting of the configuration can make it a patch-level change.",
+    },
+
+    ModuleMoved => {
+        id: "module-moved",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A whole public module was moved to another path, without any other change.",
+    },
+
+    TraitObjectBoundsModified => {
+        id: "trait-object-bounds-modified",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The auto-trait (`Send`, `Sync`...) or lifetime bounds of a trait object used by a public item changed.",
+    },
+
+    ConstructionRequirementsChanged => {
+        id: "construction-requirements-changed",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A constructor (`new` or `from_*`) requires new trait bounds on its generic parameters, or on the ones of its impl block. The code building the type with its own types may no longer compile.",
+    },
+
+    BuilderChainingChanged => {
+        id: "builder-chaining-changed",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A setter of a `*Builder` type switched between taking and returning `Self` and taking and returning `&mut Self`. The calls chained in the other style no longer compile.",
+    },
+
+    MethodReceiverAdded => {
+        id: "method-receiver-added",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "An associated function now takes `self`. The calls written as `Type::f()` no longer compile, and must become `value.f()`.",
+    },
+
+    MethodReceiverRemoved => {
+        id: "method-receiver-removed",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A method no longer takes `self`. The calls written as `value.f()` no longer compile, and must become `Type::f()`.",
+    },
+
+    MethodMovedToTraitImpl => {
+        id: "method-moved-to-trait-impl",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A method of an inherent impl block is now defined by a trait implementation of the same type. Calling it requires the trait to be in scope.",
+    },
+
+    MethodMovedToPreludeTraitImpl => {
+        id: "method-moved-to-prelude-trait-impl",
+        kind: Modification,
+        severity: Minor,
+        audience: Users,
+        description: "A method of an inherent impl block is now defined by an implementation of a trait of the prelude, such as `Default` or `Clone`, for the same type. The trait is always in scope, so the calls still compile.",
+    },
+
+    MethodMovedToInherentImpl => {
+        id: "method-moved-to-inherent-impl",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A method of a trait implementation is now defined by an inherent impl block of the same type. The generic code relying on the trait implementation no longer compiles, or calls the provided method of the trait instead.",
+    },
+
+    TraitImplRemoved => {
+        id: "trait-impl-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: Users,
+        description: "A public type no longer implements a trait.",
+    },
+
+    TraitImplModified => {
+        id: "trait-impl-modified",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The generics or associated items of a trait implementation changed.",
+    },
+
+    TraitImplAdded => {
+        id: "trait-impl-added",
+        kind: Addition,
+        severity: Minor,
+        audience: Users,
+        description: "A public type now implements a trait.",
+    },
+
+    ErrorImplRemoved => {
+        id: "error-impl-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: Users,
+        description: "A public type no longer implements `std::error::Error`. `?` can no longer convert it into `Box<dyn Error>` or `anyhow::Error`, and it can no longer be the `source()` of other errors.",
+    },
+
+    ErrorConversionRemoved => {
+        id: "error-conversion-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: Users,
+        description: "A `From` implementation of an error type was removed. `?` can no longer convert the source error into it.",
+    },
+
+    ErrorNonExhaustiveAdded => {
+        id: "error-non-exhaustive-added",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "An error type is now `#[non_exhaustive]`. The code matching on its variants without a wildcard arm no longer builds.",
+    },
+
+    ErrorNonExhaustiveRemoved => {
+        id: "error-non-exhaustive-removed",
+        kind: Modification,
+        severity: Minor,
+        audience: Users,
+        description: "An error type is no longer `#[non_exhaustive]`. Adding a variant to it will be a breaking change.",
+    },
+
+    DisplayImplRemoved => {
+        id: "display-impl-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: Users,
+        description: "A public type no longer implements `Display`. `to_string()` and `format!(\"{}\")` no longer work on it.",
+    },
+
+    FromStrImplRemoved => {
+        id: "from-str-impl-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: Users,
+        description: "A public type no longer implements `FromStr`. `str::parse()` can no longer build it.",
+    },
+
+    OperatorImplRemoved => {
+        id: "operator-impl-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: Users,
+        description: "A public type no longer implements a trait backing an operator (`Add`, `AddAssign`, `Neg`, `Index`, `PartialEq`, `PartialOrd`, `Ord`...). The expressions using the operator on the type no longer compile.",
+    },
+
+    OperatorImplModified => {
+        id: "operator-impl-modified",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The implementation of an operator trait changed: it now applies to another right-hand side type, or gives another `Output` type. The expressions using the operator may no longer compile.",
+    },
+
+    TraitImplOverridesModified => {
+        id: "trait-impl-overrides-modified",
+        kind: Modification,
+        severity: Informational,
+        audience: Maintainers,
+        description: "A trait implementation started or stopped overriding a provided method of the trait. Only reported with `--pedantic`.",
+    },
+
+    ImplMoved => {
+        id: "impl-moved",
+        kind: Modification,
+        severity: Informational,
+        audience: Maintainers,
+        description: "The impl block defining a method or a trait implementation was moved to another module. Only reported with `--pedantic`.",
+    },
+
+    ParameterWidened => {
+        id: "parameter-widened",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The only change of a function or a method is that some of its parameters have a wider numeric type, such as `u8` becoming `u16`. Arguments of the previous type no longer fit, but literals keep compiling. Only reported with the `numeric-widening` setting of the configuration, the change is reported as `item-modified` otherwise.",
+    },
+
+    DerefMethodsUnreachable => {
+        id: "deref-methods-unreachable",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "Methods which could be called on a public type through its `Deref` implementation no longer can, because the implementation was removed or its target changed.",
+    },
+
+    TraitItemRemoved => {
+        id: "trait-item-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: All,
+        description: "An associated item was removed from a public trait definition.",
+    },
+
+    TraitItemModified => {
+        id: "trait-item-modified",
+        kind: Modification,
+        severity: Breaking,
+        audience: All,
+        description: "An associated item of a public trait definition changed.",
+    },
+
+    TraitMethodDefaultRemoved => {
+        id: "trait-method-default-removed",
+        kind: Modification,
+        severity: Breaking,
+        audience: Implementors,
+        description: "A method of a public trait definition no longer has a default implementation: implementors must now provide it.",
+    },
+
+    TraitMethodDefaultAdded => {
+        id: "trait-method-default-added",
+        kind: Modification,
+        severity: Minor,
+        audience: Implementors,
+        description: "A method of a public trait definition now has a default implementation: implementors no longer need to provide it.",
+    },
+
+    TraitConstDefaultRemoved => {
+        id: "trait-const-default-removed",
+        kind: Modification,
+        severity: Breaking,
+        audience: Implementors,
+        description: "A constant of a public trait definition no longer has a default value: implementors must now specify it.",
+    },
+
+    TraitConstDefaultAdded => {
+        id: "trait-const-default-added",
+        kind: Modification,
+        severity: Minor,
+        audience: Implementors,
+        description: "A constant of a public trait definition now has a default value: implementors no longer need to specify it. The implementations which stop specifying it are compared with the default value.",
+    },
+
+    AssocConstValueModified => {
+        id: "assoc-const-value-modified",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The value of an associated constant, or the default value of a trait constant, changed. It breaks the code relying on the value, such as array lengths or patterns. Its severity is set by the `const-values` setting of the configuration.",
+    },
+
+    TraitItemAdded => {
+        id: "trait-item-added",
+        kind: Addition,
+        severity: Minor,
+        audience: Implementors,
+        description: "An associated item was added to a public trait definition.",
+    },
+
+    DeprecationAdded => {
+        id: "deprecation-added",
+        kind: Modification,
+        severity: Minor,
+        audience: Users,
+        description: "A public item is now `#[deprecated]`. Code using it gets a warning.",
+    },
+
+    DeprecationRemoved => {
+        id: "deprecation-removed",
+        kind: Modification,
+        severity: Informational,
+        audience: Users,
+        description: "A public item is no longer `#[deprecated]`.",
+    },
+
+    MustUseAdded => {
+        id: "must-use-added",
+        kind: Modification,
+        severity: Minor,
+        audience: Users,
+        description: "A public item is now `#[must_use]`. Code ignoring its value gets a warning.",
+    },
+
+    MustUseRemoved => {
+        id: "must-use-removed",
+        kind: Modification,
+        severity: Informational,
+        audience: Users,
+        description: "A public item is no longer `#[must_use]`.",
+    },
+
+    NonExhaustiveAdded => {
+        id: "non-exhaustive-added",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A public type is now `#[non_exhaustive]`: it can no longer be built or matched exhaustively outside of the crate.",
+    },
+
+    NonExhaustiveRemoved => {
+        id: "non-exhaustive-removed",
+        kind: Modification,
+        severity: Minor,
+        audience: Users,
+        description: "A public type is no longer `#[non_exhaustive]`.",
+    },
+
+    ReprAdded => {
+        id: "repr-added",
+        kind: Modification,
+        severity: Minor,
+        audience: Users,
+        description: "A public type now has a `#[repr]` attribute, which guarantees its layout.",
+    },
+
+    ReprRemoved => {
+        id: "repr-removed",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A public type no longer has a `#[repr]` attribute: its layout is no longer guaranteed.",
+    },
+
+    ReprModified => {
+        id: "repr-modified",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "The `#[repr]` attribute of a public type changed, which changes its layout.",
+    },
+
+    PlatformRestricted => {
+        id: "platform-restricted",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A public item gained a `#[doc(cfg(...))]` attribute, or its predicate changed: it may no longer be available on some platforms or configurations.",
+    },
+
+    PlatformExtended => {
+        id: "platform-extended",
+        kind: Modification,
+        severity: Minor,
+        audience: Users,
+        description: "A public item lost its `#[doc(cfg(...))]` attribute: it is now available on every platform and configuration.",
+    },
+
+    NightlyRequired => {
+        id: "nightly-required",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "A public item now uses an unstable feature in its definition, such as the `!` type in its signature or a feature-gated attribute: users on a stable compiler can no longer use it.",
+    },
+
+    InlineModified => {
+        id: "inline-modified",
+        kind: Modification,
+        severity: Informational,
+        audience: Maintainers,
+        description: "The `#[inline]` hint of a function or a method was added, removed or changed. Only reported with `--pedantic`.",
+    },
+
+    MacroArmAdded => {
+        id: "macro-arm-added",
+        kind: Modification,
+        severity: Minor,
+        audience: Users,
+        description: "A rule was added to an exported `macro_rules!` macro: it accepts new invocations.",
+    },
+
+    MacroPathRemoved => {
+        id: "macro-path-removed",
+        kind: Modification,
+        severity: Breaking,
+        audience: Users,
+        description: "An exported macro is no longer re-exported at a path: `use` declarations importing it from there no longer compile.",
+    },
+
+    MacroHelperRemoved => {
+        id: "macro-helper-removed",
+        kind: Removal,
+        severity: Breaking,
+        audience: Maintainers,
+        description: "A `#[doc(hidden)]` exported macro, or an exported macro of a `__private` module, was removed. Code generated by the macros of the previous version may still invoke it. Only reported with `--macro-internals`.",
+    },
+
+    MacroHelperModified => {
+        id: "macro-helper-modified",
+        kind: Modification,
+        severity: Breaking,
+        audience: Maintainers,
+        description: "The rules of a `#[doc(hidden)]` exported macro, or of an exported macro of a `__private` module, changed. Code generated by the macros of the previous version may no longer match them. Only reported with `--macro-internals`.",
+    },
+
+    DocAliasRemoved => {
+        id: "doc-alias-removed",
+        kind: Modification,
+        severity: Informational,
+        audience: Users,
+        description: "A `#[doc(alias)]` of a public item was removed: searching for the alias no longer finds the item.",
+    },
+
+    PrivateItemRemoved => {
+        id: "private-item-removed",
+        kind: Removal,
+        severity: Informational,
+        audience: Maintainers,
+        description: "An item which is not reachable from outside of the crate was removed.",
+    },
+
+    PrivateItemModified => {
+        id: "private-item-modified",
+        kind: Modification,
+        severity: Informational,
+        audience: Maintainers,
+        description: "An item which is not reachable from outside of the crate changed.",
+    },
+
+    PrivateItemAdded => {
+        id: "private-item-added",
+        kind: Addition,
+        severity: Informational,
+        audience: Maintainers,
+        description: "An item which is not reachable from outside of the crate was added.",
+    },
+}
+
+impl RuleId {
+    pub(crate) fn from_id(id: &str) -> Option<RuleId> {
+        RuleId::ALL
+            .iter()
+            .copied()
+            .find(|rule| rule.rule().id == id)
+    }
+
+    /// Whether the change gives new warnings or errors to the code using the
+    /// item, such as a `#[deprecated]` attribute being added. Builds which
+    /// deny warnings fail because of these.
+    pub(crate) fn is_downstream_lint(self) -> bool {
+        matches!(
+            self,
+            RuleId::DeprecationAdded
+                | RuleId::MustUseAdded
+                | RuleId::NonExhaustiveAdded
+                | RuleId::ErrorNonExhaustiveAdded
+        )
+    }
+}
+
+impl Display for RuleId {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        self.rule().id.fmt(f)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Rule {
+    pub(crate) id: &'static str,
+    pub(crate) kind: DiagnosisItemKind,
+    pub(crate) default_severity: Severity,
+    pub(crate) audience: Audience,
+    pub(crate) description: &'static str,
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        writeln!(f, "{}: {}", self.id, self.description)?;
+        writeln!(f, "  severity: {}", self.default_severity)?;
+        write!(f, "  audience: {}", self.audience)
+    }
+}
+
+/// The semver effect a diagnostic has on the next version.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Breaking,
+    Minor,
+    /// Does not affect the next version.
+    Informational,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Severity::Breaking => "breaking",
+            Severity::Minor => "minor",
+            Severity::Informational => "informational",
+        }
+        .fmt(f)
+    }
+}
+
+/// Who is affected by a change.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Audience {
+    /// Code which uses the item (calls it, names it, matches on it...).
+    Users,
+    /// Code which implements the trait the item belongs to.
+    Implementors,
+    All,
+    /// The crate itself, for items which are not reachable from outside.
+    Maintainers,
+}
+
+impl Display for Audience {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Audience::Users => "users",
+            Audience::Implementors => "implementors",
+            Audience::All => "users and implementors",
+            Audience::Maintainers => "maintainers",
+        }
+        .fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn rule_ids_are_unique() {
+        let ids = RuleId::ALL
+            .iter()
+            .map(|rule| rule.rule().id)
+            .collect::<HashSet<_>>();
+
+        assert_eq!(ids.len(), RuleId::ALL.len());
+    }
+
+    #[test]
+    fn from_id_roundtrip() {
+        for rule in RuleId::ALL {
+            assert_eq!(RuleId::from_id(rule.rule().id), Some(*rule));
+        }
+    }
+
+    #[test]
+    fn from_id_unknown() {
+        assert_eq!(RuleId::from_id("not-a-rule"), None);
+    }
+
+    #[test]
+    fn explain_display() {
+        let explanation = RuleId::ItemRemoved.rule().to_string();
+
+        assert_eq!(
+            explanation,
+            "item-removed: A public item (function, type, method or trait) was removed.\n  \
+             severity: breaking\n  \
+             audience: users"
+        );
+    }
+}
@@ -0,0 +1,478 @@
+use std::collections::BTreeMap;
+
+use syn::{
+    visit::{self, Visit},
+    Attribute, Fields, Ident, ItemEnum, ItemMod, ItemStruct, Lit, Meta, NestedMeta, Visibility,
+};
+
+use crate::{
+    ast::CrateAst,
+    diagnosis::DiagnosisItem,
+    public_api::{display_code, ItemPath},
+    rules::RuleId,
+};
+
+/// What a type deriving `Serialize` or `Deserialize` looks like once it is
+/// serialized: the names of its fields or variants, as serde writes them.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum SerdeShape {
+    /// The serialized name of each field.
+    Struct(Vec<String>),
+    /// The type of each field, in order, as tuple structs are serialized as
+    /// sequences.
+    TupleStruct(Vec<String>),
+    /// The serialized name of each variant.
+    Enum(Vec<String>),
+}
+
+/// Finds the public types which derive `Serialize` or `Deserialize`, even
+/// behind a `cfg_attr`, along with their serialized shape. The serde
+/// attributes are read from the source files, as the expanded crate no longer
+/// has them.
+pub(crate) fn extract(ast: &CrateAst) -> BTreeMap<ItemPath, SerdeShape> {
+    let mut visitor = SerdeVisitor::default();
+    visitor.visit_file(ast.ast());
+
+    visitor.types
+}
+
+#[derive(Default)]
+struct SerdeVisitor {
+    path: Vec<Ident>,
+    types: BTreeMap<ItemPath, SerdeShape>,
+}
+
+impl SerdeVisitor {
+    fn add(&mut self, ident: &Ident, shape: SerdeShape) {
+        let mut path = self.path.clone();
+        path.push(ident.clone());
+        self.types.insert(ItemPath::from_segments(path), shape);
+    }
+}
+
+impl<'ast> Visit<'ast> for SerdeVisitor {
+    fn visit_item_mod(&mut self, mod_: &'ast ItemMod) {
+        if matches!(mod_.vis, Visibility::Public(_)) {
+            self.path.push(mod_.ident.clone());
+            visit::visit_item_mod(self, mod_);
+            self.path.pop();
+        }
+    }
+
+    fn visit_item_struct(&mut self, struct_: &'ast ItemStruct) {
+        let attrs = metas(&struct_.attrs);
+        if !matches!(struct_.vis, Visibility::Public(_)) || !derives_serde(&attrs) {
+            return;
+        }
+
+        let container = serde_args(&attrs);
+        let fields = struct_
+            .fields
+            .iter()
+            .map(|field| (field, serde_args(&metas(&field.attrs))))
+            .filter(|(_, args)| !is_skipped(args));
+
+        let shape = match &struct_.fields {
+            Fields::Named(_) => SerdeShape::Struct(
+                fields
+                    .filter(|(_, args)| !args.contains_key("flatten"))
+                    .map(|(field, args)| {
+                        let ident = field.ident.as_ref().unwrap();
+                        serialized_name(ident, &args, &container, rename_field)
+                    })
+                    .collect(),
+            ),
+            Fields::Unnamed(_) => {
+                SerdeShape::TupleStruct(fields.map(|(field, _)| display_code(&field.ty)).collect())
+            }
+            Fields::Unit => return,
+        };
+
+        self.add(&struct_.ident, shape);
+    }
+
+    fn visit_item_enum(&mut self, enum_: &'ast ItemEnum) {
+        let attrs = metas(&enum_.attrs);
+        if !matches!(enum_.vis, Visibility::Public(_)) || !derives_serde(&attrs) {
+            return;
+        }
+
+        let container = serde_args(&attrs);
+        let variants = enum_
+            .variants
+            .iter()
+            .map(|variant| (variant, serde_args(&metas(&variant.attrs))))
+            .filter(|(_, args)| !is_skipped(args))
+            .map(|(variant, args)| {
+                serialized_name(&variant.ident, &args, &container, rename_variant)
+            })
+            .collect();
+
+        self.add(&enum_.ident, SerdeShape::Enum(variants));
+    }
+}
+
+type SerdeArgs = BTreeMap<String, Option<String>>;
+
+// The attributes as metas, with the ones of `cfg_attr` included whatever their
+// predicate, as serde support is usually behind a feature.
+fn metas(attrs: &[Attribute]) -> Vec<Meta> {
+    let mut metas = Vec::new();
+    let mut pending = attrs
+        .iter()
+        .filter_map(|attr| attr.parse_meta().ok())
+        .collect::<Vec<_>>();
+
+    while let Some(meta) = pending.pop() {
+        match meta {
+            Meta::List(list) if list.path.is_ident("cfg_attr") => pending.extend(
+                list.nested
+                    .into_iter()
+                    .skip(1)
+                    .filter_map(|nested| match nested {
+                        NestedMeta::Meta(meta) => Some(meta),
+                        NestedMeta::Lit(_) => None,
+                    }),
+            ),
+            meta => metas.push(meta),
+        }
+    }
+
+    metas
+}
+
+fn derives_serde(metas: &[Meta]) -> bool {
+    metas
+        .iter()
+        .filter_map(|meta| match meta {
+            Meta::List(list) if list.path.is_ident("derive") => Some(&list.nested),
+            _ => None,
+        })
+        .flatten()
+        .any(|nested| match nested {
+            NestedMeta::Meta(Meta::Path(path)) => path.segments.last().is_some_and(|segment| {
+                segment.ident == "Serialize" || segment.ident == "Deserialize"
+            }),
+            _ => false,
+        })
+}
+
+// The arguments of the `#[serde(...)]` attributes. The value of
+// `rename(serialize = "a", deserialize = "b")` is kept as written.
+fn serde_args(metas: &[Meta]) -> SerdeArgs {
+    metas
+        .iter()
+        .filter_map(|meta| match meta {
+            Meta::List(list) if list.path.is_ident("serde") => Some(&list.nested),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|nested| match nested {
+            NestedMeta::Meta(meta) => Some(meta),
+            NestedMeta::Lit(_) => None,
+        })
+        .map(|meta| {
+            let name = display_code(meta.path());
+            let value = match meta {
+                Meta::Path(_) => None,
+                Meta::NameValue(name_value) => match &name_value.lit {
+                    Lit::Str(s) => Some(s.value()),
+                    lit => Some(display_code(lit)),
+                },
+                Meta::List(list) => Some(display_code(&list.nested)),
+            };
+
+            (name, value)
+        })
+        .collect()
+}
+
+fn is_skipped(args: &SerdeArgs) -> bool {
+    args.contains_key("skip")
+        || (args.contains_key("skip_serializing") && args.contains_key("skip_deserializing"))
+}
+
+// The name serde gives to a field or a variant: its `rename` argument, or its
+// name converted by the `rename_all` argument of the container.
+fn serialized_name(
+    ident: &Ident,
+    args: &SerdeArgs,
+    container: &SerdeArgs,
+    rename: fn(&str, &str) -> Option<String>,
+) -> String {
+    if let Some(Some(name)) = args.get("rename") {
+        return name.clone();
+    }
+
+    let name = ident.to_string();
+    let name = name.strip_prefix("r#").unwrap_or(&name);
+
+    match container.get("rename_all") {
+        Some(Some(rule)) => rename(name, rule).unwrap_or_else(|| name.to_owned()),
+        _ => name.to_owned(),
+    }
+}
+
+// Applies a `rename_all` rule to a field name, which is in snake case.
+fn rename_field(name: &str, rule: &str) -> Option<String> {
+    let pascal = || {
+        name.split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars.next().map_or_else(String::new, |first| {
+                    first.to_uppercase().chain(chars).collect()
+                })
+            })
+            .collect::<String>()
+    };
+
+    let renamed = match rule {
+        "lowercase" | "snake_case" => name.to_owned(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => name.to_ascii_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => lowercase_first(&pascal()),
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.replace('_', "-").to_ascii_uppercase(),
+        _ => return None,
+    };
+
+    Some(renamed)
+}
+
+// Applies a `rename_all` rule to a variant name, which is in Pascal case.
+fn rename_variant(name: &str, rule: &str) -> Option<String> {
+    let snake = || {
+        let mut snake = String::new();
+        for (i, c) in name.char_indices() {
+            if c.is_uppercase() && i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        }
+        snake
+    };
+
+    let renamed = match rule {
+        "lowercase" => name.to_ascii_lowercase(),
+        "UPPERCASE" => name.to_ascii_uppercase(),
+        "PascalCase" => name.to_owned(),
+        "camelCase" => lowercase_first(name),
+        "snake_case" => snake(),
+        "SCREAMING_SNAKE_CASE" => snake().to_ascii_uppercase(),
+        "kebab-case" => snake().replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => snake().replace('_', "-").to_ascii_uppercase(),
+        _ => return None,
+    };
+
+    Some(renamed)
+}
+
+fn lowercase_first(name: &str) -> String {
+    let mut chars = name.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_lowercase().chain(chars).collect()
+    })
+}
+
+/// Finds the changes which make the data serialized by one version unreadable
+/// by the other: serialized fields which were renamed or removed, variants
+/// which were removed, and tuple struct fields which were reordered. Only the
+/// types which derive serde traits in both versions are compared.
+pub(crate) fn changes(
+    previous: &BTreeMap<ItemPath, SerdeShape>,
+    current: &BTreeMap<ItemPath, SerdeShape>,
+) -> Vec<DiagnosisItem> {
+    let mut diags = Vec::new();
+
+    for (path, previous) in previous {
+        let current = match current.get(path) {
+            Some(current) => current,
+            None => continue,
+        };
+
+        match (previous, current) {
+            (SerdeShape::Struct(previous), SerdeShape::Struct(current)) => {
+                diags.extend(missing(previous, current).map(|field| {
+                    DiagnosisItem::new(RuleId::SerdeFieldRenamed, path.clone(), None)
+                        .with_message(format!("serialized field `{}` renamed or removed", field))
+                }));
+            }
+            (SerdeShape::Enum(previous), SerdeShape::Enum(current)) => {
+                diags.extend(missing(previous, current).map(|variant| {
+                    DiagnosisItem::new(RuleId::SerdeVariantRemoved, path.clone(), None)
+                        .with_message(format!(
+                            "serialized variant `{}` renamed or removed",
+                            variant
+                        ))
+                }));
+            }
+            (SerdeShape::TupleStruct(previous), SerdeShape::TupleStruct(current))
+                if previous != current && is_permutation(previous, current) =>
+            {
+                diags.push(
+                    DiagnosisItem::new(RuleId::SerdeFieldOrderChanged, path.clone(), None)
+                        .with_message("serialized fields reordered")
+                        .with_signatures(
+                            Some(format!("({})", previous.join(", "))),
+                            Some(format!("({})", current.join(", "))),
+                        ),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    diags
+}
+
+fn missing<'a>(previous: &'a [String], current: &'a [String]) -> impl Iterator<Item = &'a String> {
+    previous.iter().filter(move |name| !current.contains(name))
+}
+
+fn is_permutation(previous: &[String], current: &[String]) -> bool {
+    let mut previous = previous.to_vec();
+    let mut current = current.to_vec();
+    previous.sort();
+    current.sort();
+
+    previous == current
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn shapes(code: &str) -> Vec<(String, SerdeShape)> {
+        extract(&CrateAst::from_str(code).unwrap())
+            .into_iter()
+            .map(|(path, shape)| (path.to_string(), shape))
+            .collect()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn serialized_shapes() {
+        let shapes = shapes(
+            r#"
+            #[derive(Serialize, Deserialize)]
+            #[serde(rename_all = "camelCase")]
+            pub struct Config {
+                max_size: u32,
+                #[serde(rename = "Name")]
+                name: String,
+                #[serde(skip)]
+                cache: Cache,
+                #[serde(flatten)]
+                extra: Extra,
+            }
+
+            #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+            #[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+            pub enum Mode { FastPath, #[serde(rename = "slow")] Slow }
+
+            pub mod wire {
+                #[derive(Deserialize)]
+                pub struct Pair(pub u8, #[serde(skip)] (), pub String);
+            }
+
+            #[derive(Serialize)]
+            struct Private { a: u8 }
+
+            #[derive(Clone)]
+            pub struct NotSerialized { a: u8 }
+            "#,
+        );
+
+        assert_eq!(
+            shapes,
+            [
+                (
+                    "Config".to_owned(),
+                    SerdeShape::Struct(names(&["maxSize", "Name"]))
+                ),
+                (
+                    "Mode".to_owned(),
+                    SerdeShape::Enum(names(&["fast_path", "slow"]))
+                ),
+                (
+                    "wire::Pair".to_owned(),
+                    SerdeShape::TupleStruct(names(&["u8", "String"]))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rename_all_rules() {
+        assert_eq!(
+            rename_field("max_size", "SCREAMING-KEBAB-CASE").unwrap(),
+            "MAX-SIZE"
+        );
+        assert_eq!(rename_field("max_size", "PascalCase").unwrap(), "MaxSize");
+        assert_eq!(
+            rename_variant("FastPath", "kebab-case").unwrap(),
+            "fast-path"
+        );
+        assert_eq!(rename_variant("FastPath", "camelCase").unwrap(), "fastPath");
+        assert_eq!(rename_variant("FastPath", "unknown"), None);
+    }
+
+    #[test]
+    fn serialization_changes() {
+        let previous = extract(
+            &CrateAst::from_str(
+                r#"
+                #[derive(Serialize)]
+                pub struct Config { size: u32, name: String, #[serde(rename = "v")] version: u8 }
+
+                #[derive(Serialize)]
+                pub enum Mode { Fast, Slow }
+
+                #[derive(Serialize)]
+                pub struct Pair(pub u8, pub String);
+
+                #[derive(Serialize)]
+                pub struct Point(pub u8, pub u8);
+                "#,
+            )
+            .unwrap(),
+        );
+        let current = extract(
+            &CrateAst::from_str(
+                r#"
+                #[derive(Serialize)]
+                pub struct Config { len: u32, #[serde(rename = "name")] label: String, #[serde(rename = "v")] version: u16 }
+
+                #[derive(Serialize)]
+                pub enum Mode { Fast, Turbo }
+
+                #[derive(Serialize)]
+                pub struct Pair(pub String, pub u8);
+
+                #[derive(Serialize)]
+                pub struct Point(pub u8, pub u16);
+                "#,
+            )
+            .unwrap(),
+        );
+
+        let changes = changes(&previous, &current)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            [
+                "≠ Config (serialized field `size` renamed or removed)",
+                "≠ Mode (serialized variant `Slow` renamed or removed)",
+                "≠ Pair (serialized fields reordered)",
+            ]
+        );
+    }
+}
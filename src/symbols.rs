@@ -0,0 +1,277 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result as AnyResult};
+use proc_macro2::Span;
+use serde_json::Value;
+use syn::Ident;
+
+use crate::{
+    diagnosis::DiagnosisItem,
+    glue::{self, ExtractionConfig},
+    manifest,
+    public_api::ItemPath,
+    rules::RuleId,
+};
+
+/// The symbols the standard library defines in every `staticlib`.
+const RUNTIME_SYMBOLS: &[&str] = &["rust_begin_unwind", "rust_eh_personality", "rust_panic"];
+
+/// Builds the `cdylib` or `staticlib` library of the crate located in `dir`,
+/// then returns the symbols it exports. The symbols of the Rust items, which
+/// are mangled, and the ones of the runtime are left out: what remains are
+/// the `#[no_mangle]` and `#[export_name]` items, including the ones
+/// generated by macros.
+pub(crate) fn exported(config: &ExtractionConfig, dir: &Path) -> AnyResult<BTreeSet<String>> {
+    manifest::check_c_library(dir)?;
+
+    let mut command = Command::new("cargo");
+    command
+        .current_dir(dir)
+        .args(["build", "--lib", "--message-format=json", "--quiet"])
+        .args(config.lockfile.cargo_flag(dir)?)
+        .args(glue::features_args(&config.features))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    if let Some(target_dir) = &config.target_dir {
+        command.env("CARGO_TARGET_DIR", target_dir);
+    }
+
+    let output = command.output().context("Failed to run `cargo build`")?;
+
+    if !output.status.success() {
+        bail!(
+            "The library does not build:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let library = match library_artifact(&output.stdout)? {
+        Some(library) => library,
+        None => bail!("`cargo build` built no `cdylib` or `staticlib` library"),
+    };
+
+    let mut nm = Command::new("nm");
+    nm.args(["--defined-only", "--extern-only"]);
+    if !is_static(&library) {
+        nm.arg("--dynamic");
+    }
+
+    let output = nm
+        .arg(&library)
+        .stderr(Stdio::piped())
+        .output()
+        .context("Failed to run nm")?;
+
+    if !output.status.success() {
+        bail!(
+            "Failed to read the symbols of {}:\n{}",
+            library.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(parse_nm(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// The library built for C, among the artifacts listed by the JSON messages of
+// cargo. The dynamic library is preferred when there are both.
+fn library_artifact(messages: &[u8]) -> AnyResult<Option<PathBuf>> {
+    let mut libraries = Vec::new();
+
+    for message in messages.split(|b| *b == b'\n').filter(|m| !m.is_empty()) {
+        let message = serde_json::from_slice::<Value>(message)
+            .context("Failed to parse `cargo build` output")?;
+
+        if message["reason"] != "compiler-artifact" {
+            continue;
+        }
+
+        let kinds = message["target"]["kind"].as_array().into_iter().flatten();
+        if !kinds
+            .filter_map(Value::as_str)
+            .any(|kind| kind == "cdylib" || kind == "staticlib")
+        {
+            continue;
+        }
+
+        let filenames = message["filenames"].as_array().into_iter().flatten();
+        libraries.extend(filenames.filter_map(Value::as_str).map(PathBuf::from));
+    }
+
+    libraries.sort_by_key(|path| is_static(path));
+
+    Ok(libraries.into_iter().find(|path| {
+        path.extension()
+            .is_some_and(|ext| ["so", "dylib", "dll", "a", "lib"].iter().any(|e| ext == *e))
+    }))
+}
+
+fn is_static(library: &Path) -> bool {
+    library
+        .extension()
+        .is_some_and(|ext| ext == "a" || ext == "lib")
+}
+
+// Reads the output of nm: each line is an address, a type and a name. The
+// weak symbols, which the library does not own, are left out.
+fn parse_nm(output: &str) -> BTreeSet<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().rev();
+            let name = fields.next()?;
+            let kind = fields.next()?;
+
+            matches!(kind, "T" | "D" | "B" | "R").then(|| name)
+        })
+        // Mach-O prefixes the symbols with an underscore.
+        .map(|name| {
+            if cfg!(target_os = "macos") {
+                name.strip_prefix('_').unwrap_or(name)
+            } else {
+                name
+            }
+        })
+        .filter(|name| !is_rust_internal(name))
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+fn is_rust_internal(name: &str) -> bool {
+    // Legacy and v0 mangling, and the symbols reserved to the implementation.
+    name.starts_with("_ZN")
+        || name.starts_with("_R")
+        || name.starts_with("__")
+        || RUNTIME_SYMBOLS.contains(&name)
+}
+
+/// Finds the symbols which the library no longer or newly exports. A renamed
+/// symbol is reported as removed, and the new one as added.
+pub(crate) fn changes(
+    previous: &BTreeSet<String>,
+    current: &BTreeSet<String>,
+) -> Vec<DiagnosisItem> {
+    let removals = previous
+        .difference(current)
+        .map(|symbol| diagnosis(RuleId::SymbolRemoved, symbol));
+    let additions = current
+        .difference(previous)
+        .map(|symbol| diagnosis(RuleId::SymbolAdded, symbol));
+
+    removals.chain(additions).collect()
+}
+
+// Symbols are reported at the path of the Rust item of the same name, which
+// `#[no_mangle]` items usually are at the crate root. The other ones, such as
+// `#[export_name = "lib.init"]`, are reported on the crate root.
+fn diagnosis(rule: RuleId, symbol: &str) -> DiagnosisItem {
+    let is_ident = symbol.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && !symbol.starts_with(|c: char| c.is_ascii_digit());
+
+    if is_ident {
+        let path = ItemPath::from_segments(vec![Ident::new(symbol, Span::call_site())]);
+        DiagnosisItem::new(rule, path, None).with_message("exported symbol")
+    } else {
+        DiagnosisItem::new(rule, ItemPath::from_segments(Vec::new()), None)
+            .with_message(format!("exported symbol `{}`", symbol))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    #[test]
+    fn nm_output() {
+        let output = "0000000000001120 T add\n\
+                      0000000000004010 D COUNTER\n\
+                      0000000000002000 R VERSION\n\
+                      0000000000001200 T _ZN3foo3bar17h0123456789abcdefE\n\
+                      0000000000001300 T _RNvCs123_3foo3bar\n\
+                      0000000000001400 W memcpy\n\
+                      0000000000001500 T __rust_alloc\n\
+                      0000000000001600 T rust_eh_personality\n";
+
+        assert_eq!(
+            parse_nm(output).into_iter().collect::<Vec<_>>(),
+            ["COUNTER", "VERSION", "add"]
+        );
+    }
+
+    #[test]
+    fn library_artifacts() {
+        let messages = br#"{"reason":"compiler-artifact","target":{"kind":["lib"]},"filenames":["/t/libdep.rlib"]}
+{"reason":"compiler-artifact","target":{"kind":["staticlib","cdylib"]},"filenames":["/t/libfoo.a","/t/libfoo.so"]}
+{"reason":"build-finished","success":true}
+"#;
+
+        assert_eq!(
+            library_artifact(messages).unwrap(),
+            Some(PathBuf::from("/t/libfoo.so"))
+        );
+    }
+
+    #[test]
+    fn symbol_changes() {
+        let symbols = |symbols: &[&str]| symbols.iter().map(|s| s.to_string()).collect();
+
+        let changes = changes(
+            &symbols(&["init", "lib.v1", "parse"]),
+            &symbols(&["init", "parse_str"]),
+        )
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            [
+                "- crate (exported symbol `lib.v1`)",
+                "- parse (exported symbol)",
+                "+ parse_str (exported symbol)"
+            ]
+        );
+    }
+
+    #[test]
+    fn cdylib_symbols_are_read() {
+        let dir = env::temp_dir().join(format!("cb-test-symbols-{}", process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n\
+             [lib]\ncrate-type = [\"cdylib\"]\n\n[workspace]\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("src/lib.rs"),
+            "macro_rules! export { ($name:ident) => { #[no_mangle] pub extern \"C\" fn $name() {} }; }\n\
+             export!(generated);\n\
+             #[no_mangle] pub extern \"C\" fn add(a: i32, b: i32) -> i32 { a + b }\n\
+             pub fn mangled() {}\n",
+        )
+        .unwrap();
+
+        let config = ExtractionConfig {
+            target_dir: Some(dir.join("target")),
+            ..ExtractionConfig::default()
+        };
+
+        assert_eq!(
+            exported(&config, &dir)
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            ["add", "generated"]
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,363 @@
+//! Helpers for writing comparison tests.
+//!
+//! The simplest way to compare two versions of a crate is
+//! [`diff_from_str`], which takes the code of each version as a single file.
+//! Crates spread across several files, which depend on cargo features, or
+//! which re-export the items of other crates, are described with a
+//! [`Fixture`]. The [`get_diff!`](crate::get_diff) macro
+//! builds both fixtures from inline code.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result as AnyResult};
+use cargo_toml::Manifest;
+use semver::Version;
+
+use crate::{
+    ast::{CfgOptions, CrateAst},
+    comparator::{ApiComparator, ApiCompatibilityDiagnostics},
+    facade, glue, manifest,
+    public_api::PublicApi,
+    report::{OutputFormat, Report},
+};
+
+pub use crate::get_diff;
+
+const MANIFEST_FILE: &str = "Cargo.toml";
+const CRATE_ROOT: &str = "lib.rs";
+
+/// The source code of one version of a crate.
+///
+/// File paths are relative to the `src` directory of the crate, and the crate
+/// root is `lib.rs`. An optional `Cargo.toml` can be provided: features
+/// enabled by default (as well as the ones enabled with
+/// [`Fixture::enable_feature`]) are taken into account when evaluating
+/// `#[cfg(feature = "...")]` attributes.
+///
+/// The crates the fixture depends on are fixtures too, added with
+/// [`Fixture::dependency`]. The items they define and the fixture re-exports
+/// with `pub use` are compared as if the fixture defined them, like
+/// `--follow-reexports` does. A dependency is known in the code by the name of
+/// its `[dependencies]` entry, which accounts for `package = "..."` renames, or
+/// by its package name when the fixture has no manifest.
+///
+/// Fixtures are parsed, not compiled, so constructs gated behind a nightly
+/// feature (generic associated types, trait aliases, ...) need no
+/// `#![feature(...)]` attribute.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fixture {
+    files: BTreeMap<PathBuf, String>,
+    manifest: Option<String>,
+    enabled_features: Vec<String>,
+    // By package name.
+    dependencies: BTreeMap<String, Fixture>,
+}
+
+impl Fixture {
+    /// Creates a fixture whose crate root contains `lib`.
+    pub fn new(lib: impl Into<String>) -> Fixture {
+        Fixture::default().file(CRATE_ROOT, lib)
+    }
+
+    /// Adds a file to the fixture. If `path` is `Cargo.toml`, the content is
+    /// used as the crate manifest.
+    pub fn file(mut self, path: impl AsRef<Path>, content: impl Into<String>) -> Fixture {
+        let path = path.as_ref();
+
+        if path == Path::new(MANIFEST_FILE) {
+            self.manifest = Some(content.into());
+        } else {
+            self.files.insert(path.to_owned(), content.into());
+        }
+
+        self
+    }
+
+    /// Sets the crate manifest.
+    pub fn manifest(self, manifest: impl Into<String>) -> Fixture {
+        self.file(MANIFEST_FILE, manifest)
+    }
+
+    /// Enables a feature, in addition to the default ones.
+    pub fn enable_feature(mut self, feature: impl Into<String>) -> Fixture {
+        self.enabled_features.push(feature.into());
+        self
+    }
+
+    /// Adds a crate the fixture depends on, by its package name.
+    pub fn dependency(mut self, package: impl Into<String>, dependency: Fixture) -> Fixture {
+        self.dependencies.insert(package.into(), dependency);
+        self
+    }
+
+    fn cfg_options(&self) -> AnyResult<CfgOptions> {
+        let manifest = match &self.manifest {
+            Some(manifest) => parse_manifest(manifest)?,
+            None => return Ok(CfgOptions::new(self.enabled_features.iter().cloned())),
+        };
+
+        let enabled = manifest::enabled_features(&manifest, self.enabled_features.iter().cloned());
+
+        Ok(CfgOptions::new(enabled))
+    }
+
+    // The package name of each dependency, by the name it is known by in
+    // the code.
+    fn dependency_names(&self) -> AnyResult<BTreeMap<String, String>> {
+        let manifest = match &self.manifest {
+            Some(manifest) => parse_manifest(manifest)?,
+            None => {
+                return Ok(self
+                    .dependencies
+                    .keys()
+                    .map(|package| (package.replace('-', "_"), package.clone()))
+                    .collect())
+            }
+        };
+
+        Ok(manifest
+            .dependencies
+            .iter()
+            .map(|(name, dependency)| {
+                let package = dependency.package().unwrap_or(name);
+                (name.replace('-', "_"), package.to_owned())
+            })
+            .collect())
+    }
+
+    fn crate_ast(&self) -> AnyResult<CrateAst> {
+        let cfg = self.cfg_options()?;
+        let mut ast = CrateAst::from_sources(
+            Path::new(CRATE_ROOT),
+            |path| self.files.get(path).cloned(),
+            &cfg,
+        )?;
+
+        let crates = facade::reexported_crates(&ast);
+        if crates.is_empty() {
+            return Ok(ast);
+        }
+
+        let names = self.dependency_names()?;
+        let dependencies = crates
+            .into_iter()
+            .filter_map(|(ident, name)| {
+                let package = names.get(&name.to_string())?;
+                Some((ident, self.dependencies.get(package)?))
+            })
+            .map(|(ident, dependency)| {
+                let dep = dependency
+                    .crate_ast()
+                    .with_context(|| format!("Failed to read the `{}` dependency", ident))?;
+                Ok((ident, dep))
+            })
+            .collect::<AnyResult<BTreeMap<_, _>>>()?;
+
+        facade::inline_reexports(&mut ast, &dependencies);
+
+        Ok(ast)
+    }
+
+    fn public_api(&self, include_private: bool) -> AnyResult<PublicApi> {
+        let ast = self.crate_ast()?;
+
+        if include_private {
+            Ok(PublicApi::from_ast_including_private(&ast))
+        } else {
+            Ok(PublicApi::from_ast(&ast))
+        }
+    }
+}
+
+fn parse_manifest(manifest: &str) -> AnyResult<Manifest> {
+    // Fixture manifests usually contain only the relevant tables, so a
+    // placeholder package is added when needed.
+    let manifest = if manifest.contains("[package]") {
+        manifest.to_owned()
+    } else {
+        format!(
+            "[package]\nname = \"fixture\"\nversion = \"0.0.0\"\n{}",
+            manifest
+        )
+    };
+
+    Manifest::from_str(&manifest).context("Failed to parse fixture manifest")
+}
+
+/// Compares two versions of a single-file crate.
+pub fn diff_from_str(previous: &str, current: &str) -> AnyResult<ApiCompatibilityDiagnostics> {
+    diff_fixtures(&Fixture::new(previous), &Fixture::new(current))
+}
+
+/// Renders the report of the comparison of two versions of a single-file
+/// crate, as printed with `--format text` or `--format json`. The previous
+/// version is `1.0.0`, so that the next version depends on the changes only.
+pub fn render_report(previous: &str, current: &str, format: &str) -> AnyResult<String> {
+    let format = format.parse::<OutputFormat>()?;
+    let comparator = glue::compare(previous, current)?;
+
+    let diagnosis = comparator.run();
+    let next_version = diagnosis.guess_next_version(Version::new(1, 0, 0));
+
+    let mut report = Report::new(&diagnosis, &next_version, None);
+    if format == OutputFormat::Json {
+        report = report.with_impact(comparator.impact(&diagnosis));
+    }
+
+    report.render(format, false)
+}
+
+/// Compares two versions of a crate.
+pub fn diff_fixtures(
+    previous: &Fixture,
+    current: &Fixture,
+) -> AnyResult<ApiCompatibilityDiagnostics> {
+    diff(previous, current, false)
+}
+
+/// Compares two versions of a crate, including the items which are not
+/// reachable from outside of the crate, as `--include-private` does.
+pub fn diff_fixtures_including_private(
+    previous: &Fixture,
+    current: &Fixture,
+) -> AnyResult<ApiCompatibilityDiagnostics> {
+    diff(previous, current, true)
+}
+
+fn diff(
+    previous: &Fixture,
+    current: &Fixture,
+    include_private: bool,
+) -> AnyResult<ApiCompatibilityDiagnostics> {
+    let previous = previous
+        .public_api(include_private)
+        .context("Failed to extract API of previous version")?;
+    let current = current
+        .public_api(include_private)
+        .context("Failed to extract API of current version")?;
+
+    Ok(ApiComparator::new(previous, current).run())
+}
+
+/// Compares two versions of a crate, panicking if any of them can't be
+/// processed.
+///
+/// Each version is either the code of the crate root between braces, or a
+/// list of files:
+///
+/// ```
+/// use cargo_breaking::get_diff;
+///
+/// let diff = get_diff! {
+///     {
+///         "lib.rs" => { pub mod foo; }
+///         "foo.rs" => { pub fn f() {} }
+///     },
+///     {
+///         "Cargo.toml" => "[features]\ndefault = [\"g\"]\ng = []"
+///         "lib.rs" => { pub mod foo; }
+///         "foo.rs" => {
+///             pub fn f() {}
+///
+///             #[cfg(feature = "g")]
+///             pub fn g() {}
+///         }
+///     },
+/// };
+///
+/// assert_eq!(diff.to_string(), "+ foo::g\n");
+/// ```
+#[macro_export]
+macro_rules! get_diff {
+    ( $previous:tt, $current:tt $(,)? ) => {
+        $crate::tests::diff_fixtures(
+            &$crate::__fixture!($previous),
+            &$crate::__fixture!($current),
+        )
+        .unwrap()
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fixture {
+    ({ $( $path:literal => $content:tt )+ }) => {
+        $crate::tests::Fixture::default()
+            $( .file($path, $crate::__fixture_content!($content)) )+
+    };
+
+    ({ $( $code:tt )* }) => {
+        $crate::tests::Fixture::new(stringify!($($code)*))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fixture_content {
+    ({ $( $code:tt )* }) => {
+        stringify!($($code)*)
+    };
+
+    ($content:literal) => {
+        $content
+    };
+}
+
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_features_are_enabled() {
+        let fixture =
+            Fixture::new("").manifest("[features]\ndefault = [\"a\"]\na = [\"b\"]\nb = []\nc = []");
+
+        let expected = CfgOptions::new(vec!["default".to_owned(), "a".to_owned(), "b".to_owned()]);
+
+        assert_eq!(fixture.cfg_options().unwrap(), expected);
+    }
+
+    #[test]
+    fn explicit_features_are_enabled() {
+        let fixture = Fixture::new("")
+            .manifest("[features]\na = []\nb = []")
+            .enable_feature("b");
+
+        let expected = CfgOptions::new(vec!["b".to_owned()]);
+
+        assert_eq!(fixture.cfg_options().unwrap(), expected);
+    }
+
+    #[test]
+    fn dependency_features_are_ignored() {
+        let fixture = Fixture::new("").manifest("[features]\ndefault = [\"serde/std\"]");
+
+        let expected = CfgOptions::new(vec!["default".to_owned()]);
+
+        assert_eq!(fixture.cfg_options().unwrap(), expected);
+    }
+
+    #[test]
+    fn dependencies_are_known_by_their_manifest_name() {
+        let fixture = Fixture::new("")
+            .manifest("[dependencies]\nmy-dep = \"1.0\"\nutils = { package = \"fixture-utils\", version = \"1.0\" }")
+            .dependency("my-dep", Fixture::default())
+            .dependency("fixture-utils", Fixture::default());
+
+        let names = fixture.dependency_names().unwrap();
+
+        assert_eq!(names["my_dep"], "my-dep");
+        assert_eq!(names["utils"], "fixture-utils");
+    }
+
+    #[test]
+    fn diff_from_str_simple() {
+        let diff = diff_from_str("", "pub fn f() {}").unwrap();
+
+        assert_eq!(diff.to_string(), "+ f\n");
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result as AnyResult};
+
+use crate::{error::Error, glue::EXPANSION_FLAGS};
+
+const INSTALL_COMMAND: &str = "rustup toolchain install nightly --profile minimal";
+
+/// Commit dates of the nightly compilers the expansion pipeline has been
+/// tested with. Other nightlies may work, but the unstable flags and the
+/// format of the expanded code may change at any time.
+///
+/// Keep this list sorted.
+pub(crate) const KNOWN_GOOD_NIGHTLIES: &[&str] = &["2021-06-30"];
+
+/// The nightly toolchain used to expand the crate code. It is required
+/// because the expansion relies on unstable `-Z` flags.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct NightlyToolchain {
+    release: String,
+    commit_date: Option<String>,
+}
+
+impl NightlyToolchain {
+    /// Checks that a nightly toolchain is installed, before any build is
+    /// started.
+    ///
+    /// If `install` is true and no nightly toolchain can be found, it is
+    /// installed with rustup.
+    pub(crate) fn detect(install: bool) -> AnyResult<NightlyToolchain> {
+        let toolchain = match NightlyToolchain::query() {
+            Ok(toolchain) => Ok(toolchain),
+            Err(_) if install => {
+                NightlyToolchain::install()?;
+                NightlyToolchain::query()
+            }
+            Err(e) => Err(e.context(format!(
+                "cargo-breaking requires a nightly toolchain. Install it with `{}`, \
+                 or run cargo-breaking with `--install-nightly`",
+                INSTALL_COMMAND
+            ))),
+        };
+
+        toolchain.map_err(|e| Error::ToolchainMissing(e).into())
+    }
+
+    pub(crate) fn query() -> AnyResult<NightlyToolchain> {
+        let output = Command::new("rustc")
+            .args(["+nightly", "-vV"])
+            .output()
+            .context("Failed to run `rustc +nightly -vV`")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!(
+                "No nightly toolchain found: {}",
+                stderr.lines().next().unwrap_or_default()
+            );
+        }
+
+        NightlyToolchain::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    fn install() -> AnyResult<()> {
+        eprintln!("Installing the nightly toolchain (`{}`)", INSTALL_COMMAND);
+
+        let status = Command::new("rustup")
+            .args(["toolchain", "install", "nightly", "--profile", "minimal"])
+            .status()
+            .context("Failed to run rustup")?;
+
+        if !status.success() {
+            bail!("Failed to install the nightly toolchain");
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn is_known_good(&self) -> bool {
+        self.commit_date
+            .as_deref()
+            .is_some_and(|date| KNOWN_GOOD_NIGHTLIES.contains(&date))
+    }
+
+    /// Expands an empty crate with the flags used to extract the API, so that
+    /// unsupported flags are detected without building the whole crate.
+    pub(crate) fn check_expansion_flags(&self) -> AnyResult<()> {
+        let output = Command::new("rustc")
+            .arg("+nightly")
+            .args(["-", "--crate-type", "lib", "--crate-name", "probe"])
+            .args(EXPANSION_FLAGS)
+            .stdin(Stdio::null())
+            .output()
+            .context("Failed to run `rustc +nightly`")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let message = stderr.lines().next().unwrap_or_default();
+            bail!("{}", message.trim_start_matches("error: "));
+        }
+
+        Ok(())
+    }
+
+    /// Parses the output of `rustc -vV`.
+    fn parse(version_info: &str) -> AnyResult<NightlyToolchain> {
+        let field = |name: &str| {
+            version_info
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(": "))
+                .map(str::to_owned)
+        };
+
+        let release = field("release").context("Unexpected `rustc -vV` output")?;
+
+        // Locally built compilers (`-dev`) accept unstable flags as well.
+        if !release.contains("nightly") && !release.contains("dev") {
+            bail!(
+                "The `nightly` toolchain is not a nightly compiler (found {})",
+                release
+            );
+        }
+
+        let commit_date = field("commit-date").filter(|date| date != "unknown");
+
+        Ok(NightlyToolchain {
+            release,
+            commit_date,
+        })
+    }
+}
+
+impl Display for NightlyToolchain {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "rustc {}", self.release)?;
+
+        if let Some(date) = &self.commit_date {
+            write!(f, " ({})", date)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_nightly() {
+        let output = "rustc 1.55.0-nightly (868c702d0 2021-06-30)\n\
+                      binary: rustc\n\
+                      commit-hash: 868c702d0c9a471a28fb55f0148eb1e3e8b1dcc5\n\
+                      commit-date: 2021-06-30\n\
+                      host: x86_64-unknown-linux-gnu\n\
+                      release: 1.55.0-nightly\n\
+                      LLVM version: 12.0.1\n";
+
+        let expected = NightlyToolchain {
+            release: "1.55.0-nightly".to_owned(),
+            commit_date: Some("2021-06-30".to_owned()),
+        };
+
+        assert_eq!(NightlyToolchain::parse(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn parse_stable_is_an_error() {
+        let output = "rustc 1.53.0 (53cb7b09b 2021-06-17)\n\
+                      commit-date: 2021-06-17\n\
+                      release: 1.53.0\n";
+
+        assert!(NightlyToolchain::parse(output).is_err());
+    }
+
+    #[test]
+    fn known_good_nightlies_are_sorted() {
+        assert!(KNOWN_GOOD_NIGHTLIES.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn known_good_nightly() {
+        let toolchain = NightlyToolchain {
+            release: "1.55.0-nightly".to_owned(),
+            commit_date: Some(KNOWN_GOOD_NIGHTLIES[0].to_owned()),
+        };
+
+        assert!(toolchain.is_known_good());
+    }
+
+    #[test]
+    fn parse_unknown_commit_date() {
+        let output = "release: 1.56.0-dev\ncommit-date: unknown\n";
+
+        let toolchain = NightlyToolchain::parse(output).unwrap();
+
+        assert_eq!(toolchain.commit_date, None);
+    }
+}
@@ -0,0 +1,184 @@
+use std::{fmt::Write, path::Path};
+
+use anyhow::{bail, Context, Result as AnyResult};
+use serde::Serialize;
+
+use crate::{
+    comparator::{ApiComparator, ApiCompatibilityDiagnostics},
+    diagnosis::DiagnosisItem,
+    git::CrateRepo,
+    glue::{self, Backend, CrateCodeProvider, ExtractionConfig},
+    report::OutputFormat,
+};
+
+/// The diagnostics which are emitted with one backend only.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub(crate) struct Discrepancies {
+    expanded_only: Vec<DiagnosisItem>,
+    source_only: Vec<DiagnosisItem>,
+    // The number of diagnostics both backends emit.
+    #[serde(skip)]
+    agreed: usize,
+}
+
+impl Discrepancies {
+    fn new(
+        expanded: &ApiCompatibilityDiagnostics,
+        source: &ApiCompatibilityDiagnostics,
+    ) -> Discrepancies {
+        let only_in = |left: &ApiCompatibilityDiagnostics, right: &ApiCompatibilityDiagnostics| {
+            left.items()
+                .filter(|diag| !right.items().any(|other| other == *diag))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let expanded_only = only_in(expanded, source);
+        let source_only = only_in(source, expanded);
+        let agreed = expanded.items().count() - expanded_only.len();
+
+        Discrepancies {
+            expanded_only,
+            source_only,
+            agreed,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.expanded_only.is_empty() && self.source_only.is_empty()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.expanded_only.len() + self.source_only.len()
+    }
+
+    pub(crate) fn render(&self, format: OutputFormat, verbose: bool) -> AnyResult<String> {
+        match format {
+            OutputFormat::Text => Ok(self.render_text(verbose)),
+            OutputFormat::Json => {
+                serde_json::to_string_pretty(self).context("Failed to serialize discrepancies")
+            }
+            OutputFormat::Metrics
+            | OutputFormat::CargoPatch
+            | OutputFormat::Html
+            | OutputFormat::ApiDiff => bail!(
+                "The {} format is not available when verifying the backends",
+                format
+            ),
+        }
+    }
+
+    fn render_text(&self, verbose: bool) -> String {
+        if self.is_empty() {
+            return format!("Both backends agree on {} diagnostics", self.agreed);
+        }
+
+        let mut output = String::new();
+
+        for (backend, diags) in [
+            ("expanded", &self.expanded_only),
+            ("source", &self.source_only),
+        ] {
+            if diags.is_empty() {
+                continue;
+            }
+
+            writeln!(output, "Only with the {} backend:", backend).unwrap();
+
+            for diag in diags {
+                if verbose {
+                    writeln!(output, "{:#}", diag).unwrap();
+                } else {
+                    writeln!(output, "{}", diag).unwrap();
+                }
+            }
+        }
+
+        write!(
+            output,
+            "The backends agree on {} diagnostics, and disagree on {}",
+            self.agreed,
+            self.len()
+        )
+        .unwrap();
+
+        output
+    }
+}
+
+/// Compares the crate against `provider` with each backend, and returns the
+/// diagnostics they disagree on.
+pub(crate) fn run(
+    repo: &mut CrateRepo,
+    provider: &CrateCodeProvider,
+    extraction: &ExtractionConfig,
+) -> AnyResult<Discrepancies> {
+    let mut diagnose = |backend| -> AnyResult<ApiCompatibilityDiagnostics> {
+        let extraction = ExtractionConfig {
+            backend,
+            ..extraction.clone()
+        };
+
+        let current = glue::extract_api(&extraction, Path::new("."), "next")
+            .with_context(|| format!("Failed to get crate API with the {} backend", backend))?;
+        let previous = glue::extract_previous_api(repo, provider, &extraction)
+            .with_context(|| format!("Failed to get crate API with the {} backend", backend))?;
+
+        Ok(ApiComparator::new(previous, current).run())
+    };
+
+    let expanded = diagnose(Backend::Expanded)?;
+    let source = diagnose(Backend::Source)?;
+
+    Ok(Discrepancies::new(&expanded, &source))
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn agreement() {
+        let diags: ApiCompatibilityDiagnostics = parse_quote! {
+            {},
+            { pub fn f() {} },
+        };
+
+        let discrepancies = Discrepancies::new(&diags, &diags);
+
+        assert!(discrepancies.is_empty());
+        assert_eq!(
+            discrepancies.render(OutputFormat::Text, false).unwrap(),
+            "Both backends agree on 1 diagnostics"
+        );
+    }
+
+    #[test]
+    fn disagreement() {
+        let expanded: ApiCompatibilityDiagnostics = parse_quote! {
+            {},
+            {
+                pub fn f() {}
+                pub fn generated() {}
+            },
+        };
+        let source: ApiCompatibilityDiagnostics = parse_quote! {
+            { pub fn g() {} },
+            { pub fn f() {} },
+        };
+
+        let discrepancies = Discrepancies::new(&expanded, &source);
+
+        assert_eq!(discrepancies.len(), 2);
+        assert_eq!(
+            discrepancies.render(OutputFormat::Text, false).unwrap(),
+            "Only with the expanded backend:\n\
+             + generated\n\
+             Only with the source backend:\n\
+             - g\n\
+             The backends agree on 1 diagnostics, and disagree on 2"
+        );
+    }
+}
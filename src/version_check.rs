@@ -0,0 +1,242 @@
+use std::{collections::BTreeSet, process::Command};
+
+use anyhow::{bail, Context, Result as AnyResult};
+use semver::{BuildMetadata, Version};
+use serde::Deserialize;
+
+use crate::git::CrateRepo;
+
+/// Where the published versions of the crates are read from. The index file of
+/// a crate lists one version per line.
+const SPARSE_INDEX: &str = "https://index.crates.io";
+
+/// Checks that the guessed next version of the crate can still be released:
+/// no git tag has it, and, if `registry` is set, crates.io doesn't have it
+/// either. Returns a warning suggesting the next free version otherwise.
+pub(crate) fn check(
+    repo: &CrateRepo,
+    name: &str,
+    current: &Version,
+    next: &Version,
+    registry: bool,
+) -> AnyResult<Option<String>> {
+    let tags = repo.tag_names()?;
+    let tagged = tagged_versions(tags.iter().map(String::as_str));
+
+    let published = if registry {
+        // An unreachable registry is not worth failing the comparison for.
+        published_versions(name).unwrap_or_else(|e| {
+            eprintln!("Warning: {:#}", e);
+            BTreeSet::new()
+        })
+    } else {
+        BTreeSet::new()
+    };
+
+    Ok(taken_warning(current, next, &tagged, &published))
+}
+
+/// Returns a warning when `next` is tagged or published, suggesting a version
+/// which is neither.
+fn taken_warning(
+    current: &Version,
+    next: &Version,
+    tagged: &BTreeSet<Version>,
+    published: &BTreeSet<Version>,
+) -> Option<String> {
+    let reasons = [
+        (tagged, "has a git tag"),
+        (published, "is already published on crates.io"),
+    ]
+    .iter()
+    .filter(|(versions, _)| versions.contains(next))
+    .map(|(_, reason)| *reason)
+    .collect::<Vec<_>>();
+
+    if reasons.is_empty() {
+        return None;
+    }
+
+    let taken = tagged.union(published).cloned().collect();
+
+    Some(format!(
+        "Warning: the next version {} {}, the next free version is {}",
+        next,
+        reasons.join(" and "),
+        next_free_version(current, next, &taken)
+    ))
+}
+
+/// Returns the versions of the tags, which may be prefixed with `v`.
+fn tagged_versions<'a>(tags: impl Iterator<Item = &'a str>) -> BTreeSet<Version> {
+    tags.filter_map(|tag| Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok())
+        .map(without_build)
+        .collect()
+}
+
+// crates.io considers versions which differ only by their build metadata as
+// the same.
+fn without_build(mut version: Version) -> Version {
+    version.build = BuildMetadata::EMPTY;
+    version
+}
+
+#[derive(Deserialize)]
+struct IndexEntry {
+    vers: String,
+}
+
+fn published_versions(name: &str) -> AnyResult<BTreeSet<Version>> {
+    let url = format!("{}/{}", SPARSE_INDEX, index_path(name));
+
+    let output = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--location",
+            "--connect-timeout",
+            "30",
+            "--write-out",
+            "\n%{http_code}",
+        ])
+        .arg(&url)
+        .output()
+        .context("Failed to run curl")?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (body, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+
+    match status {
+        // The crate was never published.
+        "404" => return Ok(BTreeSet::new()),
+        "200" if output.status.success() => {}
+        _ => bail!("Failed to get the published versions from {}", url),
+    }
+
+    body.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let entry = serde_json::from_str::<IndexEntry>(line)
+                .with_context(|| format!("Failed to parse the index entry `{}`", line))?;
+            Version::parse(&entry.vers)
+                .map(without_build)
+                .with_context(|| format!("Invalid published version `{}`", entry.vers))
+        })
+        .collect()
+}
+
+/// Returns the path of the index file of a crate, relative to the root of the
+/// index.
+fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+/// Bumps the component `next` bumped from `current` until the version is not
+/// taken, so that the suggestion is the same kind of release.
+fn next_free_version(current: &Version, next: &Version, taken: &BTreeSet<Version>) -> Version {
+    let mut free = next.clone();
+
+    while taken.contains(&free) {
+        if free.major != current.major {
+            free.major += 1;
+        } else if free.minor != current.minor {
+            free.minor += 1;
+        } else {
+            free.patch += 1;
+        }
+    }
+
+    free
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(versions: &[&str]) -> BTreeSet<Version> {
+        versions
+            .iter()
+            .map(|v| Version::parse(v).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn tags_versions() {
+        let tags = ["v1.2.0", "1.3.0+build", "v1.4.0-rc.1", "release"];
+
+        assert_eq!(
+            tagged_versions(tags.iter().copied()),
+            versions(&["1.2.0", "1.3.0", "1.4.0-rc.1"])
+        );
+    }
+
+    #[test]
+    fn index_paths() {
+        assert_eq!(index_path("a"), "1/a");
+        assert_eq!(index_path("cc"), "2/cc");
+        assert_eq!(index_path("syn"), "3/s/syn");
+        assert_eq!(index_path("Cargo-Breaking"), "ca/rg/cargo-breaking");
+    }
+
+    #[test]
+    fn free_version_keeps_the_kind_of_release() {
+        let taken = versions(&["1.2.4", "1.2.5", "1.3.0", "2.0.0"]);
+        let current = Version::new(1, 2, 3);
+
+        assert_eq!(
+            next_free_version(&current, &Version::new(1, 2, 4), &taken),
+            Version::new(1, 2, 6)
+        );
+        assert_eq!(
+            next_free_version(&current, &Version::new(1, 3, 0), &taken),
+            Version::new(1, 4, 0)
+        );
+        assert_eq!(
+            next_free_version(&current, &Version::new(2, 0, 0), &taken),
+            Version::new(3, 0, 0)
+        );
+    }
+
+    #[test]
+    fn warning_merges_tags_and_published_versions() {
+        let current = Version::new(1, 2, 3);
+        let tagged = versions(&["1.2.4", "1.2.5"]);
+        let published = versions(&["1.2.4", "1.2.6"]);
+
+        assert_eq!(
+            taken_warning(&current, &Version::new(1, 2, 4), &tagged, &published).unwrap(),
+            "Warning: the next version 1.2.4 has a git tag and is already published on \
+             crates.io, the next free version is 1.2.7"
+        );
+        assert_eq!(
+            taken_warning(&current, &Version::new(1, 2, 5), &tagged, &published).unwrap(),
+            "Warning: the next version 1.2.5 has a git tag, the next free version is 1.2.7"
+        );
+        assert_eq!(
+            taken_warning(&current, &Version::new(1, 2, 6), &tagged, &published).unwrap(),
+            "Warning: the next version 1.2.6 is already published on crates.io, the next \
+             free version is 1.2.7"
+        );
+        assert_eq!(
+            taken_warning(&current, &Version::new(1, 3, 0), &tagged, &published),
+            None
+        );
+    }
+
+    #[test]
+    fn untaken_version_is_free() {
+        let taken = versions(&["1.2.3"]);
+
+        assert_eq!(
+            next_free_version(&Version::new(1, 2, 3), &Version::new(1, 2, 4), &taken),
+            Version::new(1, 2, 4)
+        );
+    }
+}
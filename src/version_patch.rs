@@ -0,0 +1,296 @@
+use std::{fmt::Write, fs, io::ErrorKind, path::Path};
+
+use anyhow::{bail, Context, Result as AnyResult};
+use semver::Version;
+
+// The number of unchanged lines printed around each change.
+const CONTEXT_LINES: usize = 3;
+
+/// A file which sets the crate version on one of its lines.
+pub(crate) struct VersionedFile {
+    pub path: &'static str,
+    content: String,
+    line: usize,
+}
+
+impl VersionedFile {
+    /// Returns the content of the file, with the version set to `next`.
+    pub(crate) fn bumped(&self, current: &Version, next: &Version) -> String {
+        self.content
+            .split_inclusive('\n')
+            .enumerate()
+            .map(|(idx, line)| {
+                if idx == self.line {
+                    line.replacen(&current.to_string(), &next.to_string(), 1)
+                } else {
+                    line.to_owned()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Returns the manifest of the crate located in `dir` and, if there is one,
+/// its lockfile, along with the line which sets the crate version in each.
+pub(crate) fn versioned_files(dir: &Path, current: &Version) -> AnyResult<Vec<VersionedFile>> {
+    let manifest =
+        fs::read_to_string(dir.join("Cargo.toml")).context("Failed to read the crate manifest")?;
+    let (line, name) = manifest_version_line(&manifest, current)?;
+
+    let mut files = vec![VersionedFile {
+        path: "Cargo.toml",
+        content: manifest,
+        line,
+    }];
+
+    match fs::read_to_string(dir.join("Cargo.lock")) {
+        Ok(lockfile) => {
+            if let Some(line) = lockfile_version_line(&lockfile, &name, current) {
+                files.push(VersionedFile {
+                    path: "Cargo.lock",
+                    content: lockfile,
+                    line,
+                });
+            }
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e).context("Failed to read the crate lockfile"),
+    }
+
+    Ok(files)
+}
+
+/// Returns a unified diff which sets the version of the crate located in
+/// `dir` to `next`, in its manifest and, if there is one, in its lockfile.
+/// It can be applied with `git apply` or `patch -p1` from the crate
+/// directory.
+pub(crate) fn render(dir: &Path, current: &Version, next: &Version) -> AnyResult<String> {
+    let mut patch = String::new();
+
+    if current == next {
+        return Ok(patch);
+    }
+
+    for file in versioned_files(dir, current)? {
+        write_hunk(&mut patch, &file, current, next);
+    }
+
+    Ok(patch)
+}
+
+/// Returns the index of the line which sets the crate version, along with the
+/// crate name.
+fn manifest_version_line(manifest: &str, current: &Version) -> AnyResult<(usize, String)> {
+    let mut in_package = false;
+    let mut version = None;
+    let mut name = None;
+
+    for (idx, line) in manifest.lines().enumerate() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+            continue;
+        }
+
+        if !in_package {
+            continue;
+        }
+
+        match key_value(line) {
+            Some(("name", value)) => name = Some(value.to_owned()),
+            Some(("version", value)) if value == current.to_string() => version = Some(idx),
+            _ if line.starts_with("version.workspace") => {
+                bail!("The crate version is inherited from the workspace")
+            }
+            _ => {}
+        }
+    }
+
+    match (version, name) {
+        (Some(version), Some(name)) => Ok((version, name)),
+        _ => bail!("Failed to find the crate version in the manifest"),
+    }
+}
+
+/// Returns the index of the line which sets the version of the `name`
+/// package, if it is locked at `current`.
+fn lockfile_version_line(lockfile: &str, name: &str, current: &Version) -> Option<usize> {
+    let mut in_package = false;
+
+    for (idx, line) in lockfile.lines().enumerate() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            in_package = false;
+            continue;
+        }
+
+        match key_value(line) {
+            Some(("name", value)) => in_package = value == name,
+            Some(("version", value)) if in_package && value == current.to_string() => {
+                return Some(idx)
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Splits a `key = "value"` line.
+fn key_value(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once('=')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    Some((key.trim(), value))
+}
+
+fn write_hunk(patch: &mut String, file: &VersionedFile, current: &Version, next: &Version) {
+    let VersionedFile {
+        path,
+        content,
+        line: changed,
+    } = file;
+    let changed = *changed;
+
+    let lines = content.lines().collect::<Vec<_>>();
+    let start = changed.saturating_sub(CONTEXT_LINES);
+    let end = (changed + CONTEXT_LINES + 1).min(lines.len());
+    let len = end - start;
+
+    writeln!(patch, "--- a/{}", path).unwrap();
+    writeln!(patch, "+++ b/{}", path).unwrap();
+    writeln!(patch, "@@ -{},{} +{},{} @@", start + 1, len, start + 1, len).unwrap();
+
+    let missing_newline = |idx: usize| idx + 1 == lines.len() && !content.ends_with('\n');
+
+    for (idx, line) in lines.iter().enumerate().take(end).skip(start) {
+        if idx == changed {
+            let old = line;
+            let new = line.replacen(&current.to_string(), &next.to_string(), 1);
+
+            writeln!(patch, "-{}", old).unwrap();
+            if missing_newline(idx) {
+                writeln!(patch, "\\ No newline at end of file").unwrap();
+            }
+            writeln!(patch, "+{}", new).unwrap();
+        } else {
+            writeln!(patch, " {}", line).unwrap();
+        }
+
+        if missing_newline(idx) {
+            writeln!(patch, "\\ No newline at end of file").unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    const MANIFEST: &str = "[package]\n\
+                            name = \"foo\"\n\
+                            version = \"1.2.3\"\n\
+                            edition = \"2018\"\n\
+                            \n\
+                            [dependencies]\n\
+                            bar = { version = \"1.2.3\" }\n";
+
+    fn render_in(files: &[(&str, &str)], next: Version) -> String {
+        let dir = env::temp_dir().join(format!("cb-test-patch-{}-{}", process::id(), next));
+        fs::create_dir_all(&dir).unwrap();
+
+        for (path, content) in files {
+            fs::write(dir.join(path), content).unwrap();
+        }
+
+        let patch = render(&dir, &Version::new(1, 2, 3), &next);
+
+        fs::remove_dir_all(dir).unwrap();
+        patch.unwrap()
+    }
+
+    #[test]
+    fn manifest_patch() {
+        let patch = render_in(&[("Cargo.toml", MANIFEST)], Version::new(2, 0, 0));
+
+        assert_eq!(
+            patch,
+            "--- a/Cargo.toml\n\
+             +++ b/Cargo.toml\n\
+             @@ -1,6 +1,6 @@\n \
+             [package]\n \
+             name = \"foo\"\n\
+             -version = \"1.2.3\"\n\
+             +version = \"2.0.0\"\n \
+             edition = \"2018\"\n \
+             \n \
+             [dependencies]\n"
+        );
+    }
+
+    #[test]
+    fn lockfile_patch() {
+        let lockfile = "version = 3\n\
+                        \n\
+                        [[package]]\n\
+                        name = \"bar\"\n\
+                        version = \"1.2.3\"\n\
+                        \n\
+                        [[package]]\n\
+                        name = \"foo\"\n\
+                        version = \"1.2.3\"";
+
+        let patch = render_in(
+            &[("Cargo.toml", MANIFEST), ("Cargo.lock", lockfile)],
+            Version::new(1, 3, 0),
+        );
+
+        let lockfile_patch = patch.split_once("--- a/Cargo.lock\n").unwrap().1;
+        assert_eq!(
+            lockfile_patch,
+            "+++ b/Cargo.lock\n\
+             @@ -6,4 +6,4 @@\n \
+             \n \
+             [[package]]\n \
+             name = \"foo\"\n\
+             -version = \"1.2.3\"\n\
+             \\ No newline at end of file\n\
+             +version = \"1.3.0\"\n\
+             \\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn unchanged_version() {
+        let patch = render_in(&[("Cargo.toml", MANIFEST)], Version::new(1, 2, 3));
+
+        assert!(patch.is_empty());
+    }
+
+    #[test]
+    fn bumped_content() {
+        let current = Version::new(1, 2, 3);
+        let (line, _) = manifest_version_line(MANIFEST, &current).unwrap();
+        let file = VersionedFile {
+            path: "Cargo.toml",
+            content: MANIFEST.to_owned(),
+            line,
+        };
+
+        assert_eq!(
+            file.bumped(&current, &Version::new(1, 2, 4)),
+            MANIFEST.replacen("1.2.3", "1.2.4", 1)
+        );
+    }
+
+    #[test]
+    fn inherited_version() {
+        let manifest = "[package]\nname = \"foo\"\nversion.workspace = true\n";
+
+        assert!(manifest_version_line(manifest, &Version::new(1, 2, 3)).is_err());
+    }
+}
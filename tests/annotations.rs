@@ -0,0 +1,64 @@
+use cargo_breaking::ApiCompatibilityDiagnostics;
+use syn::parse_quote;
+
+#[test]
+fn annotated_breaking_changes_are_labeled() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn parse(s: &str) {}
+            pub fn run() {}
+        },
+        {
+            #[cfg_attr(cargo_breaking, breaking(reason = "the input is now owned"))]
+            pub fn parse(s: String) {}
+            pub fn run(n: u8) {}
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ parse [expected: the input is now owned]\n≠ run\n"
+    );
+    assert_eq!(
+        diff.iter().next().unwrap().expected_reason(),
+        Some("the input is now owned")
+    );
+}
+
+#[test]
+fn annotated_modules_cover_their_items() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod legacy {
+                pub fn f() {}
+                pub fn g() {}
+            }
+        },
+        {
+            /// Kept for compatibility.
+            ///
+            /// cargo-breaking: breaking(reason = "the v1 API is gone")
+            pub mod legacy {
+                pub fn g(n: u8) {}
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "- legacy::f [expected: the v1 API is gone]\n≠ legacy::g [expected: the v1 API is gone]\n"
+    );
+}
+
+#[test]
+fn additions_are_not_labeled() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {},
+        {
+            #[cfg_attr(cargo_breaking, breaking(reason = "new"))]
+            pub fn f() {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "+ f\n");
+}
@@ -0,0 +1,192 @@
+use cargo_breaking::ApiCompatibilityDiagnostics;
+use syn::parse_quote;
+
+#[test]
+fn non_exhaustive_added_to_enum() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub enum A {}
+        },
+        {
+            #[non_exhaustive]
+            pub enum A {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ A (now #[non_exhaustive])\n");
+}
+
+#[test]
+fn deprecated_function() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn f() {}
+        },
+        {
+            #[deprecated(note = "use g")]
+            pub fn f() {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ f (now #[deprecated])\n");
+}
+
+#[test]
+fn must_use_removed_from_method() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct S;
+
+            impl S {
+                #[must_use]
+                pub fn f(&self) -> u8 { 0 }
+            }
+        },
+        {
+            pub struct S;
+
+            impl S {
+                pub fn f(&self) -> u8 { 0 }
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ S::f (no longer #[must_use])\n");
+}
+
+#[test]
+fn signature_and_attribute_changes_are_both_reported() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn f() {}
+        },
+        {
+            #[must_use]
+            pub fn f() -> u8 { 0 }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ f\n≠ f (now #[must_use])\n");
+}
+
+#[test]
+fn repr_change_on_struct() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            #[repr(C)]
+            pub struct S(pub u8);
+        },
+        {
+            #[repr(transparent)]
+            pub struct S(pub u8);
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ S (#[repr(C)] changed to #[repr(transparent)])\n"
+    );
+}
+
+#[test]
+fn documentation_is_ignored() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            /// Foo
+            #[inline]
+            pub fn f() {}
+
+            pub trait T {}
+        },
+        {
+            /// Bar
+            pub fn f() {}
+
+            /// Baz
+            pub trait T {}
+        },
+    };
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn removed_doc_alias_is_reported() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            #[doc(alias = "len")]
+            pub fn size() -> usize { 0 }
+        },
+        {
+            pub fn size() -> usize { 0 }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ size (doc alias `len` removed)\n");
+}
+
+#[test]
+fn added_doc_alias_is_not_reported() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn size() -> usize { 0 }
+        },
+        {
+            #[doc(alias = "len")]
+            pub fn size() -> usize { 0 }
+        },
+    };
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn platform_restriction() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn open() {}
+            #[doc(cfg(feature = "std"))]
+            pub fn read() {}
+        },
+        {
+            #[doc(cfg(unix))]
+            pub fn open() {}
+            pub fn read() {}
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ open (now #[doc(cfg(unix))])\n≠ read (no longer #[doc(cfg(feature=\"std\"))])\n"
+    );
+    assert_eq!(
+        diff.iter().map(|diag| diag.rule()).collect::<Vec<_>>(),
+        ["platform-restricted", "platform-extended"]
+    );
+}
+
+#[test]
+fn nightly_requirement() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn exit() -> ! {}
+            pub fn parse() -> Result<u8, ()> {}
+            pub trait Marker {}
+        },
+        {
+            pub fn exit() -> ! {}
+            pub fn parse() -> Result<u8, !> {}
+            #[marker]
+            pub trait Marker {}
+        },
+    };
+
+    assert_eq!(
+        diff.iter().map(|diag| diag.to_string()).collect::<Vec<_>>(),
+        [
+            "≠ Marker (now requires a nightly compiler: #[marker])",
+            "≠ parse",
+            "≠ parse (now requires a nightly compiler: the `!` type)",
+        ]
+    );
+}
@@ -0,0 +1,163 @@
+use cargo_breaking::ApiCompatibilityDiagnostics;
+use syn::parse_quote;
+
+#[test]
+fn deref_removal_reports_inherited_methods() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct Wrapper;
+            pub struct Inner;
+
+            impl Deref for Wrapper {
+                type Target = Inner;
+            }
+
+            impl Inner {
+                pub fn len(&self) -> usize {}
+                pub fn is_empty(&self) -> bool {}
+            }
+        },
+        {
+            pub struct Wrapper;
+            pub struct Inner;
+
+            impl Inner {
+                pub fn len(&self) -> usize {}
+                pub fn is_empty(&self) -> bool {}
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "- Wrapper: Deref (`*` and auto-deref method calls no longer work)\n\
+         ≠ Wrapper (no longer dereferences to `Inner`, `is_empty`, `len` no longer reachable)\n"
+    );
+}
+
+#[test]
+fn deref_target_change_reports_missing_methods() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct Wrapper;
+            pub struct A;
+            pub struct B;
+
+            impl Deref for Wrapper {
+                type Target = A;
+            }
+
+            impl A {
+                pub fn len(&self) -> usize {}
+                pub fn get(&self) -> u8 {}
+            }
+
+            impl B {
+                pub fn len(&self) -> usize {}
+            }
+        },
+        {
+            pub struct Wrapper;
+            pub struct A;
+            pub struct B;
+
+            impl Deref for Wrapper {
+                type Target = B;
+            }
+
+            impl A {
+                pub fn len(&self) -> usize {}
+                pub fn get(&self) -> u8 {}
+            }
+
+            impl B {
+                pub fn len(&self) -> usize {}
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ Wrapper (deref target changed from `A` to `B`, `get` no longer reachable)\n\
+         ≠ Wrapper: Deref\n"
+    );
+}
+
+#[test]
+fn methods_moved_to_the_type_are_not_reported() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct Wrapper;
+            pub struct Inner;
+
+            impl Deref for Wrapper {
+                type Target = Inner;
+            }
+
+            impl Inner {
+                pub fn len(&self) -> usize {}
+            }
+        },
+        {
+            pub struct Wrapper;
+            pub struct Inner;
+
+            impl Wrapper {
+                pub fn len(&self) -> usize {}
+            }
+
+            impl Inner {
+                pub fn len(&self) -> usize {}
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "- Wrapper: Deref (`*` and auto-deref method calls no longer work)\n\
+         + Wrapper::len\n"
+    );
+}
+
+#[test]
+fn methods_of_deref_chain_are_tracked() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct A;
+            pub struct B;
+            pub struct C;
+
+            impl Deref for A {
+                type Target = B;
+            }
+
+            impl Deref for B {
+                type Target = C;
+            }
+
+            impl C {
+                pub fn f(&self) {}
+            }
+        },
+        {
+            pub struct A;
+            pub struct B;
+            pub struct C;
+
+            impl Deref for A {
+                type Target = B;
+            }
+
+            impl C {
+                pub fn f(&self) {}
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "- B: Deref (`*` and auto-deref method calls no longer work)\n\
+         ≠ A (`B` no longer dereferences to `C`, `f` no longer reachable)\n\
+         ≠ B (no longer dereferences to `C`, `f` no longer reachable)\n"
+    );
+}
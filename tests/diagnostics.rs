@@ -0,0 +1,52 @@
+use cargo_breaking::{ApiCompatibilityDiagnostics, Audience, DiagnosisItemKind, Severity};
+use syn::parse_quote;
+
+#[test]
+fn diagnostics_can_be_iterated() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod foo {
+                pub fn f(a: u8) {}
+                pub fn g() {}
+            }
+            pub struct S;
+        },
+        {
+            pub mod foo {
+                pub fn f(a: u16) -> u16 {}
+            }
+            pub struct S;
+            impl Clone for S {}
+        },
+    };
+
+    let diags = diff.iter().collect::<Vec<_>>();
+    assert_eq!(diags.len(), 3);
+
+    let removal = &diags[0];
+    assert_eq!(removal.kind(), DiagnosisItemKind::Removal);
+    assert_eq!(removal.path(), ["foo", "g"]);
+    assert_eq!(removal.rule(), "item-removed");
+    assert_eq!(removal.severity(), Severity::Breaking);
+    assert_eq!(removal.audience(), Audience::Users);
+    assert_eq!(removal.previous_signature(), Some("fn g()"));
+    assert_eq!(removal.current_signature(), None);
+
+    let modification = &diags[1];
+    assert_eq!(modification.kind(), DiagnosisItemKind::Modification);
+    assert_eq!(modification.path(), ["foo", "f"]);
+    assert_eq!(modification.previous_signature(), Some("fn f(a: u8)"));
+    assert_eq!(
+        modification.current_signature(),
+        Some("fn f(a: u16) -> u16")
+    );
+
+    let addition = &diags[2];
+    assert_eq!(addition.kind(), DiagnosisItemKind::Addition);
+    assert_eq!(addition.path(), ["S"]);
+    assert_eq!(addition.trait_impl().as_deref(), Some("Clone"));
+    assert_eq!(addition.rule(), "trait-impl-added");
+    assert_eq!(addition.severity(), Severity::Minor);
+    assert_eq!(addition.previous_signature(), None);
+    assert_eq!(addition.to_string(), "+ S: Clone");
+}
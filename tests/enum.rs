@@ -101,3 +101,64 @@ fn empty_variant_kind_change_is_modification() {
         }
     }
 }
+
+#[test]
+fn variant_added_to_non_exhaustive_enum() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            #[non_exhaustive]
+            pub enum A {
+                B,
+            }
+        },
+        {
+            #[non_exhaustive]
+            pub enum A {
+                B,
+                C(u8),
+                D,
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ A (variants `C`, `D` added)\n");
+}
+
+#[test]
+fn variant_modified_in_non_exhaustive_enum() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            #[non_exhaustive]
+            pub enum A {
+                B,
+            }
+        },
+        {
+            #[non_exhaustive]
+            pub enum A {
+                B(u8),
+                C,
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ A\n");
+}
+
+#[test]
+fn defaulted_generic_added() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub enum A<T> {
+                B(T),
+            }
+        },
+        {
+            pub enum A<T, U = u8> {
+                B(T),
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ A (generic parameter `U = u8` added)\n");
+}
@@ -0,0 +1,174 @@
+use cargo_breaking::tests::{diff_fixtures, diff_from_str, get_diff, Fixture};
+
+#[test]
+fn single_file_versions() {
+    let diff = get_diff! {
+        {
+            pub fn f() {}
+        },
+        {
+            pub fn f(a: u8) {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ f\n");
+}
+
+#[test]
+fn items_in_submodule_files() {
+    let diff = get_diff! {
+        {
+            "lib.rs" => { pub mod foo; }
+            "foo.rs" => { pub mod bar; }
+            "foo/bar.rs" => { pub struct S; }
+        },
+        {
+            "lib.rs" => { pub mod foo; }
+            "foo/mod.rs" => { pub mod bar; }
+            "foo/bar.rs" => {
+                pub struct S;
+
+                impl S {
+                    pub fn f() {}
+                }
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "+ foo::bar::S::f\n");
+}
+
+#[test]
+fn impl_in_another_file() {
+    let diff = get_diff! {
+        {
+            "lib.rs" => {
+                pub mod types;
+                mod impls;
+            }
+            "types.rs" => { pub struct S; }
+            "impls.rs" => {
+                impl crate::types::S {
+                    pub fn f() {}
+                }
+            }
+        },
+        {
+            "lib.rs" => {
+                pub mod types;
+                mod impls;
+            }
+            "types.rs" => { pub struct S; }
+            "impls.rs" => {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "- types::S::f\n");
+}
+
+#[test]
+fn default_features_are_enabled() {
+    let diff = get_diff! {
+        {
+            "Cargo.toml" => "[features]\ndefault = []\nextra = []"
+            "lib.rs" => {
+                #[cfg(feature = "extra")]
+                pub fn f() {}
+            }
+        },
+        {
+            "Cargo.toml" => "[features]\ndefault = [\"extra\"]\nextra = []"
+            "lib.rs" => {
+                #[cfg(feature = "extra")]
+                pub fn f() {}
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "+ f\n");
+}
+
+#[test]
+fn feature_gated_field() {
+    let previous = Fixture::new("pub struct S { #[cfg(feature = \"a\")] pub a: u8 }")
+        .manifest("[features]\na = []");
+    let current = previous.clone().enable_feature("a");
+
+    let diff = diff_fixtures(&previous, &current).unwrap();
+
+    assert_eq!(diff.to_string(), "≠ S\n");
+}
+
+#[test]
+fn feature_gated_module_file_may_be_missing() {
+    let fixture = Fixture::new("#[cfg(feature = \"a\")] pub mod a;");
+
+    let diff = diff_fixtures(&fixture, &fixture).unwrap();
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn dependency_exposed_types_are_compared_by_path() {
+    let previous = Fixture::new("pub fn f() -> serde_json::Value {}")
+        .manifest("[dependencies]\nserde_json = \"1.0\"");
+    let current = Fixture::new("pub fn f() -> json::Value {}")
+        .manifest("[dependencies]\njson = { package = \"serde_json\", version = \"1.0\" }");
+
+    let diff = diff_fixtures(&previous, &current).unwrap();
+
+    assert_eq!(diff.to_string(), "≠ f\n");
+}
+
+#[test]
+fn reexported_dependency_items_are_compared() {
+    let previous = Fixture::new("pub use core_types::Id;")
+        .manifest("[dependencies]\ncore_types = { package = \"types\", version = \"1.0\" }")
+        .dependency("types", Fixture::new("pub struct Id(pub u32);"));
+    let current = Fixture::new("pub use core_types::Id;")
+        .manifest("[dependencies]\ncore_types = { package = \"types\", version = \"2.0\" }")
+        .dependency("types", Fixture::new("pub struct Id(pub u64);"));
+
+    let diff = diff_fixtures(&previous, &current).unwrap();
+
+    assert_eq!(diff.to_string(), "≠ Id\n");
+}
+
+#[test]
+fn missing_module_file_is_an_error() {
+    assert!(diff_from_str("pub mod foo;", "").is_err());
+}
+
+#[test]
+fn generic_associated_type_change() {
+    let previous = Fixture::new(
+        "pub trait Lending { type Item<'a> where Self: 'a; fn next<'a>(&'a mut self) -> Self::Item<'a>; }",
+    );
+    let current = Fixture::new(
+        "pub trait Lending { type Item<'a, T> where Self: 'a; fn next<'a>(&'a mut self) -> Self::Item<'a, u8>; }",
+    );
+
+    let diff = diff_fixtures(&previous, &current).unwrap();
+
+    assert_eq!(diff.to_string(), "≠ Lending::Item\n≠ Lending::next\n");
+}
+
+#[test]
+fn trait_aliases_do_not_prevent_comparison() {
+    let diff = get_diff! {
+        {
+            #![feature(trait_alias)]
+
+            pub trait Shareable = Send + Sync;
+        },
+        {
+            #![feature(trait_alias)]
+
+            pub trait Shareable = Send + Sync;
+
+            pub fn f() {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "+ f\n");
+}
@@ -133,3 +133,127 @@ fn is_reported_lexicographically() {
     };
     assert_eq!(diff.to_string(), "+ a\n+ z\n");
 }
+
+#[test]
+fn trait_object_auto_trait_added() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn f(e: Box<dyn Error>) {}
+        },
+        {
+            pub fn f(e: Box<dyn Error + Send + Sync>) {}
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ f (`+ Send` added to `dyn Error`, `+ Sync` added to `dyn Error`)\n"
+    );
+}
+
+#[test]
+fn trait_object_lifetime_removed() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn f() -> Box<dyn Fn() + 'static> {}
+        },
+        {
+            pub fn f() -> Box<dyn Fn()> {}
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ f (`+ 'static` removed from `dyn Fn()`)\n"
+    );
+}
+
+#[test]
+fn sized_bound_added() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn f<T: ?Sized + Display>(t: &T) {}
+        },
+        {
+            pub fn f<T: Display>(t: &T) {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ f (`T` now requires `Sized`)\n");
+}
+
+#[test]
+fn sized_bound_relaxed() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn f<T>(t: &T) {}
+        },
+        {
+            pub fn f<T>(t: &T) where T: ?Sized {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ f (`T` is now `?Sized`)\n");
+}
+
+#[test]
+fn closure_bound_restricted() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn f<F: FnMut(u8)>(f: F) {}
+        },
+        {
+            pub fn f<F: Fn(u8)>(f: F) {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ f (`FnMut(u8)` is now `Fn(u8)`)\n");
+}
+
+#[test]
+fn closure_bound_relaxed() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn f(f: impl Fn() -> u8) {}
+        },
+        {
+            pub fn f(f: impl FnOnce() -> u8) {}
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ f (`Fn() -> u8` is now `FnOnce() -> u8`)\n"
+    );
+}
+
+#[test]
+fn generic_parameter_replaced_with_impl_trait() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn f<T: Clone, R: Read + Send>(t: T, r: &mut R) {}
+        },
+        {
+            pub fn f<U: Clone>(t: U, r: &mut impl Read + Send) {}
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ f (`r: &mut R` is now `r: &mut impl Read + Send`)\n"
+    );
+}
+
+#[test]
+fn impl_trait_with_other_bounds() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn f(r: impl Read) {}
+        },
+        {
+            pub fn f<R: Write>(r: R) {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ f\n");
+}
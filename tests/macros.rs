@@ -0,0 +1,218 @@
+use cargo_breaking::ApiCompatibilityDiagnostics;
+use syn::parse_quote;
+
+#[test]
+fn not_exported_is_not_reported() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {},
+        {
+            macro_rules! m {
+                () => {};
+            }
+        },
+    };
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn addition() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {},
+        {
+            #[macro_export]
+            macro_rules! m {
+                () => {};
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "+ m\n");
+}
+
+#[test]
+fn removal() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod inner {
+                #[macro_export]
+                macro_rules! m {
+                    () => {};
+                }
+            }
+        },
+        {
+            pub mod inner {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "- m\n");
+}
+
+#[test]
+fn transcriber_change_is_not_reported() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            #[macro_export]
+            macro_rules! m {
+                ($e:expr) => { $e };
+            }
+        },
+        {
+            #[macro_export]
+            macro_rules! m {
+                ($e:expr) => { { let e = $e; e } };
+            }
+        },
+    };
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn arm_removal() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            #[macro_export]
+            macro_rules! m {
+                () => {};
+                ($($e:expr),* $(,)?) => {};
+            }
+        },
+        {
+            #[macro_export]
+            macro_rules! m {
+                () => {};
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ m (arm `($($e:expr),* $(,)?)` removed)\n"
+    );
+}
+
+#[test]
+fn fragment_specifier_change() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            #[macro_export]
+            macro_rules! m {
+                ($name:ident = $value:expr) => {};
+            }
+        },
+        {
+            #[macro_export]
+            macro_rules! m {
+                ($name:ident = $value:literal) => {};
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ m (`$value` is now `literal` instead of `expr`)\n"
+    );
+}
+
+#[test]
+fn arm_addition() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            #[macro_export]
+            macro_rules! m {
+                ($e:expr) => {};
+            }
+        },
+        {
+            #[macro_export]
+            macro_rules! m {
+                ($e:expr) => {};
+                [$e:expr; $n:literal] => {};
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ m (arm `($e:expr; $n:literal)` added)\n"
+    );
+}
+
+#[test]
+fn local_inner_macros_removal() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            #[macro_export(local_inner_macros)]
+            macro_rules! m {
+                () => { helper!() };
+            }
+        },
+        {
+            #[macro_export]
+            macro_rules! m {
+                () => { helper!() };
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ m (no longer `local_inner_macros`)\n");
+}
+
+#[test]
+fn reexport_removal() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            #[macro_export]
+            macro_rules! m {
+                () => {};
+            }
+
+            pub mod macros {
+                pub use crate::m;
+            }
+        },
+        {
+            #[macro_export]
+            macro_rules! m {
+                () => {};
+            }
+
+            pub mod macros {}
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ m (no longer importable as `macros::m`)\n"
+    );
+}
+
+#[test]
+fn reexported_hidden_macro() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            #[doc(hidden)]
+            #[macro_export]
+            macro_rules! __m {
+                () => {};
+            }
+
+            pub mod macros {
+                pub use crate::__m as m;
+            }
+        },
+        {
+            #[doc(hidden)]
+            #[macro_export]
+            macro_rules! __m {
+                () => {};
+            }
+
+            pub mod macros {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "- macros::m\n");
+}
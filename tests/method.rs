@@ -1,4 +1,4 @@
-use cargo_breaking::ApiCompatibilityDiagnostics;
+use cargo_breaking::{ApiCompatibilityDiagnostics, SemverBump};
 use syn::parse_quote;
 
 #[test]
@@ -118,7 +118,37 @@ fn generic_arg_change_is_modification() {
         },
     };
 
-    assert_eq!(diff.to_string(), "≠ A::f\n");
+    assert_eq!(
+        diff.to_string(),
+        "≠ A::f (impl target changed from A<T> to A<U>)\n"
+    );
+}
+
+#[test]
+fn concrete_instantiation_change_is_single_modification() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct A<T>(T);
+
+            impl A<u8> {
+                pub fn f() {}
+                pub fn g(&self) -> u8 {}
+            }
+        },
+        {
+            pub struct A<T>(T);
+
+            impl A<u16> {
+                pub fn f() {}
+                pub fn g(&self) -> u8 {}
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ A::f (impl target changed from A<u8> to A<u16>)\n≠ A::g (impl target changed from A<u8> to A<u16>)\n"
+    );
 }
 
 #[test]
@@ -186,3 +216,286 @@ fn is_reported_in_type_definition_path_2() {
 
     assert_eq!(diff.to_string(), "- foo::Bar::f\n");
 }
+
+#[test]
+fn splitting_impl_block_is_not_reported() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct A<T>(T);
+
+            impl<T: Clone> A<T> {
+                pub fn f(&self) {}
+                pub fn g(&self) {}
+            }
+        },
+        {
+            pub struct A<T>(T);
+
+            impl<T: Clone> A<T> {
+                pub fn f(&self) {}
+            }
+
+            impl<T: Clone> A<T> {
+                pub fn g(&self) {}
+            }
+        },
+    };
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn merging_impl_blocks_from_different_modules_is_not_reported() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod foo {
+                pub struct Bar;
+
+                impl Bar {
+                    pub fn f() {}
+                }
+            }
+
+            pub mod baz {
+                impl crate::foo::Bar {
+                    pub fn g() {}
+                }
+            }
+
+            mod qux {
+                use crate::foo::Bar;
+
+                impl Bar {
+                    pub fn h() {}
+                }
+            }
+        },
+        {
+            pub mod foo {
+                pub struct Bar;
+
+                impl Bar {
+                    pub fn f() {}
+                    pub fn g() {}
+                    pub fn h() {}
+                }
+            }
+
+            pub mod baz {}
+        },
+    };
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn moving_method_to_block_with_other_bounds_is_reported() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct A<T>(T);
+
+            impl<T> A<T> {
+                pub fn f(&self) {}
+            }
+        },
+        {
+            pub struct A<T>(T);
+
+            impl<T: Clone> A<T> {
+                pub fn f(&self) {}
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ A::f\n");
+}
+
+#[test]
+fn defining_module_is_displayed_in_verbose_mode() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod foo {
+                pub struct Bar;
+            }
+
+            pub mod baz {
+                impl crate::foo::Bar {
+                    pub fn f() {}
+                }
+            }
+        },
+        {
+            pub mod foo {
+                pub struct Bar;
+            }
+        }
+    };
+
+    assert_eq!(
+        format!("{:#}", diff),
+        "- foo::Bar::f\n    defined in `crate::baz`\n"
+    );
+}
+
+#[test]
+fn builder_chaining_changes_are_explained() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct ClientBuilder;
+
+            impl ClientBuilder {
+                pub fn timeout(self, timeout: u32) -> Self {}
+                pub fn retries(&mut self, retries: u8) -> &mut ClientBuilder {}
+                pub fn proxy(self, proxy: String) -> Self {}
+            }
+
+            pub struct Client;
+
+            impl Client {
+                pub fn timeout(self, timeout: u32) -> Self {}
+            }
+        },
+        {
+            pub struct ClientBuilder;
+
+            impl ClientBuilder {
+                pub fn timeout(&mut self, timeout: u32) -> &mut Self {}
+                pub fn retries(self, retries: u8) -> Self {}
+                pub fn proxy(&mut self, proxy: Url) -> &mut Self {}
+            }
+
+            pub struct Client;
+
+            impl Client {
+                pub fn timeout(&mut self, timeout: u32) -> &mut Self {}
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ Client::timeout\n\
+         ≠ ClientBuilder::proxy\n\
+         ≠ ClientBuilder::retries (now takes and returns `Self`: it can no longer be called on a builder behind a `&mut`)\n\
+         ≠ ClientBuilder::timeout (now takes and returns `&mut Self`: chains ending with a method taking `self` no longer compile)\n"
+    );
+}
+
+#[test]
+fn constructor_bounds_are_construction_requirements() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct Reader<R>(R);
+
+            impl<R> Reader<R> {
+                pub fn new(inner: R) -> Self {}
+                pub fn from_parts<B>(inner: R, buf: B) -> Self {}
+                pub fn get(&self) -> &R {}
+            }
+        },
+        {
+            pub struct Reader<R>(R);
+
+            impl<R: Read> Reader<R> {
+                pub fn new(inner: R) -> Self {}
+                pub fn from_parts<B: AsRef<[u8]>>(inner: R, buf: B) -> Self {}
+                pub fn get(&self) -> &R {}
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ Reader::from_parts (`B` now requires `AsRef<[u8]>`, `R` now requires `Read`)\n\
+         ≠ Reader::get\n\
+         ≠ Reader::new (`R` now requires `Read`)\n"
+    );
+}
+
+#[test]
+fn receiver_changes_are_explained() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct HttpClient;
+
+            impl HttpClient {
+                pub fn default_port() -> u16 {}
+                pub fn connect(&mut self, url: &str) {}
+            }
+        },
+        {
+            pub struct HttpClient;
+
+            impl HttpClient {
+                pub fn default_port(&self) -> u16 {}
+                pub fn connect(url: &str) {}
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ HttpClient::connect (now an associated function: `http_client.connect(..)` becomes `HttpClient::connect(..)`)\n\
+         ≠ HttpClient::default_port (now a method: `HttpClient::default_port()` becomes `http_client.default_port()`)\n"
+    );
+}
+
+#[test]
+fn moves_between_inherent_and_trait_impls_are_explained() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct Stack;
+
+            impl Stack {
+                pub fn len(&self) -> usize {}
+            }
+
+            impl Default for Stack {
+                fn default() -> Stack {}
+            }
+        },
+        {
+            pub struct Stack;
+
+            impl Stack {
+                pub fn default() -> Stack {}
+            }
+
+            impl Length for Stack {
+                fn len(&self) -> usize {}
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ Stack::default (moved from the `Default` implementation to an inherent impl: `<Stack as Default>::default()` becomes `Stack::default()`)\n\
+         ≠ Stack::len (moved to the `Length` implementation: now requires `Length` to be in scope)\n"
+    );
+}
+
+#[test]
+fn moves_to_prelude_trait_impls_are_minor() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct Stack;
+
+            impl Stack {
+                pub fn clone(&self) -> Stack {}
+            }
+        },
+        {
+            pub struct Stack;
+
+            impl Clone for Stack {
+                fn clone(&self) -> Stack {}
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ Stack::clone (moved to the `Clone` implementation, which is in the prelude)\n"
+    );
+    assert_eq!(diff.required_bump(), SemverBump::Minor);
+}
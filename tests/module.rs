@@ -0,0 +1,176 @@
+use cargo_breaking::{ApiCompatibilityDiagnostics, SemverBump};
+use syn::parse_quote;
+
+#[test]
+fn moved_module_is_reported_once() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod foo {
+                pub fn a() {}
+                pub fn b() {}
+                pub struct C;
+            }
+        },
+        {
+            pub mod bar {
+                pub fn a() {}
+                pub fn b() {}
+                pub struct C;
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ foo (moved to bar, 3 items)\n");
+}
+
+#[test]
+fn moved_module_details_are_verbose_only() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod foo {
+                pub fn a() {}
+                pub struct C;
+            }
+        },
+        {
+            pub mod bar {
+                pub mod baz {
+                    pub fn a() {}
+                    pub struct C;
+                }
+            }
+        },
+    };
+
+    assert_eq!(
+        format!("{:#}", diff),
+        "≠ foo (moved to bar::baz, 2 items)\n    foo::C -> bar::baz::C\n    foo::a -> bar::baz::a\n"
+    );
+}
+
+#[test]
+fn modified_module_is_not_a_move() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod foo {
+                pub fn a() {}
+            }
+        },
+        {
+            pub mod bar {
+                pub fn a() {}
+                pub fn b() {}
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "- foo::a\n+ bar::a\n+ bar::b\n");
+}
+
+#[test]
+fn submodule_moved_out_of_surviving_parent() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod a {
+                pub fn keep() {}
+
+                pub mod b {
+                    pub fn f() {}
+                    pub fn g() {}
+                    pub struct S;
+                }
+            }
+        },
+        {
+            pub mod a {
+                pub fn keep() {}
+                pub fn new() {}
+            }
+
+            pub mod c {
+                pub mod b {
+                    pub fn f() {}
+                    pub fn g() {}
+                    pub struct S;
+                }
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ a::b (moved to c::b, 3 items)\n+ a::new\n"
+    );
+}
+
+#[test]
+fn move_is_breaking() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod foo {
+                pub fn a() {}
+            }
+        },
+        {
+            pub mod bar {
+                pub fn a() {}
+            }
+        },
+    };
+
+    assert_eq!(diff.required_bump(), SemverBump::Major);
+}
+
+#[test]
+fn doc_inlining_of_reexports_is_not_compared() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod error {
+                pub struct Error;
+
+                impl Error {
+                    pub fn kind(&self) {}
+                }
+            }
+
+            #[doc(inline)]
+            pub use error::Error;
+
+            #[doc(hidden)]
+            #[macro_export]
+            macro_rules! __log {
+                () => {};
+            }
+
+            pub mod log {
+                #[doc(inline)]
+                pub use crate::__log as log;
+            }
+        },
+        {
+            pub mod error {
+                pub struct Error;
+
+                impl Error {
+                    pub fn kind(&self) {}
+                }
+            }
+
+            #[doc(no_inline)]
+            pub use error::Error;
+
+            #[doc(hidden)]
+            #[macro_export]
+            macro_rules! __log {
+                () => {};
+            }
+
+            pub mod log {
+                #[doc(no_inline)]
+                pub use crate::__log as log;
+            }
+        },
+    };
+
+    assert!(diff.is_empty());
+}
@@ -0,0 +1,86 @@
+use cargo_breaking::tests::{diff_fixtures, diff_fixtures_including_private, Fixture};
+
+fn diff(previous: &str, current: &str) -> String {
+    diff_fixtures_including_private(&Fixture::new(previous), &Fixture::new(current))
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn private_changes_are_ignored_by_default() {
+    let previous = Fixture::new("fn f() {} pub(crate) fn g() {}");
+    let current = Fixture::new("fn f(a: u8) {}");
+
+    let diff = diff_fixtures(&previous, &current).unwrap();
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn private_function_modification() {
+    assert_eq!(diff("fn f() {}", "fn f(a: u8) {}"), "≠ f (private)\n");
+}
+
+#[test]
+fn crate_visible_function_addition() {
+    assert_eq!(
+        diff("", "pub(crate) fn f() {} pub fn g() {}"),
+        "+ f (private)\n+ g\n"
+    );
+}
+
+#[test]
+fn public_item_in_private_module() {
+    assert_eq!(
+        diff("mod m { pub struct S; }", "mod m {}"),
+        "- m::S (private)\n"
+    );
+}
+
+#[test]
+fn private_method_of_public_type() {
+    assert_eq!(
+        diff(
+            "pub struct S; impl S { pub fn f() {} }",
+            "pub struct S; impl S { pub fn f() {} fn g() {} }",
+        ),
+        "+ S::g (private)\n"
+    );
+}
+
+#[test]
+fn private_fields_are_not_compared() {
+    assert_eq!(
+        diff("pub struct S { a: u8 }", "pub struct S { a: u16 }"),
+        ""
+    );
+}
+
+#[test]
+fn item_made_private() {
+    assert_eq!(
+        diff("pub fn f() {}", "pub(crate) fn f() {}"),
+        "- f (no longer public)\n"
+    );
+}
+
+#[test]
+fn item_made_public() {
+    assert_eq!(diff("fn f() {}", "pub fn f() {}"), "+ f (now public)\n");
+}
+
+#[test]
+fn item_made_private_and_modified() {
+    assert_eq!(
+        diff("pub fn f() {}", "pub(crate) fn f(a: u8) {}"),
+        "- f (no longer public)\n≠ f (private)\n"
+    );
+}
+
+#[test]
+fn item_made_public_and_modified() {
+    assert_eq!(
+        diff("fn f() {}", "pub fn f(a: u8) {}"),
+        "≠ f (private)\n+ f (now public)\n"
+    );
+}
@@ -0,0 +1,76 @@
+use std::{env, fs, path::Path};
+
+use cargo_breaking::{compare, tests::render_report};
+
+// Each directory in `tests/snapshots` contains a `previous.rs` and a
+// `current.rs` file, as well as the exact outputs the comparison of both must
+// produce: the diagnostics in `output.txt`, and the whole report in
+// `report.txt` and `report.json`.
+const SNAPSHOTS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots");
+
+// When set, the snapshots are written with the current outputs instead of
+// being checked: `BLESS=1 cargo test --test snapshots`. The changes are then
+// reviewed along with the code.
+const BLESS_VAR: &str = "BLESS";
+
+// The comparison is run several times per case, so that any dependency on
+// the iteration order of a hash-based collection shows up as a failure.
+const RUNS_PER_CASE: usize = 8;
+
+fn render(case: &Path, file: &str) -> String {
+    let previous = fs::read_to_string(case.join("previous.rs")).unwrap();
+    let current = fs::read_to_string(case.join("current.rs")).unwrap();
+
+    match file {
+        "output.txt" => compare(&previous, &current).unwrap().run().to_string(),
+        // The rendered reports don't end with a line break, as it is added
+        // when printing.
+        "report.txt" => render_report(&previous, &current, "text").unwrap() + "\n",
+        "report.json" => render_report(&previous, &current, "json").unwrap() + "\n",
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn snapshots_match() {
+    let bless = env::var_os(BLESS_VAR).is_some();
+
+    let mut cases = fs::read_dir(SNAPSHOTS_DIR)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+
+    cases.sort();
+
+    assert!(!cases.is_empty(), "No snapshot found in {}", SNAPSHOTS_DIR);
+
+    for case in cases {
+        for file in ["output.txt", "report.txt", "report.json"] {
+            let path = case.join(file);
+
+            if bless {
+                fs::write(&path, render(&case, file)).unwrap();
+                continue;
+            }
+
+            let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+                panic!(
+                    "{} is missing, run `{}=1 cargo test --test snapshots` to create it",
+                    path.display(),
+                    BLESS_VAR
+                )
+            });
+
+            for _ in 0..RUNS_PER_CASE {
+                assert_eq!(
+                    render(&case, file),
+                    expected,
+                    "Snapshot mismatch for {}, run `{}=1 cargo test --test snapshots` to update it",
+                    path.display(),
+                    BLESS_VAR
+                );
+            }
+        }
+    }
+}
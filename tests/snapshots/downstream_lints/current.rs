@@ -0,0 +1,14 @@
+#[must_use]
+pub fn parse(input: &str) -> u8 {}
+
+#[deprecated]
+pub fn legacy() {}
+
+#[non_exhaustive]
+pub struct Config {
+    pub verbose: bool,
+}
+
+pub fn run(config: Config, retries: u8) -> Result<(), Error> {}
+
+pub fn check(config: &Config) -> bool {}
@@ -0,0 +1,9 @@
+pub fn parse(input: &str) -> u8 {}
+
+pub fn legacy() {}
+
+pub struct Config {
+    pub verbose: bool,
+}
+
+pub fn run(config: Config) -> Result<(), Error> {}
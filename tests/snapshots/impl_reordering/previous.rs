@@ -0,0 +1,14 @@
+pub struct S;
+
+impl From<u8> for S {}
+impl Clone for S {}
+impl From<u16> for S {}
+impl Debug for S {}
+
+impl S {
+    pub fn f() {}
+}
+
+impl S {
+    pub fn g() {}
+}
@@ -0,0 +1,7 @@
+pub mod a {
+    pub struct b;
+}
+
+pub enum c {}
+
+pub trait D {}
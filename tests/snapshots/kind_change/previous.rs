@@ -0,0 +1,7 @@
+pub mod a {
+    pub fn b() {}
+}
+
+pub fn c() {}
+
+pub struct D;
@@ -0,0 +1,34 @@
+pub mod users {
+    pub struct User {
+        pub name: String,
+        pub age: u8,
+    }
+
+    impl User {
+        pub fn from_path(p: &Path) -> User {}
+        pub fn name(&self) -> &str {}
+    }
+}
+
+impl Debug for users::User {}
+
+pub mod groups {
+    pub enum Role {
+        Admin,
+        Guest,
+        Moderator,
+    }
+
+    pub fn default_role() -> Role {}
+}
+
+pub trait Storage {
+    type Key;
+
+    fn get(&self, key: &Self::Key);
+    fn insert(&self, key: Self::Key);
+}
+
+pub fn c() {}
+pub fn b() {}
+pub fn d() {}
@@ -0,0 +1,32 @@
+pub mod users {
+    pub struct User {
+        pub name: String,
+    }
+
+    impl User {
+        pub fn from_str(s: &str) -> User {}
+        pub fn name(&self) -> &str {}
+    }
+}
+
+impl Clone for users::User {}
+
+pub mod groups {
+    pub enum Role {
+        Admin,
+        Guest,
+    }
+
+    pub fn default_role() -> Role {}
+}
+
+pub trait Storage {
+    type Key;
+
+    fn get(&self, key: Self::Key);
+    fn remove(&self, key: Self::Key);
+}
+
+pub fn a() {}
+pub fn b() {}
+pub fn c() {}
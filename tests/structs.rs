@@ -175,3 +175,135 @@ fn generic_change_is_modification() {
 
     assert_eq!(diff.to_string(), "≠ E\n");
 }
+
+#[test]
+fn trait_object_field_bound_change() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct A {
+                pub callback: Box<dyn Fn(u8) + Send>,
+            }
+        },
+        {
+            pub struct A {
+                pub callback: Box<dyn Fn(u8)>,
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ A (`+ Send` removed from `dyn Fn(u8)`)\n"
+    );
+}
+
+#[test]
+fn field_added_to_non_exhaustive_struct() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            #[non_exhaustive]
+            pub struct A {
+                pub a: u8,
+            }
+        },
+        {
+            #[non_exhaustive]
+            pub struct A {
+                pub a: u8,
+                pub b: u16,
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ A (field `b` added)\n");
+}
+
+#[test]
+fn field_added_to_exhaustive_struct() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct A {
+                pub a: u8,
+            }
+        },
+        {
+            pub struct A {
+                pub a: u8,
+                pub b: u16,
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ A\n");
+}
+
+#[test]
+fn generic_without_default_added() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct A;
+        },
+        {
+            pub struct A<T = u8, U>;
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ A\n");
+}
+
+#[test]
+fn variance_restricted_by_private_field() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct A<'a, T> {
+                value: &'a T,
+            }
+        },
+        {
+            pub struct A<'a, T> {
+                value: &'a mut T,
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ A (`T` is now invariant instead of covariant)\n"
+    );
+}
+
+#[test]
+fn variance_unchanged() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct A<T> {
+                value: Box<T>,
+            }
+        },
+        {
+            pub struct A<T> {
+                value: Vec<T>,
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "");
+}
+
+#[test]
+fn sized_bound_added_on_struct() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct A<T: ?Sized> {
+                value: Box<T>,
+            }
+        },
+        {
+            pub struct A<T> {
+                value: Box<T>,
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ A (`T` now requires `Sized`)\n");
+}
@@ -61,6 +61,29 @@ fn trait_item_removal() {
     assert_eq!(diff.to_string(), "- A::B\n");
 }
 
+#[test]
+fn constant_default_value_modification() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub trait A {
+                const B: usize = 16;
+                const C: bool = true;
+            }
+        },
+        {
+            pub trait A {
+                const B: usize = 1 << 4;
+                const C: bool = false;
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ A::C (default value changed from `true` to `false`)\n"
+    );
+}
+
 #[test]
 fn trait_item_kind_modification() {
     let diff: ApiCompatibilityDiagnostics = parse_quote! {
@@ -108,3 +131,80 @@ fn in_public_module() {
 
     assert_eq!(diff.to_string(), "+ a::A\n");
 }
+
+#[test]
+fn default_implementation_removed() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub trait A {
+                fn f(&self) {}
+            }
+        },
+        {
+            pub trait A {
+                fn f(&self);
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ A::f (default implementation removed)\n"
+    );
+}
+
+#[test]
+fn default_implementation_added() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub trait A {
+                fn f(&self);
+            }
+        },
+        {
+            pub trait A {
+                fn f(&self) {}
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ A::f (default implementation added)\n");
+}
+
+#[test]
+fn default_implementation_body_change() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub trait A {
+                fn f(&self) -> u8 { 0 }
+            }
+        },
+        {
+            pub trait A {
+                fn f(&self) -> u8 { 1 }
+            }
+        },
+    };
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn trait_impl_method_body_change() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct S;
+            impl Clone for S {
+                fn clone(&self) -> S { S }
+            }
+        },
+        {
+            pub struct S;
+            impl Clone for S {
+                fn clone(&self) -> S { todo!() }
+            }
+        },
+    };
+
+    assert!(diff.is_empty());
+}
@@ -74,7 +74,29 @@ fn constant_modification_is_modification() {
         },
     };
 
-    assert_eq!(diff.to_string(), "≠ S: T\n");
+    assert_eq!(diff.to_string(), "≠ S: T (`C` changed from `0` to `255`)\n");
+}
+
+#[test]
+fn constant_written_differently_is_not_modification() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct S;
+
+            impl T for S {
+                const C: usize = 255;
+            }
+        },
+        {
+            pub struct S;
+
+            impl T for S {
+                const C: usize = (1 << 8) - 1;
+            }
+        },
+    };
+
+    assert!(diff.is_empty());
 }
 
 #[test]
@@ -134,3 +156,341 @@ fn not_reported_when_type_is_not_public() {
 
     assert!(diff.is_empty());
 }
+
+#[test]
+fn impl_in_type_module() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod foo {
+                pub struct Bar;
+
+                impl Clone for Bar {}
+            }
+        },
+        {
+            pub mod foo {
+                pub struct Bar;
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "- foo::Bar: Clone\n");
+}
+
+#[test]
+fn impl_in_other_module() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod foo {
+                pub struct Bar;
+            }
+        },
+        {
+            pub mod foo {
+                pub struct Bar;
+            }
+
+            pub mod baz {
+                impl Clone for crate::foo::Bar {}
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "+ foo::Bar: Clone\n");
+    assert_eq!(
+        format!("{:#}", diff),
+        "+ foo::Bar: Clone\n    defined in `crate::baz`\n"
+    );
+}
+
+#[test]
+fn moving_impl_to_other_module_is_not_reported() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod foo {
+                pub struct Bar;
+
+                impl Clone for Bar {}
+            }
+        },
+        {
+            pub mod foo {
+                pub struct Bar;
+            }
+
+            mod baz {
+                use crate::foo::Bar;
+
+                impl Clone for Bar {}
+            }
+        },
+    };
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn impl_target_change() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct A<T>(T);
+
+            impl From<u8> for A<u8> {}
+        },
+        {
+            pub struct A<T>(T);
+
+            impl From<u8> for A<Vec<u16>> {}
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ A: From (impl target changed from A<u8> to A<Vec<u16>>)\n"
+    );
+}
+
+#[test]
+fn syntax_trait_impl_removal_is_explained() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct T;
+
+            impl Iterator for T {}
+            impl IntoIterator for T {}
+            impl Deref for T {}
+            impl Index<usize> for T {}
+            impl Add for T {}
+            impl MulAssign for T {}
+        },
+        {
+            pub struct T;
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "- T: Add (`a + b` no longer compiles for `T + T`)\n\
+         - T: Deref (`*` and auto-deref method calls no longer work)\n\
+         - T: Index (`a[i]` no longer compiles for `T[usize]`)\n\
+         - T: IntoIterator (can no longer be used in `for` loops)\n\
+         - T: Iterator (can no longer be iterated or used in `for` loops)\n\
+         - T: MulAssign (`a *= b` no longer compiles for `T *= T`)\n"
+    );
+}
+
+#[test]
+fn operator_trait_impl_changes_are_explained() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct Meters;
+
+            impl Add<u32> for Meters {
+                type Output = Meters;
+            }
+            impl Neg for Meters {
+                type Output = Meters;
+            }
+            impl PartialOrd<f64> for Meters {}
+            impl Ord for Meters {}
+            impl Sub for Meters {
+                type Output = Meters;
+            }
+        },
+        {
+            pub struct Meters;
+
+            impl Add<u64> for Meters {
+                type Output = Meters;
+            }
+            impl Neg for Meters {
+                type Output = i64;
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "- Meters: Ord (`a.cmp(&b)` no longer compiles for `Meters.cmp(&Meters)`)\n\
+         - Meters: PartialOrd (`a < b` no longer compiles for `Meters < f64`)\n\
+         - Meters: Sub (`a - b` no longer compiles for `Meters - Meters`)\n\
+         ≠ Meters: Add (`a + b` no longer compiles for `Meters + u32`)\n\
+         ≠ Meters: Neg (`-Meters` now gives `i64`)\n"
+    );
+}
+
+#[test]
+fn other_trait_impl_removal_has_no_message() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct T;
+
+            impl Addition for T {}
+        },
+        {
+            pub struct T;
+        },
+    };
+
+    assert_eq!(diff.to_string(), "- T: Addition\n");
+}
+
+#[test]
+fn impls_of_a_trait_are_matched_by_arguments() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct T;
+
+            impl From<u8> for T {}
+            impl From<u16> for T {}
+        },
+        {
+            pub struct T;
+
+            impl From<u16> for T {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "- T: From\n");
+}
+
+#[test]
+fn error_type_impl_removals_are_explained() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub enum E {}
+
+            impl std::error::Error for E {}
+            impl From<std::io::Error> for E {}
+            impl From<std::fmt::Error> for E {}
+        },
+        {
+            pub enum E {}
+
+            impl From<std::fmt::Error> for E {}
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "- E: Error (`?` can no longer convert it into `Box<dyn Error>`)\n\
+         - E: From (`?` can no longer convert `std::io::Error` into it)\n"
+    );
+}
+
+#[test]
+fn error_type_non_exhaustive_changes_are_explained() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub enum E {}
+
+            impl Error for E {}
+
+            #[non_exhaustive]
+            pub enum F {}
+
+            impl Error for F {}
+        },
+        {
+            #[non_exhaustive]
+            pub enum E {}
+
+            impl Error for E {}
+
+            pub enum F {}
+
+            impl Error for F {}
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ E (now #[non_exhaustive]: matching on it requires a wildcard arm)\n\
+         ≠ F (no longer #[non_exhaustive]: adding a variant will be a breaking change)\n"
+    );
+}
+
+#[test]
+fn text_conversion_impl_removals_are_explained() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct Version;
+
+            impl fmt::Display for Version {}
+            impl FromStr for Version {}
+        },
+        {
+            pub struct Version;
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "- Version: Display (`to_string()` and `format!(\"{}\")` no longer work)\n\
+         - Version: FromStr (`parse()` can no longer build it)\n"
+    );
+}
+
+#[test]
+fn moving_const_value_to_trait_default_is_not_an_impl_change() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub trait Limits {
+                const MAX: usize;
+            }
+
+            pub struct S;
+
+            impl Limits for S {
+                const MAX: usize = 16;
+            }
+        },
+        {
+            pub trait Limits {
+                const MAX: usize = 1 << 4;
+            }
+
+            pub struct S;
+
+            impl Limits for S {}
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ Limits::MAX (default value added)\n");
+}
+
+#[test]
+fn relying_on_another_trait_default_changes_the_value() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub trait Limits {
+                const MAX: usize = 16;
+                const MIN: usize = 0;
+            }
+
+            pub struct S;
+
+            impl Limits for S {
+                const MIN: usize = 1;
+                const MAX: usize = 8;
+            }
+        },
+        {
+            pub trait Limits {
+                const MAX: usize = 16;
+                const MIN: usize = 0;
+            }
+
+            pub struct S;
+
+            impl Limits for S {
+                const MAX: usize = 8;
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ S: Limits (`MIN` changed from `1` to `0`, now the default value of the trait)\n"
+    );
+}
@@ -0,0 +1,90 @@
+use cargo_breaking::ApiCompatibilityDiagnostics;
+use syn::parse_quote;
+
+#[test]
+fn using_alias_instead_of_definition_is_not_reported() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct Error;
+
+            pub fn f() -> std::result::Result<u8, Error> {}
+        },
+        {
+            pub struct Error;
+            pub type Result<T> = std::result::Result<T, Error>;
+
+            pub fn f() -> Result<u8> {}
+        },
+    };
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn alias_definition_change_is_reported_on_users() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub type Id = u32;
+
+            pub struct User {
+                pub id: Id,
+            }
+        },
+        {
+            pub type Id = u64;
+
+            pub struct User {
+                pub id: Id,
+            }
+        },
+    };
+
+    assert_eq!(diff.to_string(), "≠ User\n");
+}
+
+#[test]
+fn imported_alias_is_expanded() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub mod ids {
+                pub type Id = u32;
+            }
+
+            use crate::ids::Id;
+
+            pub fn f(id: Id) {}
+        },
+        {
+            pub mod ids {
+                pub type Id = u32;
+            }
+
+            pub fn f(id: u32) {}
+        },
+    };
+
+    assert!(diff.is_empty());
+}
+
+#[test]
+fn methods_of_aliased_type_are_attributed_to_the_type() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct S;
+
+            impl S {
+                pub fn f(&self) {}
+            }
+        },
+        {
+            pub struct S;
+            type Alias = S;
+
+            impl Alias {
+                pub fn f(&self) {}
+            }
+        },
+    };
+
+    assert!(diff.is_empty());
+}
@@ -0,0 +1,133 @@
+use cargo_breaking::{
+    tests::{diff_fixtures_including_private, diff_from_str, Fixture},
+    ApiCompatibilityDiagnostics,
+};
+use syn::parse_quote;
+
+/// Crates whose shape is unusual, or which the analysis does not support.
+/// None of them may stop the comparison.
+const CORPUS: &[&str] = &[
+    "",
+    "pub struct S {} pub fn S() {}",
+    "pub enum E {} pub fn E() {}",
+    "pub trait T {} pub fn T() {}",
+    "pub mod m {} pub fn m() {}",
+    "#[cfg(unix)] pub fn f() {} #[cfg(windows)] pub fn f() {}",
+    "pub struct S; impl S { pub fn f() {} } impl S { pub fn f() {} }",
+    "pub fn f() {} impl Clone for f {}",
+    "struct P; impl Clone for P {}",
+    "impl Clone for Vec<u8> {}",
+    "pub trait T { m!(); }",
+    "pub mod a { pub(super) use crate::b; } pub fn b() {}",
+    "pub mod a { pub(in crate) use crate::b; } pub fn b() {}",
+    "pub use crate as me;",
+    "pub use self::*;",
+    "pub use other_crate::*;",
+    "pub mod a { pub use super::b::*; } pub mod b { pub use super::a::*; }",
+    "#[macro_export] macro_rules! m { () => {} } pub fn m() {}",
+    "#[macro_export] macro_rules! m { ($($x:tt)*) => {} }",
+    "pub struct S; impl<T> Clone for S where T: Copy {}",
+    "mod a { pub struct S; } pub use a::S; impl S { pub fn f() {} }",
+];
+
+#[test]
+fn corpus_does_not_panic() {
+    for &previous in CORPUS {
+        for &current in CORPUS {
+            diff_from_str(previous, current).unwrap();
+            diff_fixtures_including_private(&Fixture::new(previous), &Fixture::new(current))
+                .unwrap();
+        }
+    }
+}
+
+#[test]
+fn several_items_at_the_same_path() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct S;
+        },
+        {
+            pub struct S;
+            pub fn S() {}
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ S (several items are defined at this path)\n"
+    );
+}
+
+#[test]
+fn type_and_function_at_the_same_path() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub struct S {}
+            pub fn S() {}
+        },
+        {
+            pub fn S() {}
+        },
+    };
+
+    // The function was hidden behind the struct.
+    assert_eq!(diff.to_string(), "- S\n+ S\n");
+}
+
+#[test]
+fn trait_containing_macro_invocations() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub trait T {
+                fn f();
+                m!();
+            }
+        },
+        {
+            pub trait T {
+                m!();
+            }
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ T (the trait contains a `m!` invocation)\n"
+    );
+}
+
+#[test]
+fn trait_implemented_for_a_function() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn f() {}
+        },
+        {
+            pub fn f() {}
+            impl Clone for f {}
+        },
+    };
+
+    assert_eq!(
+        diff.to_string(),
+        "≠ f (`Clone` is implemented for an item which is not a type)\n"
+    );
+}
+
+#[test]
+fn restricted_reexports_are_private() {
+    let diff: ApiCompatibilityDiagnostics = parse_quote! {
+        {
+            pub fn b() {}
+        },
+        {
+            pub mod a {
+                pub(super) use crate::b;
+            }
+            pub fn b() {}
+        },
+    };
+
+    assert!(diff.is_empty());
+}